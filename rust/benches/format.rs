@@ -0,0 +1,46 @@
+//! Formatting a secret whose digest is already cached, as a logger does on
+//! every request. Run with `cargo bench --bench format`.
+//!
+//! `display_via_to_string` copies the redacted form into a fresh `String`
+//! first, which is what `Display` used to do; the other cases write the
+//! cached digest straight into a reused buffer.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sensitive_string::SensitiveString;
+use std::fmt::Write;
+
+fn format(c: &mut Criterion) {
+    let secret = SensitiveString::from("correct horse battery staple");
+    let _ = secret.to_string();
+    let mut text = String::with_capacity(256);
+    let mut json = Vec::with_capacity(256);
+
+    c.bench_function("display_via_to_string", |b| {
+        b.iter(|| {
+            text.clear();
+            let shown = black_box(&secret).to_string();
+            text.push_str(&shown);
+        })
+    });
+    c.bench_function("display", |b| {
+        b.iter(|| {
+            text.clear();
+            write!(text, "{}", black_box(&secret)).unwrap();
+        })
+    });
+    c.bench_function("debug", |b| {
+        b.iter(|| {
+            text.clear();
+            write!(text, "{:?}", black_box(&secret)).unwrap();
+        })
+    });
+    c.bench_function("serialize_json", |b| {
+        b.iter(|| {
+            json.clear();
+            serde_json::to_writer(&mut json, black_box(&secret)).unwrap();
+        })
+    });
+}
+
+criterion_group!(benches, format);
+criterion_main!(benches);
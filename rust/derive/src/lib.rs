@@ -0,0 +1,159 @@
+//! `#[derive(Redact)]` for `sensitive-string`.
+//!
+//! Use it through the `derive` feature of `sensitive-string`, which
+//! re-exports the macro as `sensitive_string::Redact`. It generates `Debug`
+//! and `Display` impls that print like `#[derive(Debug)]`, except that
+//! fields marked `#[sensitive]` are shown in redacted form:
+//!
+//! - `#[sensitive]` prints the field's redacted form, for types implementing
+//!   `sensitive_string::RedactField` (strings, byte vectors, the crate's
+//!   wrappers, and `Option`s of those).
+//! - `#[sensitive(placeholder)]` prints `[REDACTED]`, for any type.
+//!
+//! ```ignore
+//! use sensitive_string::Redact;
+//!
+//! #[derive(Redact)]
+//! struct DbConfig {
+//!     host: String,
+//!     port: u16,
+//!     #[sensitive]
+//!     password: String,
+//!     #[sensitive(placeholder)]
+//!     pin: u32,
+//! }
+//! // DbConfig { host: "db", port: 5432, password: sha256:..., pin: [REDACTED] }
+//! ```
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, GenericParam, Ident};
+
+/// Derives `Debug` and `Display` with `#[sensitive]` fields redacted.
+#[proc_macro_derive(Redact, attributes(sensitive))]
+pub fn derive_redact(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let arms = match &input.data {
+        Data::Struct(data) => vec![arm(quote!(Self), name, &data.fields)?],
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| {
+                let ident = &variant.ident;
+                arm(quote!(Self::#ident), ident, &variant.fields)
+            })
+            .collect::<Result<_, _>>()?,
+        Data::Union(_) => {
+            return Err(Error::new(
+                Span::call_site(),
+                "#[derive(Redact)] does not support unions",
+            ))
+        }
+    };
+
+    for param in &mut input.generics.params {
+        if let GenericParam::Type(param) = param {
+            param.bounds.push(parse_quote!(::std::fmt::Debug));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::std::fmt::Debug for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+
+        impl #impl_generics ::std::fmt::Display for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::fmt::Debug::fmt(self, f)
+            }
+        }
+    })
+}
+
+/// How a field is printed.
+enum Mode {
+    Plain,
+    Digest,
+    Placeholder,
+}
+
+fn mode(field: &syn::Field) -> Result<Mode, Error> {
+    let mut mode = Mode::Plain;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("sensitive"))
+    {
+        mode = match &attr.meta {
+            syn::Meta::Path(_) => Mode::Digest,
+            _ => {
+                let arg: Ident = attr.parse_args()?;
+                if arg != "placeholder" {
+                    return Err(Error::new(
+                        arg.span(),
+                        "expected #[sensitive] or #[sensitive(placeholder)]",
+                    ));
+                }
+                Mode::Placeholder
+            }
+        };
+    }
+    Ok(mode)
+}
+
+/// Builds one match arm that destructures `path` and formats its fields.
+fn arm(path: TokenStream2, name: &Ident, fields: &Fields) -> Result<TokenStream2, Error> {
+    let label = name.to_string();
+    let mut bindings = Vec::new();
+    let mut calls = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let binding = format_ident!("__field{}", index);
+        let value = match mode(field)? {
+            Mode::Plain => quote!(#binding),
+            Mode::Digest => quote_spanned_redacted(field.ty.span(), &binding),
+            Mode::Placeholder => quote!(&::sensitive_string::__private::Placeholder),
+        };
+        match &field.ident {
+            Some(ident) => {
+                let key = ident.to_string();
+                bindings.push(quote!(#ident: #binding));
+                calls.push(quote!(.field(#key, #value)));
+            }
+            None => {
+                bindings.push(quote!(#binding));
+                calls.push(quote!(.field(#value)));
+            }
+        }
+    }
+
+    Ok(match fields {
+        Fields::Named(_) => quote! {
+            #path { #(#bindings),* } => f.debug_struct(#label) #(#calls)* .finish(),
+        },
+        Fields::Unnamed(_) => quote! {
+            #path ( #(#bindings),* ) => f.debug_tuple(#label) #(#calls)* .finish(),
+        },
+        Fields::Unit => quote! {
+            #path => f.write_str(#label),
+        },
+    })
+}
+
+/// Wraps a field so its type's `RedactField` impl is used, pointing errors at the field type.
+fn quote_spanned_redacted(span: Span, binding: &Ident) -> TokenStream2 {
+    quote_spanned!(span=> &::sensitive_string::__private::Redacted(#binding))
+}
@@ -20,8 +20,12 @@ fn main() {
 
     // Intentional access when you need the plaintext
     println!("\n=== Intentional Access ===");
-    println!("Plaintext (via get_value): {}", password.get_value());
-    println!("Plaintext (via value): {}", password.value());
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    {
+        println!("Plaintext (via get_value): {}", password.get_value());
+        println!("Plaintext (via value): {}", password.value());
+    }
+    password.expose(|plaintext| println!("Plaintext (via expose): {}", plaintext));
 
     // Utility methods
     println!("\n=== Utility Methods ===");
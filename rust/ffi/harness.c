@@ -0,0 +1,60 @@
+/*
+ * Exercises the C interface from C. From the rust/ directory:
+ *
+ *     cargo rustc --release --features ffi --crate-type staticlib
+ *     cc -Wall -Wextra -Iinclude ffi/harness.c target/release/libsensitive_string.a \
+ *         -lpthread -ldl -lm -o target/ffi-harness
+ *     target/ffi-harness
+ */
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "sensitive_string.h"
+
+/* SHA-256 of "foo", as in the other implementations' tests. */
+static const char FOO_DIGEST[] =
+    "sha256:2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae";
+
+static void test_display(void) {
+    SensitiveString *secret = sensitive_string_new("foo", 3);
+    assert(secret != NULL);
+
+    size_t needed = sensitive_string_display(secret, NULL, 0);
+    assert(needed == strlen(FOO_DIGEST));
+
+    char small[8] = "unused";
+    assert(sensitive_string_display(secret, small, sizeof small) == needed);
+    assert(strcmp(small, "unused") == 0);
+
+    char shown[128];
+    assert(sensitive_string_display(secret, shown, sizeof shown) == needed);
+    assert(strcmp(shown, FOO_DIGEST) == 0);
+
+    sensitive_string_free(secret);
+}
+
+static void test_get_value(void) {
+    SensitiveString *secret = sensitive_string_new("hun\0ter2", 8);
+    char plaintext[9];
+    assert(sensitive_string_get_value(secret, plaintext, sizeof plaintext - 1) == 8);
+    assert(sensitive_string_get_value(secret, plaintext, sizeof plaintext) == 8);
+    assert(memcmp(plaintext, "hun\0ter2", 9) == 0);
+    memset(plaintext, 0, sizeof plaintext);
+    sensitive_string_free(secret);
+}
+
+static void test_invalid_input(void) {
+    assert(sensitive_string_new(NULL, 0) == NULL);
+    assert(sensitive_string_new("\xff", 1) == NULL);
+    assert(sensitive_string_display(NULL, NULL, 0) == 0);
+    sensitive_string_free(NULL);
+}
+
+int main(void) {
+    test_display();
+    test_get_value();
+    test_invalid_input();
+    puts("ffi harness: all tests passed");
+    return 0;
+}
@@ -0,0 +1,40 @@
+/*
+ * C interface to the sensitive-string crate, built with the `ffi` feature:
+ *
+ *     cargo rustc --release --features ffi --crate-type staticlib
+ *
+ * See src/ffi.rs for ownership rules. In short: handles come from
+ * sensitive_string_new and are released once with sensitive_string_free,
+ * which zeroes the plaintext; the output functions copy into caller buffers,
+ * return the full length without the NUL (like snprintf), and write only if
+ * the output and its NUL fit.
+ */
+#ifndef SENSITIVE_STRING_H
+#define SENSITIVE_STRING_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct SensitiveString SensitiveString;
+
+/* Wraps a copy of `len` UTF-8 bytes. Returns NULL for NULL or invalid UTF-8. */
+SensitiveString *sensitive_string_new(const char *value, size_t len);
+
+/* Writes the redacted form, e.g. "sha256:...", into `buf`. */
+size_t sensitive_string_display(const SensitiveString *secret, char *buf, size_t buf_len);
+
+/* Copies the plaintext into `buf`. The caller must wipe `buf` after use.
+ * Not available when the crate is built with `no-plaintext-getters`. */
+size_t sensitive_string_get_value(const SensitiveString *secret, char *buf, size_t buf_len);
+
+/* Zeroes the plaintext and frees the handle. NULL is ignored. */
+void sensitive_string_free(SensitiveString *secret);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SENSITIVE_STRING_H */
@@ -0,0 +1,511 @@
+//! Enforcing who may read a secret.
+//!
+//! The `audit` feature reports plaintext reads after the fact. For secrets
+//! that must only ever be read in one place (a signing root key, a KMS
+//! master credential), the `access-policy` feature lets the process refuse
+//! the read instead. Every policy added with [`add_policy`] sees each read as
+//! an [`AccessRequest`] (label, accessor, caller location, time) and returns
+//! an [`AccessDecision`]; if any denies, the read fails.
+//!
+//! # Example
+//!
+//! ```should_panic
+//! use sensitive_string::access::{add_policy, AccessRule};
+//! use sensitive_string::SensitiveString;
+//!
+//! // Only code in `crate::signer` may read the root key, and only 08:00-18:00 UTC.
+//! add_policy(
+//!     AccessRule::label("signing-root-key")
+//!         .allow_module("crate::signer")
+//!         .allow_hours_utc(8, 18),
+//! );
+//!
+//! let root_key = SensitiveString::from("k").with_label("signing-root-key");
+//! root_key.expose(|_| ()); // panics: this is not crate::signer
+//! ```
+//!
+//! [`allow_access!`](crate::allow_access) declares a module allowlist for a
+//! label next to the code it protects; declared allowlists are collected at
+//! link time and checked before any policy.
+//!
+//! Accessors that hand out plaintext directly (`get_value()`, `expose()`,
+//! `exposed()`, ...) panic on denial, since they have no error to return, as
+//! do integrations that pass it on (`sql_quoted()`, `seal_for()`, LDAP binds,
+//! ...). Reads of secrets held in other types, such as `SensitiveBytes`, are
+//! checked as unlabeled reads.
+//! [`SensitiveString::try_expose`] returns an [`AccessDenied`] instead.
+//!
+//! Module paths are derived from the caller's source file relative to its
+//! crate's `src` directory (`src/signer/keys.rs` is `crate::signer::keys`),
+//! because `#[track_caller]` reports files, not modules. Files outside a
+//! `src` directory have no module path and fail module checks.
+
+use crate::SensitiveString;
+use std::fmt;
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static POLICIES: RwLock<Vec<Arc<dyn AccessPolicy>>> = RwLock::new(Vec::new());
+
+/// Set once the first policy is added, so reads skip the lock until then.
+static ANY_POLICIES: AtomicBool = AtomicBool::new(false);
+
+/// A read of a secret's plaintext, as seen by an [`AccessPolicy`].
+#[derive(Debug, Clone, Copy)]
+pub struct AccessRequest<'a> {
+    /// The secret's label, from [`SensitiveString::with_label`].
+    pub label: Option<&'a str>,
+    /// The accessor that was called, e.g. `"expose"`.
+    pub method: &'static str,
+    /// Where the accessor was called from.
+    pub location: &'static Location<'static>,
+    /// When the accessor was called.
+    pub timestamp: SystemTime,
+}
+
+impl AccessRequest<'_> {
+    /// Returns the caller's module path, e.g. `crate::signer::keys`, derived
+    /// from its source file.
+    pub fn module_path(&self) -> Option<String> {
+        module_path_of(self.location.file())
+    }
+}
+
+/// Turns `.../src/signer/keys.rs` into `crate::signer::keys`.
+fn module_path_of(file: &str) -> Option<String> {
+    let file = file.replace('\\', "/");
+    let relative = match file.rfind("/src/") {
+        Some(index) => &file[index + "/src/".len()..],
+        None => file.strip_prefix("src/")?,
+    };
+    let relative = relative.strip_suffix(".rs")?;
+    let mut path = String::from("crate");
+    let segments: Vec<&str> = relative.split('/').collect();
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i + 1 == segments.len();
+        if last && (*segment == "mod" || (i == 0 && (*segment == "lib" || *segment == "main"))) {
+            break;
+        }
+        path.push_str("::");
+        path.push_str(segment);
+    }
+    Some(path)
+}
+
+/// Turns `signer` or `::crate::signer` into `crate::signer`.
+fn normalize_module(module: &str) -> String {
+    let module = module.trim_start_matches("::");
+    match module.strip_prefix("crate") {
+        Some(rest) if rest.is_empty() || rest.starts_with("::") => module.to_string(),
+        _ => format!("crate::{}", module),
+    }
+}
+
+/// Returns true if `path` is `module` or one of its submodules.
+fn within(path: &str, module: &str) -> bool {
+    path == module || (path.starts_with(module) && path[module.len()..].starts_with("::"))
+}
+
+/// A policy's verdict on an [`AccessRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessDecision {
+    /// Let the read proceed (as far as this policy is concerned).
+    Allow,
+    /// Refuse the read, with a reason for the error or panic message.
+    Deny(String),
+}
+
+/// Decides whether a read may proceed.
+///
+/// Called synchronously on the reading thread for every read of every
+/// secret, so implementations should return quickly for labels they do not
+/// care about. Closures taking an [`AccessRequest`] are policies.
+pub trait AccessPolicy: Send + Sync {
+    /// Returns whether `request` may proceed.
+    fn check(&self, request: &AccessRequest<'_>) -> AccessDecision;
+}
+
+impl<F> AccessPolicy for F
+where
+    F: Fn(&AccessRequest<'_>) -> AccessDecision + Send + Sync,
+{
+    fn check(&self, request: &AccessRequest<'_>) -> AccessDecision {
+        self(request)
+    }
+}
+
+/// Adds a policy consulted on every read. Policies cannot be removed.
+pub fn add_policy(policy: impl AccessPolicy + 'static) {
+    POLICIES
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Arc::new(policy));
+    ANY_POLICIES.store(true, Ordering::Release);
+}
+
+/// Restricts reads of one label to some modules and/or hours of the day.
+///
+/// Secrets with other labels are not affected. With no modules listed, any
+/// module may read; with no hours listed, reads are allowed at any time.
+#[derive(Debug, Clone)]
+pub struct AccessRule {
+    label: String,
+    modules: Vec<String>,
+    hours_utc: Option<(u32, u32)>,
+}
+
+impl AccessRule {
+    /// Starts a rule for secrets labelled `label`.
+    pub fn label(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            modules: Vec::new(),
+            hours_utc: None,
+        }
+    }
+
+    /// Allows reads from `module` and its submodules, e.g. `crate::signer`.
+    pub fn allow_module(mut self, module: &str) -> Self {
+        self.modules.push(normalize_module(module));
+        self
+    }
+
+    /// Allows reads only from `start` (inclusive) to `end` (exclusive), in
+    /// whole UTC hours.
+    ///
+    /// A window that wraps midnight, like `(22, 6)`, allows 22:00 to 06:00.
+    pub fn allow_hours_utc(mut self, start: u32, end: u32) -> Self {
+        self.hours_utc = Some((start, end));
+        self
+    }
+
+    fn module_allowed(&self, request: &AccessRequest<'_>) -> bool {
+        if self.modules.is_empty() {
+            return true;
+        }
+        let Some(path) = request.module_path() else {
+            return false;
+        };
+        self.modules.iter().any(|module| within(&path, module))
+    }
+}
+
+impl AccessPolicy for AccessRule {
+    fn check(&self, request: &AccessRequest<'_>) -> AccessDecision {
+        if request.label != Some(self.label.as_str()) {
+            return AccessDecision::Allow;
+        }
+        if !self.module_allowed(request) {
+            return AccessDecision::Deny(format!(
+                "{} may only be read from {}",
+                self.label,
+                self.modules.join(", ")
+            ));
+        }
+        if let Some((start, end)) = self.hours_utc {
+            let seconds = request
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs());
+            let hour = ((seconds / 3600) % 24) as u32;
+            let allowed = if start <= end {
+                (start..end).contains(&hour)
+            } else {
+                hour >= start || hour < end
+            };
+            if !allowed {
+                return AccessDecision::Deny(format!(
+                    "{} may only be read between {:02}:00 and {:02}:00 UTC",
+                    self.label, start, end
+                ));
+            }
+        }
+        AccessDecision::Allow
+    }
+}
+
+/// Error returned when a policy refuses a read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessDenied {
+    /// The secret's label.
+    pub label: Option<String>,
+    /// The accessor that was called.
+    pub method: &'static str,
+    /// Where the accessor was called from.
+    pub location: &'static Location<'static>,
+    /// The reason given by the denying policy.
+    pub reason: String,
+}
+
+impl fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} at {} denied: {}",
+            self.method,
+            self.label.as_deref().unwrap_or("unlabeled secret"),
+            self.location,
+            self.reason
+        )
+    }
+}
+
+impl std::error::Error for AccessDenied {}
+
+/// A module allowlist declared with [`allow_access!`](crate::allow_access).
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct Allowlist {
+    pub label: &'static str,
+    pub modules: &'static [&'static str],
+    /// `module_path!()` where the macro was used, for allowlists without `modules`.
+    pub declared_in: &'static str,
+}
+
+#[doc(hidden)]
+#[linkme::distributed_slice]
+pub static ALLOWLISTS: [Allowlist];
+
+/// Turns a `module_path!()`, which starts with the crate's name, into `crate::...`.
+fn crate_relative(module_path: &str) -> String {
+    match module_path.find("::") {
+        Some(index) => format!("crate{}", &module_path[index..]),
+        None => "crate".to_string(),
+    }
+}
+
+/// Denies reads of labels with declared allowlists from modules none of them list.
+fn check_allowlists(request: &AccessRequest<'_>) -> AccessDecision {
+    let Some(label) = request.label else {
+        return AccessDecision::Allow;
+    };
+    let mut allowed = Vec::new();
+    for allowlist in ALLOWLISTS.iter().filter(|a| a.label == label) {
+        if allowlist.modules.is_empty() {
+            allowed.push(crate_relative(allowlist.declared_in));
+        } else {
+            allowed.extend(allowlist.modules.iter().map(|m| normalize_module(m)));
+        }
+    }
+    if allowed.is_empty() {
+        return AccessDecision::Allow;
+    }
+    match request.module_path() {
+        Some(path) if allowed.iter().any(|module| within(&path, module)) => AccessDecision::Allow,
+        _ => AccessDecision::Deny(format!(
+            "{} may only be read from {}",
+            label,
+            allowed.join(", ")
+        )),
+    }
+}
+
+/// Runs the declared allowlists and every policy against a read of `secret` through `method`.
+#[track_caller]
+pub(crate) fn check(secret: &SensitiveString, method: &'static str) -> Result<(), AccessDenied> {
+    check_label(secret.label(), method)
+}
+
+/// Like [`check`], for a secret that is not a `SensitiveString` and is
+/// known only by its label.
+#[track_caller]
+pub(crate) fn check_label(label: Option<&str>, method: &'static str) -> Result<(), AccessDenied> {
+    if ALLOWLISTS.is_empty() && !ANY_POLICIES.load(Ordering::Acquire) {
+        return Ok(());
+    }
+    let request = AccessRequest {
+        label,
+        method,
+        location: Location::caller(),
+        timestamp: SystemTime::now(),
+    };
+    let deny = |reason| AccessDenied {
+        label: request.label.map(str::to_string),
+        method,
+        location: request.location,
+        reason,
+    };
+    if let AccessDecision::Deny(reason) = check_allowlists(&request) {
+        return Err(deny(reason));
+    }
+    let policies = POLICIES.read().unwrap_or_else(|e| e.into_inner());
+    for policy in policies.iter() {
+        if let AccessDecision::Deny(reason) = policy.check(&request) {
+            return Err(deny(reason));
+        }
+    }
+    Ok(())
+}
+
+/// Restricts reads of a label to the listed modules (or, without `modules`,
+/// to the module the macro is used in) and their submodules.
+///
+/// The allowlist is collected at link time, so it applies from the start of
+/// `main` without any registration call. Several declarations for the same
+/// label allow the union of their modules. Requires the `access-policy`
+/// feature.
+///
+/// ```
+/// use sensitive_string::{allow_access, SensitiveString};
+///
+/// // In src/signer.rs, this is the same as `modules = ["crate::signer"]`.
+/// allow_access!(label = "signing-key", modules = ["crate::signer"]);
+/// allow_access!(label = "billing-key", modules = ["crate::billing", "crate::jobs::invoices"]);
+///
+/// let key = SensitiveString::from("k").with_label("signing-key");
+/// assert!(key.try_expose(|_| ()).is_err()); // not called from crate::signer
+/// ```
+///
+/// Callers are matched by source file (see the [module docs](crate::access)),
+/// so code in an inline `mod` counts as part of its file's module. Declare
+/// allowlists at the top of a file, or list file modules explicitly.
+#[macro_export]
+macro_rules! allow_access {
+    (label = $label:expr $(,)?) => {
+        $crate::allow_access!(@declare $label, &[]);
+    };
+    (label = $label:expr, modules = [$($module:expr),* $(,)?] $(,)?) => {
+        $crate::allow_access!(@declare $label, &[$($module),*]);
+    };
+    (@declare $label:expr, $modules:expr) => {
+        const _: () = {
+            #[$crate::__private::linkme::distributed_slice($crate::__private::ALLOWLISTS)]
+            #[linkme(crate = $crate::__private::linkme)]
+            static ALLOWLIST: $crate::__private::Allowlist = $crate::__private::Allowlist {
+                label: $label,
+                modules: $modules,
+                declared_in: ::std::module_path!(),
+            };
+        };
+    };
+}
+
+impl SensitiveString {
+    /// Like [`expose`](SensitiveString::expose), but returns an error instead
+    /// of panicking if an access policy denies the read.
+    #[track_caller]
+    pub fn try_expose<R>(&self, f: impl FnOnce(&str) -> R) -> Result<R, AccessDenied> {
+        check(self, "try_expose")?;
+        #[cfg(feature = "audit")]
+        crate::audit::record(self, "try_expose");
+        Ok(f(&self.value))
+    }
+}
+
+/// Asserts that `read` panics because an access policy denied it.
+///
+/// The denying policy only applies on the calling thread, so tests running
+/// in parallel are unaffected.
+#[cfg(test)]
+pub(crate) fn assert_denied<R>(read: impl FnOnce() -> R) {
+    use std::cell::Cell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::Once;
+
+    thread_local! {
+        static DENY: Cell<bool> = const { Cell::new(false) };
+    }
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        add_policy(|_: &AccessRequest<'_>| {
+            if DENY.with(Cell::get) {
+                AccessDecision::Deny("denied by test policy".to_string())
+            } else {
+                AccessDecision::Allow
+            }
+        })
+    });
+
+    DENY.with(|deny| deny.set(true));
+    let result = catch_unwind(AssertUnwindSafe(read));
+    DENY.with(|deny| deny.set(false));
+    let payload = result
+        .err()
+        .expect("read was not checked against access policies");
+    let message = payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .unwrap_or_default();
+    assert!(message.contains("denied by test policy"), "{}", message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_paths_from_files() {
+        assert_eq!(module_path_of("src/lib.rs").as_deref(), Some("crate"));
+        assert_eq!(
+            module_path_of("src/signer/mod.rs").as_deref(),
+            Some("crate::signer")
+        );
+        assert_eq!(
+            module_path_of("/home/ci/.cargo/registry/src/x-1.0/src/signer/keys.rs").as_deref(),
+            Some("crate::signer::keys")
+        );
+        assert_eq!(module_path_of("build.rs"), None);
+    }
+
+    #[test]
+    fn test_rule_denies_other_modules_and_labels_pass() {
+        add_policy(AccessRule::label("access-test-root").allow_module("crate::signer"));
+        add_policy(AccessRule::label("access-test-here").allow_module("access"));
+
+        let root = SensitiveString::from("k").with_label("access-test-root");
+        let error = root.try_expose(|_| ()).unwrap_err();
+        assert_eq!(error.method, "try_expose");
+        assert!(error
+            .to_string()
+            .contains("may only be read from crate::signer"));
+        assert!(
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| root.expose(|_| ()))).is_err()
+        );
+
+        let here = SensitiveString::from("k").with_label("access-test-here");
+        assert_eq!(here.try_expose(|p| p.len()), Ok(1));
+        assert_eq!(SensitiveString::from("k").try_expose(|p| p.len()), Ok(1));
+    }
+
+    crate::allow_access!(label = "access-test-here", modules = ["crate::access"]);
+    crate::allow_access!(label = "access-test-elsewhere", modules = ["crate::signer"]);
+    crate::allow_access!(label = "access-test-elsewhere", modules = ["jobs"]);
+
+    #[test]
+    fn test_declared_allowlists_restrict_to_their_modules() {
+        let here = SensitiveString::from("k").with_label("access-test-here");
+        assert_eq!(here.try_expose(|p| p.len()), Ok(1));
+
+        let elsewhere = SensitiveString::from("k").with_label("access-test-elsewhere");
+        let error = elsewhere.try_expose(|_| ()).unwrap_err();
+        assert!(error.reason.contains("crate::signer"));
+        assert!(error.reason.contains("crate::jobs"));
+
+        assert_eq!(
+            crate_relative("my_app::signer::keys"),
+            "crate::signer::keys"
+        );
+        assert_eq!(crate_relative("my_app"), "crate");
+    }
+
+    #[test]
+    fn test_hours_outside_window_are_denied() {
+        let rule = AccessRule::label("root").allow_hours_utc(9, 17);
+        let at = |hour: u64| AccessRequest {
+            label: Some("root"),
+            method: "expose",
+            location: Location::caller(),
+            timestamp: UNIX_EPOCH + std::time::Duration::from_secs(hour * 3600),
+        };
+        assert_eq!(rule.check(&at(10)), AccessDecision::Allow);
+        assert!(matches!(rule.check(&at(17)), AccessDecision::Deny(_)));
+        assert!(matches!(rule.check(&at(24 + 3)), AccessDecision::Deny(_)));
+
+        let overnight = AccessRule::label("root").allow_hours_utc(22, 6);
+        assert_eq!(overnight.check(&at(23)), AccessDecision::Allow);
+        assert_eq!(overnight.check(&at(5)), AccessDecision::Allow);
+        assert!(matches!(overnight.check(&at(12)), AccessDecision::Deny(_)));
+    }
+}
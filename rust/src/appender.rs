@@ -0,0 +1,155 @@
+//! Scrubbing writers for `tracing-subscriber` file sinks.
+//!
+//! `tracing_appender::non_blocking` and `tracing_appender::rolling` hand
+//! formatted events straight to a file. [`ScrubbingMakeWriter`] wraps any
+//! `MakeWriter` so that each formatted event passes through a
+//! [`Scrubber`] before the bytes are queued for the file.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::appender::ScrubbingMakeWriter;
+//! use sensitive_string::scrub::Scrubber;
+//! use sensitive_string::SensitiveString;
+//!
+//! let db_password = SensitiveString::from("hunter2");
+//! let scrubber = Scrubber::new().secret(&db_password);
+//!
+//! let (writer, _guard) = tracing_appender::non_blocking(std::io::sink());
+//! let subscriber = tracing_subscriber::fmt()
+//!     .with_writer(ScrubbingMakeWriter::new(writer, scrubber))
+//!     .finish();
+//! # drop(subscriber);
+//! ```
+//!
+//! The `fmt` layer formats each event into a buffer and writes it with a
+//! single call, so every `write` seen here holds whole events and a secret
+//! cannot be split across two writes.
+
+use crate::scrub::Scrubber;
+use std::borrow::Cow;
+use std::io;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// A `MakeWriter` that scrubs known secrets from every event before writing it.
+#[derive(Clone, Debug)]
+pub struct ScrubbingMakeWriter<M> {
+    inner: M,
+    scrubber: Scrubber,
+}
+
+impl<M> ScrubbingMakeWriter<M> {
+    /// Wraps `inner`, scrubbing its output with `scrubber`.
+    pub fn new(inner: M, scrubber: Scrubber) -> Self {
+        Self { inner, scrubber }
+    }
+
+    /// Returns the scrubber, for adding secrets learned after startup.
+    pub fn scrubber_mut(&mut self) -> &mut Scrubber {
+        &mut self.scrubber
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for ScrubbingMakeWriter<M> {
+    type Writer = ScrubbingEventWriter<'a, M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        ScrubbingEventWriter {
+            inner: self.inner.make_writer(),
+            scrubber: &self.scrubber,
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing_core::Metadata<'_>) -> Self::Writer {
+        ScrubbingEventWriter {
+            inner: self.inner.make_writer_for(meta),
+            scrubber: &self.scrubber,
+        }
+    }
+}
+
+/// Writer returned by [`ScrubbingMakeWriter`] for a single event.
+#[derive(Debug)]
+pub struct ScrubbingEventWriter<'a, W> {
+    inner: W,
+    scrubber: &'a Scrubber,
+}
+
+impl<W: io::Write> io::Write for ScrubbingEventWriter<'_, W> {
+    /// Scrubs `buf` and writes all of it; non-UTF-8 buffers pass through unchanged.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Ok(text) = std::str::from_utf8(buf) else {
+            return self.inner.write(buf);
+        };
+        match self.scrubber.scrub(text) {
+            Cow::Borrowed(_) => self.inner.write_all(buf)?,
+            Cow::Owned(scrubbed) => self.inner.write_all(scrubbed.as_bytes())?,
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SensitiveString;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_event_writer_scrubs() {
+        let secret = SensitiveString::from("hunter2");
+        let output = Shared::default();
+        let sink = output.clone();
+        let make_writer =
+            ScrubbingMakeWriter::new(move || sink.clone(), Scrubber::new().secret(&secret));
+        make_writer
+            .make_writer()
+            .write_all(b"password=hunter2\n")
+            .unwrap();
+
+        let written = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(written, format!("password={}\n", secret));
+    }
+
+    #[test]
+    fn test_non_blocking_file_sink() {
+        let secret = SensitiveString::from("tok-abc");
+        let output = Shared::default();
+        let (writer, guard) = tracing_appender::non_blocking(output.clone());
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(ScrubbingMakeWriter::new(
+                writer,
+                Scrubber::new().secret(&secret),
+            ))
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("upstream sent token tok-abc");
+        });
+        drop(guard);
+
+        let logged = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(logged.contains(&secret.to_string()));
+        assert!(!logged.contains("tok-abc"));
+    }
+}
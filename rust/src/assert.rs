@@ -0,0 +1,296 @@
+//! Assertions that never print plaintext.
+//!
+//! `assert_eq!` on two secrets prints their `Debug` forms, which are
+//! digests, so when a test fails it is tempting to compare `get_value()`
+//! instead and leak the plaintext into CI logs. [`assert_secret_eq!`] and
+//! [`assert_secret_matches!`] print each side's fingerprint and length on
+//! failure, which is usually enough to see what went wrong: an empty value,
+//! a stray newline, or the wrong secret altogether.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{assert_secret_eq, assert_secret_matches, SensitiveString};
+//!
+//! let token = SensitiveString::from("sk-live-123");
+//! assert_secret_eq!(token, "sk-live-123");
+//! assert_secret_matches!(token, |plaintext| plaintext.starts_with("sk-"));
+//! ```
+//!
+//! A failing `assert_secret_eq!(token, "sk-live-124")` panics with:
+//!
+//! ```text
+//! assertion `left == right` failed: secrets differ
+//!   left: sha256:8a3f... (len 11)
+//!  right: sha256:51c0... (len 11)
+//! ```
+//!
+//! Fingerprints are full digests, whatever the redaction policy, so they
+//! stay comparable across runs; with the `test-util` feature's deterministic
+//! digests they show each secret's label instead.
+
+use crate::SensitiveString;
+use std::fmt;
+
+/// A value that can be compared by [`assert_secret_eq!`]: a secret or a plain string.
+pub trait SecretOperand {
+    #[doc(hidden)]
+    fn fingerprint(&self) -> String;
+
+    #[doc(hidden)]
+    fn plaintext(&self) -> &str;
+}
+
+impl SecretOperand for SensitiveString {
+    fn fingerprint(&self) -> String {
+        self.hash_string()
+    }
+
+    fn plaintext(&self) -> &str {
+        &self.value
+    }
+}
+
+impl SecretOperand for str {
+    fn fingerprint(&self) -> String {
+        crate::redact_bytes(self.as_bytes())
+    }
+
+    fn plaintext(&self) -> &str {
+        self
+    }
+}
+
+impl SecretOperand for String {
+    fn fingerprint(&self) -> String {
+        self.as_str().fingerprint()
+    }
+
+    fn plaintext(&self) -> &str {
+        self
+    }
+}
+
+impl<T: SecretOperand + ?Sized> SecretOperand for &T {
+    fn fingerprint(&self) -> String {
+        (**self).fingerprint()
+    }
+
+    fn plaintext(&self) -> &str {
+        (**self).plaintext()
+    }
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_eq<L, R>(left: &L, right: &R, message: Option<fmt::Arguments<'_>>)
+where
+    L: SecretOperand + ?Sized,
+    R: SecretOperand + ?Sized,
+{
+    if left.plaintext() == right.plaintext() {
+        return;
+    }
+    panic!(
+        "assertion `left == right` failed: {}\n  left: {} (len {})\n right: {} (len {})",
+        message.unwrap_or(format_args!("secrets differ")),
+        left.fingerprint(),
+        left.plaintext().len(),
+        right.fingerprint(),
+        right.plaintext().len(),
+    );
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_matches(
+    secret: &SensitiveString,
+    predicate: impl FnOnce(&str) -> bool,
+    expression: &str,
+    message: Option<fmt::Arguments<'_>>,
+) {
+    secret.record_access("assert_secret_matches");
+    if predicate(&secret.value) {
+        return;
+    }
+    panic!(
+        "assertion failed: {}\n  secret: {} (len {})\n  predicate: {}",
+        message.unwrap_or(format_args!("secret does not match")),
+        secret.hash_string(),
+        secret.len(),
+        expression,
+    );
+}
+
+/// Asserts that two secrets (or a secret and a string) are equal, printing
+/// only fingerprints and lengths on failure.
+///
+/// Accepts an optional `format!`-style message, like `assert_eq!`.
+#[macro_export]
+macro_rules! assert_secret_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::__private::assert_secret_eq(&$left, &$right, ::std::option::Option::None)
+    };
+    ($left:expr, $right:expr, $($message:tt)+) => {
+        $crate::__private::assert_secret_eq(
+            &$left,
+            &$right,
+            ::std::option::Option::Some(::std::format_args!($($message)+)),
+        )
+    };
+}
+
+/// Asserts that a secret's plaintext satisfies a predicate, printing only its
+/// fingerprint, length, and the predicate's source on failure.
+#[macro_export]
+macro_rules! assert_secret_matches {
+    ($secret:expr, $predicate:expr $(,)?) => {
+        $crate::__private::assert_secret_matches(
+            &$secret,
+            $predicate,
+            ::std::stringify!($predicate),
+            ::std::option::Option::None,
+        )
+    };
+    ($secret:expr, $predicate:expr, $($message:tt)+) => {
+        $crate::__private::assert_secret_matches(
+            &$secret,
+            $predicate,
+            ::std::stringify!($predicate),
+            ::std::option::Option::Some(::std::format_args!($($message)+)),
+        )
+    };
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_redacted_eq<T>(left: &T, right: &T, message: Option<fmt::Arguments<'_>>)
+where
+    T: PartialEq + fmt::Debug + ?Sized,
+{
+    if left == right {
+        return;
+    }
+    let diff = crate::diff::diff_debug(left, right, &crate::diff::DiffRules::default());
+    panic!(
+        "assertion `left == right` failed: {}\nDiff - left / + right:\n{}",
+        message.unwrap_or(format_args!("values differ")),
+        diff,
+    );
+}
+
+/// Asserts that two values are equal, printing a redacted line diff of their
+/// pretty `Debug` output on failure.
+///
+/// A drop-in for `pretty_assertions::assert_eq!` on structs holding secrets:
+/// `SensitiveString` fields show as fingerprints, and plain fields named like
+/// `password` or `token` are fingerprinted (see [`diff::DiffRules`](crate::diff::DiffRules)).
+///
+/// ```should_panic
+/// use sensitive_string::{assert_redacted_eq, SensitiveString};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Config {
+///     host: String,
+///     password: String,
+/// }
+///
+/// assert_redacted_eq!(
+///     Config { host: "db".into(), password: "old".into() },
+///     Config { host: "db".into(), password: "new".into() },
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_redacted_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::__private::assert_redacted_eq(&$left, &$right, ::std::option::Option::None)
+    };
+    ($left:expr, $right:expr, $($message:tt)+) => {
+        $crate::__private::assert_redacted_eq(
+            &$left,
+            &$right,
+            ::std::option::Option::Some(::std::format_args!($($message)+)),
+        )
+    };
+}
+
+/// Like [`assert_secret_eq!`], but only checked in debug builds.
+#[macro_export]
+macro_rules! debug_assert_secret_eq {
+    ($($arg:tt)*) => {
+        if ::std::cfg!(debug_assertions) {
+            $crate::assert_secret_eq!($($arg)*);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn panic_message(f: impl FnOnce() + std::panic::UnwindSafe) -> String {
+        let payload = std::panic::catch_unwind(f).unwrap_err();
+        payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_failure_shows_fingerprints_not_plaintext() {
+        let message = panic_message(|| {
+            crate::assert_secret_eq!(SensitiveString::from("hunter2"), "hunter3", "token {}", 1)
+        });
+        assert!(message.contains("token 1"));
+        assert!(message.contains(&SensitiveString::from("hunter2").hash_string()));
+        assert!(message.contains("(len 7)"));
+        assert!(!message.contains("hunter"));
+
+        crate::assert_secret_eq!(SensitiveString::from("a"), SensitiveString::from("a"));
+        crate::debug_assert_secret_eq!("a".to_string(), SensitiveString::from("a"));
+    }
+
+    #[test]
+    fn test_redacted_eq_shows_field_diff() {
+        #[derive(Debug, PartialEq)]
+        struct Config {
+            host: &'static str,
+            password: &'static str,
+            key: SensitiveString,
+        }
+
+        let message = panic_message(|| {
+            crate::assert_redacted_eq!(
+                Config {
+                    host: "db",
+                    password: "old-pass",
+                    key: "k1".into()
+                },
+                Config {
+                    host: "db",
+                    password: "new-pass",
+                    key: "k22".into()
+                },
+            )
+        });
+        assert!(message.contains("Diff - left / + right:"));
+        assert!(message.contains(&format!(
+            "-    key: SensitiveString({}),",
+            SensitiveString::from("k1")
+        )));
+        assert!(message.contains(" host: \"db\","));
+        assert!(!message.contains("old-pass") && !message.contains("new-pass"));
+    }
+
+    #[test]
+    fn test_matches_shows_predicate() {
+        let secret = SensitiveString::from("sk-123");
+        crate::assert_secret_matches!(secret, |s| s.starts_with("sk-"));
+
+        let message = panic_message(|| {
+            crate::assert_secret_matches!(SensitiveString::from("pk-123"), |s| s.len() > 10)
+        });
+        assert!(message.contains("predicate: |s| s.len() > 10"));
+        assert!(!message.contains("pk-123"));
+    }
+}
@@ -0,0 +1,215 @@
+//! Auditing plaintext access.
+//!
+//! Knowing where and how often secrets are unwrapped in production tells you
+//! which code paths hold plaintext and whether a secret is still used at all.
+//! With the `audit` feature, every `get_value()`, `value()`, `expose()`, and
+//! `exposed()` call on a [`SensitiveString`] reports an [`AccessEvent`] to an
+//! [`AccessObserver`]: the one attached with [`SensitiveString::with_observer`],
+//! else the one installed with [`set_global_observer`].
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::audit::{set_global_observer, AccessEvent, AccessObserver};
+//! use sensitive_string::SensitiveString;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! static READS: AtomicUsize = AtomicUsize::new(0);
+//!
+//! struct CountReads;
+//!
+//! impl AccessObserver for CountReads {
+//!     fn on_access(&self, event: &AccessEvent<'_>) {
+//!         READS.fetch_add(1, Ordering::Relaxed);
+//!         eprintln!("{} read at {}", event.label.unwrap_or("?"), event.location);
+//!     }
+//! }
+//!
+//! set_global_observer(CountReads).unwrap();
+//!
+//! let password = SensitiveString::from("hunter2").with_label("db-password");
+//! password.expose(|plaintext| assert_eq!(plaintext.len(), 7));
+//! assert_eq!(READS.load(Ordering::Relaxed), 1);
+//! ```
+//!
+//! Caller locations come from `#[track_caller]`, so they point at the code
+//! that unwrapped the secret, not at this crate.
+//!
+//! [`AuditFilter`] samples, filters by label, and rate-limits events before
+//! they reach an observer, for secrets read on every request.
+//!
+//! [`sinks`] has ready-made observers that write JSON lines to a file, emit
+//! `tracing` events, or post batches to an HTTP collector.
+
+mod filter;
+#[cfg(any(
+    feature = "audit-file",
+    feature = "audit-http",
+    feature = "audit-tracing"
+))]
+pub mod sinks;
+
+pub use filter::AuditFilter;
+
+use crate::SensitiveString;
+use std::fmt;
+use std::panic::Location;
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
+
+static GLOBAL: OnceLock<Box<dyn AccessObserver>> = OnceLock::new();
+
+/// One read of a secret's plaintext.
+#[derive(Debug, Clone, Copy)]
+pub struct AccessEvent<'a> {
+    /// The secret's label, from [`SensitiveString::with_label`].
+    pub label: Option<&'a str>,
+    /// The accessor that was called, e.g. `"expose"`.
+    pub method: &'static str,
+    /// Where the accessor was called from.
+    pub location: &'static Location<'static>,
+    /// When the accessor was called.
+    pub timestamp: SystemTime,
+}
+
+/// Receives an event for every plaintext read.
+///
+/// Called synchronously on the reading thread, so implementations should be
+/// cheap (bump a counter, push to a channel) and must not read the secret again.
+pub trait AccessObserver: Send + Sync {
+    /// Called once per access.
+    fn on_access(&self, event: &AccessEvent<'_>);
+}
+
+/// Lets a shared observer (e.g. a [`HealthRegistry`](crate::health::HealthRegistry)
+/// that is also queried elsewhere) be installed globally.
+impl<T: AccessObserver + ?Sized> AccessObserver for Arc<T> {
+    fn on_access(&self, event: &AccessEvent<'_>) {
+        (**self).on_access(event);
+    }
+}
+
+/// Error returned by [`set_global_observer`] when a global observer is already installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobalObserverAlreadySet;
+
+impl fmt::Display for GlobalObserverAlreadySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a global access observer is already installed")
+    }
+}
+
+impl std::error::Error for GlobalObserverAlreadySet {}
+
+/// Installs the observer used by every secret without a per-instance observer.
+///
+/// Can only be called once per process.
+pub fn set_global_observer(
+    observer: impl AccessObserver + 'static,
+) -> Result<(), GlobalObserverAlreadySet> {
+    GLOBAL
+        .set(Box::new(observer))
+        .map_err(|_| GlobalObserverAlreadySet)
+}
+
+/// Reports an access of `secret` through `method` to its observer.
+#[track_caller]
+pub(crate) fn record(secret: &SensitiveString, method: &'static str) {
+    record_label(secret.observer.as_deref(), secret.label(), method);
+}
+
+/// Reports an access through `method` of a secret known only by its label,
+/// to `observer` or else the global observer.
+#[track_caller]
+pub(crate) fn record_label(
+    observer: Option<&dyn AccessObserver>,
+    label: Option<&str>,
+    method: &'static str,
+) {
+    let observer = match observer {
+        Some(observer) => observer,
+        None => match GLOBAL.get() {
+            Some(observer) => observer.as_ref(),
+            None => return,
+        },
+    };
+    observer.on_access(&AccessEvent {
+        label,
+        method,
+        location: Location::caller(),
+        timestamp: SystemTime::now(),
+    });
+}
+
+impl SensitiveString {
+    /// Attaches an observer to this secret, overriding the global one.
+    ///
+    /// Clones share the observer.
+    pub fn with_observer(mut self, observer: Arc<dyn AccessObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<(Option<String>, &'static str, u32)>>);
+
+    impl AccessObserver for Recorder {
+        fn on_access(&self, event: &AccessEvent<'_>) {
+            self.0.lock().unwrap().push((
+                event.label.map(str::to_string),
+                event.method,
+                event.location.line(),
+            ));
+        }
+    }
+
+    #[test]
+    fn test_records_label_method_and_caller() {
+        let recorder = Arc::new(Recorder::default());
+        let secret = SensitiveString::from("hunter2")
+            .with_label("db")
+            .with_observer(recorder.clone());
+
+        let line = line!() + 1;
+        secret.expose(|_| ());
+        let _guard = secret.exposed();
+
+        let events = recorder.0.lock().unwrap();
+        assert_eq!(
+            *events,
+            vec![
+                (Some("db".to_string()), "expose", line),
+                (Some("db".to_string()), "exposed", line + 1)
+            ]
+        );
+    }
+
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[test]
+    fn test_extract_value_is_recorded() {
+        let recorder = Arc::new(Recorder::default());
+        let secret = SensitiveString::from("hunter2").with_observer(recorder.clone());
+
+        let line = line!() + 1;
+        assert_eq!(SensitiveString::extract_value(&secret), "hunter2");
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![(None, "extract_value", line)]
+        );
+    }
+
+    #[test]
+    fn test_formatting_is_not_an_access() {
+        let recorder = Arc::new(Recorder::default());
+        let secret = SensitiveString::from("hunter2").with_observer(recorder.clone());
+        let _ = format!("{} {:?} {}", secret, secret, secret.len());
+        assert!(recorder.0.lock().unwrap().is_empty());
+    }
+}
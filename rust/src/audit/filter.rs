@@ -0,0 +1,260 @@
+//! Sampling, label filtering, and rate limiting in front of an observer.
+
+use super::{AccessEvent, AccessObserver};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Settings for one label, or the defaults. `None` falls back to the defaults.
+#[derive(Debug, Clone, Copy, Default)]
+struct Rule {
+    sample: Option<u64>,
+    per_second: Option<u32>,
+}
+
+/// Per-label counters.
+#[derive(Debug)]
+struct Counters {
+    seen: u64,
+    window_start: Instant,
+    in_window: u32,
+}
+
+impl Counters {
+    fn new() -> Self {
+        Self {
+            seen: 0,
+            window_start: Instant::now(),
+            in_window: 0,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    unlabeled: Option<Counters>,
+    labeled: HashMap<String, Counters>,
+}
+
+/// Forwards only some access events to another observer.
+///
+/// A hot request-signing key can be read thousands of times a second;
+/// auditing every read floods the sink without telling you anything new.
+/// `AuditFilter` keeps 1 in N events, drops or keeps events by label, and
+/// caps events per second, each per label, before passing the rest on.
+///
+/// ```
+/// use sensitive_string::audit::{AccessEvent, AccessObserver, AuditFilter};
+/// use std::sync::Arc;
+/// # use std::sync::atomic::{AtomicUsize, Ordering};
+/// # struct Count(AtomicUsize);
+/// # impl AccessObserver for Count {
+/// #     fn on_access(&self, _: &AccessEvent<'_>) { self.0.fetch_add(1, Ordering::Relaxed); }
+/// # }
+///
+/// let sink = Arc::new(Count(AtomicUsize::new(0)));
+/// let filter = AuditFilter::new(sink.clone())
+///     .label_sample("request-signing-key", 100)
+///     .rate_limit(50)
+///     .ignore_label("health-check-token");
+/// # let _ = filter;
+/// ```
+///
+/// Sampling is deterministic: the first event for a label is kept, then
+/// every Nth after it. Rate limits use one-second windows.
+#[derive(Debug)]
+pub struct AuditFilter<O> {
+    inner: O,
+    defaults: Rule,
+    labels: HashMap<String, Rule>,
+    ignored: HashSet<String>,
+    only: Option<HashSet<String>>,
+    state: Mutex<State>,
+    suppressed: AtomicU64,
+}
+
+impl<O: AccessObserver> AuditFilter<O> {
+    /// Wraps `inner`, initially forwarding every event.
+    pub fn new(inner: O) -> Self {
+        Self {
+            inner,
+            defaults: Rule::default(),
+            labels: HashMap::new(),
+            ignored: HashSet::new(),
+            only: None,
+            state: Mutex::new(State::default()),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// Keeps 1 in `n` events for each label. `0` drops everything.
+    pub fn sample(mut self, n: u64) -> Self {
+        self.defaults.sample = Some(n);
+        self
+    }
+
+    /// Keeps at most `per_second` events per second for each label.
+    pub fn rate_limit(mut self, per_second: u32) -> Self {
+        self.defaults.per_second = Some(per_second);
+        self
+    }
+
+    /// Keeps 1 in `n` events for `label`, overriding [`sample`](Self::sample).
+    pub fn label_sample(mut self, label: &str, n: u64) -> Self {
+        self.labels.entry(label.to_string()).or_default().sample = Some(n);
+        self
+    }
+
+    /// Keeps at most `per_second` events per second for `label`, overriding
+    /// [`rate_limit`](Self::rate_limit).
+    pub fn label_rate_limit(mut self, label: &str, per_second: u32) -> Self {
+        self.labels.entry(label.to_string()).or_default().per_second = Some(per_second);
+        self
+    }
+
+    /// Drops every event for `label`.
+    pub fn ignore_label(mut self, label: &str) -> Self {
+        self.ignored.insert(label.to_string());
+        self
+    }
+
+    /// Drops events for any label not added with `only_label`, and unlabeled events.
+    pub fn only_label(mut self, label: &str) -> Self {
+        self.only
+            .get_or_insert_with(HashSet::new)
+            .insert(label.to_string());
+        self
+    }
+
+    /// Returns how many events were not forwarded.
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+
+    fn keep(&self, label: Option<&str>) -> bool {
+        if let Some(label) = label {
+            if self.ignored.contains(label) {
+                return false;
+            }
+        }
+        if let Some(only) = &self.only {
+            if !label.is_some_and(|label| only.contains(label)) {
+                return false;
+            }
+        }
+
+        let rule = label.and_then(|label| self.labels.get(label));
+        let sample = rule.and_then(|r| r.sample).or(self.defaults.sample);
+        let per_second = rule.and_then(|r| r.per_second).or(self.defaults.per_second);
+        if sample.is_none() && per_second.is_none() {
+            return true;
+        }
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let counters = match label {
+            Some(label) => match state.labeled.get_mut(label) {
+                Some(counters) => counters,
+                None => state
+                    .labeled
+                    .entry(label.to_string())
+                    .or_insert_with(Counters::new),
+            },
+            None => state.unlabeled.get_or_insert_with(Counters::new),
+        };
+
+        let seen = counters.seen;
+        counters.seen += 1;
+        if let Some(n) = sample {
+            if n == 0 || seen % n != 0 {
+                return false;
+            }
+        }
+        if let Some(limit) = per_second {
+            let now = Instant::now();
+            if now.duration_since(counters.window_start) >= Duration::from_secs(1) {
+                counters.window_start = now;
+                counters.in_window = 0;
+            }
+            if counters.in_window >= limit {
+                return false;
+            }
+            counters.in_window += 1;
+        }
+        true
+    }
+}
+
+impl<O: AccessObserver> AccessObserver for AuditFilter<O> {
+    fn on_access(&self, event: &AccessEvent<'_>) {
+        if self.keep(event.label) {
+            self.inner.on_access(event);
+        } else {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SensitiveString;
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct Count(AtomicU64);
+
+    impl AccessObserver for Count {
+        fn on_access(&self, _: &AccessEvent<'_>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn read(filter: &Arc<AuditFilter<Arc<Count>>>, label: Option<&str>, times: usize) {
+        let mut secret = SensitiveString::from("hunter2");
+        if let Some(label) = label {
+            secret = secret.with_label(label);
+        }
+        let secret = secret.with_observer(filter.clone());
+        for _ in 0..times {
+            secret.expose(|_| ());
+        }
+    }
+
+    #[test]
+    fn test_samples_per_label_with_overrides() {
+        let count = Arc::new(Count::default());
+        let filter = Arc::new(
+            AuditFilter::new(count.clone())
+                .sample(10)
+                .label_sample("hot", 100),
+        );
+        read(&filter, Some("hot"), 200);
+        read(&filter, Some("warm"), 20);
+        read(&filter, None, 5);
+        assert_eq!(count.0.load(Ordering::Relaxed), 2 + 2 + 1);
+        assert_eq!(filter.suppressed(), 225 - 5);
+    }
+
+    #[test]
+    fn test_rate_limit_and_label_lists() {
+        let count = Arc::new(Count::default());
+        let filter = Arc::new(
+            AuditFilter::new(count.clone())
+                .rate_limit(3)
+                .label_rate_limit("db", 5)
+                .ignore_label("noisy"),
+        );
+        read(&filter, Some("api"), 10);
+        read(&filter, Some("db"), 10);
+        read(&filter, Some("noisy"), 10);
+        assert_eq!(count.0.load(Ordering::Relaxed), 3 + 5);
+
+        let count = Arc::new(Count::default());
+        let filter = Arc::new(AuditFilter::new(count.clone()).only_label("db"));
+        read(&filter, Some("db"), 2);
+        read(&filter, Some("api"), 2);
+        read(&filter, None, 2);
+        assert_eq!(count.0.load(Ordering::Relaxed), 2);
+    }
+}
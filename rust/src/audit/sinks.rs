@@ -0,0 +1,442 @@
+//! Ready-made [`AccessObserver`]s.
+//!
+//! Each sink is behind its own feature, so adopting access auditing does not
+//! mean writing an observer first:
+//!
+//! - [`JsonLinesObserver`] (`audit-file`): appends one JSON object per access
+//!   to a file or any writer.
+//! - [`TracingObserver`] (`audit-tracing`): emits a `tracing` event per access
+//!   under the `sensitive_string::audit` target.
+//! - [`HttpObserver`] (`audit-http`): posts batches of events as JSON arrays
+//!   to a collector from a background thread.
+//!
+//! File and HTTP sinks write events as:
+//!
+//! ```json
+//! {"timestamp_ms":1718000000000,"label":"db-password","method":"expose","file":"src/db.rs","line":42,"column":9}
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! # #[cfg(feature = "audit-file")]
+//! # {
+//! use sensitive_string::audit::set_global_observer;
+//! use sensitive_string::audit::sinks::JsonLinesObserver;
+//!
+//! let observer = JsonLinesObserver::create("/var/log/app/secret-access.jsonl").unwrap();
+//! set_global_observer(observer).unwrap();
+//! # }
+//! ```
+//!
+//! Observers run on the thread that read the secret. The file sink writes
+//! and flushes under a lock; the HTTP sink only queues, and drops events
+//! (counting them in [`HttpObserver::dropped`]) when the collector falls
+//! behind rather than blocking callers.
+
+#[cfg(any(feature = "audit-file", feature = "audit-http"))]
+use super::AccessEvent;
+#[cfg(any(
+    feature = "audit-file",
+    feature = "audit-http",
+    feature = "audit-tracing"
+))]
+use super::AccessObserver;
+#[cfg(feature = "audit-http")]
+use crate::SensitiveString;
+#[cfg(feature = "audit-file")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "audit-file")]
+use std::io::{self, Write};
+#[cfg(feature = "audit-file")]
+use std::path::Path;
+#[cfg(feature = "audit-http")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(any(feature = "audit-file", feature = "audit-http"))]
+use std::sync::Mutex;
+#[cfg(feature = "audit-http")]
+use std::sync::{mpsc, Arc};
+#[cfg(feature = "audit-http")]
+use std::thread::JoinHandle;
+#[cfg(any(feature = "audit-file", feature = "audit-http"))]
+use std::time::UNIX_EPOCH;
+#[cfg(feature = "audit-http")]
+use std::time::{Duration, Instant};
+
+/// Converts an event to the JSON object written by the file and HTTP sinks.
+#[cfg(any(feature = "audit-file", feature = "audit-http"))]
+fn event_json(event: &AccessEvent<'_>) -> serde_json::Value {
+    let timestamp_ms = event
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64);
+    serde_json::json!({
+        "timestamp_ms": timestamp_ms,
+        "label": event.label,
+        "method": event.method,
+        "file": event.location.file(),
+        "line": event.location.line(),
+        "column": event.location.column(),
+    })
+}
+
+/// Appends each access as a line of JSON.
+///
+/// Write errors are ignored: auditing must not make reading a secret fail.
+#[cfg(feature = "audit-file")]
+pub struct JsonLinesObserver {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+#[cfg(feature = "audit-file")]
+impl JsonLinesObserver {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file: File = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::new(file))
+    }
+
+    /// Writes to any writer, e.g. stderr or a pipe to a log shipper.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+}
+
+#[cfg(feature = "audit-file")]
+impl AccessObserver for JsonLinesObserver {
+    fn on_access(&self, event: &AccessEvent<'_>) {
+        let mut line = event_json(event).to_string();
+        line.push('\n');
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writer
+            .write_all(line.as_bytes())
+            .and_then(|()| writer.flush());
+    }
+}
+
+/// Emits each access as a `tracing` event with `label`, `method`, and `location` fields.
+#[cfg(feature = "audit-tracing")]
+#[derive(Debug, Clone, Copy)]
+pub struct TracingObserver {
+    level: tracing::Level,
+}
+
+#[cfg(feature = "audit-tracing")]
+impl Default for TracingObserver {
+    /// Emits at `INFO`.
+    fn default() -> Self {
+        Self {
+            level: tracing::Level::INFO,
+        }
+    }
+}
+
+#[cfg(feature = "audit-tracing")]
+impl TracingObserver {
+    /// Creates an observer emitting at `INFO`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the level events are emitted at.
+    pub fn level(mut self, level: tracing::Level) -> Self {
+        self.level = level;
+        self
+    }
+}
+
+#[cfg(feature = "audit-tracing")]
+impl AccessObserver for TracingObserver {
+    fn on_access(&self, event: &super::AccessEvent<'_>) {
+        macro_rules! emit {
+            ($level:expr) => {
+                tracing::event!(
+                    target: "sensitive_string::audit",
+                    $level,
+                    label = event.label.unwrap_or(""),
+                    method = event.method,
+                    location = %event.location,
+                    "secret accessed"
+                )
+            };
+        }
+        match self.level {
+            tracing::Level::ERROR => emit!(tracing::Level::ERROR),
+            tracing::Level::WARN => emit!(tracing::Level::WARN),
+            tracing::Level::INFO => emit!(tracing::Level::INFO),
+            tracing::Level::DEBUG => emit!(tracing::Level::DEBUG),
+            tracing::Level::TRACE => emit!(tracing::Level::TRACE),
+        }
+    }
+}
+
+/// Messages from observers to the sender thread.
+#[cfg(feature = "audit-http")]
+enum Message {
+    Event(serde_json::Value),
+    Shutdown,
+}
+
+/// Posts events in batches to an HTTP collector.
+///
+/// Events are queued for a background thread, which posts a JSON array when
+/// a batch fills or the flush interval passes. Failed posts are not retried.
+/// Dropping the observer posts what is queued and stops the thread.
+#[cfg(feature = "audit-http")]
+pub struct HttpObserver {
+    sender: mpsc::SyncSender<Message>,
+    dropped: Arc<AtomicU64>,
+    failed: Arc<AtomicU64>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Configures an [`HttpObserver`].
+#[cfg(feature = "audit-http")]
+#[derive(Debug, Clone)]
+pub struct HttpObserverBuilder {
+    url: String,
+    batch_size: usize,
+    flush_interval: Duration,
+    queue_capacity: usize,
+    timeout: Duration,
+    bearer_token: Option<SensitiveString>,
+}
+
+#[cfg(feature = "audit-http")]
+impl HttpObserverBuilder {
+    /// Posts once this many events are queued. Defaults to 100.
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Posts queued events at least this often. Defaults to 5 seconds.
+    pub fn flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Drops events beyond this many waiting to be sent. Defaults to 10,000.
+    pub fn queue_capacity(mut self, queue_capacity: usize) -> Self {
+        self.queue_capacity = queue_capacity;
+        self
+    }
+
+    /// Gives up on a post after this long. Defaults to 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends `Authorization: Bearer <token>` with each post.
+    pub fn bearer_token(mut self, token: SensitiveString) -> Self {
+        self.bearer_token = Some(token);
+        self
+    }
+
+    /// Starts the sender thread.
+    pub fn build(self) -> HttpObserver {
+        let (sender, receiver) = mpsc::sync_channel(self.queue_capacity);
+        let failed = Arc::new(AtomicU64::new(0));
+        let worker_failed = failed.clone();
+        let worker = std::thread::Builder::new()
+            .name("audit-http".into())
+            .spawn(move || self.run(receiver, &worker_failed))
+            .expect("failed to spawn audit sender thread");
+        HttpObserver {
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+            failed,
+            worker: Mutex::new(Some(worker)),
+        }
+    }
+
+    fn run(self, receiver: mpsc::Receiver<Message>, failed: &AtomicU64) {
+        let agent = ureq::AgentBuilder::new().timeout(self.timeout).build();
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut deadline = Instant::now() + self.flush_interval;
+        loop {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            let shutdown = match receiver.recv_timeout(wait) {
+                Ok(Message::Event(event)) => {
+                    batch.push(event);
+                    false
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => false,
+                Ok(Message::Shutdown) | Err(mpsc::RecvTimeoutError::Disconnected) => true,
+            };
+            if shutdown || batch.len() >= self.batch_size || Instant::now() >= deadline {
+                if !batch.is_empty() && self.post(&agent, &batch).is_err() {
+                    failed.fetch_add(batch.len() as u64, Ordering::Relaxed);
+                }
+                batch.clear();
+                deadline = Instant::now() + self.flush_interval;
+            }
+            if shutdown {
+                return;
+            }
+        }
+    }
+
+    fn post(&self, agent: &ureq::Agent, batch: &[serde_json::Value]) -> Result<(), ()> {
+        let body = serde_json::to_vec(batch).map_err(|_| ())?;
+        let mut request = agent
+            .post(&self.url)
+            .set("Content-Type", "application/json");
+        if let Some(token) = &self.bearer_token {
+            // Read directly rather than through `expose`, which would report
+            // an access to this observer for every post.
+            request = request.set("Authorization", &format!("Bearer {}", token.value));
+        }
+        request.send_bytes(&body).map(|_| ()).map_err(|_| ())
+    }
+}
+
+#[cfg(feature = "audit-http")]
+impl HttpObserver {
+    /// Starts configuring an observer that posts to `url`.
+    pub fn builder(url: impl Into<String>) -> HttpObserverBuilder {
+        HttpObserverBuilder {
+            url: url.into(),
+            batch_size: 100,
+            flush_interval: Duration::from_secs(5),
+            queue_capacity: 10_000,
+            timeout: Duration::from_secs(10),
+            bearer_token: None,
+        }
+    }
+
+    /// Returns how many events were dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many events were lost to failed posts.
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(feature = "audit-http")]
+impl AccessObserver for HttpObserver {
+    fn on_access(&self, event: &AccessEvent<'_>) {
+        if self
+            .sender
+            .try_send(Message::Event(event_json(event)))
+            .is_err()
+        {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(feature = "audit-http")]
+impl Drop for HttpObserver {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Message::Shutdown);
+        let worker = self.worker.get_mut().unwrap_or_else(|e| e.into_inner());
+        if let Some(worker) = worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "audit-file", feature = "audit-http"))]
+    use super::*;
+    #[cfg(any(feature = "audit-file", feature = "audit-http"))]
+    use std::sync::Arc;
+
+    #[cfg(feature = "audit-file")]
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    #[cfg(feature = "audit-file")]
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "audit-file")]
+    #[test]
+    fn test_json_lines_records_one_line_per_access() {
+        let out = Shared::default();
+        let secret = crate::SensitiveString::from("hunter2")
+            .with_label("db")
+            .with_observer(Arc::new(JsonLinesObserver::new(out.clone())));
+        secret.expose(|_| ());
+        secret.expose(|_| ());
+
+        let text = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["label"], "db");
+        assert_eq!(lines[0]["method"], "expose");
+        assert_eq!(lines[0]["file"], file!());
+        assert!(!text.contains("hunter2"));
+    }
+
+    #[cfg(feature = "audit-http")]
+    #[test]
+    fn test_http_posts_batches_with_token() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/audit", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                headers.push(line.trim_end().to_ascii_lowercase());
+            }
+            let length: usize = headers
+                .iter()
+                .find_map(|h| h.strip_prefix("content-length: "))
+                .unwrap()
+                .parse()
+                .unwrap();
+            let mut body = vec![0u8; length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            (headers, body)
+        });
+
+        let observer = Arc::new(
+            HttpObserver::builder(url)
+                .batch_size(2)
+                .bearer_token(SensitiveString::from("collector-token"))
+                .build(),
+        );
+        let secret = SensitiveString::from("hunter2").with_observer(observer.clone());
+        secret.expose(|_| ());
+        secret.expose(|_| ());
+
+        let (headers, body) = server.join().unwrap();
+        assert!(headers.contains(&"authorization: bearer collector-token".to_string()));
+        let batch: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[1]["method"], "expose");
+        assert_eq!(observer.dropped(), 0);
+    }
+}
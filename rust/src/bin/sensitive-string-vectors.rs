@@ -0,0 +1,27 @@
+//! Writes the standard conformance vectors as JSON.
+//!
+//! ```text
+//! cargo run --bin sensitive-string-vectors > ../test-vectors/digests.json
+//! cargo run --bin sensitive-string-vectors -- path/to/digests.json
+//! ```
+
+use sensitive_string::conformance;
+use std::io::Write;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut json = serde_json::to_string_pretty(&conformance::standard_vectors())
+        .expect("vectors serialize to JSON");
+    json.push('\n');
+    let written = match std::env::args_os().nth(1) {
+        Some(path) => std::fs::write(path, json),
+        None => std::io::stdout().write_all(json.as_bytes()),
+    };
+    match written {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("sensitive-string-vectors: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Borrowed secrets that can live in statics.
+//!
+//! A `SensitiveString` owns a `String`, so it cannot be built in a `const`
+//! context. Test fixtures and tokens injected at build time with `env!` are
+//! already `&'static str`; [`SensitiveStr`] wraps a borrowed string with the
+//! same redaction, and its constructor is a `const fn`, so it can be stored
+//! in a `static` without allocating.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{SensitiveStr, SensitiveString};
+//!
+//! static BUILD_TOKEN: SensitiveStr<'static> = SensitiveStr::new_static(env!("CARGO_PKG_NAME"));
+//!
+//! assert_eq!(
+//!     BUILD_TOKEN.to_string(),
+//!     SensitiveString::from(env!("CARGO_PKG_NAME")).to_string()
+//! );
+//! let owned: SensitiveString = BUILD_TOKEN.into();
+//! assert_eq!(owned.len(), BUILD_TOKEN.len());
+//! ```
+//!
+//! A `SensitiveStr` is shown exactly as the equivalent `SensitiveString`
+//! with default settings. A string embedded in the binary cannot be wiped,
+//! and anyone with the binary can read it, so this suits fixtures and
+//! low-value tokens rather than long-lived credentials.
+
+use crate::{redact_plaintext, SensitiveString};
+use std::fmt;
+
+/// A borrowed secret, constructible in `const` contexts.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SensitiveStr<'a> {
+    value: &'a str,
+}
+
+impl<'a> SensitiveStr<'a> {
+    /// Wraps a borrowed value.
+    pub const fn new(value: &'a str) -> Self {
+        Self { value }
+    }
+
+    /// Explicitly retrieves the plaintext.
+    ///
+    /// Counts as a plaintext read for auditing and access policies.
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
+    pub fn get_value(&self) -> &'a str {
+        crate::record_plaintext_read(None, "get_value");
+        self.value
+    }
+
+    /// Returns the length of the value without exposing it.
+    pub const fn len(&self) -> usize {
+        self.value.len()
+    }
+
+    /// Returns true if the value is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// Copies the value into an owned `SensitiveString`.
+    pub fn to_sensitive_string(&self) -> SensitiveString {
+        SensitiveString::from(self.value)
+    }
+}
+
+impl SensitiveStr<'static> {
+    /// Wraps a `'static` value, e.g. a literal or the result of `env!`.
+    pub const fn new_static(value: &'static str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl fmt::Display for SensitiveStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&redact_plaintext(self.value))
+    }
+}
+
+impl fmt::Debug for SensitiveStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SensitiveStr({})", self)
+    }
+}
+
+impl<'a> From<&'a str> for SensitiveStr<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<SensitiveStr<'_>> for SensitiveString {
+    fn from(secret: SensitiveStr<'_>) -> Self {
+        secret.to_sensitive_string()
+    }
+}
+
+impl PartialEq<SensitiveString> for SensitiveStr<'_> {
+    fn eq(&self, other: &SensitiveString) -> bool {
+        self.value == other.value
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SensitiveStr<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: SensitiveStr<'static> = SensitiveStr::new_static("hunter2");
+
+    #[test]
+    fn test_static_matches_owned() {
+        static TOKEN: SensitiveStr<'static> = FIXTURE;
+        let owned = SensitiveString::from("hunter2");
+        assert_eq!(TOKEN.to_string(), owned.to_string());
+        assert!(!format!("{:?}", TOKEN).contains("hunter2"));
+        assert_eq!(TOKEN, owned);
+        assert_eq!(SensitiveString::from(TOKEN), owned);
+        const LEN: usize = FIXTURE.len();
+        assert_eq!(LEN, 7);
+    }
+
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[test]
+    fn test_borrowed_value_outlives_wrapper() {
+        let plaintext = String::from("hunter2");
+        let value = SensitiveStr::from(plaintext.as_str()).get_value();
+        assert_eq!(value, "hunter2");
+    }
+}
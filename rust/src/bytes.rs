@@ -0,0 +1,126 @@
+//! Binary secrets.
+//!
+//! Raw key material (AES keys, HMAC secrets, DER-encoded private keys) is
+//! usually not valid UTF-8, so it cannot live in a `SensitiveString`.
+//! [`SensitiveBytes`] wraps a `Vec<u8>` with the same behavior: `Display`,
+//! `Debug`, and `Serialize` show a hash of the bytes, never the bytes.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{SensitiveBytes, SensitiveString};
+//!
+//! let key = SensitiveBytes::from(vec![0x00, 0xff, 0x10, 0x80]);
+//! assert_eq!(key.len(), 4);
+//! assert!(SensitiveString::looks_redacted(&key.to_string()));
+//! assert_eq!(&*key.exposed(), &[0x00, 0xff, 0x10, 0x80]);
+//! ```
+
+use crate::policy::{default_policy, RedactionPolicy, PLACEHOLDER};
+use crate::redact_bytes;
+use std::fmt;
+
+/// A wrapper for binary secrets that prevents accidental exposure.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SensitiveBytes {
+    pub(crate) value: Vec<u8>,
+}
+
+impl SensitiveBytes {
+    /// Creates a new `SensitiveBytes` from the given bytes.
+    pub fn new(value: Vec<u8>) -> Self {
+        Self { value }
+    }
+
+    /// Explicitly retrieves the plaintext bytes.
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
+    pub fn get_value(&self) -> &[u8] {
+        crate::record_plaintext_read(None, "get_value");
+        &self.value
+    }
+
+    /// Returns the number of bytes without exposing them.
+    pub fn len(&self) -> usize {
+        self.value.len()
+    }
+
+    /// Returns true if there are no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// Follows the process-wide policy; bytes are never partially masked.
+    fn hash_string(&self) -> String {
+        match default_policy() {
+            RedactionPolicy::Hash => redact_bytes(&self.value),
+            RedactionPolicy::Placeholder | RedactionPolicy::MaskLast(_) => PLACEHOLDER.to_string(),
+        }
+    }
+}
+
+/// Shows the hash of the bytes, like `SensitiveString`.
+impl fmt::Display for SensitiveBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.hash_string())
+    }
+}
+
+impl fmt::Debug for SensitiveBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SensitiveBytes({})", self.hash_string())
+    }
+}
+
+impl From<Vec<u8>> for SensitiveBytes {
+    fn from(value: Vec<u8>) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&[u8]> for SensitiveBytes {
+    fn from(value: &[u8]) -> Self {
+        Self::new(value.to_vec())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SensitiveBytes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.hash_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SensitiveString;
+
+    #[test]
+    fn test_display_and_debug_are_hashed() {
+        let key = SensitiveBytes::from(&b"\x01\x02secret"[..]);
+        let shown = key.to_string();
+        assert_eq!(format!("{:?}", key), format!("SensitiveBytes({})", shown));
+        assert!(!format!("{:?}", key).contains("secret"));
+    }
+
+    #[test]
+    fn test_hash_matches_sensitive_string_for_utf8() {
+        let bytes = SensitiveBytes::from(b"hunter2".to_vec());
+        assert_eq!(
+            bytes.to_string(),
+            SensitiveString::from("hunter2").to_string()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize_is_hashed() {
+        let key = SensitiveBytes::from(vec![0xde, 0xad, 0xbe, 0xef]);
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(json, format!("\"{}\"", key));
+    }
+}
@@ -0,0 +1,539 @@
+//! Caching secret providers and serving stale values through outages.
+//!
+//! Fetching from Vault or a cloud secrets manager on every use is slow and
+//! turns a short provider outage into an application outage. A
+//! [`CachedProvider`] wraps any [`SecretProvider`], keeps each secret for a
+//! time-to-live, and, if configured with [`CachedProvider::serve_stale_for`],
+//! keeps serving the last good value for a while after a refresh fails.
+//! Refreshes are retried with exponential backoff rather than on every fetch
+//! (see [`CachedProvider::retry_backoff`]). Once the window has passed, the
+//! refresh error is returned. While a stale value is being served the secret
+//! is reported as [`Degraded`](crate::health::HealthStatus::Degraded) to the
+//! [`HealthRegistry`] given to [`CachedProvider::report_to`], and as
+//! [`Failed`](crate::health::HealthStatus::Failed) once errors are returned.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::cache::CachedProvider;
+//! use sensitive_string::health::{HealthRegistry, HealthStatus};
+//! use sensitive_string::source::{ProviderError, SecretProvider};
+//! use sensitive_string::SensitiveString;
+//! use std::sync::atomic::{AtomicBool, Ordering};
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! struct Vault {
+//!     down: AtomicBool,
+//! }
+//!
+//! impl SecretProvider for Vault {
+//!     fn fetch(&self, _key: &str) -> Result<SensitiveString, ProviderError> {
+//!         if self.down.load(Ordering::SeqCst) {
+//!             return Err(ProviderError::new("connection refused"));
+//!         }
+//!         Ok(SensitiveString::from("hunter2"))
+//!     }
+//! }
+//!
+//! let health = Arc::new(HealthRegistry::new());
+//! let vault = CachedProvider::new(Vault { down: AtomicBool::new(false) }, Duration::ZERO)
+//!     .serve_stale_for(Duration::from_secs(600))
+//!     .report_to(health.clone());
+//!
+//! vault.fetch("db").unwrap();
+//! vault.inner().down.store(true, Ordering::SeqCst);
+//!
+//! assert_eq!(vault.fetch("db").unwrap(), SensitiveString::from("hunter2"));
+//! assert_eq!(health.report().secrets[0].status, HealthStatus::Degraded);
+//! ```
+//!
+//! A `CachedProvider` is itself a [`SecretProvider`], so it can be passed to
+//! [`register_provider`](crate::source::register_provider) in place of the
+//! provider it wraps. Health entries are labeled with the provider key.
+//!
+//! # Bounded caches
+//!
+//! A gateway serving thousands of tenants cannot keep every tenant's
+//! credentials in memory at once. A [`SecretCache`] holds secrets keyed by
+//! tenant and name, evicts the least recently used ones once it holds too
+//! many entries or too many bytes, and overwrites each evicted plaintext
+//! with zeros before freeing it.
+//!
+//! ```
+//! use sensitive_string::cache::SecretCache;
+//! use sensitive_string::SensitiveString;
+//!
+//! let mut cache = SecretCache::new(2);
+//! cache.insert("acme", "db", SensitiveString::from("acme-db"));
+//! cache.insert("globex", "db", SensitiveString::from("globex-db"));
+//! cache.get("acme", "db");
+//! cache.insert("initech", "db", SensitiveString::from("initech-db"));
+//!
+//! assert!(cache.get("acme", "db").is_some());
+//! assert!(cache.get("globex", "db").is_none());
+//! ```
+//!
+//! Only the cache's own copy is zeroed: clones taken from it are not.
+
+use crate::health::HealthRegistry;
+use crate::source::{FetchFuture, ProviderError, SecretProvider};
+use crate::SensitiveString;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DEFAULT_MIN_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A [`SecretProvider`] that caches another provider's secrets.
+pub struct CachedProvider<P> {
+    inner: P,
+    ttl: Duration,
+    stale_for: Duration,
+    backoff: (Duration, Duration),
+    health: Option<Arc<HealthRegistry>>,
+    entries: Mutex<HashMap<String, Cached>>,
+}
+
+struct Cached {
+    value: SensitiveString,
+    fetched_at: Instant,
+    /// Failed refreshes since `fetched_at`.
+    failures: u32,
+    /// No refresh is attempted before this while the stale value is served.
+    retry_at: Option<Instant>,
+}
+
+impl<P: SecretProvider> CachedProvider<P> {
+    /// Wraps `inner`, refetching each secret once it is older than `ttl`.
+    ///
+    /// By default a failed refresh is returned as an error straight away.
+    pub fn new(inner: P, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            stale_for: Duration::ZERO,
+            backoff: (DEFAULT_MIN_BACKOFF, DEFAULT_MAX_BACKOFF),
+            health: None,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Keeps serving the last good value for up to `window` past its
+    /// time-to-live when refreshing fails.
+    pub fn serve_stale_for(mut self, window: Duration) -> Self {
+        self.stale_for = window;
+        self
+    }
+
+    /// Sets how long to wait before retrying a failed refresh while a stale
+    /// value is served: `min` after the first failure, doubling after each
+    /// further one up to `max`. Defaults to one second and one minute.
+    pub fn retry_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.backoff = (min, max);
+        self
+    }
+
+    /// Reports degraded, failed, and recovered secrets to `health`.
+    pub fn report_to(mut self, health: Arc<HealthRegistry>) -> Self {
+        self.health = Some(health);
+        self
+    }
+
+    /// Returns the wrapped provider.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Drops the cached value for `key`, so the next fetch goes to the provider.
+    pub fn invalidate(&self, key: &str) {
+        self.lock().remove(key);
+    }
+
+    fn fetch_at(&self, key: &str, now: Instant) -> Result<SensitiveString, ProviderError> {
+        if let Some(value) = self.cached(key, now) {
+            return Ok(value);
+        }
+        let result = self.inner.fetch(key);
+        self.settle(key, result, now)
+    }
+
+    /// Returns the cached value if it is fresh, or if it is stale and the
+    /// next refresh is still backing off.
+    fn cached(&self, key: &str, now: Instant) -> Option<SensitiveString> {
+        let entries = self.lock();
+        let cached = entries.get(key)?;
+        let age = now.saturating_duration_since(cached.fetched_at);
+        let backing_off = cached.retry_at.is_some_and(|retry_at| now < retry_at)
+            && age < self.ttl + self.stale_for;
+        (age < self.ttl || backing_off).then(|| cached.value.clone())
+    }
+
+    fn settle(
+        &self,
+        key: &str,
+        result: Result<SensitiveString, ProviderError>,
+        now: Instant,
+    ) -> Result<SensitiveString, ProviderError> {
+        let mut entries = self.lock();
+        match result {
+            Ok(value) => {
+                entries.insert(
+                    key.to_string(),
+                    Cached {
+                        value: value.clone(),
+                        fetched_at: now,
+                        failures: 0,
+                        retry_at: None,
+                    },
+                );
+                if let Some(health) = &self.health {
+                    health.record_recovered(key);
+                }
+                Ok(value)
+            }
+            Err(error) => match entries.get_mut(key) {
+                Some(cached)
+                    if now.saturating_duration_since(cached.fetched_at)
+                        < self.ttl + self.stale_for =>
+                {
+                    cached.failures += 1;
+                    cached.retry_at = Some(now + self.backoff_after(cached.failures));
+                    if let Some(health) = &self.health {
+                        health.record_degraded(key);
+                    }
+                    Ok(cached.value.clone())
+                }
+                _ => {
+                    if let Some(health) = &self.health {
+                        health.record_failed(key);
+                    }
+                    Err(error)
+                }
+            },
+        }
+    }
+
+    fn backoff_after(&self, failures: u32) -> Duration {
+        let (min, max) = self.backoff;
+        min.saturating_mul(2u32.saturating_pow(failures - 1))
+            .min(max)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Cached>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<P: SecretProvider> SecretProvider for CachedProvider<P> {
+    fn fetch(&self, key: &str) -> Result<SensitiveString, ProviderError> {
+        self.fetch_at(key, Instant::now())
+    }
+
+    fn fetch_async<'a>(&'a self, key: &'a str) -> FetchFuture<'a> {
+        Box::pin(async move {
+            if let Some(value) = self.cached(key, Instant::now()) {
+                return Ok(value);
+            }
+            let result = self.inner.fetch_async(key).await;
+            self.settle(key, result, Instant::now())
+        })
+    }
+}
+
+/// A size-bounded cache of secrets keyed by tenant and name.
+///
+/// When a bound is exceeded the least recently used entries are evicted and
+/// their plaintexts zeroed. Wrap it in a `Mutex` to share it between threads.
+#[derive(Debug)]
+pub struct SecretCache {
+    max_entries: usize,
+    max_bytes: Option<usize>,
+    bytes: usize,
+    tick: u64,
+    entries: HashMap<(String, String), Entry>,
+    recency: BTreeMap<u64, (String, String)>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: SensitiveString,
+    bytes: usize,
+    used: u64,
+}
+
+impl SecretCache {
+    /// Creates a cache that holds at most `max_entries` secrets.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            max_bytes: None,
+            bytes: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+        }
+    }
+
+    /// Also evicts once the secrets occupy more than `max_bytes`, as
+    /// estimated by [`SensitiveString::approx_memory_bytes`].
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Caches `value` for `tenant` and `name`, replacing (and zeroing) any previous value.
+    ///
+    /// A secret larger than the byte bound is not cached at all.
+    pub fn insert(&mut self, tenant: &str, name: &str, value: SensitiveString) {
+        self.remove(tenant, name);
+        let bytes = value.approx_memory_bytes();
+        if self.max_entries == 0 || self.max_bytes.is_some_and(|max| bytes > max) {
+            discard(value);
+            return;
+        }
+        let key = (tenant.to_string(), name.to_string());
+        let used = self.next_tick();
+        self.recency.insert(used, key.clone());
+        self.entries.insert(key, Entry { value, bytes, used });
+        self.bytes += bytes;
+        while self.entries.len() > self.max_entries
+            || self.max_bytes.is_some_and(|max| self.bytes > max)
+        {
+            self.evict_oldest();
+        }
+    }
+
+    /// Returns the secret for `tenant` and `name`, marking it as recently used.
+    pub fn get(&mut self, tenant: &str, name: &str) -> Option<&SensitiveString> {
+        let used = self.next_tick();
+        let entry = self
+            .entries
+            .get_mut(&(tenant.to_string(), name.to_string()))?;
+        let key = self.recency.remove(&entry.used)?;
+        self.recency.insert(used, key);
+        entry.used = used;
+        Some(&entry.value)
+    }
+
+    /// Removes and zeroes the secret for `tenant` and `name`. Returns true if it was cached.
+    pub fn remove(&mut self, tenant: &str, name: &str) -> bool {
+        match self.entries.remove(&(tenant.to_string(), name.to_string())) {
+            Some(entry) => {
+                self.recency.remove(&entry.used);
+                self.bytes -= entry.bytes;
+                discard(entry.value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes and zeroes every secret belonging to `tenant`, e.g. when it is offboarded.
+    pub fn remove_tenant(&mut self, tenant: &str) {
+        let names: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|(owner, _)| owner == tenant)
+            .map(|(_, name)| name.clone())
+            .collect();
+        for name in names {
+            self.remove(tenant, &name);
+        }
+    }
+
+    /// Returns the number of cached secrets.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the approximate bytes the cached secrets occupy.
+    pub fn approx_memory_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some((_, (tenant, name))) = self.recency.pop_first() {
+            if let Some(entry) = self.entries.remove(&(tenant, name)) {
+                self.bytes -= entry.bytes;
+                discard(entry.value);
+            }
+        }
+    }
+}
+
+impl Drop for SecretCache {
+    fn drop(&mut self) {
+        for (_, entry) in self.entries.drain() {
+            discard(entry.value);
+        }
+    }
+}
+
+/// Overwrites the plaintext with zeros before it is freed.
+fn discard(mut secret: SensitiveString) {
+    // SAFETY: zero bytes are valid UTF-8, so the string stays well-formed.
+    crate::wipe(unsafe { secret.value.as_bytes_mut() });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::HealthStatus;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct Flaky {
+        down: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    impl SecretProvider for Flaky {
+        fn fetch(&self, key: &str) -> Result<SensitiveString, ProviderError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.down.load(Ordering::SeqCst) {
+                return Err(ProviderError::new("down"));
+            }
+            Ok(SensitiveString::new(key.to_uppercase()))
+        }
+    }
+
+    #[test]
+    fn test_fresh_values_are_cached() {
+        let cache = CachedProvider::new(Flaky::default(), Duration::from_secs(60));
+        cache.fetch("a").unwrap();
+        cache.fetch("a").unwrap();
+        assert_eq!(cache.inner().calls.load(Ordering::SeqCst), 1);
+
+        cache.invalidate("a");
+        cache.fetch("a").unwrap();
+        assert_eq!(cache.inner().calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_errors_without_stale_window() {
+        let cache = CachedProvider::new(Flaky::default(), Duration::ZERO);
+        cache.fetch("a").unwrap();
+        cache.inner().down.store(true, Ordering::SeqCst);
+        assert!(cache.fetch("a").is_err());
+    }
+
+    #[test]
+    fn test_serves_stale_and_reports_degradation() {
+        let health = Arc::new(HealthRegistry::new());
+        let cache = CachedProvider::new(Flaky::default(), Duration::ZERO)
+            .serve_stale_for(Duration::from_secs(60))
+            .report_to(health.clone());
+        cache.fetch("a").unwrap();
+
+        cache.inner().down.store(true, Ordering::SeqCst);
+        assert_eq!(cache.fetch("a").unwrap(), SensitiveString::from("A"));
+        let report = health.report();
+        assert_eq!(report.secrets[0].status, HealthStatus::Degraded);
+        assert!(report.secrets[0].degraded_since.is_some());
+        assert!(report.is_healthy());
+
+        cache.inner().down.store(false, Ordering::SeqCst);
+        cache
+            .fetch_at("a", Instant::now() + DEFAULT_MIN_BACKOFF)
+            .unwrap();
+        assert_eq!(health.report().secrets[0].status, HealthStatus::Ok);
+    }
+
+    #[test]
+    fn test_stale_refreshes_back_off() {
+        const SECOND: Duration = Duration::from_secs(1);
+        let cache = CachedProvider::new(Flaky::default(), Duration::ZERO)
+            .serve_stale_for(60 * SECOND)
+            .retry_backoff(10 * SECOND, 25 * SECOND);
+        let start = Instant::now();
+        let calls = || cache.inner().calls.load(Ordering::SeqCst);
+        cache.fetch_at("a", start).unwrap();
+        cache.inner().down.store(true, Ordering::SeqCst);
+
+        // Each failure serves the stale value and pushes the next retry out:
+        // 10s, then 20s, then the 25s cap.
+        for (at, expected_calls) in [(1, 2), (10, 2), (11, 3), (30, 3), (31, 4), (55, 4), (56, 5)] {
+            assert_eq!(
+                cache.fetch_at("a", start + at * SECOND).unwrap(),
+                SensitiveString::from("A")
+            );
+            assert_eq!(calls(), expected_calls, "at {}s", at);
+        }
+
+        // Past the stale window every fetch goes to the provider again.
+        assert!(cache.fetch_at("a", start + 61 * SECOND).is_err());
+        assert!(cache.fetch_at("a", start + 62 * SECOND).is_err());
+        assert_eq!(calls(), 7);
+    }
+
+    #[test]
+    fn test_reports_failed_once_stale_window_passes() {
+        const SECOND: Duration = Duration::from_secs(1);
+        let health = Arc::new(HealthRegistry::new());
+        let cache = CachedProvider::new(Flaky::default(), Duration::ZERO)
+            .serve_stale_for(60 * SECOND)
+            .report_to(health.clone());
+        let start = Instant::now();
+        cache.fetch_at("a", start).unwrap();
+        cache.inner().down.store(true, Ordering::SeqCst);
+
+        cache.fetch_at("a", start + SECOND).unwrap();
+        assert_eq!(health.report().secrets[0].status, HealthStatus::Degraded);
+        let since = health.report().secrets[0].degraded_since;
+
+        assert!(cache.fetch_at("a", start + 61 * SECOND).is_err());
+        let report = health.report();
+        assert_eq!(report.secrets[0].status, HealthStatus::Failed);
+        assert_eq!(report.secrets[0].degraded_since, since);
+        assert!(!report.is_healthy());
+
+        cache.inner().down.store(false, Ordering::SeqCst);
+        cache.fetch_at("a", start + 62 * SECOND).unwrap();
+        assert_eq!(health.report().secrets[0].status, HealthStatus::Ok);
+    }
+
+    #[test]
+    fn test_secret_cache_evicts_least_recently_used() {
+        let mut cache = SecretCache::new(2);
+        cache.insert("a", "db", SensitiveString::from("a-db"));
+        cache.insert("b", "db", SensitiveString::from("b-db"));
+        assert!(cache.get("a", "db").is_some());
+        cache.insert("c", "db", SensitiveString::from("c-db"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b", "db").is_none());
+        assert_eq!(cache.get("a", "db"), Some(&SensitiveString::from("a-db")));
+    }
+
+    #[test]
+    fn test_secret_cache_byte_bound_and_tenant_removal() {
+        let one = SensitiveString::from("x").approx_memory_bytes();
+        let mut cache = SecretCache::new(100).max_bytes(2 * one);
+        cache.insert("a", "db", SensitiveString::from("x"));
+        cache.insert("a", "api", SensitiveString::from("y"));
+        cache.insert("b", "db", SensitiveString::from("z"));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.approx_memory_bytes(), 2 * one);
+
+        cache.remove_tenant("a");
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("b", "db").is_some());
+
+        cache.insert("b", "big", SensitiveString::new("x".repeat(2 * one)));
+        assert!(cache.get("b", "big").is_none());
+    }
+}
@@ -0,0 +1,241 @@
+//! Encrypted checkpoints of a [`SensitiveMap`].
+//!
+//! Tools that save state to disk and load it on restart (session caches,
+//! daemons with warm restarts) can write their secrets with
+//! [`SensitiveMap::export_encrypted`] and read them back with
+//! [`SensitiveMap::import_encrypted`], so the state file contains neither
+//! the secrets nor their names.
+//!
+//! A checkpoint is `sscp1`, a random 12-byte nonce, and the
+//! ChaCha20-Poly1305 encryption of the entries under a 32-byte key, with
+//! `sscp1` as associated data. The encrypted entries are sorted by key, each
+//! written as two netstrings (`<len>:<key>,<len>:<value>,`, lengths in
+//! bytes). Where the key comes from is up to the application: a key file
+//! readable only by the service account, a KMS, or an OS keyring.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{SensitiveMap, SensitiveString};
+//!
+//! let key = [7u8; 32]; // load this from somewhere other than the state file
+//!
+//! let mut sessions: SensitiveMap = SensitiveMap::new();
+//! sessions.insert("alice", SensitiveString::from("sess-123"));
+//! let checkpoint = sessions.export_encrypted(&key).unwrap();
+//! assert!(!String::from_utf8_lossy(&checkpoint).contains("alice"));
+//!
+//! let restored = SensitiveMap::import_encrypted(&checkpoint, &key).unwrap();
+//! assert_eq!(restored, sessions);
+//! ```
+
+use crate::{SensitiveMap, SensitiveString};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use std::fmt;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+/// The bytes every checkpoint starts with.
+pub const MAGIC: &[u8] = b"sscp1";
+
+const NONCE_LEN: usize = 12;
+
+/// Error returned when writing or reading a checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointError {
+    /// The input is not a checkpoint, or its entries are malformed.
+    Malformed,
+    /// Decryption failed: wrong key, or the checkpoint was modified.
+    Crypto,
+    /// A decrypted key or value is not valid UTF-8.
+    InvalidPlaintext,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Malformed => write!(f, "malformed checkpoint"),
+            CheckpointError::Crypto => write!(f, "checkpoint could not be decrypted"),
+            CheckpointError::InvalidPlaintext => write!(f, "checkpoint entry is not valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl SensitiveMap {
+    /// Encrypts every entry into a checkpoint under `key`.
+    ///
+    /// Each value counts as a plaintext read for auditing and access
+    /// policies, and all are checked before anything is copied.
+    #[track_caller]
+    pub fn export_encrypted(&self, key: &[u8; 32]) -> Result<Vec<u8>, CheckpointError> {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by_key(|(name, _)| name.as_str());
+        for (_, secret) in &entries {
+            secret.record_access("export_encrypted");
+        }
+        let mut plaintext = Vec::new();
+        for (name, secret) in entries {
+            for field in [name.as_bytes(), secret.value.as_bytes()] {
+                plaintext.extend_from_slice(field.len().to_string().as_bytes());
+                plaintext.push(b':');
+                plaintext.extend_from_slice(field);
+                plaintext.push(b',');
+            }
+        }
+
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let sealed = ChaCha20Poly1305::new(key.into()).encrypt(
+            &nonce,
+            Payload {
+                msg: &plaintext,
+                aad: MAGIC,
+            },
+        );
+        wipe(&mut plaintext);
+        let sealed = sealed.map_err(|_| CheckpointError::Crypto)?;
+
+        let mut checkpoint = Vec::with_capacity(MAGIC.len() + NONCE_LEN + sealed.len());
+        checkpoint.extend_from_slice(MAGIC);
+        checkpoint.extend_from_slice(&nonce);
+        checkpoint.extend_from_slice(&sealed);
+        Ok(checkpoint)
+    }
+
+    /// Decrypts a checkpoint made by [`export_encrypted`](Self::export_encrypted).
+    pub fn import_encrypted(checkpoint: &[u8], key: &[u8; 32]) -> Result<Self, CheckpointError> {
+        let body = checkpoint
+            .strip_prefix(MAGIC)
+            .filter(|body| body.len() >= NONCE_LEN)
+            .ok_or(CheckpointError::Malformed)?;
+        let (nonce, sealed) = body.split_at(NONCE_LEN);
+        let mut plaintext = ChaCha20Poly1305::new(key.into())
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: sealed,
+                    aad: MAGIC,
+                },
+            )
+            .map_err(|_| CheckpointError::Crypto)?;
+        let map = read_entries(&plaintext);
+        wipe(&mut plaintext);
+        map
+    }
+}
+
+/// Parses the netstring pairs of a decrypted checkpoint.
+fn read_entries(mut rest: &[u8]) -> Result<SensitiveMap, CheckpointError> {
+    let mut map = SensitiveMap::new();
+    while !rest.is_empty() {
+        let name = read_netstring(&mut rest)?;
+        let value = read_netstring(&mut rest)?;
+        map.insert(name, SensitiveString::new(value));
+    }
+    Ok(map)
+}
+
+fn read_netstring(rest: &mut &[u8]) -> Result<String, CheckpointError> {
+    let colon = rest
+        .iter()
+        .position(|&b| b == b':')
+        .ok_or(CheckpointError::Malformed)?;
+    let digits = &rest[..colon];
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return Err(CheckpointError::Malformed);
+    }
+    let len: usize = std::str::from_utf8(digits)
+        .ok()
+        .and_then(|digits| digits.parse().ok())
+        .ok_or(CheckpointError::Malformed)?;
+    let end = (colon + 1)
+        .checked_add(len)
+        .filter(|&end| rest.get(end) == Some(&b','))
+        .ok_or(CheckpointError::Malformed)?;
+    let field = String::from_utf8(rest[colon + 1..end].to_vec())
+        .map_err(|_| CheckpointError::InvalidPlaintext)?;
+    *rest = &rest[end + 1..];
+    Ok(field)
+}
+
+fn wipe(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        // SAFETY: `byte` is a valid, aligned reference into the buffer.
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_hides_names_and_values() {
+        let key = [7u8; 32];
+        let mut map: SensitiveMap = SensitiveMap::new();
+        map.insert("db", "hunter2");
+        map.insert("", "");
+        map.insert("é:,", "a,b:1");
+        let checkpoint = map.export_encrypted(&key).unwrap();
+        let text = String::from_utf8_lossy(&checkpoint);
+        assert!(!text.contains("hunter2") && !text.contains("db"));
+        assert_eq!(checkpoint.len(), MAGIC.len() + NONCE_LEN + 36 + 16);
+        assert_eq!(SensitiveMap::import_encrypted(&checkpoint, &key), Ok(map));
+    }
+
+    #[test]
+    fn test_rejects_wrong_key_and_tampering() {
+        let key = [7u8; 32];
+        let mut map: SensitiveMap = SensitiveMap::new();
+        map.insert("db", "hunter2");
+        let mut checkpoint = map.export_encrypted(&key).unwrap();
+        assert_eq!(
+            SensitiveMap::import_encrypted(&checkpoint, &[8u8; 32]),
+            Err(CheckpointError::Crypto)
+        );
+        *checkpoint.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            SensitiveMap::import_encrypted(&checkpoint, &key),
+            Err(CheckpointError::Crypto)
+        );
+        for input in [&b""[..], b"sscp1", b"sscp2000000000000", &checkpoint[..16]] {
+            assert_eq!(
+                SensitiveMap::import_encrypted(input, &key),
+                Err(CheckpointError::Malformed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_entries_rejects_malformed_netstrings() {
+        assert!(read_entries(b"").unwrap().is_empty());
+        for body in [
+            "x",
+            "2:db",
+            "+2:db,0:,",
+            "99999999999999999999999:",
+            "1:a,1:b;",
+        ] {
+            assert_eq!(
+                read_entries(body.as_bytes()),
+                Err(CheckpointError::Malformed),
+                "{}",
+                body
+            );
+        }
+        assert_eq!(
+            read_entries(b"1:\xff,0:,"),
+            Err(CheckpointError::InvalidPlaintext)
+        );
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_export_is_checked_against_access_policies() {
+        let mut map: SensitiveMap = SensitiveMap::new();
+        map.insert("db", "hunter2");
+        crate::access::assert_denied(|| map.export_encrypted(&[7u8; 32]));
+    }
+}
@@ -0,0 +1,179 @@
+//! Command-line secrets with clap.
+//!
+//! When a flag fails validation, clap's built-in parsers echo the raw value
+//! back in the error, and `--help` shows default and environment values.
+//! With the `clap` feature, `SensitiveString` has its own value parser, so
+//! `#[arg(long)] token: SensitiveString` builds the wrapper directly from
+//! argv or the environment, and a [`SensitiveStringParser`] validator's
+//! errors show only the redacted form. [`SensitiveArg::sensitive`] hides
+//! default and environment values from help.
+//!
+//! # Example
+//!
+//! ```
+//! use clap::{Arg, Command};
+//! use sensitive_string::clap::{SensitiveArg, SensitiveStringParser};
+//! use sensitive_string::SensitiveString;
+//!
+//! let command = Command::new("deploy").arg(
+//!     Arg::new("token")
+//!         .long("token")
+//!         .env("DEPLOY_TOKEN")
+//!         .value_parser(SensitiveStringParser::new().validate(|token| {
+//!             if token.starts_with("sk-") { Ok(()) } else { Err("must start with sk-".into()) }
+//!         }))
+//!         .sensitive(),
+//! );
+//!
+//! let error = command.clone().try_get_matches_from(["deploy", "--token", "hunter2"]).unwrap_err();
+//! assert!(!error.to_string().contains("hunter2"));
+//!
+//! let matches = command.try_get_matches_from(["deploy", "--token", "sk-123"]).unwrap();
+//! assert_eq!(matches.get_one::<SensitiveString>("token"), Some(&SensitiveString::from("sk-123")));
+//! ```
+//!
+//! With the derive API, add `hide_env_values = true` and `hide_default_value
+//! = true` to the `#[arg]` attribute. Errors clap raises before a value
+//! reaches the parser, such as an unexpected positional argument, are
+//! outside this crate's control.
+
+use crate::SensitiveString;
+use ::clap::builder::{TypedValueParser, ValueParserFactory};
+use ::clap::error::ErrorKind;
+use ::clap::{Arg, Command, Error};
+use std::ffi::OsStr;
+use std::fmt;
+use std::sync::Arc;
+
+type Validator = dyn Fn(&str) -> Result<(), String> + Send + Sync;
+
+/// Parses a command-line value into a [`SensitiveString`], with optional validation.
+#[derive(Clone, Default)]
+pub struct SensitiveStringParser {
+    validator: Option<Arc<Validator>>,
+}
+
+impl SensitiveStringParser {
+    /// Creates a parser that accepts any UTF-8 value.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rejects values for which `validator` returns an error.
+    ///
+    /// The error message is shown to the user next to the redacted value, so
+    /// it must not include the value itself.
+    pub fn validate(
+        mut self,
+        validator: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+}
+
+impl fmt::Debug for SensitiveStringParser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SensitiveStringParser")
+            .field("validates", &self.validator.is_some())
+            .finish()
+    }
+}
+
+impl TypedValueParser for SensitiveStringParser {
+    type Value = SensitiveString;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<SensitiveString, Error> {
+        let name = arg.map_or_else(|| "...".to_string(), ToString::to_string);
+        let Some(value) = value.to_str() else {
+            return Err(cmd.clone().error(
+                ErrorKind::InvalidUtf8,
+                format!("invalid UTF-8 in the value for '{}'", name),
+            ));
+        };
+        let secret = SensitiveString::from(value);
+        if let Some(validator) = &self.validator {
+            if let Err(reason) = validator(value) {
+                return Err(cmd.clone().error(
+                    ErrorKind::ValueValidation,
+                    format!("invalid value '{}' for '{}': {}", secret, name, reason),
+                ));
+            }
+        }
+        Ok(secret)
+    }
+}
+
+impl ValueParserFactory for SensitiveString {
+    type Parser = SensitiveStringParser;
+
+    fn value_parser() -> SensitiveStringParser {
+        SensitiveStringParser::new()
+    }
+}
+
+/// Hides a secret argument's values from help output.
+pub trait SensitiveArg {
+    /// Hides default, environment, and possible values from `--help`.
+    fn sensitive(self) -> Self;
+}
+
+impl SensitiveArg for Arg {
+    fn sensitive(self) -> Self {
+        self.hide_default_value(true)
+            .hide_env_values(true)
+            .hide_possible_values(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::clap::value_parser;
+
+    #[test]
+    fn test_default_parser_builds_the_wrapper() {
+        let matches = Command::new("app")
+            .arg(
+                Arg::new("password")
+                    .long("password")
+                    .value_parser(value_parser!(SensitiveString)),
+            )
+            .try_get_matches_from(["app", "--password", "hunter2"])
+            .unwrap();
+        assert_eq!(
+            matches.get_one::<SensitiveString>("password"),
+            Some(&SensitiveString::from("hunter2"))
+        );
+    }
+
+    #[test]
+    fn test_validation_errors_are_redacted() {
+        let arg = Arg::new("token")
+            .long("token")
+            .default_value("sk-default")
+            .value_parser(SensitiveStringParser::new().validate(|token| {
+                (token.len() > 8)
+                    .then_some(())
+                    .ok_or_else(|| "too short".to_string())
+            }))
+            .sensitive();
+        assert!(arg.is_hide_default_value_set());
+        assert!(arg.is_hide_env_values_set());
+
+        let error = Command::new("app")
+            .arg(arg)
+            .try_get_matches_from(["app", "--token", "hunter2"])
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ValueValidation);
+        let message = error.to_string();
+        assert!(message.contains(&SensitiveString::from("hunter2").to_string()));
+        assert!(message.contains("too short"));
+        assert!(!message.contains("hunter2"));
+    }
+}
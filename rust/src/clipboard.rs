@@ -0,0 +1,143 @@
+//! Copying secrets to the system clipboard with automatic clearing.
+//!
+//! Intended for password-manager-style CLIs. The text is marked as excluded
+//! from clipboard history where the platform has a convention for it, and a
+//! background thread clears the clipboard after a delay unless the user has
+//! copied something else in the meantime.
+
+use crate::exposure::{require_exposure, ExposureRequired};
+use crate::SensitiveString;
+use arboard::Clipboard;
+use std::fmt;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+#[cfg(target_os = "macos")]
+use arboard::SetExtApple;
+#[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
+use arboard::SetExtLinux;
+#[cfg(windows)]
+use arboard::SetExtWindows;
+
+/// Errors from [`SensitiveString::copy_to_clipboard`].
+#[derive(Debug)]
+pub enum ClipboardError {
+    /// No [`ExposureGuard`](crate::ExposureGuard) was active.
+    ExposureRequired(ExposureRequired),
+    /// The platform clipboard could not be opened or written.
+    Clipboard(arboard::Error),
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClipboardError::ExposureRequired(err) => err.fmt(f),
+            ClipboardError::Clipboard(err) => write!(f, "clipboard error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClipboardError::ExposureRequired(err) => Some(err),
+            ClipboardError::Clipboard(err) => Some(err),
+        }
+    }
+}
+
+impl From<ExposureRequired> for ClipboardError {
+    fn from(err: ExposureRequired) -> Self {
+        ClipboardError::ExposureRequired(err)
+    }
+}
+
+impl From<arboard::Error> for ClipboardError {
+    fn from(err: arboard::Error) -> Self {
+        ClipboardError::Clipboard(err)
+    }
+}
+
+/// Handle to the background thread that clears the clipboard.
+///
+/// Dropping the handle does not cancel the clear. Short-lived programs should
+/// call [`wait`](Self::wait) before exiting: on X11 and Wayland the clipboard
+/// contents disappear when the owning process exits.
+#[derive(Debug)]
+pub struct ClipboardClear {
+    handle: JoinHandle<()>,
+}
+
+impl ClipboardClear {
+    /// Blocks until the clipboard has been cleared (or left alone because it changed).
+    pub fn wait(self) {
+        let _ = self.handle.join();
+    }
+}
+
+impl SensitiveString {
+    /// Places the plaintext on the system clipboard and clears it after `clear_after`.
+    ///
+    /// The clipboard is only cleared if it still holds this secret, so text
+    /// the user copied in the meantime is left alone. The comparison is done
+    /// by digest, so the clearing thread holds no copy of the plaintext.
+    ///
+    /// Returns an error unless an [`ExposureGuard`](crate::ExposureGuard) is
+    /// active on the current thread.
+    #[track_caller]
+    pub fn copy_to_clipboard(
+        &self,
+        clear_after: Duration,
+    ) -> Result<ClipboardClear, ClipboardError> {
+        require_exposure("copy_to_clipboard")?;
+        self.record_access("copy_to_clipboard");
+
+        let mut clipboard = Clipboard::new()?;
+        let set = clipboard.set();
+        #[cfg(any(
+            target_os = "linux",
+            target_os = "freebsd",
+            target_os = "openbsd",
+            target_os = "macos",
+            windows
+        ))]
+        let set = set.exclude_from_history();
+        set.text(self.value.as_str())?;
+
+        let digest = self.hash_string();
+        let handle = thread::spawn(move || {
+            thread::sleep(clear_after);
+            let unchanged = clipboard
+                .get_text()
+                .map(|text| SensitiveString::unregistered(text).hash_string() == digest)
+                .unwrap_or(false);
+            if unchanged {
+                let _ = clipboard.clear();
+            }
+        });
+        Ok(ClipboardClear { handle })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_requires_exposure() {
+        let secret = SensitiveString::from("clipboard-secret");
+        let err = secret
+            .copy_to_clipboard(Duration::from_secs(1))
+            .unwrap_err();
+        assert!(matches!(err, ClipboardError::ExposureRequired(_)));
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_copy_is_checked_against_access_policies() {
+        // The policy check comes before the clipboard is opened.
+        let secret = SensitiveString::from("clipboard-secret");
+        let _guard = crate::ExposureGuard::new("test");
+        crate::access::assert_denied(|| secret.copy_to_clipboard(Duration::from_secs(1)));
+    }
+}
@@ -0,0 +1,261 @@
+//! Passing secrets to child processes through their environment.
+//!
+//! CLI tools such as `psql` or `aws` take credentials from environment
+//! variables. Setting them on a `std::process::Command` works, but the
+//! command's `Debug` output, which ends up in logs whenever a spawn fails,
+//! prints every variable that was set. [`SensitiveCommand`] wraps a
+//! `Command` and shows secrets set with
+//! [`env_sensitive`](SensitiveCommand::env_sensitive) in their redacted
+//! form; [`CommandSensitiveExt`] adds the same method to `Command` itself,
+//! where it at least makes each injection a recorded plaintext read.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::command::SensitiveCommand;
+//! use sensitive_string::SensitiveString;
+//!
+//! let password = SensitiveString::from("hunter2");
+//! let mut command = SensitiveCommand::new("sh");
+//! command
+//!     .args(["-c", "test ${#PGPASSWORD} = 7"])
+//!     .env("PGUSER", "app")
+//!     .env_sensitive("PGPASSWORD", &password);
+//!
+//! let shown = format!("{:?}", command);
+//! assert!(shown.contains("\"PGUSER\": \"app\""));
+//! assert!(!shown.contains("hunter2"));
+//! # #[cfg(unix)]
+//! assert!(command.status()?.success());
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! Environment variables are inherited by the child's own children and can
+//! be read by other processes of the same user. On Unix, the `handoff`
+//! feature passes a secret over a pipe only the child inherits instead.
+
+use crate::SensitiveString;
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+
+/// Adds [`env_sensitive`](Self::env_sensitive) to [`Command`].
+pub trait CommandSensitiveExt {
+    /// Sets the environment variable `key` to the plaintext of `secret`.
+    ///
+    /// Counts as a plaintext read for auditing and access policies. The
+    /// command's own `Debug` output still shows the value; use
+    /// [`SensitiveCommand`] where the command may be logged.
+    fn env_sensitive(&mut self, key: impl AsRef<OsStr>, secret: &SensitiveString) -> &mut Self;
+}
+
+impl CommandSensitiveExt for Command {
+    #[track_caller]
+    fn env_sensitive(&mut self, key: impl AsRef<OsStr>, secret: &SensitiveString) -> &mut Self {
+        secret.record_access("env_sensitive");
+        self.env(key, &secret.value)
+    }
+}
+
+/// A [`Command`] whose `Debug` output redacts the secrets passed to it.
+pub struct SensitiveCommand {
+    command: Command,
+    secrets: BTreeMap<OsString, String>,
+}
+
+impl SensitiveCommand {
+    /// Creates a command that runs `program`, like [`Command::new`].
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Command::new(program).into()
+    }
+
+    /// Adds an argument.
+    pub fn arg(&mut self, arg: impl AsRef<OsStr>) -> &mut Self {
+        self.command.arg(arg);
+        self
+    }
+
+    /// Adds arguments.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.command.args(args);
+        self
+    }
+
+    /// Sets an environment variable that is not a secret.
+    pub fn env(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> &mut Self {
+        self.secrets.remove(key.as_ref());
+        self.command.env(key, value);
+        self
+    }
+
+    /// Sets the environment variable `key` to the plaintext of `secret`.
+    ///
+    /// `Debug` shows the secret's redacted form in place of the value. Counts
+    /// as a plaintext read for auditing and access policies.
+    #[track_caller]
+    pub fn env_sensitive(&mut self, key: impl AsRef<OsStr>, secret: &SensitiveString) -> &mut Self {
+        self.command.env_sensitive(key.as_ref(), secret);
+        self.secrets
+            .insert(key.as_ref().to_os_string(), secret.to_string());
+        self
+    }
+
+    /// Removes an environment variable from the child's environment.
+    pub fn env_remove(&mut self, key: impl AsRef<OsStr>) -> &mut Self {
+        self.secrets.remove(key.as_ref());
+        self.command.env_remove(key);
+        self
+    }
+
+    /// Sets the working directory of the child.
+    pub fn current_dir(&mut self, dir: impl AsRef<Path>) -> &mut Self {
+        self.command.current_dir(dir);
+        self
+    }
+
+    /// Configures the child's standard input.
+    pub fn stdin(&mut self, cfg: impl Into<Stdio>) -> &mut Self {
+        self.command.stdin(cfg);
+        self
+    }
+
+    /// Configures the child's standard output.
+    pub fn stdout(&mut self, cfg: impl Into<Stdio>) -> &mut Self {
+        self.command.stdout(cfg);
+        self
+    }
+
+    /// Configures the child's standard error.
+    pub fn stderr(&mut self, cfg: impl Into<Stdio>) -> &mut Self {
+        self.command.stderr(cfg);
+        self
+    }
+
+    /// Starts the child, like [`Command::spawn`].
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        self.command.spawn()
+    }
+
+    /// Runs the child to completion and collects its output, like [`Command::output`].
+    pub fn output(&mut self) -> io::Result<Output> {
+        self.command.output()
+    }
+
+    /// Runs the child to completion, like [`Command::status`].
+    pub fn status(&mut self) -> io::Result<ExitStatus> {
+        self.command.status()
+    }
+
+    /// Returns the wrapped command, e.g. to set platform-specific options.
+    ///
+    /// Variables set through it are shown by `Debug` unless they replace one
+    /// set with [`env_sensitive`](Self::env_sensitive).
+    pub fn command_mut(&mut self) -> &mut Command {
+        &mut self.command
+    }
+
+    /// Returns the wrapped command, whose `Debug` output shows the secrets.
+    pub fn into_inner(self) -> Command {
+        self.command
+    }
+}
+
+impl From<Command> for SensitiveCommand {
+    fn from(command: Command) -> Self {
+        Self {
+            command,
+            secrets: BTreeMap::new(),
+        }
+    }
+}
+
+impl fmt::Debug for SensitiveCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let env = self
+            .command
+            .get_envs()
+            .map(|(key, value)| {
+                let value = match (value, self.secrets.get(key)) {
+                    (Some(_), Some(redacted)) => EnvValue::Redacted(redacted),
+                    (Some(value), None) => EnvValue::Set(value),
+                    (None, _) => EnvValue::Removed,
+                };
+                (key, value)
+            })
+            .collect();
+        f.debug_struct("SensitiveCommand")
+            .field("program", &self.command.get_program())
+            .field("args", &self.command.get_args().collect::<Vec<_>>())
+            .field("env", &DebugMap(env))
+            .finish_non_exhaustive()
+    }
+}
+
+enum EnvValue<'a> {
+    Set(&'a OsStr),
+    Redacted(&'a str),
+    Removed,
+}
+
+impl fmt::Debug for EnvValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvValue::Set(value) => fmt::Debug::fmt(value, f),
+            EnvValue::Redacted(redacted) => f.write_str(redacted),
+            EnvValue::Removed => f.write_str("<removed>"),
+        }
+    }
+}
+
+struct DebugMap<'a>(Vec<(&'a OsStr, EnvValue<'a>)>);
+
+impl fmt::Debug for DebugMap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(key, value)| (key, value)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_redacts_only_secrets() {
+        let token = SensitiveString::from("tok-7c1e");
+        let mut command = SensitiveCommand::new("deploy");
+        command
+            .arg("--region=eu")
+            .env_sensitive("TOKEN", &token)
+            .env("REGION", "eu")
+            .env_remove("HOME");
+        let shown = format!("{:?}", command);
+        assert!(shown.contains(&format!("\"TOKEN\": {}", token)));
+        assert!(shown.contains("\"REGION\": \"eu\""));
+        assert!(shown.contains("\"HOME\": <removed>"));
+        assert!(shown.contains("--region=eu"));
+        assert!(!shown.contains("tok-7c1e"));
+
+        command.env("TOKEN", "public");
+        assert!(format!("{:?}", command).contains("\"TOKEN\": \"public\""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_child_sees_plaintext() {
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", "printf %s \"$TOKEN\""])
+            .env_sensitive("TOKEN", &SensitiveString::from("tok-7c1e"));
+        let output = SensitiveCommand::from(command).output().unwrap();
+        assert_eq!(output.stdout, b"tok-7c1e");
+    }
+}
@@ -0,0 +1,120 @@
+//! Building and transforming secrets without taking the plaintext out.
+//!
+//! Prefixing a generated token, or trimming the newline from a secret read
+//! from a file, otherwise means reading the plaintext into a `String`,
+//! editing it, and wrapping it again, leaving an unwrapped copy behind each
+//! time. These methods do the same edits on the wrapped value.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::SensitiveString;
+//!
+//! let random = SensitiveString::from("9f3e4f2a");
+//! let mut key = SensitiveString::from("sk_live_");
+//! key.push_str_sensitive(&random);
+//! assert_eq!(key, SensitiveString::from("sk_live_9f3e4f2a"));
+//!
+//! let from_file = SensitiveString::from("  hunter2\n").with_label("db-password");
+//! let password = from_file.trim();
+//! assert_eq!(password, SensitiveString::from("hunter2"));
+//! assert_eq!(password.label(), Some("db-password"));
+//!
+//! let upper = password.map(|plaintext| plaintext.make_ascii_uppercase());
+//! assert_eq!(upper, SensitiveString::from("HUNTER2"));
+//! ```
+//!
+//! Results keep the label, policy, hasher, observer, and validators of the
+//! secret they were made from; [`concat`](SensitiveString::concat) starts
+//! from a plain secret.
+
+use crate::SensitiveString;
+use std::sync::OnceLock;
+
+impl SensitiveString {
+    /// Appends the plaintext of `other`.
+    pub fn push_str_sensitive(&mut self, other: &SensitiveString) {
+        self.push_str(&other.value);
+    }
+
+    /// Appends `text`, e.g. a separator or a fixed suffix.
+    pub fn push_str(&mut self, text: &str) {
+        self.value.push_str(text);
+        self.digest = OnceLock::new();
+    }
+
+    /// Lets `f` edit the plaintext in place and returns the result.
+    ///
+    /// Counts as a plaintext read for auditing and access policies, since
+    /// `f` sees the value.
+    #[track_caller]
+    pub fn map(mut self, f: impl FnOnce(&mut String)) -> Self {
+        self.record_access("map");
+        f(&mut self.value);
+        self.digest = OnceLock::new();
+        self
+    }
+
+    /// Returns a copy without leading and trailing whitespace.
+    pub fn trim(&self) -> Self {
+        self.derive(self.value.trim().to_string())
+    }
+
+    /// Joins the plaintexts of `parts` into one secret.
+    ///
+    /// ```
+    /// use sensitive_string::SensitiveString;
+    ///
+    /// let user = SensitiveString::from("app");
+    /// let password = SensitiveString::from("hunter2");
+    /// let basic = SensitiveString::concat([&user, &SensitiveString::from(":"), &password]);
+    /// assert_eq!(basic, SensitiveString::from("app:hunter2"));
+    /// ```
+    pub fn concat<'a>(parts: impl IntoIterator<Item = &'a SensitiveString>) -> Self {
+        let mut value = String::new();
+        for part in parts {
+            value.push_str(&part.value);
+        }
+        Self::unregistered(value)
+    }
+
+    /// Wraps `value` with this secret's settings.
+    fn derive(&self, value: String) -> Self {
+        let mut derived = Self::unregistered(value);
+        derived.hasher.clone_from(&self.hasher);
+        derived.policy = self.policy;
+        derived.label.clone_from(&self.label);
+        #[cfg(feature = "audit")]
+        derived.observer.clone_from(&self.observer);
+        derived.validator.clone_from(&self.validator);
+        derived
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::RedactionPolicy;
+
+    #[test]
+    fn test_redacted_form_follows_edits() {
+        let mut secret = SensitiveString::from("abc").with_policy(RedactionPolicy::MaskLast(2));
+        assert_eq!(secret.to_string(), "****c");
+        secret.push_str("def");
+        assert_eq!(secret.to_string(), "****ef");
+        let secret = secret.map(|plaintext| plaintext.truncate(4));
+        assert_eq!(secret.to_string(), "****cd");
+    }
+
+    #[test]
+    fn test_trim_keeps_settings() {
+        let secret = SensitiveString::from("\thunter2 \n")
+            .with_label("trimmed")
+            .with_policy(RedactionPolicy::Placeholder);
+        let trimmed = secret.trim();
+        assert_eq!(trimmed.len(), 7);
+        assert_eq!(trimmed.label(), Some("trimmed"));
+        assert_eq!(trimmed.to_string(), secret.to_string());
+        assert_eq!(SensitiveString::concat([]), SensitiveString::from(""));
+    }
+}
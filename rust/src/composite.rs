@@ -0,0 +1,205 @@
+//! Credentials made of several named secrets.
+//!
+//! A database login (user, password, host) or an OAuth client (id, secret)
+//! is one credential with several fields, each of which would otherwise get
+//! its own `SensitiveString` field in a hand-written struct with the same
+//! derives repeated everywhere. [`CompositeSecret`] holds the parts by name,
+//! in insertion order. Each part is a [`SensitiveString`], so it keeps its
+//! own [`RedactionPolicy`](crate::policy::RedactionPolicy), and with the
+//! `serde` feature the whole credential serializes as an object of redacted
+//! parts and deserializes from an object of plaintext ones.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::policy::RedactionPolicy;
+//! use sensitive_string::{CompositeSecret, SensitiveString};
+//!
+//! let client = CompositeSecret::new()
+//!     .part("client_id", SensitiveString::from("app-7781").with_policy(RedactionPolicy::MaskLast(4)))
+//!     .part("client_secret", "s3cr3t-value");
+//!
+//! assert_eq!(client.get("client_id").unwrap().to_string(), "****7781");
+//! assert_eq!(client.get("client_secret"), Some(&SensitiveString::from("s3cr3t-value")));
+//! assert!(!format!("{:?}", client).contains("s3cr3t"));
+//! ```
+
+use crate::SensitiveString;
+use std::fmt;
+
+/// A credential made of named secret parts.
+///
+/// Equality compares the parts in order.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct CompositeSecret {
+    parts: Vec<(String, SensitiveString)>,
+}
+
+impl CompositeSecret {
+    /// Creates a credential with no parts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the part `name`, replacing any part with the same name.
+    pub fn part(mut self, name: impl Into<String>, secret: impl Into<SensitiveString>) -> Self {
+        self.insert(name, secret);
+        self
+    }
+
+    /// Sets the part `name`, returning the secret it replaced.
+    ///
+    /// A replaced part keeps its position; a new part goes last.
+    pub fn insert(
+        &mut self,
+        name: impl Into<String>,
+        secret: impl Into<SensitiveString>,
+    ) -> Option<SensitiveString> {
+        let name = name.into();
+        let secret = secret.into();
+        match self.parts.iter_mut().find(|(part, _)| *part == name) {
+            Some((_, existing)) => Some(std::mem::replace(existing, secret)),
+            None => {
+                self.parts.push((name, secret));
+                None
+            }
+        }
+    }
+
+    /// Returns the part `name`.
+    pub fn get(&self, name: &str) -> Option<&SensitiveString> {
+        self.parts
+            .iter()
+            .find(|(part, _)| part == name)
+            .map(|(_, secret)| secret)
+    }
+
+    /// Removes and returns the part `name`.
+    pub fn remove(&mut self, name: &str) -> Option<SensitiveString> {
+        let index = self.parts.iter().position(|(part, _)| part == name)?;
+        Some(self.parts.remove(index).1)
+    }
+
+    /// Returns the number of parts.
+    pub fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Returns true if there are no parts.
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    /// Returns the part names in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the parts in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &SensitiveString)> {
+        self.parts
+            .iter()
+            .map(|(name, secret)| (name.as_str(), secret))
+    }
+}
+
+impl<N: Into<String>> FromIterator<(N, SensitiveString)> for CompositeSecret {
+    fn from_iter<I: IntoIterator<Item = (N, SensitiveString)>>(iter: I) -> Self {
+        let mut composite = Self::new();
+        for (name, secret) in iter {
+            composite.insert(name, secret);
+        }
+        composite
+    }
+}
+
+impl fmt::Debug for CompositeSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CompositeSecret {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// Deserializes from an object of plaintext parts, keeping their order.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CompositeSecret {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct PartsVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for PartsVisitor {
+            type Value = CompositeSecret;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of secret parts")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<CompositeSecret, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut composite = CompositeSecret::new();
+                while let Some((name, secret)) = map.next_entry::<String, SensitiveString>()? {
+                    composite.insert(name, secret);
+                }
+                Ok(composite)
+            }
+        }
+
+        deserializer.deserialize_map(PartsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_replaces_in_place() {
+        let mut db = CompositeSecret::new()
+            .part("user", "app")
+            .part("password", "hunter2")
+            .part("host", "db.internal");
+        let old = db.insert("password", "correct horse");
+        assert_eq!(old, Some(SensitiveString::from("hunter2")));
+        assert_eq!(db.names().collect::<Vec<_>>(), ["user", "password", "host"]);
+        assert_eq!(
+            db.remove("host"),
+            Some(SensitiveString::from("db.internal"))
+        );
+        assert_eq!(db.len(), 2);
+        assert!(!format!("{:?}", db).contains("correct horse"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serializes_as_object_of_redacted_parts() {
+        let client: CompositeSecret =
+            serde_json::from_str(r#"{"client_secret": "s3cr3t", "client_id": "app-1"}"#).unwrap();
+        assert_eq!(
+            client.names().collect::<Vec<_>>(),
+            ["client_secret", "client_id"]
+        );
+
+        let json = serde_json::to_string(&client).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"client_secret":"{}","client_id":"{}"}}"#,
+                SensitiveString::from("s3cr3t"),
+                SensitiveString::from("app-1")
+            )
+        );
+    }
+}
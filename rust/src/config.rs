@@ -0,0 +1,406 @@
+//! Loading whole config documents with their secrets already wrapped.
+//!
+//! Dumping the parsed config at startup, or attaching it to an error report,
+//! is one of the most common ways secrets leak: the config was read into a
+//! generic value tree, and the tree prints everything. [`RedactedValue`] is
+//! such a tree, built directly from any serde deserializer (JSON, YAML, TOML,
+//! ...), in which the fields chosen by [`SecretPaths`] are turned into
+//! [`SensitiveString`]s as they are read. Its `Display`, `Debug`, and
+//! `Serialize` output is safe to log.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::config::{RedactedValue, SecretPaths};
+//!
+//! let input = r#"{
+//!     "database": { "host": "db1", "password": "hunter2" },
+//!     "upstreams": [{ "url": "https://api", "auth": "Bearer abc" }]
+//! }"#;
+//!
+//! let paths = SecretPaths::default().path("upstreams.*.auth");
+//! let config = RedactedValue::from_json_str(input, &paths)?;
+//!
+//! let dump = config.to_string();
+//! assert!(dump.contains("db1"));
+//! assert!(!dump.contains("hunter2"));
+//! assert!(!dump.contains("Bearer abc"));
+//! assert_eq!(config.get("database.password").unwrap().as_secret().unwrap().len(), 7);
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+//!
+//! For YAML or TOML, pass the format's deserializer to
+//! [`from_deserializer`](RedactedValue::from_deserializer), e.g.
+//! `serde_yaml::Deserializer::from_str(input)`.
+
+use crate::diff::DEFAULT_SENSITIVE_KEYS;
+use crate::SensitiveString;
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json::Number;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Rules deciding which fields of a document are secrets.
+///
+/// Paths are dotted, with array elements addressed by index, e.g.
+/// `servers.0.dsn`. In a pattern, `*` matches any one segment and `**`
+/// matches any number of segments, so `*.password` matches
+/// `database.password` and `**.password` matches `password` at any depth.
+#[derive(Debug, Clone)]
+pub struct SecretPaths {
+    sensitive_keys: Vec<String>,
+    patterns: Vec<Vec<String>>,
+}
+
+impl Default for SecretPaths {
+    /// Treats values of keys containing any of [`DEFAULT_SENSITIVE_KEYS`] as secrets.
+    fn default() -> Self {
+        Self {
+            sensitive_keys: DEFAULT_SENSITIVE_KEYS
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl SecretPaths {
+    /// Treats nothing as a secret until keys or paths are added.
+    pub fn empty() -> Self {
+        Self {
+            sensitive_keys: Vec::new(),
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Also treats values of any key whose name contains `fragment` (case-insensitive) as secrets.
+    pub fn sensitive_key(mut self, fragment: &str) -> Self {
+        self.sensitive_keys.push(fragment.to_ascii_lowercase());
+        self
+    }
+
+    /// Also treats values at paths matching `pattern` as secrets.
+    pub fn path(mut self, pattern: &str) -> Self {
+        self.patterns
+            .push(pattern.split('.').map(str::to_string).collect());
+        self
+    }
+
+    /// Returns true if the value at `path` is a secret.
+    pub fn matches(&self, path: &[String]) -> bool {
+        if self
+            .patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern, path))
+        {
+            return true;
+        }
+        let key = match path.last() {
+            Some(key) => key.to_ascii_lowercase(),
+            None => return false,
+        };
+        self.sensitive_keys
+            .iter()
+            .any(|fragment| key.contains(fragment.as_str()))
+    }
+}
+
+fn glob_matches(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| glob_matches(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path)) => (first == "*" || first == segment) && glob_matches(rest, path),
+            None => false,
+        },
+    }
+}
+
+/// A config document whose secret fields are [`SensitiveString`]s.
+///
+/// `Display` writes the document as pretty-printed JSON with secrets
+/// redacted; `Serialize` writes the same structure to any format.
+/// Deserializing one directly uses [`SecretPaths::default`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RedactedValue {
+    /// A null or missing value.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A number.
+    Number(Number),
+    /// A string that is not a secret.
+    String(String),
+    /// A secret, from a string or number at a secret path.
+    Secret(SensitiveString),
+    /// An array.
+    Array(Vec<RedactedValue>),
+    /// A table, with keys sorted.
+    Object(BTreeMap<String, RedactedValue>),
+}
+
+impl RedactedValue {
+    /// Reads a document from `deserializer`, wrapping the fields chosen by `paths`.
+    pub fn from_deserializer<'de, D>(deserializer: D, paths: &SecretPaths) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Seed {
+            paths,
+            path: &mut Vec::new(),
+        }
+        .deserialize(deserializer)
+    }
+
+    /// Reads a JSON document, wrapping the fields chosen by `paths`.
+    pub fn from_json_str(input: &str, paths: &SecretPaths) -> Result<Self, serde_json::Error> {
+        let mut deserializer = serde_json::Deserializer::from_str(input);
+        let value = Self::from_deserializer(&mut deserializer, paths)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+
+    /// Returns the value at a dotted path, e.g. `database.password` or `servers.0`.
+    pub fn get(&self, path: &str) -> Option<&RedactedValue> {
+        path.split('.')
+            .try_fold(self, |value, segment| match value {
+                RedactedValue::Object(fields) => fields.get(segment),
+                RedactedValue::Array(items) => items.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+
+    /// Returns the secret, if this is one.
+    pub fn as_secret(&self) -> Option<&SensitiveString> {
+        match self {
+            RedactedValue::Secret(secret) => Some(secret),
+            _ => None,
+        }
+    }
+
+    /// Returns the string, if this is a string that is not a secret.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RedactedValue::String(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RedactedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string_pretty(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+impl Serialize for RedactedValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            RedactedValue::Null => serializer.serialize_unit(),
+            RedactedValue::Bool(value) => serializer.serialize_bool(*value),
+            RedactedValue::Number(number) => number.serialize(serializer),
+            RedactedValue::String(text) => serializer.serialize_str(text),
+            RedactedValue::Secret(secret) => secret.serialize(serializer),
+            RedactedValue::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            RedactedValue::Object(fields) => {
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (key, field) in fields {
+                    map.serialize_entry(key, field)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for RedactedValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::from_deserializer(deserializer, &SecretPaths::default())
+    }
+}
+
+/// Deserializes the value at `path`, and is its own visitor.
+struct Seed<'a> {
+    paths: &'a SecretPaths,
+    path: &'a mut Vec<String>,
+}
+
+impl Seed<'_> {
+    fn child(&mut self) -> Seed<'_> {
+        Seed {
+            paths: self.paths,
+            path: self.path,
+        }
+    }
+
+    fn scalar(&self, text: impl fmt::Display, value: RedactedValue) -> RedactedValue {
+        if self.paths.matches(self.path) {
+            RedactedValue::Secret(SensitiveString::new(text.to_string()))
+        } else {
+            value
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Seed<'_> {
+    type Value = RedactedValue;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<RedactedValue, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Seed<'_> {
+    type Value = RedactedValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a config value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<RedactedValue, E> {
+        Ok(RedactedValue::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<RedactedValue, E> {
+        Ok(self.scalar(value, RedactedValue::Number(value.into())))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<RedactedValue, E> {
+        Ok(self.scalar(value, RedactedValue::Number(value.into())))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<RedactedValue, E> {
+        let number = Number::from_f64(value).map_or(RedactedValue::Null, RedactedValue::Number);
+        Ok(self.scalar(value, number))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<RedactedValue, E> {
+        self.visit_string(value.to_string())
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<RedactedValue, E> {
+        if self.paths.matches(self.path) {
+            Ok(RedactedValue::Secret(SensitiveString::new(value)))
+        } else {
+            Ok(RedactedValue::String(value))
+        }
+    }
+
+    fn visit_unit<E>(self) -> Result<RedactedValue, E> {
+        Ok(RedactedValue::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<RedactedValue, E> {
+        Ok(RedactedValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<RedactedValue, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<RedactedValue, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<RedactedValue, A::Error> {
+        let mut items = Vec::new();
+        loop {
+            self.path.push(items.len().to_string());
+            let item = seq.next_element_seed(self.child());
+            self.path.pop();
+            match item? {
+                Some(item) => items.push(item),
+                None => return Ok(RedactedValue::Array(items)),
+            }
+        }
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<RedactedValue, A::Error> {
+        let mut fields = BTreeMap::new();
+        while let Some(key) = map.next_key::<String>()? {
+            self.path.push(key);
+            let field = map.next_value_seed(self.child());
+            let key = self.path.pop().unwrap_or_default();
+            fields.insert(key, field?);
+        }
+        Ok(RedactedValue::Object(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_and_default_keys() {
+        let path = |dotted: &str| dotted.split('.').map(str::to_string).collect::<Vec<_>>();
+        let paths = SecretPaths::empty()
+            .path("*.dsn")
+            .path("**.auth")
+            .path("servers.0.key");
+        assert!(paths.matches(&path("primary.dsn")));
+        assert!(!paths.matches(&path("a.b.dsn")));
+        assert!(paths.matches(&path("auth")));
+        assert!(paths.matches(&path("a.b.auth")));
+        assert!(paths.matches(&path("servers.0.key")));
+        assert!(!paths.matches(&path("servers.1.key")));
+        assert!(!paths.matches(&path("db.password")));
+        assert!(SecretPaths::default().matches(&path("db.Admin_Password")));
+    }
+
+    #[test]
+    fn test_dump_is_redacted() {
+        let config = RedactedValue::from_json_str(
+            r#"{"pin": 1234, "users": [{"name": "ann", "token": "t-1"}], "debug": true}"#,
+            &SecretPaths::default().path("pin"),
+        )
+        .unwrap();
+        assert_eq!(
+            config.get("users.0.name").and_then(RedactedValue::as_str),
+            Some("ann")
+        );
+        assert_eq!(
+            config
+                .get("users.0.token")
+                .and_then(RedactedValue::as_secret),
+            Some(&SensitiveString::from("t-1"))
+        );
+        assert_eq!(
+            config.get("pin").and_then(RedactedValue::as_secret),
+            Some(&SensitiveString::from("1234"))
+        );
+        for dump in [config.to_string(), format!("{:?}", config)] {
+            assert!(dump.contains("ann"));
+            assert!(!dump.contains("t-1"));
+            assert!(!dump.contains("1234"));
+        }
+    }
+
+    #[test]
+    fn test_other_formats() {
+        let input = "db:\n  password: hunter2\n  port: 5432\n";
+        let config = RedactedValue::from_deserializer(
+            serde_yaml::Deserializer::from_str(input),
+            &SecretPaths::default(),
+        )
+        .unwrap();
+        assert!(config.get("db.password").unwrap().as_secret().is_some());
+
+        let table: RedactedValue = toml::from_str("[db]\npassword = \"hunter2\"\n").unwrap();
+        assert!(!serde_json::to_string(&table).unwrap().contains("hunter2"));
+    }
+}
@@ -0,0 +1,194 @@
+//! Test vectors shared with the other implementations in this repository.
+//!
+//! Every implementation shows a secret as `sha256:` followed by the hex
+//! SHA-256 of its UTF-8 bytes, so a digest in one service's logs can be
+//! matched against another's. This module defines a JSON format for
+//! input/expected-output pairs, the standard set of vectors, and a check of
+//! this process's output against them. The `sensitive-string-vectors`
+//! binary writes the standard set; the copy checked in at
+//! `test-vectors/digests.json` in the repository root is what other
+//! implementations test against.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::conformance::{self, VectorFile};
+//!
+//! let vectors = conformance::standard_vectors();
+//! let json = serde_json::to_string(&vectors)?;
+//! let parsed: VectorFile = serde_json::from_str(&json)?;
+//! assert!(conformance::verify(&parsed).is_empty());
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+//!
+//! # Format
+//!
+//! ```json
+//! {
+//!   "format": 1,
+//!   "algorithm": "sha256",
+//!   "vectors": [
+//!     { "name": "ascii", "input": "foo", "expected": "sha256:2c26b4..." },
+//!     { "name": "long-ascii", "input": "a", "repeat": 1000000, "expected": "sha256:cdc76e..." }
+//!   ]
+//! }
+//! ```
+//!
+//! The plaintext of a vector is `input` repeated `repeat` times (once if
+//! absent), which keeps very long inputs out of the file. `expected` is the
+//! redacted form with default settings.
+
+use crate::{builtin_redact, SensitiveString};
+use serde::{Deserialize, Serialize};
+
+/// The version of the vector file format described in [the module docs](self).
+pub const FORMAT_VERSION: u32 = 1;
+
+/// An input and the redacted form every implementation should show for it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestVector {
+    /// A short, unique, kebab-case name.
+    pub name: String,
+    /// The text that, repeated [`repeat`](Self::repeat) times, is the plaintext.
+    pub input: String,
+    /// How many times `input` is repeated.
+    #[serde(default = "one", skip_serializing_if = "is_one")]
+    pub repeat: usize,
+    /// The expected redacted form.
+    pub expected: String,
+}
+
+fn one() -> usize {
+    1
+}
+
+fn is_one(repeat: &usize) -> bool {
+    *repeat == 1
+}
+
+impl TestVector {
+    /// Creates a vector for `input` repeated `repeat` times, computing `expected`.
+    pub fn new(name: &str, input: &str, repeat: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            input: input.to_string(),
+            repeat,
+            expected: builtin_redact(input.repeat(repeat).as_bytes()),
+        }
+    }
+
+    /// Returns the plaintext the vector describes.
+    pub fn plaintext(&self) -> String {
+        self.input.repeat(self.repeat)
+    }
+}
+
+/// A versioned set of test vectors, as stored in JSON.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorFile {
+    /// The format version; see [`FORMAT_VERSION`].
+    pub format: u32,
+    /// The digest algorithm, `sha256`.
+    pub algorithm: String,
+    /// The vectors.
+    pub vectors: Vec<TestVector>,
+}
+
+/// Returns the standard vectors: empty, ASCII, control characters, several
+/// scripts and emoji, and inputs up to a million bytes.
+pub fn standard_vectors() -> VectorFile {
+    let vectors = [
+        ("empty", "", 1),
+        ("ascii", "foo", 1),
+        ("password", "password123", 1),
+        ("whitespace", "  leading and trailing  ", 1),
+        ("control-characters", "line1\nline2\r\n\ttab\0nul", 1),
+        ("json-special", "{\"quote\":\"\\\"\",\"slash\":\"\\\\\"}", 1),
+        ("latin-1", "pässwörd-ñ", 1),
+        ("cjk", "密码パスワード비밀번호", 1),
+        ("right-to-left", "كلمة السر", 1),
+        ("combining-marks", "e\u{301}a\u{308}", 1),
+        ("emoji", "🔑🗝️👩‍💻", 1),
+        ("astral-plane", "𝔰𝔢𝔠𝔯𝔢𝔱", 1),
+        ("long-ascii", "a", 1_000_000),
+        ("long-digits", "0123456789", 10_000),
+        ("long-unicode", "ключ🔑", 50_000),
+    ]
+    .into_iter()
+    .map(|(name, input, repeat)| TestVector::new(name, input, repeat))
+    .collect();
+    VectorFile {
+        format: FORMAT_VERSION,
+        algorithm: "sha256".to_string(),
+        vectors,
+    }
+}
+
+/// Returns the vectors whose plaintext this process does not show as expected.
+///
+/// Compares against what a `SensitiveString` actually displays, so a global
+/// hasher or redaction policy that changes the output shows up as failures.
+pub fn verify(file: &VectorFile) -> Vec<&TestVector> {
+    file.vectors
+        .iter()
+        .filter(|vector| {
+            SensitiveString::unregistered(vector.plaintext()).to_string() != vector.expected
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::RedactionPolicy;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_checked_in_vectors_match() {
+        let checked_in: VectorFile =
+            serde_json::from_str(include_str!("../../test-vectors/digests.json")).unwrap();
+        assert_eq!(checked_in, standard_vectors());
+        assert!(verify(&checked_in).is_empty());
+        // Known values, also used by the TypeScript tests.
+        let expected = |name: &str| {
+            checked_in
+                .vectors
+                .iter()
+                .find(|vector| vector.name == name)
+                .map(|vector| vector.expected.as_str())
+        };
+        assert_eq!(
+            expected("ascii"),
+            Some("sha256:2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae")
+        );
+        assert_eq!(
+            expected("long-ascii"),
+            Some("sha256:cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0")
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_output_never_contains_plaintext(plaintext in ".{8,200}") {
+            for policy in [RedactionPolicy::Hash, RedactionPolicy::Placeholder] {
+                let secret = SensitiveString::from(plaintext.as_str()).with_policy(policy);
+                for shown in [
+                    secret.to_string(),
+                    format!("{:#}", secret),
+                    format!("{:?}", secret),
+                    serde_json::to_string(&secret).unwrap(),
+                ] {
+                    prop_assert!(!shown.contains(&plaintext));
+                }
+            }
+        }
+
+        #[test]
+        fn prop_digest_matches_vector_format(plaintext in any::<String>()) {
+            let vector = TestVector::new("arbitrary", &plaintext, 1);
+            prop_assert_eq!(SensitiveString::from(plaintext).to_string(), vector.expected.clone());
+            prop_assert!(vector.expected.starts_with("sha256:"));
+            prop_assert_eq!(vector.expected.len(), "sha256:".len() + 64);
+        }
+    }
+}
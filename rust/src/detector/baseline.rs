@@ -0,0 +1,89 @@
+//! Baselines of acknowledged findings.
+
+use super::Finding;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// A finding that has been looked at and accepted.
+///
+/// The line number is left out so that edits elsewhere in the file do not
+/// bring the finding back.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BaselineEntry {
+    /// The path the blob was scanned as.
+    pub path: PathBuf,
+    /// The name of the rule that matched.
+    pub rule: String,
+    /// The finding's [`fingerprint`](Finding::fingerprint).
+    pub fingerprint: String,
+}
+
+impl From<&Finding> for BaselineEntry {
+    fn from(finding: &Finding) -> Self {
+        Self {
+            path: finding.path.clone(),
+            rule: finding.rule.to_string(),
+            fingerprint: finding.fingerprint.clone(),
+        }
+    }
+}
+
+/// A set of acknowledged findings, stored by digest and never by value.
+///
+/// With `serde` it is a JSON list of `{path, rule, fingerprint}` objects,
+/// sorted so that regenerating a baseline gives a small diff. Without the
+/// `hash` feature fingerprints only record the length of the match, so a
+/// baseline also hides other secrets of the same length, rule, and path.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Baseline {
+    entries: BTreeSet<BaselineEntry>,
+}
+
+impl Baseline {
+    /// Creates an empty baseline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accepts `finding`, returning false if it was already accepted.
+    pub fn insert(&mut self, finding: &Finding) -> bool {
+        self.entries.insert(finding.into())
+    }
+
+    /// Returns true if `finding` has been accepted.
+    pub fn contains(&self, finding: &Finding) -> bool {
+        self.entries.contains(&BaselineEntry::from(finding))
+    }
+
+    /// Removes the accepted findings from `findings`, keeping the new ones.
+    pub fn filter(&self, mut findings: Vec<Finding>) -> Vec<Finding> {
+        findings.retain(|finding| !self.contains(finding));
+        findings
+    }
+
+    /// Returns the accepted findings, sorted by path, rule, and fingerprint.
+    pub fn entries(&self) -> impl Iterator<Item = &BaselineEntry> {
+        self.entries.iter()
+    }
+
+    /// Returns the number of accepted findings.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no findings have been accepted.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<'a> FromIterator<&'a Finding> for Baseline {
+    fn from_iter<I: IntoIterator<Item = &'a Finding>>(findings: I) -> Self {
+        Self {
+            entries: findings.into_iter().map(BaselineEntry::from).collect(),
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Serializable rule packs and the packs built into the crate.
+
+use super::InvalidPattern;
+use std::fmt;
+
+/// How serious a match of a rule is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// Likely noise, such as a random identifier.
+    Low,
+    /// A credential of unknown provenance.
+    #[default]
+    Medium,
+    /// A credential in a provider's documented format.
+    High,
+    /// A credential that grants broad access on its own.
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        })
+    }
+}
+
+/// One rule of a [`RulePack`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuleSpec {
+    /// The name reported in each [`Detection`](super::Detection).
+    pub name: String,
+    /// A regular expression. If it has capture groups, the first group that
+    /// matched is the secret; otherwise the whole match is.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pattern: Option<String>,
+    /// Words of which at least one must appear in the text, ignoring ASCII
+    /// case, for the rule to run at all. Empty means the rule always runs.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub keywords: Vec<String>,
+    /// Bits of entropy per character a match must exceed. A rule without a
+    /// pattern needs one, and then matches base64 runs containing a digit.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub entropy: Option<f64>,
+    /// For a rule without a pattern, the shortest base64 run checked; 32 if absent.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub min_len: Option<usize>,
+    /// The severity reported in each detection.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub severity: Severity,
+}
+
+/// A named set of detector rules, loadable from JSON or TOML with `serde`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RulePack {
+    /// The pack name, e.g. `cloud-keys`.
+    pub name: String,
+    /// The rules, in the order they are added to a detector.
+    pub rules: Vec<RuleSpec>,
+}
+
+/// Error returned when a [`RuleSpec`] cannot be added to a detector.
+#[derive(Debug)]
+pub enum RulePackError {
+    /// The rule's pattern is not a valid regular expression.
+    Pattern(InvalidPattern),
+    /// The rule, named here, has neither a pattern nor an entropy threshold.
+    NoMatcher(String),
+}
+
+impl fmt::Display for RulePackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulePackError::Pattern(err) => err.fmt(f),
+            RulePackError::NoMatcher(name) => write!(
+                f,
+                "detector rule `{}` has neither a pattern nor an entropy threshold",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RulePackError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RulePackError::Pattern(err) => Some(err),
+            RulePackError::NoMatcher(_) => None,
+        }
+    }
+}
+
+impl From<InvalidPattern> for RulePackError {
+    fn from(err: InvalidPattern) -> Self {
+        RulePackError::Pattern(err)
+    }
+}
+
+struct BuiltinRule {
+    name: &'static str,
+    pattern: Option<&'static str>,
+    keywords: &'static [&'static str],
+    entropy: Option<f64>,
+    min_len: Option<usize>,
+    severity: Severity,
+}
+
+const fn pattern(
+    name: &'static str,
+    severity: Severity,
+    keywords: &'static [&'static str],
+    pattern: &'static str,
+) -> BuiltinRule {
+    BuiltinRule {
+        name,
+        pattern: Some(pattern),
+        keywords,
+        entropy: None,
+        min_len: None,
+        severity,
+    }
+}
+
+const BUILTIN: [(&str, &[BuiltinRule]); 4] = [
+    (
+        "default",
+        &[
+            pattern(
+                "aws-access-key-id",
+                Severity::High,
+                &[],
+                r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+            ),
+            pattern(
+                "sk-prefixed-key",
+                Severity::High,
+                &[],
+                r"\bsk[-_][A-Za-z0-9_-]{20,}",
+            ),
+            pattern(
+                "jwt",
+                Severity::Medium,
+                &[],
+                r"\beyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+",
+            ),
+            BuiltinRule {
+                name: "high-entropy",
+                pattern: None,
+                keywords: &[],
+                entropy: Some(4.3),
+                min_len: Some(32),
+                severity: Severity::Low,
+            },
+        ],
+    ),
+    (
+        "cloud-keys",
+        &[
+            pattern(
+                "aws-access-key-id",
+                Severity::High,
+                &["akia", "asia"],
+                r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+            ),
+            pattern(
+                "aws-secret-access-key",
+                Severity::Critical,
+                &["aws"],
+                r#"(?i:aws_?secret_?access_?key)["']?\s*[:=]\s*["']?([A-Za-z0-9/+]{40})(?:[^A-Za-z0-9/+]|$)"#,
+            ),
+            pattern(
+                "gcp-api-key",
+                Severity::High,
+                &["aiza"],
+                r"\bAIza[0-9A-Za-z_-]{35}\b",
+            ),
+            pattern(
+                "azure-storage-account-key",
+                Severity::Critical,
+                &["accountkey"],
+                r"(?i:accountkey)=([A-Za-z0-9+/]{86}==)",
+            ),
+        ],
+    ),
+    (
+        "vcs-tokens",
+        &[
+            pattern(
+                "github-token",
+                Severity::High,
+                &["ghp_", "gho_", "ghu_", "ghs_", "ghr_"],
+                r"\bgh[pousr]_[A-Za-z0-9]{36}\b",
+            ),
+            pattern(
+                "github-fine-grained-token",
+                Severity::High,
+                &["github_pat_"],
+                r"\bgithub_pat_[A-Za-z0-9_]{82}\b",
+            ),
+            pattern(
+                "gitlab-token",
+                Severity::High,
+                &["glpat-"],
+                r"\bglpat-[A-Za-z0-9_-]{20}\b",
+            ),
+        ],
+    ),
+    (
+        "database-urls",
+        &[pattern(
+            "database-url-password",
+            Severity::High,
+            &["://"],
+            r"\b(?:postgres(?:ql)?|mysql|mariadb|mongodb(?:\+srv)?|rediss?|amqps?)://[^:@/\s]*:([^@/\s]+)@",
+        )],
+    ),
+];
+
+/// Checks the structure of the built-in packs while compiling the crate.
+/// Their patterns are compiled by the test suite.
+const _: () = {
+    let mut pack = 0;
+    while pack < BUILTIN.len() {
+        let rules = BUILTIN[pack].1;
+        let mut rule = 0;
+        while rule < rules.len() {
+            assert!(!rules[rule].name.is_empty(), "built-in rule without a name");
+            assert!(
+                rules[rule].pattern.is_some() || rules[rule].entropy.is_some(),
+                "built-in rule without a pattern or entropy threshold"
+            );
+            rule += 1;
+        }
+        pack += 1;
+    }
+};
+
+impl RulePack {
+    /// Returns the built-in pack called `name`; see [`builtin_names`](Self::builtin_names).
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (name, rules) = BUILTIN.iter().find(|(pack, _)| *pack == name)?;
+        Some(Self {
+            name: name.to_string(),
+            rules: rules
+                .iter()
+                .map(|rule| RuleSpec {
+                    name: rule.name.to_string(),
+                    pattern: rule.pattern.map(str::to_string),
+                    keywords: rule.keywords.iter().map(|k| k.to_string()).collect(),
+                    entropy: rule.entropy,
+                    min_len: rule.min_len,
+                    severity: rule.severity,
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns the names of the built-in packs: `default`, `cloud-keys`,
+    /// `vcs-tokens`, and `database-urls`.
+    pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+        BUILTIN.iter().map(|(name, _)| *name)
+    }
+}
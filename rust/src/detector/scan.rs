@@ -0,0 +1,186 @@
+//! Scanning file contents, as a pre-commit hook does.
+
+use super::{Baseline, Detector, RulePack, Severity};
+use crate::redact_bytes;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// How much of a blob is checked for a NUL byte.
+const BINARY_PROBE: usize = 8 * 1024;
+
+const LOCK_FILES: [&str; 9] = [
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "poetry.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+];
+
+/// A detection in a scanned blob.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// The path the blob was scanned as.
+    pub path: PathBuf,
+    /// The name of the rule that matched.
+    pub rule: Arc<str>,
+    /// The severity of the rule that matched.
+    pub severity: Severity,
+    /// The 1-based line of the start of the match.
+    pub line: usize,
+    /// The byte range of the match in the blob.
+    pub range: Range<usize>,
+    /// The redacted form of the matched text, as `Display` shows a secret.
+    pub fingerprint: String,
+}
+
+impl fmt::Display for Finding {
+    /// `path:line: rule (severity) fingerprint`, never the matched text.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {} ({}) {}",
+            self.path.display(),
+            self.line,
+            self.rule,
+            self.severity,
+            self.fingerprint
+        )
+    }
+}
+
+/// Why a [`BlobScanner`] did not look at a blob.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+    /// A NUL byte in the first 8 KiB, or the blob is not UTF-8.
+    Binary,
+    /// The blob is larger than [`BlobScanner::max_size`].
+    TooLarge,
+    /// The file name is on the skip list, e.g. `Cargo.lock`.
+    Skipped,
+}
+
+/// A detector with the checks a pre-commit hook needs around it.
+///
+/// Blobs that are binary, larger than the size limit (1 MiB by default), or
+/// named like a lock file are not scanned: they are slow to scan and full of
+/// hashes that look like keys. Findings in a [`Baseline`] given to
+/// [`baseline`](Self::baseline) are not reported.
+#[derive(Clone, Debug)]
+pub struct BlobScanner {
+    detector: Detector,
+    max_size: usize,
+    skip: Vec<String>,
+    baseline: Baseline,
+}
+
+impl Default for BlobScanner {
+    /// Scans with every built-in rule pack.
+    fn default() -> Self {
+        let detector = RulePack::builtin_names()
+            .filter_map(RulePack::builtin)
+            .try_fold(Detector::empty(), |detector, pack| {
+                detector.with_pack(&pack)
+            })
+            .expect("built-in packs are valid");
+        Self::with_detector(detector)
+    }
+}
+
+impl BlobScanner {
+    /// Creates a scanner with every built-in rule pack.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a scanner using `detector`, e.g. one built from a custom pack.
+    pub fn with_detector(detector: Detector) -> Self {
+        Self {
+            detector,
+            max_size: 1024 * 1024,
+            skip: LOCK_FILES.iter().map(|name| name.to_string()).collect(),
+            baseline: Baseline::new(),
+        }
+    }
+
+    /// Sets the largest blob, in bytes, that is scanned.
+    pub fn max_size(mut self, bytes: usize) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Adds a file name (not a path) whose blobs are never scanned.
+    pub fn skip_file_name(mut self, name: &str) -> Self {
+        self.skip.push(name.to_string());
+        self
+    }
+
+    /// Stops reporting the findings accepted in `baseline`.
+    pub fn baseline(mut self, baseline: Baseline) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Returns why `bytes` at `path` would not be scanned, if it would not.
+    pub fn skip_reason(&self, path: &Path, bytes: &[u8]) -> Option<SkipReason> {
+        let name = path.file_name().and_then(|name| name.to_str());
+        if name.is_some_and(|name| self.skip.iter().any(|skip| skip == name)) {
+            Some(SkipReason::Skipped)
+        } else if bytes.len() > self.max_size {
+            Some(SkipReason::TooLarge)
+        } else if bytes[..bytes.len().min(BINARY_PROBE)].contains(&0)
+            || std::str::from_utf8(bytes).is_err()
+        {
+            Some(SkipReason::Binary)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the findings in `bytes` that are not in the baseline, or none
+    /// if the blob is skipped.
+    pub fn scan(&self, path: &Path, bytes: &[u8]) -> Vec<Finding> {
+        if self.skip_reason(path, bytes).is_some() {
+            return Vec::new();
+        }
+        let text = std::str::from_utf8(bytes).expect("checked by skip_reason");
+        let (mut line, mut counted) = (1, 0);
+        self.detector
+            .detect(text)
+            .into_iter()
+            .map(|detection| {
+                line += count_newlines(&bytes[counted..detection.range.start]);
+                counted = detection.range.start;
+                Finding {
+                    path: path.to_path_buf(),
+                    fingerprint: redact_bytes(&bytes[detection.range.clone()]),
+                    rule: detection.rule,
+                    severity: detection.severity,
+                    line,
+                    range: detection.range,
+                }
+            })
+            .filter(|finding| !self.baseline.contains(finding))
+            .collect()
+    }
+}
+
+fn count_newlines(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&b| b == b'\n').count()
+}
+
+/// Scans a blob with [`BlobScanner::new`]'s settings.
+///
+/// `path` is only used to skip lock files and to label findings; nothing is
+/// read from disk.
+pub fn scan_blob(path: impl AsRef<Path>, bytes: &[u8]) -> Vec<Finding> {
+    static SCANNER: OnceLock<BlobScanner> = OnceLock::new();
+    SCANNER
+        .get_or_init(BlobScanner::default)
+        .scan(path.as_ref(), bytes)
+}
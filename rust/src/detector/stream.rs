@@ -0,0 +1,157 @@
+//! Detecting across the chunks of a stream.
+
+use super::{Detection, Detector};
+use std::io::{self, Read};
+
+const DEFAULT_WINDOW: usize = 4 * 1024;
+const READ_SIZE: usize = 64 * 1024;
+
+/// Runs a [`Detector`] over input that arrives in chunks.
+///
+/// The last `window` bytes of each chunk (4 KiB by default) are held back
+/// and scanned again with the next one, so a match split across chunks is
+/// still found, and memory stays bounded by the window plus one chunk.
+/// Ranges in the returned detections are offsets into the whole stream.
+///
+/// A match longer than the window may be reported in pieces, and a rule's
+/// keywords must appear within the window before its match. Bytes that are
+/// not UTF-8 are scanned as NUL, so they end a match but never start one.
+#[derive(Debug)]
+pub struct StreamDetector<'a> {
+    detector: &'a Detector,
+    window: usize,
+    buffer: Vec<u8>,
+    /// The stream offset of `buffer[0]`.
+    offset: usize,
+    /// The stream offset where the last reported detection ended.
+    reported: usize,
+}
+
+impl<'a> StreamDetector<'a> {
+    fn new(detector: &'a Detector) -> Self {
+        Self {
+            detector,
+            window: DEFAULT_WINDOW,
+            buffer: Vec::new(),
+            offset: 0,
+            reported: 0,
+        }
+    }
+
+    /// Sets how many bytes are held back between chunks.
+    pub fn window(mut self, bytes: usize) -> Self {
+        self.window = bytes;
+        self
+    }
+
+    /// Scans the next chunk, returning the detections that can no longer
+    /// change. Matches that might continue into the next chunk are held back.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<Detection> {
+        self.buffer.extend_from_slice(chunk);
+        self.scan(false)
+    }
+
+    /// Scans what is left at the end of the stream.
+    pub fn finish(mut self) -> Vec<Detection> {
+        self.scan(true)
+    }
+
+    fn scan(&mut self, last: bool) -> Vec<Detection> {
+        let len = self.replace_invalid_utf8(last);
+        let text = std::str::from_utf8(&self.buffer[..len]).expect("invalid UTF-8 was replaced");
+        let mut keep_from = if last {
+            len
+        } else {
+            len.saturating_sub(self.window)
+        };
+        let mut found = Vec::new();
+        for mut detection in self.detector.detect(text) {
+            if self.offset + detection.range.start < self.reported {
+                continue;
+            }
+            if !last && detection.range.end == len && len - detection.range.start <= self.window {
+                keep_from = keep_from.min(detection.range.start);
+                break;
+            }
+            detection.range =
+                self.offset + detection.range.start..self.offset + detection.range.end;
+            self.reported = detection.range.end;
+            found.push(detection);
+        }
+        self.buffer.drain(..keep_from);
+        self.offset += keep_from;
+        found
+    }
+
+    /// Overwrites invalid UTF-8 in the buffer with NUL bytes, returning the
+    /// length of the part that can be scanned. An incomplete character at the
+    /// end is left for the next chunk, unless this is the last one.
+    fn replace_invalid_utf8(&mut self, last: bool) -> usize {
+        let mut start = 0;
+        loop {
+            let err = match std::str::from_utf8(&self.buffer[start..]) {
+                Ok(_) => return self.buffer.len(),
+                Err(err) => err,
+            };
+            let invalid = start + err.valid_up_to();
+            match err.error_len() {
+                Some(bad) => {
+                    self.buffer[invalid..invalid + bad].fill(0);
+                    start = invalid + bad;
+                }
+                None if last => {
+                    self.buffer[invalid..].fill(0);
+                    return self.buffer.len();
+                }
+                None => return invalid,
+            }
+        }
+    }
+}
+
+impl Detector {
+    /// Returns a [`StreamDetector`] for scanning input chunk by chunk.
+    pub fn stream(&self) -> StreamDetector<'_> {
+        StreamDetector::new(self)
+    }
+
+    /// Reads `reader` to the end in chunks, returning every detection with
+    /// its offset in the stream.
+    pub fn detect_reader(&self, mut reader: impl Read) -> io::Result<Vec<Detection>> {
+        let mut stream = self.stream();
+        let mut found = Vec::new();
+        let mut chunk = vec![0; READ_SIZE];
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => found.extend(stream.push(&chunk[..read])),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        found.extend(stream.finish());
+        Ok(found)
+    }
+
+    /// Like [`detect_reader`](Self::detect_reader), for a Tokio `AsyncRead`.
+    #[cfg(feature = "tokio")]
+    pub async fn detect_async_reader(
+        &self,
+        mut reader: impl tokio::io::AsyncRead + Unpin,
+    ) -> io::Result<Vec<Detection>> {
+        let mut stream = self.stream();
+        let mut found = Vec::new();
+        let mut chunk = vec![0; READ_SIZE];
+        loop {
+            let mut buf = tokio::io::ReadBuf::new(&mut chunk);
+            std::future::poll_fn(|cx| std::pin::Pin::new(&mut reader).poll_read(cx, &mut buf))
+                .await?;
+            if buf.filled().is_empty() {
+                break;
+            }
+            found.extend(stream.push(buf.filled()));
+        }
+        found.extend(stream.finish());
+        Ok(found)
+    }
+}
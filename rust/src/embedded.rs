@@ -0,0 +1,590 @@
+//! Strings with embedded sensitive regions.
+//!
+//! A shell command, connection string, or config blob is mostly safe to show
+//! but contains a few secrets. [`SensitiveEmbedded`] keeps the whole string
+//! and remembers which byte ranges are sensitive; `Display`, `Debug`, and
+//! `Serialize` replace only those ranges with their hashes.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{SensitiveEmbedded, SensitiveString};
+//!
+//! let password = SensitiveString::from("hunter2");
+//! let mut command = SensitiveEmbedded::new();
+//! command
+//!     .push_str("psql -h db.internal --password=")
+//!     .push_sensitive(&password);
+//!
+//! let shown = command.to_string();
+//! assert_eq!(shown, format!("psql -h db.internal --password={}", password));
+//! assert_eq!(&*command.exposed(), "psql -h db.internal --password=hunter2");
+//! ```
+//!
+//! The [`sensitive_embed!`](crate::sensitive_embed) macro builds the same
+//! value from a template, with `{#secret name}` marking sensitive arguments:
+//!
+//! ```
+//! use sensitive_string::{sensitive_embed, SensitiveString};
+//!
+//! let password = SensitiveString::from("hunter2");
+//! let command = sensitive_embed!(
+//!     "psql -h {host} -p {port} --password={#secret pw}",
+//!     host = "db.internal",
+//!     port = 5432,
+//!     pw = password,
+//! );
+//! assert_eq!(&*command.exposed(), "psql -h db.internal -p 5432 --password=hunter2");
+//! ```
+
+use crate::{redact_plaintext, SensitiveString};
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+
+/// A string in which some byte ranges are sensitive.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct SensitiveEmbedded {
+    pub(crate) text: String,
+    regions: Vec<Range<usize>>,
+}
+
+/// Error returned when a sensitive region is not valid for the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidRegion {
+    region: Range<usize>,
+}
+
+impl InvalidRegion {
+    /// Returns the rejected byte range.
+    pub fn region(&self) -> Range<usize> {
+        self.region.clone()
+    }
+}
+
+impl fmt::Display for InvalidRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sensitive region {}..{} is out of bounds, not on a character boundary, or overlaps another region",
+            self.region.start, self.region.end
+        )
+    }
+}
+
+impl std::error::Error for InvalidRegion {}
+
+/// Error returned when a template passed to [`SensitiveEmbedded::from_template`] is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{` was not closed, or a lone `}` appeared.
+    Unbalanced,
+    /// A placeholder names an argument that was not supplied.
+    UnknownArgument(String),
+    /// A placeholder uses a `#` directive other than `#secret`.
+    UnknownDirective(String),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Unbalanced => write!(f, "unbalanced braces in template"),
+            TemplateError::UnknownArgument(name) => {
+                write!(f, "template refers to unknown argument `{}`", name)
+            }
+            TemplateError::UnknownDirective(directive) => {
+                write!(f, "unknown template directive `#{}`", directive)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// A value that can be substituted into a [`SensitiveEmbedded`] template.
+///
+/// `SensitiveString` arguments are always sensitive, even in a plain `{name}`
+/// placeholder. Other values are sensitive only when the placeholder says
+/// `{#secret name}`.
+pub trait EmbedArg {
+    /// Returns the text to substitute.
+    fn embed_text(&self) -> Cow<'_, str>;
+
+    /// Returns true if the value is sensitive regardless of the placeholder.
+    fn is_sensitive(&self) -> bool {
+        false
+    }
+}
+
+impl EmbedArg for SensitiveString {
+    #[track_caller]
+    fn embed_text(&self) -> Cow<'_, str> {
+        self.record_access("embed_text");
+        Cow::Borrowed(&self.value)
+    }
+
+    fn is_sensitive(&self) -> bool {
+        true
+    }
+}
+
+impl EmbedArg for str {
+    fn embed_text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl EmbedArg for String {
+    fn embed_text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl<T: EmbedArg + ?Sized> EmbedArg for &T {
+    fn embed_text(&self) -> Cow<'_, str> {
+        (**self).embed_text()
+    }
+
+    fn is_sensitive(&self) -> bool {
+        (**self).is_sensitive()
+    }
+}
+
+macro_rules! embed_arg_via_to_string {
+    ($($ty:ty),*) => {
+        $(impl EmbedArg for $ty {
+            fn embed_text(&self) -> Cow<'_, str> {
+                Cow::Owned(self.to_string())
+            }
+        })*
+    };
+}
+
+embed_arg_via_to_string!(
+    bool, char, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64
+);
+
+impl SensitiveEmbedded {
+    /// Builds a value from a template and named arguments.
+    ///
+    /// Placeholders are `{name}` or `{#secret name}`; `{{` and `}}` are
+    /// literal braces. This is what [`sensitive_embed!`](crate::sensitive_embed)
+    /// expands to.
+    pub fn from_template(
+        template: &str,
+        args: &[(&str, &dyn EmbedArg)],
+    ) -> Result<Self, TemplateError> {
+        let mut embedded = Self::new();
+        let mut rest = template;
+        while let Some(index) = rest.find(['{', '}']) {
+            embedded.text.push_str(&rest[..index]);
+            let brace = rest.as_bytes()[index];
+            rest = &rest[index + 1..];
+
+            if let Some(after) = rest.strip_prefix(brace as char) {
+                embedded.text.push(brace as char);
+                rest = after;
+                continue;
+            }
+            if brace == b'}' {
+                return Err(TemplateError::Unbalanced);
+            }
+
+            let close = rest.find('}').ok_or(TemplateError::Unbalanced)?;
+            let placeholder = rest[..close].trim();
+            rest = &rest[close + 1..];
+
+            let (forced, name) = match placeholder.strip_prefix('#') {
+                Some(directive) => match directive.split_once(char::is_whitespace) {
+                    Some(("secret", name)) => (true, name.trim()),
+                    _ => {
+                        let directive = directive.split_whitespace().next().unwrap_or_default();
+                        return Err(TemplateError::UnknownDirective(directive.to_string()));
+                    }
+                },
+                None => (false, placeholder),
+            };
+
+            let (_, arg) = args
+                .iter()
+                .find(|(arg_name, _)| *arg_name == name)
+                .ok_or_else(|| TemplateError::UnknownArgument(name.to_string()))?;
+            let text = arg.embed_text();
+            let start = embedded.text.len();
+            embedded.text.push_str(&text);
+            if (forced || arg.is_sensitive()) && !text.is_empty() {
+                embedded.regions.push(start..embedded.text.len());
+            }
+        }
+        embedded.text.push_str(rest);
+        Ok(embedded)
+    }
+
+    /// Creates an empty string with no sensitive regions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wraps existing text, marking the given byte ranges as sensitive.
+    ///
+    /// Ranges must lie on character boundaries and must not overlap.
+    pub fn with_regions(
+        text: impl Into<String>,
+        regions: impl IntoIterator<Item = Range<usize>>,
+    ) -> Result<Self, InvalidRegion> {
+        let mut embedded = Self {
+            text: text.into(),
+            regions: Vec::new(),
+        };
+        for region in regions {
+            embedded.mark_sensitive(region)?;
+        }
+        Ok(embedded)
+    }
+
+    /// Appends text that is safe to display.
+    pub fn push_str(&mut self, text: &str) -> &mut Self {
+        self.text.push_str(text);
+        self
+    }
+
+    /// Appends a secret as a sensitive region.
+    #[track_caller]
+    pub fn push_sensitive(&mut self, secret: &SensitiveString) -> &mut Self {
+        secret.record_access("push_sensitive");
+        let start = self.text.len();
+        self.text.push_str(&secret.value);
+        if !secret.value.is_empty() {
+            self.regions.push(start..self.text.len());
+        }
+        self
+    }
+
+    /// Marks an existing byte range of the text as sensitive.
+    pub fn mark_sensitive(&mut self, region: Range<usize>) -> Result<&mut Self, InvalidRegion> {
+        let valid = region.start <= region.end
+            && region.end <= self.text.len()
+            && self.text.is_char_boundary(region.start)
+            && self.text.is_char_boundary(region.end)
+            && !self
+                .regions
+                .iter()
+                .any(|r| r.start < region.end && region.start < r.end);
+        if !valid {
+            return Err(InvalidRegion { region });
+        }
+        if !region.is_empty() {
+            let index = self.regions.partition_point(|r| r.start < region.start);
+            self.regions.insert(index, region);
+        }
+        Ok(self)
+    }
+
+    /// Returns the sensitive byte ranges, in order.
+    pub fn regions(&self) -> &[Range<usize>] {
+        &self.regions
+    }
+
+    /// Explicitly retrieves the full plaintext, including sensitive regions.
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
+    pub fn get_value(&self) -> &str {
+        crate::record_plaintext_read(None, "get_value");
+        &self.text
+    }
+
+    /// Returns the text with each sensitive region replaced by its hash.
+    pub fn redacted(&self) -> String {
+        self.to_string()
+    }
+
+    fn write_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cursor = 0;
+        for region in &self.regions {
+            f.write_str(&self.text[cursor..region.start])?;
+            f.write_str(&redact_plaintext(&self.text[region.clone()]))?;
+            cursor = region.end;
+        }
+        f.write_str(&self.text[cursor..])
+    }
+}
+
+/// Shows the text with sensitive regions replaced by their hashes.
+impl fmt::Display for SensitiveEmbedded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_redacted(f)
+    }
+}
+
+impl fmt::Debug for SensitiveEmbedded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SensitiveEmbedded(\"")?;
+        self.write_redacted(f)?;
+        f.write_str("\")")
+    }
+}
+
+impl From<SensitiveString> for SensitiveEmbedded {
+    fn from(secret: SensitiveString) -> Self {
+        let mut embedded = Self::new();
+        embedded.push_sensitive(&secret);
+        embedded
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SensitiveEmbedded {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// Checks a template the way [`SensitiveEmbedded::from_template`] parses
+/// it, panicking on the errors it would return. `sensitive_embed!` calls this
+/// in a constant, so a bad template fails to compile.
+#[doc(hidden)]
+pub const fn validate_template(template: &str, names: &[&str]) {
+    let bytes = template.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let brace = bytes[i];
+        if brace != b'{' && brace != b'}' {
+            i += 1;
+            continue;
+        }
+        if i + 1 < bytes.len() && bytes[i + 1] == brace {
+            i += 2;
+            continue;
+        }
+        if brace == b'}' {
+            panic!("unbalanced braces in sensitive_embed! template");
+        }
+        let mut end = i + 1;
+        while end < bytes.len() && bytes[end] != b'}' {
+            end += 1;
+        }
+        if end == bytes.len() {
+            panic!("unbalanced braces in sensitive_embed! template");
+        }
+        let (mut start, close) = (i + 1, end);
+        while start < end && bytes[start].is_ascii_whitespace() {
+            start += 1;
+        }
+        while end > start && bytes[end - 1].is_ascii_whitespace() {
+            end -= 1;
+        }
+        if start < end && bytes[start] == b'#' {
+            let directive = b"#secret";
+            let mut matched = 0;
+            while matched < directive.len()
+                && start + matched < end
+                && bytes[start + matched] == directive[matched]
+            {
+                matched += 1;
+            }
+            if matched < directive.len()
+                || start + matched == end
+                || !bytes[start + matched].is_ascii_whitespace()
+            {
+                panic!("unknown directive in sensitive_embed! template");
+            }
+            start += matched;
+            while start < end && bytes[start].is_ascii_whitespace() {
+                start += 1;
+            }
+        }
+        if !names_contain(names, bytes, start, end) {
+            panic!("sensitive_embed! template names an argument that was not supplied");
+        }
+        i = close + 1;
+    }
+}
+
+/// Returns true if `bytes[start..end]` is one of `names`.
+const fn names_contain(names: &[&str], bytes: &[u8], start: usize, end: usize) -> bool {
+    let mut n = 0;
+    while n < names.len() {
+        let name = names[n].as_bytes();
+        if name.len() == end - start {
+            let mut k = 0;
+            while k < name.len() && name[k] == bytes[start + k] {
+                k += 1;
+            }
+            if k == name.len() {
+                return true;
+            }
+        }
+        n += 1;
+    }
+    false
+}
+
+/// Builds a [`SensitiveEmbedded`] from a template with named arguments.
+///
+/// `{name}` substitutes an argument; `{#secret name}` substitutes it as a
+/// sensitive region. `SensitiveString` arguments are always sensitive.
+/// Arguments may be strings, numbers, `bool`, `char`, `SensitiveString`, or
+/// anything else implementing [`EmbedArg`].
+///
+/// The template must be a string literal. Like a `format!` string, it is
+/// checked at compile time: unbalanced braces, an unknown `#` directive, or a
+/// placeholder naming an argument that was not supplied is a compile error.
+/// Use [`SensitiveEmbedded::from_template`] for templates known only at run
+/// time.
+///
+/// # Example
+///
+/// ```
+/// use sensitive_string::{sensitive_embed, SensitiveString};
+///
+/// let key = SensitiveString::from("AKIAEXAMPLE");
+/// let line = sensitive_embed!("aws configure set aws_access_key_id {k}", k = key);
+/// assert!(line.to_string().ends_with(&key.to_string()));
+/// ```
+///
+/// A typo in a placeholder does not compile:
+///
+/// ```compile_fail
+/// use sensitive_string::{sensitive_embed, SensitiveString};
+///
+/// let key = SensitiveString::from("AKIAEXAMPLE");
+/// let line = sensitive_embed!("aws configure set aws_access_key_id {key}", k = key);
+/// ```
+#[macro_export]
+macro_rules! sensitive_embed {
+    ($template:literal $(, $name:ident = $value:expr)* $(,)?) => {{
+        const _: () = $crate::__private::validate_template($template, &[$(stringify!($name)),*]);
+        $crate::SensitiveEmbedded::from_template(
+            $template,
+            &[$((stringify!($name), &$value as &dyn $crate::EmbedArg)),*],
+        )
+        .expect("sensitive_embed! templates are checked at compile time")
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_redacts_only_regions() {
+        let secret = SensitiveString::from("s3cret");
+        let mut embedded = SensitiveEmbedded::new();
+        embedded
+            .push_str("user=admin password=")
+            .push_sensitive(&secret)
+            .push_str(" host=db");
+
+        let shown = embedded.to_string();
+        assert_eq!(shown, format!("user=admin password={} host=db", secret));
+        assert!(!format!("{:?}", embedded).contains("s3cret"));
+    }
+
+    #[test]
+    fn test_with_regions_sorts_and_validates() {
+        let embedded = SensitiveEmbedded::with_regions("a=XX b=YY", [7..9, 2..4]).unwrap();
+        assert_eq!(embedded.regions(), &[2..4, 7..9]);
+        assert!(!embedded.to_string().contains("XX"));
+        assert!(!embedded.to_string().contains("YY"));
+
+        assert!(SensitiveEmbedded::with_regions("abc", Some(0..4)).is_err());
+        assert!(SensitiveEmbedded::with_regions("abcd", [0..2, 1..3]).is_err());
+        assert!(SensitiveEmbedded::with_regions("é", Some(0..1)).is_err());
+    }
+
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[test]
+    fn test_get_value_returns_full_plaintext() {
+        let embedded = SensitiveEmbedded::with_regions("token=abc", Some(6..9)).unwrap();
+        assert_eq!(embedded.get_value(), "token=abc");
+    }
+
+    #[test]
+    fn test_template_marks_secret_placeholders() {
+        let password = SensitiveString::from("pw");
+        let embedded = crate::sensitive_embed!(
+            "{{host}}={h} port={p} pass={#secret p2} key={pw}",
+            h = "db",
+            p = 5432,
+            p2 = "plain-but-secret",
+            pw = password,
+        );
+
+        assert_eq!(
+            embedded.text,
+            "{host}=db port=5432 pass=plain-but-secret key=pw"
+        );
+        assert_eq!(embedded.regions().len(), 2);
+        assert!(!embedded.to_string().contains("plain-but-secret"));
+    }
+
+    #[test]
+    fn test_template_errors() {
+        let args: &[(&str, &dyn EmbedArg)] = &[("a", &"x")];
+        assert_eq!(
+            SensitiveEmbedded::from_template("{b}", args),
+            Err(TemplateError::UnknownArgument("b".to_string()))
+        );
+        assert_eq!(
+            SensitiveEmbedded::from_template("{#hidden a}", args),
+            Err(TemplateError::UnknownDirective("hidden".to_string()))
+        );
+        assert_eq!(
+            SensitiveEmbedded::from_template("{a", args),
+            Err(TemplateError::Unbalanced)
+        );
+        assert_eq!(
+            SensitiveEmbedded::from_template("a}", args),
+            Err(TemplateError::Unbalanced)
+        );
+    }
+
+    #[test]
+    fn test_validate_template_agrees_with_from_template() {
+        let args: &[(&str, &dyn EmbedArg)] = &[("a", &"x"), ("b", &"y")];
+        for template in [
+            "{a}{{b}}",
+            "{ a } and {#secret  b }",
+            "{{}}",
+            "{b}",
+            "{c}",
+            "{#hidden a}",
+            "{#secret}",
+            "{#secreta}",
+            "{a",
+            "a}",
+        ] {
+            let validated =
+                std::panic::catch_unwind(|| validate_template(template, &["a", "b"])).is_ok();
+            let parsed = SensitiveEmbedded::from_template(template, args).is_ok();
+            assert_eq!(validated, parsed, "{}", template);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_serialize_is_redacted() {
+        let embedded = SensitiveEmbedded::with_regions("token=abc", Some(6..9)).unwrap();
+        let json = serde_json::to_string(&embedded).unwrap();
+        assert!(json.starts_with("\"token=sha256:"));
+        assert!(!json.contains("abc\""));
+    }
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_embedding_is_checked_against_access_policies() {
+        let secret = SensitiveString::from("s3cret");
+        crate::access::assert_denied(|| {
+            SensitiveEmbedded::new()
+                .push_sensitive(&secret)
+                .regions()
+                .len()
+        });
+        crate::access::assert_denied(|| {
+            SensitiveEmbedded::from_template("password={p}", &[("p", &secret)])
+        });
+    }
+}
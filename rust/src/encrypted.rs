@@ -0,0 +1,200 @@
+//! Secrets kept encrypted in memory.
+//!
+//! A `SensitiveString` keeps its plaintext on the heap for its whole life,
+//! so a core dump or heap snapshot taken at any moment contains it. With the
+//! `memory-encryption` feature, [`EncryptedSensitiveString`] keeps only a
+//! ChaCha20-Poly1305 ciphertext under a random key generated once per
+//! process, and decrypts into a scratch buffer that is zeroed as soon as
+//! [`expose`](EncryptedSensitiveString::expose) returns.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::EncryptedSensitiveString;
+//!
+//! let password = EncryptedSensitiveString::new("hunter2");
+//! assert_eq!(password.len(), 7);
+//! password.expose(|plaintext| assert_eq!(plaintext, "hunter2"));
+//! println!("{}", password); // redacted, like SensitiveString
+//! ```
+//!
+//! This is defense in depth, not a guarantee: the process key is itself in
+//! memory, and plaintext is present while `expose` runs. It stops a secret
+//! being found by grepping a dump for its value, and limits the window in
+//! which it can be found at all. Each access costs a decryption.
+
+use crate::{wipe, SensitiveString};
+use chacha20poly1305::aead::{Aead, OsRng};
+use chacha20poly1305::{AeadCore, ChaCha20Poly1305, Key, KeyInit, Nonce};
+use std::fmt;
+use std::sync::OnceLock;
+
+static PROCESS_KEY: OnceLock<Key> = OnceLock::new();
+
+fn cipher() -> ChaCha20Poly1305 {
+    ChaCha20Poly1305::new(PROCESS_KEY.get_or_init(|| ChaCha20Poly1305::generate_key(&mut OsRng)))
+}
+
+/// Zeroes a buffer when dropped, including while unwinding from a panic.
+struct WipeOnDrop<'a>(&'a mut [u8]);
+
+impl Drop for WipeOnDrop<'_> {
+    fn drop(&mut self) {
+        wipe(self.0);
+    }
+}
+
+/// A secret string that is encrypted while at rest in memory.
+#[derive(Clone)]
+pub struct EncryptedSensitiveString {
+    nonce: Nonce,
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedSensitiveString {
+    /// Encrypts `value`, zeroing the original buffer.
+    pub fn new(value: impl Into<String>) -> Self {
+        let mut bytes = value.into().into_bytes();
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher()
+            .encrypt(&nonce, bytes.as_slice())
+            .expect("encrypting in memory cannot fail");
+        wipe(&mut bytes);
+        Self { nonce, ciphertext }
+    }
+
+    /// Runs `f` with the decrypted plaintext, zeroing it afterwards, even if `f` panics.
+    ///
+    /// Access policies are checked, and the read audited, before decrypting.
+    #[track_caller]
+    pub fn expose<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        crate::record_plaintext_read(None, "expose");
+        self.with_plaintext(f)
+    }
+
+    /// Decrypts into a plain [`SensitiveString`].
+    pub fn decrypt(&self) -> SensitiveString {
+        self.with_plaintext(|plaintext| SensitiveString::from(plaintext))
+    }
+
+    fn with_plaintext<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        let mut plaintext = cipher()
+            .decrypt(&self.nonce, self.ciphertext.as_slice())
+            .expect("ciphertext was produced by this process");
+        let plaintext = WipeOnDrop(&mut plaintext);
+        f(std::str::from_utf8(plaintext.0).expect("encrypted from a String"))
+    }
+
+    /// Returns the length of the plaintext in bytes.
+    pub fn len(&self) -> usize {
+        self.ciphertext.len() - 16
+    }
+
+    /// Returns true if the plaintext is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl From<SensitiveString> for EncryptedSensitiveString {
+    fn from(secret: SensitiveString) -> Self {
+        Self::new(secret.value)
+    }
+}
+
+impl From<&str> for EncryptedSensitiveString {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for EncryptedSensitiveString {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl PartialEq for EncryptedSensitiveString {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.with_plaintext(|a| other.with_plaintext(|b| a == b))
+    }
+}
+
+impl Eq for EncryptedSensitiveString {}
+
+/// Shows the redacted form `SensitiveString` would, decrypting briefly to compute it.
+impl fmt::Display for EncryptedSensitiveString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = self.with_plaintext(|plaintext| {
+            crate::policy::default_policy()
+                .apply(plaintext, || crate::redact_bytes(plaintext.as_bytes()))
+        });
+        f.write_str(&redacted)
+    }
+}
+
+impl fmt::Debug for EncryptedSensitiveString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EncryptedSensitiveString({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_without_storing_plaintext() {
+        let secret = EncryptedSensitiveString::new("hunter2");
+        assert!(!secret
+            .ciphertext
+            .windows(7)
+            .any(|window| window == b"hunter2"));
+        assert_eq!(secret.decrypt(), SensitiveString::from("hunter2"));
+        assert_eq!(
+            secret,
+            EncryptedSensitiveString::from(SensitiveString::from("hunter2"))
+        );
+        assert_ne!(secret, EncryptedSensitiveString::from("hunter3"));
+        assert!(EncryptedSensitiveString::from("").is_empty());
+    }
+
+    #[test]
+    fn test_formats_like_sensitive_string() {
+        let secret = EncryptedSensitiveString::new("hunter2");
+        assert_eq!(
+            secret.to_string(),
+            SensitiveString::from("hunter2").to_string()
+        );
+        assert!(format!("{:?}", secret).starts_with("EncryptedSensitiveString("));
+        assert!(!format!("{:?}", secret).contains("hunter2"));
+    }
+    #[test]
+    fn test_buffer_is_wiped_on_panic() {
+        let mut buffer = b"hunter2".to_vec();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _guard = WipeOnDrop(&mut buffer);
+            panic!("closure failed");
+        }));
+        assert!(result.is_err());
+        assert_eq!(buffer, [0; 7]);
+
+        let secret = EncryptedSensitiveString::new("hunter2");
+        let result = std::panic::catch_unwind(|| secret.expose(|_| panic!("closure failed")));
+        assert!(result.is_err());
+        assert_eq!(secret.decrypt(), SensitiveString::from("hunter2"));
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_expose_is_checked_against_access_policies() {
+        let secret = EncryptedSensitiveString::new("hunter2");
+        crate::access::assert_denied(|| secret.expose(|plaintext| plaintext.len()));
+        // Comparing and formatting are not reads.
+        let _ = format!(
+            "{} {}",
+            secret,
+            secret == EncryptedSensitiveString::new("x")
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Reading secrets from environment variables.
+//!
+//! Nearly every program starts with `SensitiveString::new(std::env::var("API_KEY")?)`.
+//! [`SensitiveString::from_env`] does the same in one call, with an error
+//! that names the variable but can never contain its value (a `VarError`
+//! carries the raw `OsString`). [`SensitiveString::take_env`] also removes the
+//! variable, so child processes do not inherit it.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::SensitiveString;
+//!
+//! std::env::set_var("EXAMPLE_API_KEY", "sk-live-123");
+//! let key = SensitiveString::take_env("EXAMPLE_API_KEY").unwrap();
+//! assert_eq!(key, SensitiveString::from("sk-live-123"));
+//! assert!(std::env::var_os("EXAMPLE_API_KEY").is_none());
+//!
+//! assert!(SensitiveString::from_env_opt("EXAMPLE_API_KEY").unwrap().is_none());
+//! ```
+//!
+//! Removing a variable does not erase it from `/proc/<pid>/environ`, which
+//! shows the environment the process started with, and, like
+//! `std::env::remove_var`, is only safe while no other thread reads the
+//! environment. Call `take_env` early in `main`.
+
+use crate::{SensitiveOsString, SensitiveString};
+use std::fmt;
+
+/// Error returned when a secret cannot be read from an environment variable.
+///
+/// Holds only the variable name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FromEnvError {
+    /// The variable is not set.
+    NotPresent(String),
+    /// The variable is not valid Unicode.
+    NotUnicode(String),
+}
+
+impl fmt::Display for FromEnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromEnvError::NotPresent(name) => write!(f, "environment variable {} is not set", name),
+            FromEnvError::NotUnicode(name) => {
+                write!(f, "environment variable {} is not valid Unicode", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FromEnvError {}
+
+impl SensitiveString {
+    /// Reads the environment variable `name`.
+    pub fn from_env(name: &str) -> Result<Self, FromEnvError> {
+        Self::from_env_opt(name)?.ok_or_else(|| FromEnvError::NotPresent(name.to_string()))
+    }
+
+    /// Reads the environment variable `name`, or `None` if it is not set.
+    pub fn from_env_opt(name: &str) -> Result<Option<Self>, FromEnvError> {
+        match std::env::var_os(name) {
+            Some(value) => value
+                .into_string()
+                .map(|value| Some(Self::new(value)))
+                .map_err(|_| FromEnvError::NotUnicode(name.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the environment variable `name` and removes it from the process environment.
+    ///
+    /// The variable is removed even if it is not valid Unicode.
+    pub fn take_env(name: &str) -> Result<Self, FromEnvError> {
+        let secret = Self::from_env(name);
+        if !matches!(secret, Err(FromEnvError::NotPresent(_))) {
+            std::env::remove_var(name);
+        }
+        secret
+    }
+}
+
+impl SensitiveOsString {
+    /// Reads the environment variable `name`, whether or not it is valid Unicode.
+    pub fn from_env(name: &str) -> Result<Self, FromEnvError> {
+        Self::from_env_opt(name).ok_or_else(|| FromEnvError::NotPresent(name.to_string()))
+    }
+
+    /// Reads the environment variable `name`, or `None` if it is not set.
+    pub fn from_env_opt(name: &str) -> Option<Self> {
+        std::env::var_os(name).map(Self::new)
+    }
+
+    /// Reads the environment variable `name` and removes it from the process environment.
+    pub fn take_env(name: &str) -> Result<Self, FromEnvError> {
+        let secret = Self::from_env(name)?;
+        std::env::remove_var(name);
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_env_and_missing() {
+        std::env::set_var("SENSITIVE_STRING_ENV_TEST", "from-env");
+        assert_eq!(
+            SensitiveString::from_env("SENSITIVE_STRING_ENV_TEST"),
+            Ok(SensitiveString::from("from-env"))
+        );
+        assert_eq!(
+            SensitiveString::from_env("SENSITIVE_STRING_ENV_MISSING"),
+            Err(FromEnvError::NotPresent(
+                "SENSITIVE_STRING_ENV_MISSING".into()
+            ))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_not_unicode_error_omits_value() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let value = std::ffi::OsStr::from_bytes(b"secret\xff");
+        std::env::set_var("SENSITIVE_STRING_ENV_BINARY", value);
+        let error = SensitiveString::take_env("SENSITIVE_STRING_ENV_BINARY").unwrap_err();
+        assert!(!format!("{} {:?}", error, error).contains("secret"));
+        assert!(std::env::var_os("SENSITIVE_STRING_ENV_BINARY").is_none());
+    }
+}
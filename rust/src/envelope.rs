@@ -0,0 +1,670 @@
+//! Encrypted envelopes for passing secrets between services.
+//!
+//! Two services that share a key can hand a secret across an untrusted
+//! channel (a queue, a webhook, a ticket) by sealing it into an envelope:
+//!
+//! ```text
+//! ssenv1:<key id>:<base64url(nonce || ciphertext || tag)>
+//! ```
+//!
+//! - The AEAD is ChaCha20-Poly1305 with a 32-byte key and a random 12-byte nonce.
+//! - The associated data is the header `ssenv1:<key id>`, so the key id cannot
+//!   be swapped without failing authentication.
+//! - Base64 is the URL-safe alphabet without padding.
+//! - Key ids are 1 to 64 characters from `A-Z a-z 0-9 . _ -`.
+//!
+//! The format is deliberately small so the other language implementations in
+//! this repository can read and write it with their standard AEAD libraries.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::envelope::Keyring;
+//! use sensitive_string::{SensitiveBytes, SensitiveString};
+//!
+//! let mut keyring = Keyring::new();
+//! keyring.insert("billing-2024", SensitiveBytes::from(vec![7u8; 32])).unwrap();
+//!
+//! let secret = SensitiveString::from("sk-live-123");
+//! let envelope = secret.seal_for("billing-2024", &keyring).unwrap();
+//! assert!(envelope.starts_with("ssenv1:billing-2024:"));
+//!
+//! let opened = SensitiveString::open_with(&envelope, &keyring).unwrap();
+//! assert_eq!(opened, secret);
+//! ```
+//!
+//! # Multiple recipients
+//!
+//! [`SensitiveString::seal_for_all`] seals a secret once for several consumers
+//! (CI, a production service, a break-glass admin), like age's recipients:
+//!
+//! ```text
+//! ssenv1m:<key id>=<base64url(wrapped key)>,...:<base64url(nonce || ciphertext || tag)>
+//! ```
+//!
+//! The body is encrypted under a random 32-byte data key, with the whole
+//! header before the last `:` as associated data. Each stanza wraps that data
+//! key with one recipient's key (same AEAD, random nonce, associated data
+//! `ssenv1m:<key id>`). [`SensitiveString::open_with`] accepts both formats
+//! and uses the first recipient the keyring holds.
+//!
+//! # Key rotation
+//!
+//! A [`KeyringProvider`] keeps the rotation bookkeeping out of application
+//! code: it always seals with the current key, opens envelopes sealed with any
+//! key it still holds, and reports which envelopes should be re-sealed.
+//!
+//! ```
+//! use sensitive_string::envelope::KeyringProvider;
+//! use sensitive_string::{SensitiveBytes, SensitiveString};
+//!
+//! let mut keys = KeyringProvider::new("2024", SensitiveBytes::from(vec![1u8; 32])).unwrap();
+//! let old = keys.seal(&SensitiveString::from("hunter2")).unwrap();
+//!
+//! keys.rotate("2025", SensitiveBytes::from(vec![2u8; 32])).unwrap();
+//! assert!(keys.needs_rewrap(&old));
+//! let new = keys.rewrap(&old).unwrap();
+//! assert!(new.starts_with("ssenv1:2025:"));
+//!
+//! keys.retire("2024").unwrap();
+//! assert!(keys.open(&old).is_err());
+//! assert_eq!(keys.open(&new).unwrap(), SensitiveString::from("hunter2"));
+//! ```
+
+use crate::{SensitiveBytes, SensitiveString};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, OsRng, Payload};
+use chacha20poly1305::{AeadCore, ChaCha20Poly1305, Key, KeyInit, Nonce};
+use std::collections::BTreeMap;
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// Prefix identifying version 1 of the envelope format.
+pub const PREFIX: &str = "ssenv1";
+
+/// Prefix identifying version 1 of the multi-recipient envelope format.
+pub const MULTI_PREFIX: &str = "ssenv1m";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const MAX_KEY_ID_LEN: usize = 64;
+
+/// Error returned when sealing or opening an envelope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvelopeError {
+    /// The key id is empty, too long, or contains characters outside `A-Z a-z 0-9 . _ -`.
+    InvalidKeyId,
+    /// A key was not exactly 32 bytes.
+    InvalidKeyLength(usize),
+    /// No key with this id is in the keyring.
+    UnknownKey(String),
+    /// The input is not a `ssenv1:` envelope.
+    Malformed,
+    /// Authentication failed: wrong key, or the envelope was tampered with.
+    Crypto,
+    /// The decrypted value was not valid UTF-8.
+    InvalidPlaintext,
+    /// The current key of a [`KeyringProvider`] cannot be retired.
+    RetiringCurrentKey,
+    /// A multi-recipient envelope needs at least one recipient.
+    NoRecipients,
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeError::InvalidKeyId => write!(f, "invalid envelope key id"),
+            EnvelopeError::InvalidKeyLength(len) => {
+                write!(f, "envelope keys must be {} bytes, got {}", KEY_LEN, len)
+            }
+            EnvelopeError::UnknownKey(id) => write!(f, "no envelope key with id {:?}", id),
+            EnvelopeError::Malformed => write!(f, "not a {} envelope", PREFIX),
+            EnvelopeError::Crypto => write!(f, "envelope authentication failed"),
+            EnvelopeError::InvalidPlaintext => write!(f, "envelope plaintext is not valid UTF-8"),
+            EnvelopeError::RetiringCurrentKey => {
+                write!(f, "cannot retire the current envelope key")
+            }
+            EnvelopeError::NoRecipients => write!(f, "an envelope needs at least one recipient"),
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+/// Envelope keys by id.
+///
+/// `Debug` lists the key ids only.
+#[derive(Clone, Default)]
+pub struct Keyring {
+    keys: BTreeMap<String, SensitiveBytes>,
+}
+
+impl Keyring {
+    /// Creates an empty keyring.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds (or replaces) the 32-byte key for `key_id`.
+    pub fn insert(
+        &mut self,
+        key_id: impl Into<String>,
+        key: SensitiveBytes,
+    ) -> Result<(), EnvelopeError> {
+        let key_id = key_id.into();
+        validate_key_id(&key_id)?;
+        if key.len() != KEY_LEN {
+            return Err(EnvelopeError::InvalidKeyLength(key.len()));
+        }
+        self.keys.insert(key_id, key);
+        Ok(())
+    }
+
+    /// Removes the key for `key_id`, returning true if there was one.
+    pub fn remove(&mut self, key_id: &str) -> bool {
+        self.keys.remove(key_id).is_some()
+    }
+
+    /// Returns true if the keyring has a key for `key_id`.
+    pub fn contains(&self, key_id: &str) -> bool {
+        self.keys.contains_key(key_id)
+    }
+
+    fn cipher(&self, key_id: &str) -> Result<ChaCha20Poly1305, EnvelopeError> {
+        let key = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| EnvelopeError::UnknownKey(key_id.to_string()))?;
+        Ok(ChaCha20Poly1305::new(Key::from_slice(&key.value)))
+    }
+}
+
+impl fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keyring")
+            .field("key_ids", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+fn validate_key_id(key_id: &str) -> Result<(), EnvelopeError> {
+    let valid = !key_id.is_empty()
+        && key_id.len() <= MAX_KEY_ID_LEN
+        && key_id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(EnvelopeError::InvalidKeyId)
+    }
+}
+
+/// Seals `plaintext` with the key `key_id`.
+pub(crate) fn seal(
+    plaintext: &[u8],
+    key_id: &str,
+    keyring: &Keyring,
+) -> Result<String, EnvelopeError> {
+    validate_key_id(key_id)?;
+    let cipher = keyring.cipher(key_id)?;
+    let header = format!("{}:{}", PREFIX, key_id);
+    let body = encrypt(&cipher, plaintext, &header)?;
+    Ok(format!("{}:{}", header, URL_SAFE_NO_PAD.encode(body)))
+}
+
+/// Seals `plaintext` under a fresh data key, wrapped once for each of `key_ids`.
+pub(crate) fn seal_multi(
+    plaintext: &[u8],
+    key_ids: &[&str],
+    keyring: &Keyring,
+) -> Result<String, EnvelopeError> {
+    if key_ids.is_empty() {
+        return Err(EnvelopeError::NoRecipients);
+    }
+    let dek = ChaCha20Poly1305::generate_key(&mut OsRng);
+    let mut stanzas = Vec::with_capacity(key_ids.len());
+    for key_id in key_ids {
+        validate_key_id(key_id)?;
+        let wrapped = encrypt(
+            &keyring.cipher(key_id)?,
+            &dek,
+            &format!("{}:{}", MULTI_PREFIX, key_id),
+        )?;
+        stanzas.push(format!("{}={}", key_id, URL_SAFE_NO_PAD.encode(wrapped)));
+    }
+    let header = format!("{}:{}", MULTI_PREFIX, stanzas.join(","));
+    let body = encrypt(&ChaCha20Poly1305::new(&dek), plaintext, &header)?;
+    Ok(format!("{}:{}", header, URL_SAFE_NO_PAD.encode(body)))
+}
+
+/// Decrypts a single- or multi-recipient envelope.
+pub(crate) fn open(envelope: &str, keyring: &Keyring) -> Result<Vec<u8>, EnvelopeError> {
+    if envelope.starts_with(MULTI_PREFIX) {
+        return open_multi(envelope, keyring);
+    }
+    let (key_id, body) = parse(envelope)?;
+    let cipher = keyring.cipher(key_id)?;
+    let header = &envelope[..envelope.len() - body.len() - 1];
+    decrypt(&cipher, body, header)
+}
+
+/// Tries every recipient whose key id is held, since a held key may share its
+/// id with a different key that the envelope was sealed for.
+fn open_multi(envelope: &str, keyring: &Keyring) -> Result<Vec<u8>, EnvelopeError> {
+    let (stanzas, body) = parse_multi(envelope)?;
+    let header = &envelope[..envelope.len() - body.len() - 1];
+    let mut error = EnvelopeError::UnknownKey(stanzas[0].0.to_string());
+    for (key_id, wrapped) in stanzas
+        .iter()
+        .filter(|(key_id, _)| keyring.contains(key_id))
+    {
+        let dek = match decrypt(
+            &keyring.cipher(key_id)?,
+            wrapped,
+            &format!("{}:{}", MULTI_PREFIX, key_id),
+        ) {
+            Ok(dek) => Zeroizing::new(dek),
+            Err(unwrap_error) => {
+                error = unwrap_error;
+                continue;
+            }
+        };
+        if dek.len() != KEY_LEN {
+            return Err(EnvelopeError::Malformed);
+        }
+        return decrypt(&ChaCha20Poly1305::new(Key::from_slice(&dek)), body, header);
+    }
+    Err(error)
+}
+
+/// Returns `nonce || ciphertext || tag`, authenticating `aad`.
+fn encrypt(
+    cipher: &ChaCha20Poly1305,
+    plaintext: &[u8],
+    aad: &str,
+) -> Result<Vec<u8>, EnvelopeError> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: aad.as_bytes(),
+            },
+        )
+        .map_err(|_| EnvelopeError::Crypto)?;
+    let mut body = nonce.to_vec();
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+/// Decodes and decrypts a base64url `nonce || ciphertext || tag`.
+fn decrypt(cipher: &ChaCha20Poly1305, body: &str, aad: &str) -> Result<Vec<u8>, EnvelopeError> {
+    let body = URL_SAFE_NO_PAD
+        .decode(body)
+        .map_err(|_| EnvelopeError::Malformed)?;
+    if body.len() < NONCE_LEN {
+        return Err(EnvelopeError::Malformed);
+    }
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: aad.as_bytes(),
+            },
+        )
+        .map_err(|_| EnvelopeError::Crypto)
+}
+
+/// Returns the key id a single-recipient envelope was sealed for, without decrypting it.
+pub fn key_id(envelope: &str) -> Result<&str, EnvelopeError> {
+    parse(envelope).map(|(key_id, _)| key_id)
+}
+
+/// Returns every key id an envelope of either kind was sealed for, without decrypting it.
+pub fn recipients(envelope: &str) -> Result<Vec<&str>, EnvelopeError> {
+    if envelope.starts_with(MULTI_PREFIX) {
+        let (stanzas, _) = parse_multi(envelope)?;
+        Ok(stanzas.into_iter().map(|(key_id, _)| key_id).collect())
+    } else {
+        key_id(envelope).map(|key_id| vec![key_id])
+    }
+}
+
+/// The structure of an envelope, read without decrypting it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvelopeHeader<'a> {
+    /// True for `ssenv1m:` envelopes.
+    pub multi_recipient: bool,
+    /// The key ids the envelope was sealed for.
+    pub recipients: Vec<&'a str>,
+    /// The length of the encrypted secret, in bytes.
+    pub plaintext_len: usize,
+}
+
+/// Checks that `envelope` is well-formed without decrypting it.
+///
+/// Validates the prefix, key ids, base64, and that every ciphertext is long
+/// enough for its nonce and tag (and, for wrapped data keys, a 32-byte key).
+/// Never panics, whatever the input.
+pub fn inspect(envelope: &str) -> Result<EnvelopeHeader<'_>, EnvelopeError> {
+    let multi_recipient = envelope.starts_with(MULTI_PREFIX);
+    let (recipients, body) = if multi_recipient {
+        let (stanzas, body) = parse_multi(envelope)?;
+        for (_, wrapped) in &stanzas {
+            if sealed_len(wrapped)? != KEY_LEN {
+                return Err(EnvelopeError::Malformed);
+            }
+        }
+        (
+            stanzas.into_iter().map(|(key_id, _)| key_id).collect(),
+            body,
+        )
+    } else {
+        let (key_id, body) = parse(envelope)?;
+        (vec![key_id], body)
+    };
+    Ok(EnvelopeHeader {
+        multi_recipient,
+        recipients,
+        plaintext_len: sealed_len(body)?,
+    })
+}
+
+/// Returns the plaintext length of a base64url `nonce || ciphertext || tag`.
+fn sealed_len(body: &str) -> Result<usize, EnvelopeError> {
+    let body = URL_SAFE_NO_PAD
+        .decode(body)
+        .map_err(|_| EnvelopeError::Malformed)?;
+    body.len()
+        .checked_sub(NONCE_LEN + TAG_LEN)
+        .ok_or(EnvelopeError::Malformed)
+}
+
+fn parse(envelope: &str) -> Result<(&str, &str), EnvelopeError> {
+    let rest = envelope
+        .strip_prefix(PREFIX)
+        .and_then(|rest| rest.strip_prefix(':'))
+        .ok_or(EnvelopeError::Malformed)?;
+    let (key_id, body) = rest.split_once(':').ok_or(EnvelopeError::Malformed)?;
+    validate_key_id(key_id).map_err(|_| EnvelopeError::Malformed)?;
+    Ok((key_id, body))
+}
+
+/// A `(key id, wrapped data key)` pair from a multi-recipient header.
+type Stanza<'a> = (&'a str, &'a str);
+
+/// Splits a multi-recipient envelope into its stanzas and the body.
+fn parse_multi(envelope: &str) -> Result<(Vec<Stanza<'_>>, &str), EnvelopeError> {
+    let rest = envelope
+        .strip_prefix(MULTI_PREFIX)
+        .and_then(|rest| rest.strip_prefix(':'))
+        .ok_or(EnvelopeError::Malformed)?;
+    let (stanzas, body) = rest.split_once(':').ok_or(EnvelopeError::Malformed)?;
+    let stanzas = stanzas
+        .split(',')
+        .map(|stanza| {
+            let (key_id, wrapped) = stanza.split_once('=').ok_or(EnvelopeError::Malformed)?;
+            validate_key_id(key_id).map_err(|_| EnvelopeError::Malformed)?;
+            Ok((key_id, wrapped))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((stanzas, body))
+}
+
+impl SensitiveString {
+    /// Encrypts this secret into a `ssenv1:` envelope with the key `key_id` from `keyring`.
+    #[track_caller]
+    pub fn seal_for(&self, key_id: &str, keyring: &Keyring) -> Result<String, EnvelopeError> {
+        self.record_access("seal_for");
+        seal(self.value.as_bytes(), key_id, keyring)
+    }
+
+    /// Encrypts this secret once for several recipients into a `ssenv1m:` envelope.
+    ///
+    /// Any one of the keys `key_ids` can open it.
+    #[track_caller]
+    pub fn seal_for_all(
+        &self,
+        key_ids: &[&str],
+        keyring: &Keyring,
+    ) -> Result<String, EnvelopeError> {
+        self.record_access("seal_for_all");
+        seal_multi(self.value.as_bytes(), key_ids, keyring)
+    }
+
+    /// Decrypts a `ssenv1:` or `ssenv1m:` envelope with a matching key from `keyring`.
+    pub fn open_with(envelope: &str, keyring: &Keyring) -> Result<Self, EnvelopeError> {
+        let plaintext = open(envelope, keyring)?;
+        String::from_utf8(plaintext)
+            .map(Self::new)
+            .map_err(|_| EnvelopeError::InvalidPlaintext)
+    }
+}
+
+/// A keyring that tracks which key is current.
+///
+/// Seals with the current key and opens with any key still held.
+#[derive(Clone, Debug)]
+pub struct KeyringProvider {
+    keyring: Keyring,
+    current: String,
+}
+
+impl KeyringProvider {
+    /// Creates a provider whose current key is `key_id`.
+    pub fn new(key_id: impl Into<String>, key: SensitiveBytes) -> Result<Self, EnvelopeError> {
+        let current = key_id.into();
+        let mut keyring = Keyring::new();
+        keyring.insert(current.clone(), key)?;
+        Ok(Self { keyring, current })
+    }
+
+    /// Returns the id of the key new envelopes are sealed with.
+    pub fn current_key_id(&self) -> &str {
+        &self.current
+    }
+
+    /// Returns every key held, current and historical.
+    pub fn keyring(&self) -> &Keyring {
+        &self.keyring
+    }
+
+    /// Makes `key_id` the current key. Previous keys are kept for opening.
+    pub fn rotate(
+        &mut self,
+        key_id: impl Into<String>,
+        key: SensitiveBytes,
+    ) -> Result<(), EnvelopeError> {
+        let key_id = key_id.into();
+        self.keyring.insert(key_id.clone(), key)?;
+        self.current = key_id;
+        Ok(())
+    }
+
+    /// Forgets a historical key once nothing sealed with it remains.
+    ///
+    /// Returns true if the key was held.
+    pub fn retire(&mut self, key_id: &str) -> Result<bool, EnvelopeError> {
+        if key_id == self.current {
+            return Err(EnvelopeError::RetiringCurrentKey);
+        }
+        Ok(self.keyring.remove(key_id))
+    }
+
+    /// Seals `secret` with the current key.
+    #[track_caller]
+    pub fn seal(&self, secret: &SensitiveString) -> Result<String, EnvelopeError> {
+        secret.seal_for(&self.current, &self.keyring)
+    }
+
+    /// Opens an envelope sealed with any key still held.
+    pub fn open(&self, envelope: &str) -> Result<SensitiveString, EnvelopeError> {
+        SensitiveString::open_with(envelope, &self.keyring)
+    }
+
+    /// Returns true if `envelope` was sealed only for keys other than the current one.
+    pub fn needs_rewrap(&self, envelope: &str) -> bool {
+        recipients(envelope).is_ok_and(|key_ids| !key_ids.contains(&self.current.as_str()))
+    }
+
+    /// Re-seals `envelope` with the current key.
+    pub fn rewrap(&self, envelope: &str) -> Result<String, EnvelopeError> {
+        self.seal(&self.open(envelope)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyring() -> Keyring {
+        let mut keyring = Keyring::new();
+        keyring
+            .insert("a", SensitiveBytes::from(vec![1u8; 32]))
+            .unwrap();
+        keyring
+            .insert("b", SensitiveBytes::from(vec![2u8; 32]))
+            .unwrap();
+        keyring
+    }
+
+    #[test]
+    fn test_round_trip_and_fresh_nonces() {
+        let keyring = keyring();
+        let secret = SensitiveString::from("hunter2");
+        let first = secret.seal_for("a", &keyring).unwrap();
+        let second = secret.seal_for("a", &keyring).unwrap();
+        assert_ne!(first, second);
+        assert!(!first.contains("hunter2"));
+        assert_eq!(key_id(&first), Ok("a"));
+        assert_eq!(SensitiveString::open_with(&second, &keyring), Ok(secret));
+    }
+
+    #[test]
+    fn test_swapped_key_id_fails_authentication() {
+        let keyring = keyring();
+        let envelope = SensitiveString::from("hunter2")
+            .seal_for("a", &keyring)
+            .unwrap();
+        let swapped = envelope.replacen(":a:", ":b:", 1);
+        assert_eq!(
+            SensitiveString::open_with(&swapped, &keyring),
+            Err(EnvelopeError::Crypto)
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        let keyring = keyring();
+        assert_eq!(
+            SensitiveString::open_with("ssenv2:a:AAAA", &keyring),
+            Err(EnvelopeError::Malformed)
+        );
+        assert_eq!(
+            SensitiveString::open_with("ssenv1:c:AAAA", &keyring),
+            Err(EnvelopeError::UnknownKey("c".into()))
+        );
+        assert_eq!(
+            SensitiveString::from("x").seal_for("a:b", &keyring),
+            Err(EnvelopeError::InvalidKeyId)
+        );
+        assert_eq!(
+            Keyring::new().insert("short", SensitiveBytes::from(vec![0u8; 16])),
+            Err(EnvelopeError::InvalidKeyLength(16))
+        );
+    }
+
+    #[test]
+    fn test_provider_cannot_retire_current_key() {
+        let mut keys = KeyringProvider::new("a", SensitiveBytes::from(vec![1u8; 32])).unwrap();
+        assert_eq!(keys.retire("a"), Err(EnvelopeError::RetiringCurrentKey));
+        keys.rotate("b", SensitiveBytes::from(vec![2u8; 32]))
+            .unwrap();
+        assert_eq!(keys.current_key_id(), "b");
+        assert_eq!(keys.retire("a"), Ok(true));
+        assert_eq!(keys.retire("a"), Ok(false));
+    }
+
+    #[test]
+    fn test_multi_recipient_round_trip() {
+        let keyring = keyring();
+        let secret = SensitiveString::from("hunter2");
+        let envelope = secret.seal_for_all(&["a", "b"], &keyring).unwrap();
+        assert!(envelope.starts_with("ssenv1m:a="));
+        assert_eq!(recipients(&envelope), Ok(vec!["a", "b"]));
+
+        for key_id in ["a", "b"] {
+            let mut only = Keyring::new();
+            only.insert(key_id, keyring.keys[key_id].clone()).unwrap();
+            assert_eq!(
+                SensitiveString::open_with(&envelope, &only),
+                Ok(secret.clone())
+            );
+        }
+        assert_eq!(
+            SensitiveString::open_with(&envelope, &Keyring::new()),
+            Err(EnvelopeError::UnknownKey("a".into()))
+        );
+    }
+
+    #[test]
+    fn test_multi_recipient_header_is_authenticated() {
+        let keyring = keyring();
+        let envelope = SensitiveString::from("hunter2")
+            .seal_for_all(&["a", "b"], &keyring)
+            .unwrap();
+        let (header, body) = envelope.rsplit_once(':').unwrap();
+        let (first, _) = header.split_once(',').unwrap();
+        let stripped = format!("{}:{}", first, body);
+        assert_eq!(
+            SensitiveString::open_with(&stripped, &keyring),
+            Err(EnvelopeError::Crypto)
+        );
+        assert_eq!(
+            SensitiveString::from("x").seal_for_all(&[], &keyring),
+            Err(EnvelopeError::NoRecipients)
+        );
+    }
+
+    #[test]
+    fn test_multi_recipient_tries_every_held_key() {
+        let keyring = keyring();
+        let secret = SensitiveString::from("hunter2");
+        let envelope = secret.seal_for_all(&["a", "b"], &keyring).unwrap();
+
+        let mut mismatched = keyring.clone();
+        mismatched
+            .insert("a", SensitiveBytes::from(vec![3u8; 32]))
+            .unwrap();
+        assert_eq!(
+            SensitiveString::open_with(&envelope, &mismatched),
+            Ok(secret.clone())
+        );
+        mismatched.remove("b");
+        assert_eq!(
+            SensitiveString::open_with(&envelope, &mismatched),
+            Err(EnvelopeError::Crypto)
+        );
+
+        let mut keys = KeyringProvider::new("a", keyring.keys["a"].clone()).unwrap();
+        assert!(!keys.needs_rewrap(&envelope));
+        keys.rotate("c", SensitiveBytes::from(vec![4u8; 32]))
+            .unwrap();
+        assert!(keys.needs_rewrap(&envelope));
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_sealing_is_checked_against_access_policies() {
+        let keyring = keyring();
+        let secret = SensitiveString::from("hunter2");
+        crate::access::assert_denied(|| secret.seal_for("a", &keyring));
+        crate::access::assert_denied(|| secret.seal_for_all(&["a", "b"], &keyring));
+    }
+}
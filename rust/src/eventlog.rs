@@ -0,0 +1,264 @@
+//! Windows Event Log entries with secrets removed.
+//!
+//! Windows services log to the Event Log the way Unix daemons log to syslog,
+//! and the entries are often forwarded off the machine. [`EventLogWriter`]
+//! reports events with `ReportEventW` after an [`EventLogFormatter`] has
+//! replaced registered secrets (via a [`Scrubber`]) in the message, so what
+//! is stored and forwarded never contains them.
+//!
+//! The message is the event's only insertion string. Event Viewer shows it
+//! as the description when the source is registered with a message file
+//! whose message for the event ID is `%1`; otherwise it shows it after a note
+//! that the description cannot be found.
+//!
+//! The Event Log only exists on Windows. On other platforms the feature still
+//! compiles, the formatter works, and [`EventLogWriter::open`] fails with
+//! [`io::ErrorKind::Unsupported`], so cross-platform services need no `cfg`
+//! of their own.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use sensitive_string::eventlog::{EventLogFormatter, EventLogWriter, EventType};
+//! use sensitive_string::scrub::Scrubber;
+//! use sensitive_string::SensitiveString;
+//!
+//! let password = SensitiveString::from("hunter2");
+//! let formatter = EventLogFormatter::new().scrubber(Scrubber::new().secret(&password));
+//! let log = EventLogWriter::open(formatter, "vpn-agent")?;
+//! log.report(EventType::Warning, 1001, "bind failed with password hunter2")?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::scrub::Scrubber;
+use crate::syslog::Severity;
+use std::io;
+
+/// The longest insertion string `ReportEventW` accepts, in UTF-16 code units.
+pub const MAX_MESSAGE_LEN: usize = 31_839;
+
+/// The type of an event, as shown in Event Viewer's Level column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum EventType {
+    Error = 1,
+    Warning = 2,
+    Information = 4,
+}
+
+impl From<Severity> for EventType {
+    /// Maps syslog severities onto the three event types, so code that logs
+    /// to both can pick one level.
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Emergency | Severity::Alert | Severity::Critical | Severity::Error => {
+                EventType::Error
+            }
+            Severity::Warning => EventType::Warning,
+            Severity::Notice | Severity::Informational | Severity::Debug => EventType::Information,
+        }
+    }
+}
+
+/// Builds Event Log messages, scrubbing secrets from them.
+#[derive(Debug, Clone, Default)]
+pub struct EventLogFormatter {
+    scrubber: Scrubber,
+}
+
+impl EventLogFormatter {
+    /// Creates a formatter that removes no secrets until given a scrubber.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces registered secrets wherever they appear in a message.
+    pub fn scrubber(mut self, scrubber: Scrubber) -> Self {
+        self.scrubber = scrubber;
+        self
+    }
+
+    /// Returns the scrubbed message as a NUL-terminated UTF-16 string.
+    ///
+    /// NULs in the message are replaced with U+FFFD so they cannot cut it
+    /// short, and the message is truncated to [`MAX_MESSAGE_LEN`] code
+    /// units without splitting a surrogate pair.
+    pub fn format(&self, message: &str) -> Vec<u16> {
+        let scrubbed = self.scrubber.scrub(message);
+        let mut wide: Vec<u16> = scrubbed
+            .chars()
+            .map(|c| if c == '\0' { '\u{FFFD}' } else { c })
+            .flat_map(|c| c.encode_utf16(&mut [0; 2]).to_vec())
+            .take(MAX_MESSAGE_LEN)
+            .collect();
+        if wide.len() == MAX_MESSAGE_LEN && wide.last().is_some_and(|&u| is_high_surrogate(u)) {
+            wide.pop();
+        }
+        wide.push(0);
+        wide
+    }
+}
+
+fn is_high_surrogate(unit: u16) -> bool {
+    (0xD800..0xDC00).contains(&unit)
+}
+
+/// Reports scrubbed events to the local Event Log under one source name.
+#[derive(Debug)]
+pub struct EventLogWriter {
+    formatter: EventLogFormatter,
+    source: sys::Source,
+}
+
+impl EventLogWriter {
+    /// Opens the event source `source` on the local computer.
+    ///
+    /// A source that is not registered under
+    /// `HKLM\SYSTEM\CurrentControlSet\Services\EventLog\Application` still
+    /// works; its events go to the Application log.
+    pub fn open(formatter: EventLogFormatter, source: &str) -> io::Result<Self> {
+        Ok(Self {
+            formatter,
+            source: sys::Source::register(source)?,
+        })
+    }
+
+    /// Scrubs `message` and reports it. Scrubbing happens before the event is built.
+    pub fn report(&self, event_type: EventType, event_id: u32, message: &str) -> io::Result<()> {
+        let message = self.formatter.format(message);
+        self.source.report(event_type as u16, event_id, &message)
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::io;
+    use std::ptr;
+    use windows_sys::Win32::Foundation::HANDLE;
+    use windows_sys::Win32::System::EventLog::{
+        DeregisterEventSource, RegisterEventSourceW, ReportEventW,
+    };
+
+    #[derive(Debug)]
+    pub(super) struct Source(HANDLE);
+
+    // SAFETY: an event source handle is not tied to the thread that opened
+    // it, and `ReportEventW` may be called on it from several threads.
+    unsafe impl Send for Source {}
+    unsafe impl Sync for Source {}
+
+    impl Source {
+        pub(super) fn register(name: &str) -> io::Result<Self> {
+            let name: Vec<u16> = name.encode_utf16().chain(Some(0)).collect();
+            // SAFETY: a null server name means the local computer, and `name`
+            // is NUL-terminated.
+            let handle = unsafe { RegisterEventSourceW(ptr::null(), name.as_ptr()) };
+            if handle.is_null() {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(Self(handle))
+            }
+        }
+
+        pub(super) fn report(
+            &self,
+            event_type: u16,
+            event_id: u32,
+            message: &[u16],
+        ) -> io::Result<()> {
+            let strings = [message.as_ptr()];
+            // SAFETY: the handle is open, `strings` holds one NUL-terminated
+            // string, and there is no SID or binary data.
+            let reported = unsafe {
+                ReportEventW(
+                    self.0,
+                    event_type,
+                    0,
+                    event_id,
+                    ptr::null_mut(),
+                    1,
+                    0,
+                    strings.as_ptr(),
+                    ptr::null(),
+                )
+            };
+            if reported == 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl Drop for Source {
+        fn drop(&mut self) {
+            // SAFETY: the handle came from RegisterEventSourceW and is closed once.
+            unsafe { DeregisterEventSource(self.0) };
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod sys {
+    use std::convert::Infallible;
+    use std::io;
+
+    #[derive(Debug)]
+    pub(super) struct Source(Infallible);
+
+    impl Source {
+        pub(super) fn register(_: &str) -> io::Result<Self> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the Event Log is only available on Windows",
+            ))
+        }
+
+        pub(super) fn report(&self, _: u16, _: u32, _: &[u16]) -> io::Result<()> {
+            match self.0 {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SensitiveString;
+
+    #[test]
+    fn test_format_scrubs_and_terminates() {
+        let secret = SensitiveString::from("s3cr3t");
+        let formatter = EventLogFormatter::new().scrubber(Scrubber::new().secret(&secret));
+        let wide = formatter.format("login with s3cr3t\0failed");
+
+        assert_eq!(wide.last(), Some(&0));
+        let text = String::from_utf16(&wide[..wide.len() - 1]).unwrap();
+        assert_eq!(text, format!("login with {}\u{FFFD}failed", secret));
+    }
+
+    #[test]
+    fn test_format_truncates_without_splitting_surrogates() {
+        let formatter = EventLogFormatter::new();
+        let wide = formatter.format(&"a".repeat(MAX_MESSAGE_LEN + 10));
+        assert_eq!(wide.len(), MAX_MESSAGE_LEN + 1);
+
+        let wide = formatter.format(&format!("{}😀", "a".repeat(MAX_MESSAGE_LEN - 1)));
+        assert_eq!(wide.len(), MAX_MESSAGE_LEN);
+        assert!(String::from_utf16(&wide[..wide.len() - 1]).is_ok());
+    }
+
+    #[test]
+    fn test_event_type_from_syslog_severity() {
+        assert_eq!(EventType::from(Severity::Critical), EventType::Error);
+        assert_eq!(EventType::from(Severity::Warning), EventType::Warning);
+        assert_eq!(EventType::from(Severity::Debug), EventType::Information);
+        assert_eq!(EventType::Information as u16, 4);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_open_unsupported_off_windows() {
+        let err = EventLogWriter::open(EventLogFormatter::new(), "app").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+}
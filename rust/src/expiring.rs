@@ -0,0 +1,279 @@
+//! Secrets that expire, and handles that refresh them.
+//!
+//! OAuth access tokens and cloud session credentials stop working at a known
+//! time. [`ExpiringSensitiveString`] carries that time with the secret, and
+//! a [`RotatingSecret`] holds the current one and calls a refresh closure,
+//! blocking or async, whenever it has expired (or is about to), so callers
+//! always get a usable token without tracking expiry themselves.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::source::ProviderError;
+//! use sensitive_string::{ExpiringSensitiveString, RotatingSecret, SensitiveString};
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use std::time::Duration;
+//!
+//! let refreshes = AtomicUsize::new(0);
+//! let token = RotatingSecret::new(move || {
+//!     let n = refreshes.fetch_add(1, Ordering::SeqCst);
+//!     // In practice: POST to the token endpoint.
+//!     Ok::<_, ProviderError>(ExpiringSensitiveString::with_ttl(
+//!         SensitiveString::from(format!("access-token-{}", n)),
+//!         Duration::from_secs(3600),
+//!     ))
+//! })
+//! .refresh_before(Duration::from_secs(60));
+//!
+//! let current = token.get()?;
+//! assert!(!current.is_expired());
+//! assert_eq!(current.secret(), &SensitiveString::from("access-token-0"));
+//! assert_eq!(token.get()?.secret(), current.secret()); // still fresh, not refreshed
+//!
+//! token.invalidate(); // e.g. after a 401
+//! assert_eq!(token.get()?.secret(), &SensitiveString::from("access-token-1"));
+//! # Ok::<(), ProviderError>(())
+//! ```
+//!
+//! Concurrent [`get`](RotatingSecret::get) calls wait for one refresh;
+//! concurrent [`get_async`](RotatingSecret::get_async) calls may each
+//! refresh, since no lock is held across an `.await`.
+
+use crate::source::ProviderError;
+use crate::SensitiveString;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+/// A secret with the time it stops being valid.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExpiringSensitiveString {
+    secret: SensitiveString,
+    expires_at: SystemTime,
+}
+
+impl ExpiringSensitiveString {
+    /// Wraps `secret`, valid until `expires_at`.
+    pub fn new(secret: SensitiveString, expires_at: SystemTime) -> Self {
+        Self { secret, expires_at }
+    }
+
+    /// Wraps `secret`, valid for `ttl` from now, e.g. an OAuth `expires_in`.
+    pub fn with_ttl(secret: SensitiveString, ttl: Duration) -> Self {
+        Self::new(secret, SystemTime::now() + ttl)
+    }
+
+    /// Returns the secret, whether or not it has expired.
+    pub fn secret(&self) -> &SensitiveString {
+        &self.secret
+    }
+
+    /// Returns the secret, whether or not it has expired.
+    pub fn into_inner(self) -> SensitiveString {
+        self.secret
+    }
+
+    /// Returns when the secret stops being valid.
+    pub fn expires_at(&self) -> SystemTime {
+        self.expires_at
+    }
+
+    /// Returns true once the expiry time has been reached.
+    pub fn is_expired(&self) -> bool {
+        self.expires_within(Duration::ZERO)
+    }
+
+    /// Returns true if the secret expires within `window` from now.
+    pub fn expires_within(&self, window: Duration) -> bool {
+        SystemTime::now() + window >= self.expires_at
+    }
+
+    /// Returns the time left before expiry, or zero if it has passed.
+    pub fn remaining(&self) -> Duration {
+        self.expires_at
+            .duration_since(SystemTime::now())
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for ExpiringSensitiveString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExpiringSensitiveString")
+            .field("secret", &self.secret)
+            .field("expires_at", &crate::syslog::rfc3339_utc(self.expires_at))
+            .finish()
+    }
+}
+
+/// The future returned by an async refresh closure.
+type RefreshFuture =
+    Pin<Box<dyn Future<Output = Result<ExpiringSensitiveString, ProviderError>> + Send>>;
+
+enum Refresh {
+    Blocking(Box<dyn Fn() -> Result<ExpiringSensitiveString, ProviderError> + Send + Sync>),
+    Async(Box<dyn Fn() -> RefreshFuture + Send + Sync>),
+}
+
+/// A secret that is refreshed on use once it expires.
+pub struct RotatingSecret {
+    current: Mutex<Option<ExpiringSensitiveString>>,
+    refresh: Refresh,
+    margin: Duration,
+}
+
+impl RotatingSecret {
+    /// Creates a handle that calls `refresh` for the first value and whenever
+    /// the current one has expired.
+    pub fn new(
+        refresh: impl Fn() -> Result<ExpiringSensitiveString, ProviderError> + Send + Sync + 'static,
+    ) -> Self {
+        Self::with_refresh(Refresh::Blocking(Box::new(refresh)))
+    }
+
+    /// Like [`new`](Self::new), with an async refresh closure; use
+    /// [`get_async`](Self::get_async) to read it.
+    pub fn new_async<F>(refresh: impl Fn() -> F + Send + Sync + 'static) -> Self
+    where
+        F: Future<Output = Result<ExpiringSensitiveString, ProviderError>> + Send + 'static,
+    {
+        Self::with_refresh(Refresh::Async(Box::new(move || Box::pin(refresh()))))
+    }
+
+    fn with_refresh(refresh: Refresh) -> Self {
+        Self {
+            current: Mutex::new(None),
+            refresh,
+            margin: Duration::ZERO,
+        }
+    }
+
+    /// Refreshes once the current value expires within `margin`, so a token
+    /// is not sent moments before it stops working. Defaults to zero.
+    pub fn refresh_before(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Returns the current value, refreshing it first if it is stale.
+    ///
+    /// Returns an error for handles created with
+    /// [`new_async`](Self::new_async); use [`get_async`](Self::get_async).
+    pub fn get(&self) -> Result<ExpiringSensitiveString, ProviderError> {
+        let mut current = self.lock();
+        if let Some(fresh) = self.fresh(&current) {
+            return Ok(fresh);
+        }
+        let refreshed = match &self.refresh {
+            Refresh::Blocking(refresh) => refresh()?,
+            Refresh::Async(_) => {
+                return Err(ProviderError::new(
+                    "this secret refreshes asynchronously; use get_async()",
+                ))
+            }
+        };
+        *current = Some(refreshed.clone());
+        Ok(refreshed)
+    }
+
+    /// Returns the current value, refreshing it first if it is stale.
+    pub async fn get_async(&self) -> Result<ExpiringSensitiveString, ProviderError> {
+        if let Some(fresh) = self.fresh(&self.lock()) {
+            return Ok(fresh);
+        }
+        let refreshed = match &self.refresh {
+            Refresh::Blocking(refresh) => refresh()?,
+            Refresh::Async(refresh) => refresh().await?,
+        };
+        *self.lock() = Some(refreshed.clone());
+        Ok(refreshed)
+    }
+
+    /// Drops the current value, so the next read refreshes it.
+    pub fn invalidate(&self) {
+        *self.lock() = None;
+    }
+
+    fn fresh(&self, current: &Option<ExpiringSensitiveString>) -> Option<ExpiringSensitiveString> {
+        current
+            .as_ref()
+            .filter(|value| !value.expires_within(self.margin))
+            .cloned()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<ExpiringSensitiveString>> {
+        self.current
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl fmt::Debug for RotatingSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RotatingSecret")
+            .field("current", &*self.lock())
+            .field("margin", &self.margin)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_expiry() {
+        let past = ExpiringSensitiveString::new(
+            SensitiveString::from("old"),
+            SystemTime::now() - Duration::from_secs(1),
+        );
+        assert!(past.is_expired());
+        assert_eq!(past.remaining(), Duration::ZERO);
+
+        let future = ExpiringSensitiveString::with_ttl(
+            SensitiveString::from("new"),
+            Duration::from_secs(30),
+        );
+        assert!(!future.is_expired());
+        assert!(future.expires_within(Duration::from_secs(60)));
+        assert!(!format!("{:?}", future).contains("\"new\""));
+    }
+
+    #[test]
+    fn test_refreshes_within_margin() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        let token = RotatingSecret::new(move || {
+            counted.fetch_add(1, Ordering::SeqCst);
+            Ok(ExpiringSensitiveString::with_ttl(
+                SensitiveString::from("tok"),
+                Duration::from_secs(30),
+            ))
+        });
+        token.get().unwrap();
+        token.get().unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let token = token.refresh_before(Duration::from_secs(60));
+        token.get().unwrap();
+        token.get().unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_async_refresh() {
+        let token = RotatingSecret::new_async(|| async {
+            Ok(ExpiringSensitiveString::with_ttl(
+                SensitiveString::from("tok"),
+                Duration::from_secs(3600),
+            ))
+        });
+        assert!(token.get().is_err());
+        let value = token.get_async().await.unwrap();
+        assert_eq!(value.secret(), &SensitiveString::from("tok"));
+        assert_eq!(token.get().unwrap(), value);
+    }
+}
@@ -0,0 +1,153 @@
+//! Lexically scoped plaintext access.
+//!
+//! `get_value()` returns a `&str` that is easy to pass straight into
+//! `format!` or a log macro. [`SensitiveString::expose`] hands the plaintext
+//! to a closure instead, so every use of it sits inside one visible block that
+//! code review can audit. When a closure is awkward (e.g. an API that borrows
+//! the plaintext for a while), [`SensitiveString::exposed`] returns an
+//! [`ExposedGuard`] that derefs to the plaintext but never prints it.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::SensitiveString;
+//!
+//! let password = SensitiveString::from("hunter2");
+//!
+//! let length = password.expose(|plaintext| plaintext.len());
+//! assert_eq!(length, 7);
+//!
+//! let guard = password.exposed();
+//! assert!(guard.starts_with("hunter"));
+//! assert!(!format!("{:?}", guard).contains("hunter2"));
+//! ```
+//!
+//! [`SensitiveBytes`], [`SensitiveEmbedded`], and
+//! [`Sensitive<T>`](crate::Sensitive) have the same pair of methods.
+
+use crate::{record_plaintext_read, Sensitive, SensitiveBytes, SensitiveEmbedded, SensitiveString};
+use std::fmt;
+use std::ops::Deref;
+
+/// A borrow of a secret's plaintext.
+///
+/// Derefs to the plaintext. `Debug` is redacted and there is no `Display`, so
+/// the guard itself cannot end up in a log by accident.
+pub struct ExposedGuard<'a, T: ?Sized = str> {
+    value: &'a T,
+}
+
+impl<T: ?Sized> Deref for ExposedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for ExposedGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ExposedGuard(..)")
+    }
+}
+
+impl SensitiveString {
+    /// Runs `f` with the plaintext and returns its result.
+    #[track_caller]
+    pub fn expose<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        self.record_access("expose");
+        f(&self.value)
+    }
+
+    /// Returns a guard that derefs to the plaintext.
+    #[track_caller]
+    pub fn exposed(&self) -> ExposedGuard<'_> {
+        self.record_access("exposed");
+        ExposedGuard { value: &self.value }
+    }
+}
+
+impl SensitiveBytes {
+    /// Runs `f` with the plaintext bytes and returns its result.
+    #[track_caller]
+    pub fn expose<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        record_plaintext_read(None, "expose");
+        f(&self.value)
+    }
+
+    /// Returns a guard that derefs to the plaintext bytes.
+    #[track_caller]
+    pub fn exposed(&self) -> ExposedGuard<'_, [u8]> {
+        record_plaintext_read(None, "exposed");
+        ExposedGuard { value: &self.value }
+    }
+}
+
+impl SensitiveEmbedded {
+    /// Runs `f` with the full plaintext and returns its result.
+    #[track_caller]
+    pub fn expose<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        record_plaintext_read(None, "expose");
+        f(&self.text)
+    }
+
+    /// Returns a guard that derefs to the full plaintext.
+    #[track_caller]
+    pub fn exposed(&self) -> ExposedGuard<'_> {
+        record_plaintext_read(None, "exposed");
+        ExposedGuard { value: &self.text }
+    }
+}
+
+impl<T> Sensitive<T> {
+    /// Runs `f` with the wrapped value and returns its result.
+    #[track_caller]
+    pub fn expose<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        record_plaintext_read(None, "expose");
+        f(&self.value)
+    }
+
+    /// Returns a guard that derefs to the wrapped value.
+    #[track_caller]
+    pub fn exposed(&self) -> ExposedGuard<'_, T> {
+        record_plaintext_read(None, "exposed");
+        ExposedGuard { value: &self.value }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expose_returns_closure_result() {
+        let secret = SensitiveString::from("hunter2");
+        assert!(secret.expose(|plaintext| plaintext == "hunter2"));
+        let key = SensitiveBytes::from(vec![1, 2, 3]);
+        assert_eq!(key.expose(|bytes| bytes.iter().sum::<u8>()), 6);
+    }
+
+    #[test]
+    fn test_guard_derefs_but_does_not_print() {
+        let pin = Sensitive::new(1234u32);
+        let guard = pin.exposed();
+        assert_eq!(*guard, 1234);
+        assert_eq!(format!("{:?}", guard), "ExposedGuard(..)");
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_every_type_is_checked_against_access_policies() {
+        use crate::access::assert_denied;
+
+        let key = SensitiveBytes::from(vec![1, 2, 3]);
+        assert_denied(|| key.expose(|bytes| bytes.len()));
+        assert_denied(|| key.exposed().len());
+        let embedded = SensitiveEmbedded::with_regions("token=abc", Some(6..9)).unwrap();
+        assert_denied(|| embedded.expose(str::len));
+        assert_denied(|| embedded.exposed().len());
+        let pin = Sensitive::new(1234u32);
+        assert_denied(|| pin.expose(|pin| *pin));
+        assert_denied(|| *pin.exposed());
+    }
+}
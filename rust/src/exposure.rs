@@ -0,0 +1,236 @@
+//! Explicit exposure scopes.
+//!
+//! Some operations hand the plaintext to a place where it is easy to lose
+//! track of it (SQL text, terminals, clipboards). Those operations refuse to
+//! run unless the caller has opened an exposure scope with an
+//! [`ExposureGuard`], which makes the intent visible at the call site and
+//! gives code review something to grep for.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{ExposureGuard, SensitiveString};
+//!
+//! let password = SensitiveString::from("hunter2");
+//! assert!(password.sql_quoted().is_err());
+//!
+//! let _guard = ExposureGuard::new("CREATE USER DDL");
+//! assert_eq!(password.sql_quoted().unwrap(), "'hunter2'");
+//! ```
+//!
+//! # Async code
+//!
+//! `ExposureGuard` is thread-local and `!Send`, so it cannot be held across an
+//! `.await` in a task that may migrate between threads. With the `tokio`
+//! feature, [`ExposureContext::scope`] opens a task-local scope instead: it
+//! follows the task across `.await` points and worker threads, but is not
+//! inherited by tasks spawned from inside it.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::marker::PhantomData;
+
+thread_local! {
+    /// The open scopes, innermost last, each with the id of its guard.
+    static ACTIVE_REASONS: RefCell<Vec<(u64, &'static str)>> = const { RefCell::new(Vec::new()) };
+    static NEXT_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// An RAII guard that marks the current thread as intentionally exposing plaintext.
+///
+/// The scope ends when the guard is dropped. Guards nest; the innermost reason
+/// is reported by [`current_exposure_reason`]. The guard is deliberately
+/// `!Send` so a scope opened on one thread cannot be closed on another.
+pub struct ExposureGuard {
+    id: u64,
+    reason: &'static str,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ExposureGuard {
+    /// Opens an exposure scope on the current thread.
+    ///
+    /// The `reason` should describe why plaintext is leaving the wrapper, so
+    /// that it shows up in audits and code review.
+    pub fn new(reason: &'static str) -> Self {
+        let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+        ACTIVE_REASONS.with(|reasons| reasons.borrow_mut().push((id, reason)));
+        Self {
+            id,
+            reason,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Returns the reason this scope was opened with.
+    pub fn reason(&self) -> &'static str {
+        self.reason
+    }
+}
+
+/// Closes this guard's own scope, even if guards opened after it are still alive.
+impl Drop for ExposureGuard {
+    fn drop(&mut self) {
+        ACTIVE_REASONS.with(|reasons| {
+            let mut reasons = reasons.borrow_mut();
+            if let Some(at) = reasons.iter().rposition(|(id, _)| *id == self.id) {
+                reasons.remove(at);
+            }
+        });
+    }
+}
+
+impl fmt::Debug for ExposureGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExposureGuard")
+            .field("reason", &self.reason)
+            .finish()
+    }
+}
+
+/// Returns true if an exposure scope is active on the current thread or task.
+pub fn is_exposure_active() -> bool {
+    current_exposure_reason().is_some()
+}
+
+/// Returns the reason of the innermost active exposure scope, if any.
+///
+/// A thread-local [`ExposureGuard`] takes precedence over a task-local
+/// `ExposureContext` scope.
+pub fn current_exposure_reason() -> Option<&'static str> {
+    let thread_reason =
+        ACTIVE_REASONS.with(|reasons| reasons.borrow().last().map(|(_, reason)| *reason));
+    #[cfg(feature = "tokio")]
+    let thread_reason = thread_reason.or_else(|| TASK_REASON.try_with(|reason| *reason).ok());
+    thread_reason
+}
+
+#[cfg(feature = "tokio")]
+tokio::task_local! {
+    static TASK_REASON: &'static str;
+}
+
+/// Task-local exposure scopes for tokio.
+///
+/// # Example
+///
+/// ```
+/// use sensitive_string::{ExposureContext, SensitiveString};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let password = SensitiveString::from("hunter2");
+/// let sql = ExposureContext::scope("CREATE USER DDL", async {
+///     tokio::task::yield_now().await;
+///     password.sql_quoted()
+/// })
+/// .await;
+/// assert!(sql.is_ok());
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy)]
+pub struct ExposureContext;
+
+#[cfg(feature = "tokio")]
+impl ExposureContext {
+    /// Runs `future` with an exposure scope active for the current task.
+    pub async fn scope<F: std::future::Future>(reason: &'static str, future: F) -> F::Output {
+        TASK_REASON.scope(reason, future).await
+    }
+}
+
+/// Error returned by operations that require an active exposure scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExposureRequired {
+    operation: &'static str,
+}
+
+impl ExposureRequired {
+    pub(crate) fn new(operation: &'static str) -> Self {
+        Self { operation }
+    }
+
+    /// Returns the name of the operation that was refused.
+    pub fn operation(&self) -> &'static str {
+        self.operation
+    }
+}
+
+impl fmt::Display for ExposureRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} requires an active exposure scope (see ExposureGuard)",
+            self.operation
+        )
+    }
+}
+
+impl std::error::Error for ExposureRequired {}
+
+/// Returns `Ok(())` if an exposure scope is active, otherwise an error naming `operation`.
+pub(crate) fn require_exposure(operation: &'static str) -> Result<(), ExposureRequired> {
+    if is_exposure_active() {
+        Ok(())
+    } else {
+        Err(ExposureRequired::new(operation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_activates_and_deactivates() {
+        assert!(!is_exposure_active());
+        {
+            let guard = ExposureGuard::new("test");
+            assert!(is_exposure_active());
+            assert_eq!(guard.reason(), "test");
+        }
+        assert!(!is_exposure_active());
+    }
+
+    #[test]
+    fn test_guards_nest() {
+        let _outer = ExposureGuard::new("outer");
+        {
+            let _inner = ExposureGuard::new("inner");
+            assert_eq!(current_exposure_reason(), Some("inner"));
+        }
+        assert_eq!(current_exposure_reason(), Some("outer"));
+    }
+
+    #[test]
+    fn test_out_of_order_drop_closes_own_scope() {
+        let outer = ExposureGuard::new("outer");
+        let inner = ExposureGuard::new("inner");
+        drop(outer);
+        assert_eq!(current_exposure_reason(), Some("inner"));
+        drop(inner);
+        assert!(!is_exposure_active());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_task_scope_survives_await_but_not_spawn() {
+        ExposureContext::scope("task", async {
+            tokio::task::yield_now().await;
+            assert_eq!(current_exposure_reason(), Some("task"));
+
+            let spawned = tokio::spawn(async { is_exposure_active() }).await.unwrap();
+            assert!(!spawned);
+        })
+        .await;
+        assert!(!is_exposure_active());
+    }
+
+    #[test]
+    fn test_guard_is_thread_local() {
+        let _guard = ExposureGuard::new("main thread");
+        let active = std::thread::spawn(is_exposure_active).join().unwrap();
+        assert!(!active);
+    }
+}
@@ -0,0 +1,188 @@
+//! A C interface for using the same redaction from C and C++.
+//!
+//! The `ffi` feature exports four functions, declared in
+//! `include/sensitive_string.h`. Build the library with
+//! `cargo rustc --release --features ffi --crate-type staticlib` (or
+//! `cdylib`) and link against it.
+//!
+//! ```c
+//! SensitiveString *secret = sensitive_string_new(password, strlen(password));
+//! char shown[160];
+//! sensitive_string_display(secret, shown, sizeof shown);
+//! log_info("password=%s", shown);  /* sha256:... */
+//! sensitive_string_free(secret);
+//! ```
+//!
+//! # Ownership
+//!
+//! - [`sensitive_string_new`] copies the input; the caller still owns, and
+//!   should wipe, its own buffer. The returned handle is owned by the caller
+//!   and must be released with [`sensitive_string_free`] exactly once.
+//! - [`sensitive_string_free`] overwrites the plaintext with zeros before
+//!   freeing it. Passing `NULL` does nothing.
+//! - [`sensitive_string_display`] and [`sensitive_string_get_value`] write
+//!   into a caller-provided buffer and never allocate for the caller. They
+//!   return the length of the full output without the terminating NUL, like
+//!   `snprintf`, and write it only if it fits together with the NUL, so a
+//!   plaintext is never left truncated in a buffer. A plaintext copied out
+//!   is the caller's to wipe.
+//!
+//! Handles may be shared between threads for reading. The functions do not
+//! unwind: a panic inside one, such as an access policy denying a read,
+//! aborts the process.
+
+use crate::{wipe, SensitiveString};
+use std::ffi::c_char;
+
+/// Copies `text` and a NUL into `buf` if they fit, and returns `text.len()`.
+///
+/// # Safety
+///
+/// `buf` must be null or valid for writes of `buf_len` bytes.
+unsafe fn write_out(text: &[u8], buf: *mut c_char, buf_len: usize) -> usize {
+    if !buf.is_null() && text.len() < buf_len {
+        // SAFETY: the caller guarantees `buf_len` writable bytes, and
+        // `text.len() + 1 <= buf_len`.
+        unsafe {
+            std::ptr::copy_nonoverlapping(text.as_ptr(), buf.cast::<u8>(), text.len());
+            *buf.add(text.len()) = 0;
+        }
+    }
+    text.len()
+}
+
+/// Wraps a copy of the `len` bytes at `value`, which must be UTF-8.
+///
+/// Returns `NULL` if `value` is `NULL` or the bytes are not UTF-8. The
+/// bytes may contain NULs.
+///
+/// # Safety
+///
+/// `value` must be null or valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sensitive_string_new(
+    value: *const c_char,
+    len: usize,
+) -> *mut SensitiveString {
+    if value.is_null() {
+        return std::ptr::null_mut();
+    }
+    // SAFETY: the caller guarantees `len` readable bytes at `value`.
+    let bytes = unsafe { std::slice::from_raw_parts(value.cast::<u8>(), len) };
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Box::into_raw(Box::new(SensitiveString::from(text))),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Writes the redacted form (e.g. `sha256:...`), NUL-terminated, into `buf`.
+///
+/// Returns its length without the NUL; nothing is written unless
+/// `buf_len` is greater than that. Returns 0 if `secret` is `NULL`.
+///
+/// # Safety
+///
+/// `secret` must be null or a live handle from [`sensitive_string_new`],
+/// and `buf` must be null or valid for writes of `buf_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sensitive_string_display(
+    secret: *const SensitiveString,
+    buf: *mut c_char,
+    buf_len: usize,
+) -> usize {
+    // SAFETY: the caller guarantees `secret` is null or a live handle.
+    match unsafe { secret.as_ref() } {
+        // SAFETY: forwarded from the caller.
+        Some(secret) => unsafe { write_out(secret.to_string().as_bytes(), buf, buf_len) },
+        None => 0,
+    }
+}
+
+/// Copies the plaintext, NUL-terminated, into `buf`.
+///
+/// Returns its length without the NUL; nothing is written unless
+/// `buf_len` is greater than that, so call with a `NULL` buffer first to
+/// size it. Returns 0 if `secret` is `NULL`. Counts as a plaintext read for
+/// auditing and access policies.
+///
+/// # Safety
+///
+/// `secret` must be null or a live handle from [`sensitive_string_new`],
+/// and `buf` must be null or valid for writes of `buf_len` bytes.
+#[cfg(not(feature = "no-plaintext-getters"))]
+#[no_mangle]
+pub unsafe extern "C" fn sensitive_string_get_value(
+    secret: *const SensitiveString,
+    buf: *mut c_char,
+    buf_len: usize,
+) -> usize {
+    // SAFETY: the caller guarantees `secret` is null or a live handle.
+    match unsafe { secret.as_ref() } {
+        Some(secret) => {
+            if !buf.is_null() && secret.value.len() < buf_len {
+                secret.record_access("sensitive_string_get_value");
+            }
+            // SAFETY: forwarded from the caller.
+            unsafe { write_out(secret.value.as_bytes(), buf, buf_len) }
+        }
+        None => 0,
+    }
+}
+
+/// Zeroes the plaintext and frees the handle. Does nothing for `NULL`.
+///
+/// # Safety
+///
+/// `secret` must be null or a live handle from [`sensitive_string_new`],
+/// not used again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn sensitive_string_free(secret: *mut SensitiveString) {
+    if secret.is_null() {
+        return;
+    }
+    // SAFETY: the caller guarantees a live handle from `Box::into_raw`.
+    let mut secret = unsafe { Box::from_raw(secret) };
+    // SAFETY: zeros are valid UTF-8.
+    wipe(unsafe { secret.value.as_bytes_mut() });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_and_free() {
+        let secret = unsafe { sensitive_string_new(c"hunter2".as_ptr(), 7) };
+        assert!(!secret.is_null());
+        let expected = SensitiveString::from("hunter2").to_string();
+
+        let needed = unsafe { sensitive_string_display(secret, std::ptr::null_mut(), 0) };
+        assert_eq!(needed, expected.len());
+        let mut small = [1 as c_char; 8];
+        unsafe { sensitive_string_display(secret, small.as_mut_ptr(), small.len()) };
+        assert_eq!(small, [1; 8]);
+
+        let mut buf = vec![0 as c_char; needed + 1];
+        unsafe { sensitive_string_display(secret, buf.as_mut_ptr(), buf.len()) };
+        let shown = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+        assert_eq!(shown.to_str().unwrap(), expected);
+
+        unsafe { sensitive_string_free(secret) };
+        unsafe { sensitive_string_free(std::ptr::null_mut()) };
+    }
+
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[test]
+    fn test_get_value_and_invalid_input() {
+        let secret = unsafe { sensitive_string_new(b"a\0b".as_ptr().cast(), 3) };
+        let mut buf = [0 as c_char; 4];
+        let len = unsafe { sensitive_string_get_value(secret, buf.as_mut_ptr(), buf.len()) };
+        assert_eq!(len, 3);
+        assert_eq!(buf.map(|c| c as u8), *b"a\0b\0");
+        unsafe { sensitive_string_free(secret) };
+
+        let invalid = [0xffu8 as c_char];
+        assert!(unsafe { sensitive_string_new(invalid.as_ptr(), 1) }.is_null());
+        assert!(unsafe { sensitive_string_new(std::ptr::null(), 0) }.is_null());
+    }
+}
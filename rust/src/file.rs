@@ -0,0 +1,278 @@
+//! Reading secrets from files, such as Docker and Kubernetes secret mounts.
+//!
+//! Orchestrators mount secrets as files (`/run/secrets/db_password`), usually
+//! written with a trailing newline. [`SensitiveString::from_file`] and
+//! [`SensitiveBytes::from_file`] read one, strip one trailing newline from
+//! text, and return a [`FromFileError`] that names the path but never
+//! contains any of the file's contents. [`SecretFile`] adds options: keep the
+//! newline, or read files that other users can read.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{PermissionCheck, SecretFile, SensitiveString};
+//!
+//! let path = std::env::temp_dir().join("sensitive-string-doc-secret");
+//! std::fs::write(&path, "hunter2\n").unwrap();
+//! # #[cfg(unix)]
+//! # {
+//! # use std::os::unix::fs::PermissionsExt;
+//! # std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+//! # }
+//!
+//! let password = SensitiveString::from_file(&path).unwrap();
+//! assert_eq!(password, SensitiveString::from("hunter2"));
+//!
+//! let raw = SecretFile::new(&path)
+//!     .keep_newline()
+//!     .permissions(PermissionCheck::Allow)
+//!     .read_string()
+//!     .unwrap();
+//! assert_eq!(raw.len(), 8);
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+//!
+//! By default, a file that its group or other users can read is refused with
+//! [`FromFileError::TooPermissive`], which names the file and its mode; a
+//! caller that would rather warn can log that error and read again with
+//! [`PermissionCheck::Allow`]. Permission checks only run on Unix. Docker
+//! secrets are mounted `0444` by default, so reading them needs `Allow`
+//! unless the mounts are locked down.
+
+use crate::{SensitiveBytes, SensitiveOsString, SensitiveString};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Whether to check that only its owner can read a secret file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PermissionCheck {
+    /// Fail with [`FromFileError::TooPermissive`] if the group or other
+    /// users can read the file.
+    #[default]
+    Strict,
+    /// Read the file whatever its permissions.
+    Allow,
+}
+
+/// Error returned when a secret cannot be read from a file.
+///
+/// Holds the path, never the contents.
+#[derive(Debug)]
+pub enum FromFileError {
+    /// The file could not be read.
+    Io(PathBuf, io::Error),
+    /// The file is not valid UTF-8.
+    NotUnicode(PathBuf),
+    /// The file can be read by its group or other users (Unix mode in the
+    /// second field).
+    TooPermissive(PathBuf, u32),
+}
+
+impl fmt::Display for FromFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromFileError::Io(path, e) => {
+                write!(f, "cannot read secret file {}: {}", path.display(), e)
+            }
+            FromFileError::NotUnicode(path) => {
+                write!(f, "secret file {} is not valid UTF-8", path.display())
+            }
+            FromFileError::TooPermissive(path, mode) => write!(
+                f,
+                "secret file {} is readable by other users (mode {:o})",
+                path.display(),
+                mode
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FromFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FromFileError::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Options for reading a secret file.
+#[derive(Debug, Clone)]
+pub struct SecretFile {
+    path: PathBuf,
+    trim_newline: bool,
+    permissions: PermissionCheck,
+}
+
+impl SecretFile {
+    /// Reads `path`, stripping one trailing newline and refusing files other
+    /// users can read.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            trim_newline: true,
+            permissions: PermissionCheck::Strict,
+        }
+    }
+
+    /// Keeps a trailing newline instead of stripping it.
+    pub fn keep_newline(mut self) -> Self {
+        self.trim_newline = false;
+        self
+    }
+
+    /// Sets whether to refuse a file other users can read.
+    pub fn permissions(mut self, check: PermissionCheck) -> Self {
+        self.permissions = check;
+        self
+    }
+
+    /// Reads the file as text.
+    pub fn read_string(&self) -> Result<SensitiveString, FromFileError> {
+        let bytes = self.read()?;
+        String::from_utf8(bytes)
+            .map(SensitiveString::new)
+            .map_err(|_| FromFileError::NotUnicode(self.path.clone()))
+    }
+
+    /// Reads the file as bytes.
+    pub fn read_bytes(&self) -> Result<SensitiveBytes, FromFileError> {
+        self.read().map(SensitiveBytes::new)
+    }
+
+    /// Reads the file as a platform string.
+    ///
+    /// On Unix any bytes are accepted; elsewhere the file must be valid UTF-8.
+    pub fn read_os_string(&self) -> Result<SensitiveOsString, FromFileError> {
+        let bytes = self.read()?;
+        #[cfg(unix)]
+        let value = std::os::unix::ffi::OsStringExt::from_vec(bytes);
+        #[cfg(not(unix))]
+        let value = String::from_utf8(bytes)
+            .map_err(|_| FromFileError::NotUnicode(self.path.clone()))?
+            .into();
+        Ok(SensitiveOsString::new(value))
+    }
+
+    fn read(&self) -> Result<Vec<u8>, FromFileError> {
+        self.check_permissions()?;
+        let mut bytes =
+            std::fs::read(&self.path).map_err(|e| FromFileError::Io(self.path.clone(), e))?;
+        if self.trim_newline {
+            if bytes.ends_with(b"\r\n") {
+                bytes.truncate(bytes.len() - 2);
+            } else if bytes.ends_with(b"\n") {
+                bytes.pop();
+            }
+        }
+        Ok(bytes)
+    }
+
+    #[cfg(unix)]
+    fn check_permissions(&self) -> Result<(), FromFileError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        if self.permissions == PermissionCheck::Allow {
+            return Ok(());
+        }
+        let mode = std::fs::metadata(&self.path)
+            .map_err(|e| FromFileError::Io(self.path.clone(), e))?
+            .permissions()
+            .mode()
+            & 0o7777;
+        if mode & 0o044 == 0 {
+            Ok(())
+        } else {
+            Err(FromFileError::TooPermissive(self.path.clone(), mode))
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn check_permissions(&self) -> Result<(), FromFileError> {
+        Ok(())
+    }
+}
+
+impl SensitiveString {
+    /// Reads a secret file, stripping one trailing newline and refusing files
+    /// other users can read. See [`SecretFile`] for options.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FromFileError> {
+        SecretFile::new(path).read_string()
+    }
+}
+
+impl SensitiveOsString {
+    /// Reads a secret file, stripping one trailing newline and refusing files
+    /// other users can read. See [`SecretFile`] for options.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FromFileError> {
+        SecretFile::new(path).read_os_string()
+    }
+}
+
+impl SensitiveBytes {
+    /// Reads a binary secret file as-is, refusing files other users can read.
+    /// See [`SecretFile`] for options.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FromFileError> {
+        SecretFile::new(path).keep_newline().read_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `contents` to a temporary file only its owner can read.
+    fn private_file(name: &str, contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, contents).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_trims_newline_unless_asked_not_to() {
+        let path = private_file("sensitive-string-file-test", b"secret\r\n");
+        assert_eq!(
+            SensitiveString::from_file(&path).unwrap(),
+            SensitiveString::from("secret")
+        );
+        assert_eq!(
+            SecretFile::new(&path).keep_newline().read_string().unwrap(),
+            SensitiveString::from("secret\r\n")
+        );
+        assert_eq!(SensitiveBytes::from_file(&path).unwrap().len(), 8);
+
+        std::fs::write(&path, b"secret\xff").unwrap();
+        let error = SensitiveString::from_file(&path).unwrap_err();
+        assert!(matches!(error, FromFileError::NotUnicode(_)));
+        assert_eq!(format!("{:?}", error), format!("NotUnicode({:?})", path));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_refuses_files_others_can_read_by_default() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = private_file("sensitive-string-file-mode-test", b"hunter2");
+        assert!(SensitiveString::from_file(&path).is_ok());
+        for mode in [0o644, 0o640, 0o604] {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+            let error = SensitiveString::from_file(&path).unwrap_err();
+            assert!(matches!(error, FromFileError::TooPermissive(_, m) if m == mode));
+            assert!(!error.to_string().contains("hunter2"));
+        }
+
+        let allowed = SecretFile::new(&path).permissions(PermissionCheck::Allow);
+        assert_eq!(
+            allowed.read_string().unwrap(),
+            SensitiveString::from("hunter2")
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
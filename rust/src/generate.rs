@@ -0,0 +1,102 @@
+//! Generating random secrets.
+//!
+//! API tokens and session secrets are usually generated and then wrapped,
+//! leaving the unwrapped `String` behind in freed memory. With the `rand`
+//! feature, [`SensitiveString::generate`] and [`SensitiveBytes::generate`]
+//! draw from the operating system's CSPRNG straight into the wrapper's own
+//! buffer, which is allocated once at its final size and never reallocated.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "rand")]
+//! # {
+//! use sensitive_string::generate::HEX;
+//! use sensitive_string::{SensitiveBytes, SensitiveString};
+//!
+//! let token = SensitiveString::generate(32);
+//! assert_eq!(token.len(), 32);
+//!
+//! let session = SensitiveString::generate_with_charset(64, HEX);
+//! assert!(session.expose(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit())));
+//!
+//! let key = SensitiveBytes::generate(32);
+//! assert_eq!(key.len(), 32);
+//! # }
+//! ```
+//!
+//! Characters are chosen uniformly, so a `len`-character secret from an
+//! `n`-character set carries `len * log2(n)` bits of entropy: 32 alphanumeric
+//! characters give about 190 bits.
+
+use crate::{SensitiveBytes, SensitiveString};
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+
+/// Upper- and lowercase ASCII letters and digits, used by [`SensitiveString::generate`].
+pub const ALPHANUMERIC: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Lowercase hexadecimal digits.
+pub const HEX: &str = "0123456789abcdef";
+
+/// The base64url alphabet, safe in URLs and file names.
+pub const URL_SAFE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+impl SensitiveString {
+    /// Generates `len` random alphanumeric characters.
+    pub fn generate(len: usize) -> Self {
+        Self::generate_with_charset(len, ALPHANUMERIC)
+    }
+
+    /// Generates `len` characters drawn uniformly from `charset`.
+    ///
+    /// Repeated characters in `charset` are drawn more often.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `charset` is empty.
+    pub fn generate_with_charset(len: usize, charset: &str) -> Self {
+        let chars: Vec<char> = charset.chars().collect();
+        assert!(!chars.is_empty(), "charset must not be empty");
+        let widest = chars.iter().map(|c| c.len_utf8()).max().unwrap_or(1);
+
+        let mut value = String::with_capacity(len * widest);
+        for _ in 0..len {
+            value.push(chars[OsRng.gen_range(0..chars.len())]);
+        }
+        Self::new(value)
+    }
+}
+
+impl SensitiveBytes {
+    /// Generates `len` random bytes.
+    pub fn generate(len: usize) -> Self {
+        let mut value = vec![0u8; len];
+        OsRng.fill_bytes(&mut value);
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_uses_charset_without_reallocating() {
+        let secret = SensitiveString::generate_with_charset(100, "é-");
+        assert_eq!(secret.value.chars().count(), 100);
+        assert!(secret.value.chars().all(|c| c == 'é' || c == '-'));
+        assert!(secret.value.capacity() <= 200);
+
+        assert!(SensitiveString::generate(40)
+            .value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric()));
+    }
+
+    #[test]
+    fn test_generated_secrets_differ() {
+        assert_ne!(SensitiveString::generate(32), SensitiveString::generate(32));
+        assert_ne!(SensitiveBytes::generate(32), SensitiveBytes::generate(32));
+    }
+}
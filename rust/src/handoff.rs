@@ -0,0 +1,195 @@
+//! Passing secrets to child processes over an inherited pipe.
+//!
+//! Command-line arguments show up in `ps` and `/proc/<pid>/cmdline`, and
+//! environment variables are inherited by every grandchild and readable via
+//! `/proc/<pid>/environ`. A pipe that only the intended child inherits, read
+//! once and closed, is the most leak-resistant way to hand over a secret.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::SensitiveString;
+//! use std::process::{Command, Stdio};
+//!
+//! let token = SensitiveString::from("child-token");
+//! let mut command = Command::new("sh");
+//! command.args(["-c", "cat <&3"]).stdout(Stdio::piped());
+//!
+//! let sender = token.pass_to_child_fd(&mut command, 3).unwrap();
+//! let child = command.spawn().unwrap();
+//! sender.send().unwrap();
+//!
+//! let output = child.wait_with_output().unwrap();
+//! assert_eq!(output.stdout, b"child-token");
+//! ```
+//!
+//! On the receiving side (a Rust child), the secret is read back with
+//! [`SensitiveString::from_fd`]:
+//!
+//! ```no_run
+//! use sensitive_string::SensitiveString;
+//! use std::os::fd::{FromRawFd, OwnedFd};
+//!
+//! // SAFETY: the parent arranged for fd 3 to be ours and open.
+//! let fd = unsafe { OwnedFd::from_raw_fd(3) };
+//! let token = SensitiveString::from_fd(fd).unwrap();
+//! ```
+
+use crate::SensitiveString;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, PipeWriter, Read, Write};
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+
+/// The parent's end of a secret handoff pipe.
+///
+/// Call [`send`](Self::send) after spawning the child. Dropping the sender
+/// without sending closes the pipe, and the child reads an empty secret.
+pub struct SecretSender {
+    writer: PipeWriter,
+    value: SensitiveString,
+}
+
+impl SecretSender {
+    /// Writes the secret into the pipe and closes it.
+    pub fn send(self) -> io::Result<()> {
+        let SecretSender { mut writer, value } = self;
+        writer.write_all(value.value.as_bytes())?;
+        writer.flush()
+    }
+}
+
+impl fmt::Debug for SecretSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretSender")
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+impl SensitiveString {
+    /// Arranges for `command`'s child to inherit a pipe at descriptor `child_fd`
+    /// that will carry this secret.
+    ///
+    /// Only the child sees the read end; the write end is close-on-exec.
+    /// Call [`SecretSender::send`] once the child has been spawned.
+    #[track_caller]
+    pub fn pass_to_child_fd(
+        &self,
+        command: &mut Command,
+        child_fd: RawFd,
+    ) -> io::Result<SecretSender> {
+        self.record_access("pass_to_child_fd");
+        let (reader, writer) = io::pipe()?;
+        let read_fd = reader.as_raw_fd();
+
+        // SAFETY: the closure only calls async-signal-safe libc functions.
+        unsafe {
+            command.pre_exec(move || {
+                // Keep the reader alive (and its descriptor valid) until exec.
+                let _ = &reader;
+                if read_fd == child_fd {
+                    let flags = libc::fcntl(child_fd, libc::F_GETFD);
+                    if flags == -1
+                        || libc::fcntl(child_fd, libc::F_SETFD, flags & !libc::FD_CLOEXEC) == -1
+                    {
+                        return Err(io::Error::last_os_error());
+                    }
+                } else if libc::dup2(read_fd, child_fd) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        Ok(SecretSender {
+            writer,
+            value: self.clone(),
+        })
+    }
+
+    /// Reads a secret from a descriptor until end of file, then closes it.
+    ///
+    /// The contents must be valid UTF-8. Errors never include the data read.
+    pub fn from_fd(fd: OwnedFd) -> io::Result<Self> {
+        Self::from_reader(File::from(fd))
+    }
+
+    /// Reads a secret from any reader until end of file.
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        String::from_utf8(bytes)
+            .map(Self::new)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "secret is not valid UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Stdio;
+
+    #[test]
+    fn test_child_reads_secret_from_fd() {
+        let secret = SensitiveString::from("handoff-secret");
+        let mut command = Command::new("sh");
+        command.args(["-c", "cat <&5"]).stdout(Stdio::piped());
+
+        let sender = secret.pass_to_child_fd(&mut command, 5).unwrap();
+        let child = command.spawn().unwrap();
+        sender.send().unwrap();
+
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.stdout, b"handoff-secret");
+    }
+
+    #[test]
+    fn test_secret_not_in_child_environment_or_args() {
+        let secret = SensitiveString::from("handoff-secret");
+        let mut command = Command::new("sh");
+        command
+            .args(["-c", "env; echo \"$0 $@\""])
+            .stdout(Stdio::piped());
+
+        let sender = secret.pass_to_child_fd(&mut command, 3).unwrap();
+        let child = command.spawn().unwrap();
+        sender.send().unwrap();
+
+        let output = child.wait_with_output().unwrap();
+        assert!(!String::from_utf8_lossy(&output.stdout).contains("handoff-secret"));
+    }
+
+    #[test]
+    fn test_from_fd_round_trip() {
+        let (reader, mut writer) = io::pipe().unwrap();
+        writer.write_all(b"piped-secret").unwrap();
+        drop(writer);
+
+        let secret = SensitiveString::from_fd(OwnedFd::from(reader)).unwrap();
+        assert_eq!(secret.value, "piped-secret");
+    }
+
+    #[test]
+    fn test_from_reader_rejects_invalid_utf8() {
+        let err = SensitiveString::from_reader(&[0xff, 0xfe][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_sender_debug_is_redacted() {
+        let secret = SensitiveString::from("handoff-secret");
+        let mut command = Command::new("true");
+        let sender = secret.pass_to_child_fd(&mut command, 3).unwrap();
+        assert!(!format!("{:?}", sender).contains("handoff-secret"));
+    }
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_handoff_is_checked_against_access_policies() {
+        let secret = SensitiveString::from("handoff-secret");
+        let mut command = Command::new("true");
+        crate::access::assert_denied(|| secret.pass_to_child_fd(&mut command, 3));
+    }
+}
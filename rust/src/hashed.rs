@@ -0,0 +1,151 @@
+//! Storing only the digest of a secret.
+//!
+//! Audit records and "has this token been seen before" tables need to
+//! recognize a secret later without ever holding it. A [`HashedSecret`]
+//! keeps only the SHA-256 digest, prints and serializes as `sha256:<hex>`,
+//! parses the same form back, and checks a candidate with
+//! [`verify`](HashedSecret::verify).
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{HashedSecret, SensitiveString};
+//!
+//! let token = SensitiveString::from("tok-123");
+//! let recorded = HashedSecret::from(&token).to_string();   // store this
+//!
+//! let hashed: HashedSecret = recorded.parse().unwrap();    // later
+//! assert!(hashed.verify(&token));
+//! assert!(!hashed.verify(&SensitiveString::from("tok-124")));
+//! ```
+//!
+//! The digest is unsalted, so it only protects high-entropy secrets such as
+//! generated tokens and API keys. Store passwords with a password hash.
+
+use crate::SensitiveString;
+use std::fmt;
+use std::str::FromStr;
+
+/// The prefix of the textual form.
+const PREFIX: &str = "sha256:";
+
+/// The SHA-256 digest of a secret, without the secret.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HashedSecret {
+    digest: [u8; 32],
+}
+
+impl HashedSecret {
+    /// Wraps a raw SHA-256 digest, e.g. one read from a database column.
+    pub fn from_digest(digest: [u8; 32]) -> Self {
+        Self { digest }
+    }
+
+    /// Returns the raw digest.
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+
+    /// Returns true if `secret` hashes to this digest.
+    ///
+    /// The digests are compared in constant time.
+    pub fn verify(&self, secret: &SensitiveString) -> bool {
+        let candidate = secret.digest();
+        self.digest
+            .iter()
+            .zip(candidate.iter())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+    }
+}
+
+impl From<&SensitiveString> for HashedSecret {
+    fn from(secret: &SensitiveString) -> Self {
+        Self::from_digest(secret.digest())
+    }
+}
+
+impl fmt::Display for HashedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", PREFIX, hex::encode(self.digest))
+    }
+}
+
+impl fmt::Debug for HashedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HashedSecret({})", self)
+    }
+}
+
+/// Error returned when a string is not `sha256:` followed by 64 lowercase hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseHashedSecretError;
+
+impl fmt::Display for ParseHashedSecretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected sha256: followed by 64 lowercase hex digits")
+    }
+}
+
+impl std::error::Error for ParseHashedSecretError {}
+
+impl FromStr for HashedSecret {
+    type Err = ParseHashedSecretError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if !crate::is_prefixed_hex_digest(value, PREFIX, 64) {
+            return Err(ParseHashedSecretError);
+        }
+        let mut digest = [0u8; 32];
+        hex::decode_to_slice(&value[PREFIX.len()..], &mut digest)
+            .map_err(|_| ParseHashedSecretError)?;
+        Ok(Self::from_digest(digest))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for HashedSecret {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HashedSecret {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_through_text() {
+        let secret = SensitiveString::from("hunter2");
+        let hashed = HashedSecret::from(&secret);
+        assert_eq!(
+            hashed.to_string(),
+            format!("sha256:{}", secret.digest_hex())
+        );
+        assert_eq!(hashed.to_string().parse(), Ok(hashed));
+        assert!(hashed.verify(&secret));
+
+        assert!("sha256:abc".parse::<HashedSecret>().is_err());
+        assert!(format!("sha256:{}", "A".repeat(64))
+            .parse::<HashedSecret>()
+            .is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_uses_text_form() {
+        let hashed = HashedSecret::from(&SensitiveString::from("hunter2"));
+        let json = serde_json::to_string(&hashed).unwrap();
+        assert_eq!(json, format!("\"{}\"", hashed));
+        assert_eq!(serde_json::from_str::<HashedSecret>(&json).unwrap(), hashed);
+        assert!(serde_json::from_str::<HashedSecret>("\"hunter2\"").is_err());
+    }
+}
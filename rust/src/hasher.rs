@@ -0,0 +1,498 @@
+//! Pluggable redaction digests.
+//!
+//! By default the redacted form is computed in software with SHA256. Some
+//! certified builds must compute it elsewhere: an HMAC keyed inside a TPM or
+//! secure element, or a crypto accelerator on a microcontroller. A
+//! [`Hasher`] computes the string shown in place of the plaintext; it can be
+//! installed once for the whole process with [`set_global_hasher`] or attached
+//! to a single secret with [`SensitiveString::with_hasher`].
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::hasher::{set_global_hasher, Hasher};
+//! use sensitive_string::SensitiveString;
+//!
+//! struct SecureElement;
+//!
+//! impl Hasher for SecureElement {
+//!     fn redact(&self, plaintext: &[u8]) -> String {
+//!         // A real implementation would ask the device for an HMAC.
+//!         format!("se-hmac:{:08x}", plaintext.len())
+//!     }
+//! }
+//!
+//! set_global_hasher(SecureElement).unwrap();
+//! assert_eq!(SensitiveString::from("hunter2").to_string(), "se-hmac:00000007");
+//! ```
+//!
+//! # Peppered digests
+//!
+//! A bare SHA256 of a short password can be reversed with a rainbow table, so
+//! the default output is only safe for high-entropy secrets. With the `hash`
+//! feature, [`set_global_pepper`] installs [`HmacSha256`], which shows
+//! `hmac-sha256:<hex>` keyed with a process-wide pepper instead. Keep the
+//! pepper stable across restarts if redacted logs need to be correlated.
+//!
+//! ```
+//! # #[cfg(feature = "hash")]
+//! # {
+//! use sensitive_string::hasher::set_global_pepper;
+//! use sensitive_string::SensitiveString;
+//!
+//! set_global_pepper(b"load me from a secret store".to_vec()).unwrap();
+//! assert!(SensitiveString::from("hunter2").to_string().starts_with("hmac-sha256:"));
+//! # }
+//! ```
+//!
+//! # Other algorithms
+//!
+//! [`Digest`] shows a plain digest with another [`DigestAlgorithm`]
+//! (SHA-512, or BLAKE3 with the `blake3` feature), optionally truncated to a
+//! number of hex characters for shorter log lines. The prefix names the
+//! algorithm, e.g. `blake3:1a2b3c4d5e6f7a8b`.
+//!
+//! ```
+//! # #[cfg(feature = "hash")]
+//! # {
+//! use sensitive_string::hasher::{Digest, DigestAlgorithm};
+//! use sensitive_string::SensitiveString;
+//!
+//! let secret = SensitiveString::from("hunter2")
+//!     .with_digest(Digest::new(DigestAlgorithm::Sha512).truncate(16));
+//! assert_eq!(secret.to_string(), "sha512:6b97ed68d14eb3f1");
+//! # }
+//! ```
+//!
+//! Truncated digests are for correlating log lines, not for proving that two
+//! secrets are different: short prefixes can collide.
+
+#[cfg(feature = "hash")]
+use crate::SensitiveBytes;
+use crate::SensitiveString;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+static GLOBAL: OnceLock<Box<dyn Hasher>> = OnceLock::new();
+/// Every truncated digest configured in this process, so that their output
+/// is recognized by [`SensitiveString::looks_redacted`].
+#[cfg(feature = "hash")]
+static TRUNCATED: std::sync::RwLock<Vec<Digest>> = std::sync::RwLock::new(Vec::new());
+
+/// Computes the redacted form of a secret.
+pub trait Hasher: Send + Sync {
+    /// Returns the string shown in place of `plaintext`, e.g. `hmac-sha256:<hex>`.
+    ///
+    /// The result must not reveal the plaintext and should be stable for
+    /// equal inputs, so that redacted logs can still be correlated.
+    fn redact(&self, plaintext: &[u8]) -> String;
+
+    /// Returns true if `value` is in this hasher's redacted form.
+    ///
+    /// Used by [`SensitiveString::looks_redacted`], e.g. to reject redacted
+    /// output fed back in as config. Defaults to `false`.
+    fn looks_redacted(&self, value: &str) -> bool {
+        let _ = value;
+        false
+    }
+}
+
+/// Error returned by [`set_global_hasher`] when a global hasher is already installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobalHasherAlreadySet;
+
+impl fmt::Display for GlobalHasherAlreadySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a global redaction hasher is already installed")
+    }
+}
+
+impl std::error::Error for GlobalHasherAlreadySet {}
+
+/// Installs the hasher used by every secret without a per-instance hasher.
+///
+/// Can only be called once per process, so that the redacted form of a secret
+/// cannot change halfway through a log.
+pub fn set_global_hasher(hasher: impl Hasher + 'static) -> Result<(), GlobalHasherAlreadySet> {
+    GLOBAL
+        .set(Box::new(hasher))
+        .map_err(|_| GlobalHasherAlreadySet)
+}
+
+/// Returns the global hasher, if one has been installed.
+pub fn global_hasher() -> Option<&'static dyn Hasher> {
+    GLOBAL.get().map(|hasher| hasher.as_ref())
+}
+
+/// Prefix of the digests produced by [`HmacSha256`].
+#[cfg(feature = "hash")]
+pub const HMAC_SHA256_PREFIX: &str = "hmac-sha256:";
+
+/// A keyed digest: `hmac-sha256:<hex>` with a secret pepper.
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone)]
+pub struct HmacSha256 {
+    key: SensitiveBytes,
+}
+
+#[cfg(feature = "hash")]
+impl HmacSha256 {
+    /// Creates a hasher keyed with `key`.
+    pub fn new(key: impl Into<SensitiveBytes>) -> Self {
+        Self { key: key.into() }
+    }
+}
+
+#[cfg(feature = "hash")]
+impl Hasher for HmacSha256 {
+    fn redact(&self, plaintext: &[u8]) -> String {
+        use hmac::Mac;
+
+        // Hashing for display is not a read of the key.
+        let mut mac = hmac::Hmac::<sha2::Sha256>::new_from_slice(&self.key.value)
+            .expect("HMAC accepts keys of any length");
+        mac.update(plaintext);
+        format!(
+            "{}{}",
+            HMAC_SHA256_PREFIX,
+            hex::encode(mac.finalize().into_bytes())
+        )
+    }
+
+    fn looks_redacted(&self, value: &str) -> bool {
+        crate::is_prefixed_hex_digest(value, HMAC_SHA256_PREFIX, 64)
+    }
+}
+
+/// Installs [`HmacSha256`] keyed with `pepper` as the global hasher.
+#[cfg(feature = "hash")]
+pub fn set_global_pepper(pepper: impl Into<SensitiveBytes>) -> Result<(), GlobalHasherAlreadySet> {
+    set_global_hasher(HmacSha256::new(pepper))
+}
+
+/// A digest algorithm for [`Digest`].
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum DigestAlgorithm {
+    /// SHA-256, shown as `sha256:<hex>`. The crate default.
+    #[default]
+    Sha256,
+    /// SHA-512, shown as `sha512:<hex>`.
+    Sha512,
+    /// BLAKE3, shown as `blake3:<hex>`.
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+#[cfg(feature = "hash")]
+impl DigestAlgorithm {
+    /// Returns the name shown before the hex digest, e.g. `sha512`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DigestAlgorithm::Sha256 => "sha256",
+            DigestAlgorithm::Sha512 => "sha512",
+            #[cfg(feature = "blake3")]
+            DigestAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Returns the number of hex characters in a full digest.
+    pub fn hex_len(self) -> usize {
+        match self {
+            DigestAlgorithm::Sha256 => 64,
+            DigestAlgorithm::Sha512 => 128,
+            #[cfg(feature = "blake3")]
+            DigestAlgorithm::Blake3 => 64,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sha256" => Some(DigestAlgorithm::Sha256),
+            "sha512" => Some(DigestAlgorithm::Sha512),
+            #[cfg(feature = "blake3")]
+            "blake3" => Some(DigestAlgorithm::Blake3),
+            _ => None,
+        }
+    }
+
+    fn hex_digest(self, plaintext: &[u8]) -> String {
+        use sha2::Digest as _;
+
+        match self {
+            DigestAlgorithm::Sha256 => hex::encode(sha2::Sha256::digest(plaintext)),
+            DigestAlgorithm::Sha512 => hex::encode(sha2::Sha512::digest(plaintext)),
+            #[cfg(feature = "blake3")]
+            DigestAlgorithm::Blake3 => blake3::hash(plaintext).to_hex().to_string(),
+        }
+    }
+}
+
+/// An unkeyed digest: `<algorithm>:<hex>`, optionally truncated.
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Digest {
+    algorithm: DigestAlgorithm,
+    hex_len: Option<usize>,
+}
+
+#[cfg(feature = "hash")]
+impl Digest {
+    /// Creates a hasher showing full digests computed with `algorithm`.
+    pub fn new(algorithm: DigestAlgorithm) -> Self {
+        Self {
+            algorithm,
+            hex_len: None,
+        }
+    }
+
+    /// Shows only the first `hex_chars` hex characters of the digest.
+    ///
+    /// Clamped to between 1 and the full digest length.
+    pub fn truncate(mut self, hex_chars: usize) -> Self {
+        self.hex_len = Some(hex_chars.clamp(1, self.algorithm.hex_len()));
+        let mut truncated = TRUNCATED
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !truncated.contains(&self) {
+            truncated.push(self);
+        }
+        self
+    }
+
+    /// Returns the algorithm.
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    fn shown_len(&self) -> usize {
+        self.hex_len.unwrap_or(self.algorithm.hex_len())
+    }
+}
+
+#[cfg(feature = "hash")]
+impl From<DigestAlgorithm> for Digest {
+    fn from(algorithm: DigestAlgorithm) -> Self {
+        Digest::new(algorithm)
+    }
+}
+
+#[cfg(feature = "hash")]
+impl Hasher for Digest {
+    fn redact(&self, plaintext: &[u8]) -> String {
+        let mut digest = self.algorithm.hex_digest(plaintext);
+        digest.truncate(self.shown_len());
+        format!("{}:{}", self.algorithm.name(), digest)
+    }
+
+    fn looks_redacted(&self, value: &str) -> bool {
+        value
+            .strip_prefix(self.algorithm.name())
+            .is_some_and(|rest| crate::is_prefixed_hex_digest(rest, ":", self.shown_len()))
+    }
+}
+
+/// Returns true if `value` is the output of a truncated [`Digest`] configured in this process.
+#[cfg(feature = "hash")]
+pub(crate) fn is_truncated_digest(value: &str) -> bool {
+    TRUNCATED
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .any(|digest| digest.looks_redacted(value))
+}
+
+/// The shortest truncated digest [`SensitiveString::matches_digest`] accepts.
+#[cfg(feature = "hash")]
+const MIN_MATCH_HEX_LEN: usize = 16;
+
+/// Installs `digest` as the global hasher.
+#[cfg(feature = "hash")]
+pub fn set_global_digest(digest: impl Into<Digest>) -> Result<(), GlobalHasherAlreadySet> {
+    set_global_hasher(digest.into())
+}
+
+impl SensitiveString {
+    /// Attaches a digest algorithm to this secret, overriding the global hasher.
+    #[cfg(feature = "hash")]
+    pub fn with_digest(self, digest: impl Into<Digest>) -> Self {
+        self.with_hasher(Arc::new(digest.into()))
+    }
+
+    /// Returns the SHA-256 digest of the plaintext, whatever hasher or policy is in use.
+    #[cfg(feature = "hash")]
+    pub fn digest(&self) -> [u8; 32] {
+        use sha2::Digest as _;
+
+        sha2::Sha256::digest(self.value.as_bytes()).into()
+    }
+
+    /// Returns [`digest`](Self::digest) as 64 lowercase hex characters.
+    #[cfg(feature = "hash")]
+    pub fn digest_hex(&self) -> String {
+        hex::encode(self.digest())
+    }
+
+    /// Returns true if `recorded` is a digest of this secret.
+    ///
+    /// Accepts this secret's current redacted form, `sha256:`, `sha512:`, or
+    /// `blake3:` digests (truncated to no fewer than 16 hex characters), and
+    /// bare 64-character SHA-256 hex.
+    ///
+    /// ```
+    /// use sensitive_string::SensitiveString;
+    ///
+    /// let key = SensitiveString::from("sk-live-123");
+    /// let recorded = format!("sha256:{}", key.digest_hex());
+    /// assert!(key.matches_digest(&recorded));
+    /// assert!(key.matches_digest(&recorded[..30]));
+    /// assert!(!SensitiveString::from("sk-live-124").matches_digest(&recorded));
+    /// ```
+    #[cfg(feature = "hash")]
+    pub fn matches_digest(&self, recorded: &str) -> bool {
+        if recorded == self.hash_string() {
+            return true;
+        }
+        let (algorithm, hex) = match recorded.split_once(':') {
+            Some((name, hex)) => match DigestAlgorithm::from_name(name) {
+                Some(algorithm) => (algorithm, hex),
+                None => return false,
+            },
+            None if recorded.len() == 64 => (DigestAlgorithm::Sha256, recorded),
+            None => return false,
+        };
+        hex.len() >= MIN_MATCH_HEX_LEN
+            && hex.len() <= algorithm.hex_len()
+            && algorithm.hex_digest(self.value.as_bytes()).starts_with(hex)
+    }
+
+    /// Attaches a hasher to this secret, overriding the global one.
+    ///
+    /// Clones share the hasher. Equality and `Hash` ignore it.
+    pub fn with_hasher(mut self, hasher: Arc<dyn Hasher>) -> Self {
+        self.hasher = Some(hasher);
+        self.digest = OnceLock::new();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Prefixed;
+
+    impl Hasher for Prefixed {
+        fn redact(&self, plaintext: &[u8]) -> String {
+            format!("test:{}", plaintext.len())
+        }
+
+        fn looks_redacted(&self, value: &str) -> bool {
+            value.starts_with("test:")
+        }
+    }
+
+    #[test]
+    fn test_per_instance_hasher() {
+        let secret = SensitiveString::from("abc").with_hasher(Arc::new(Prefixed));
+        assert_eq!(secret.to_string(), "test:3");
+        assert_eq!(format!("{:?}", secret), "SensitiveString(test:3)");
+        assert_eq!(secret.clone().to_string(), "test:3");
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_hmac_sha256_matches_rfc_4231() {
+        let hasher = HmacSha256::new(b"Jefe".to_vec());
+        let digest = hasher.redact(b"what do ya want for nothing?");
+        assert_eq!(
+            digest,
+            "hmac-sha256:5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(hasher.looks_redacted(&digest));
+        assert!(SensitiveString::looks_redacted(&digest));
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_digest_algorithms_and_truncation() {
+        let full = Digest::new(DigestAlgorithm::Sha512);
+        let digest = full.redact(b"abc");
+        assert!(digest.starts_with("sha512:ddaf35a193617aba"));
+        assert_eq!(digest.len(), "sha512:".len() + 128);
+        assert!(full.looks_redacted(&digest));
+
+        let short = full.truncate(16);
+        assert_eq!(short.redact(b"abc"), "sha512:ddaf35a193617aba");
+        assert!(short.looks_redacted("sha512:ddaf35a193617aba"));
+        assert!(!short.looks_redacted(&digest));
+
+        let secret = SensitiveString::from("abc").with_digest(short);
+        assert!(SensitiveString::looks_redacted(&secret.to_string()));
+        assert!(!SensitiveString::looks_redacted("sha512:ddaf35a193617ab"));
+
+        let sha256 = Digest::from(DigestAlgorithm::Sha256);
+        assert_eq!(sha256.redact(b"abc"), crate::builtin_redact(b"abc"));
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn test_blake3_digest() {
+        let secret = SensitiveString::from("abc").with_digest(DigestAlgorithm::Blake3);
+        assert_eq!(
+            secret.to_string(),
+            "blake3:6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+    }
+
+    #[test]
+    fn test_digest_is_computed_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        struct Counting(AtomicUsize);
+
+        impl Hasher for Counting {
+            fn redact(&self, plaintext: &[u8]) -> String {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                format!("count:{}", plaintext.len())
+            }
+        }
+
+        let counting = Arc::new(Counting::default());
+        let secret = SensitiveString::from("abc").with_hasher(counting.clone());
+        let _ = format!("{} {:?}", secret, secret);
+        let _ = secret.clone().to_string();
+        assert_eq!(counting.0.load(Ordering::SeqCst), 1);
+
+        let secret = secret.with_hasher(Arc::new(Prefixed));
+        assert_eq!(secret.to_string(), "test:3");
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_digest_accessors_and_matching() {
+        let secret = SensitiveString::from("hunter2").with_digest(DigestAlgorithm::Sha512);
+        assert_eq!(
+            secret.digest_hex(),
+            "f52fbd32b2b3b86ff88ef6c490628285f482af15ddcb29541f94bcf526a3f6c7"
+        );
+        assert_eq!(secret.digest()[0], 0xf5);
+
+        assert!(secret.matches_digest(&secret.to_string()));
+        assert!(secret.matches_digest(&secret.digest_hex()));
+        assert!(secret.matches_digest("sha256:f52fbd32b2b3b86f"));
+        assert!(!secret.matches_digest("sha256:f52fbd32b2b3b86"));
+        assert!(!secret.matches_digest("md5:f52fbd32b2b3b86ff88ef6c490628285"));
+        assert!(!secret.matches_digest("hunter2"));
+    }
+
+    #[test]
+    fn test_hasher_does_not_affect_equality() {
+        let plain = SensitiveString::from("abc");
+        let custom = SensitiveString::from("abc").with_hasher(Arc::new(Prefixed));
+        assert_eq!(plain, custom);
+        assert_ne!(plain.to_string(), custom.to_string());
+    }
+}
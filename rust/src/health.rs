@@ -0,0 +1,350 @@
+//! Freshness reporting for `/healthz`-style endpoints.
+//!
+//! Credentials usually break auth by expiring quietly. A [`HealthRegistry`]
+//! tracks, per labeled secret, where it came from, when it was last
+//! refreshed, when it expires, and when it was last read, and produces a
+//! [`HealthReport`] operators can see before anything fails. Reports contain
+//! no secret material, only labels, source descriptions, and times.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::health::{HealthRegistry, HealthStatus};
+//! use sensitive_string::source::SecretSource;
+//! use std::time::{Duration, SystemTime};
+//!
+//! let health = HealthRegistry::new().warn_within(Duration::from_secs(3600));
+//! let source = SecretSource::Env { name: "DB_PASSWORD".into() };
+//!
+//! let now = SystemTime::now();
+//! health.record_refresh("db-password", &source, Some(now + Duration::from_secs(600)));
+//!
+//! let report = health.report_at(now);
+//! assert_eq!(report.secrets[0].source.as_deref(), Some("env:DB_PASSWORD"));
+//! assert_eq!(report.secrets[0].status, HealthStatus::ExpiringSoon);
+//! assert!(report.is_healthy());
+//! ```
+//!
+//! A [`CachedProvider`](crate::cache::CachedProvider) reports here when it
+//! serves stale values during a provider outage.
+//!
+//! With the `audit` feature, a `HealthRegistry` is also an
+//! [`AccessObserver`](crate::audit::AccessObserver), so installing it records
+//! last-access times automatically. With the `serde` feature, reports
+//! serialize with RFC 3339 timestamps.
+
+use crate::source::SecretSource;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// How far ahead of expiry a secret is reported as [`HealthStatus::ExpiringSoon`] by default.
+pub const DEFAULT_WARN_WITHIN: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Tracks the freshness of labeled secrets.
+#[derive(Debug)]
+pub struct HealthRegistry {
+    entries: Mutex<BTreeMap<String, Entry>>,
+    warn_within: Duration,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    source: Option<String>,
+    last_refreshed: Option<SystemTime>,
+    expires_at: Option<SystemTime>,
+    last_access: Option<SystemTime>,
+    access_count: u64,
+    degraded_since: Option<SystemTime>,
+    failed: bool,
+    classification: Option<String>,
+}
+
+/// Whether a secret is usable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum HealthStatus {
+    /// No known expiry, or expiry is further away than the warning window.
+    Ok,
+    /// Expires within the warning window.
+    ExpiringSoon,
+    /// Refreshing failed and a stale value is being served.
+    Degraded,
+    /// Refreshing failed and no value is being served.
+    Failed,
+    /// Already expired.
+    Expired,
+}
+
+/// The health of one labeled secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SecretHealth {
+    /// The secret's label.
+    pub label: String,
+    /// Where the secret was last loaded from, e.g. `env:DB_PASSWORD`.
+    pub source: Option<String>,
+    /// When the secret was last loaded.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+    pub last_refreshed: Option<SystemTime>,
+    /// When the secret stops working, if known.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+    pub expires_at: Option<SystemTime>,
+    /// When the plaintext was last read.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+    pub last_access: Option<SystemTime>,
+    /// How many times the plaintext has been read.
+    pub access_count: u64,
+    /// When refreshing started failing, until it succeeds again.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+    pub degraded_since: Option<SystemTime>,
+    /// The classification recorded with [`HealthRegistry::record_classification`], e.g. `"pci"`.
+    pub classification: Option<String>,
+    /// Whether the secret is usable.
+    pub status: HealthStatus,
+}
+
+/// A snapshot of every tracked secret, ordered by label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HealthReport {
+    /// One entry per labeled secret.
+    pub secrets: Vec<SecretHealth>,
+}
+
+impl HealthReport {
+    /// Returns true if no secret has expired or failed.
+    ///
+    /// Degraded secrets still count as healthy: they are being served.
+    pub fn is_healthy(&self) -> bool {
+        self.secrets
+            .iter()
+            .all(|secret| !matches!(secret.status, HealthStatus::Expired | HealthStatus::Failed))
+    }
+}
+
+impl Default for HealthRegistry {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(BTreeMap::new()),
+            warn_within: DEFAULT_WARN_WITHIN,
+        }
+    }
+}
+
+impl HealthRegistry {
+    /// Creates an empty registry that warns a day before expiry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how far ahead of expiry a secret is reported as expiring soon.
+    pub fn warn_within(mut self, window: Duration) -> Self {
+        self.warn_within = window;
+        self
+    }
+
+    /// Records that the secret `label` was (re)loaded from `source`.
+    pub fn record_refresh(
+        &self,
+        label: impl Into<String>,
+        source: &SecretSource,
+        expires_at: Option<SystemTime>,
+    ) {
+        let mut entries = self.lock();
+        let entry = entries.entry(label.into()).or_default();
+        entry.source = Some(source.to_string());
+        entry.last_refreshed = Some(SystemTime::now());
+        entry.expires_at = expires_at;
+    }
+
+    /// Records that refreshing the secret `label` failed and a stale value is being served.
+    ///
+    /// Keeps the time of the first failure until [`record_recovered`](Self::record_recovered).
+    pub fn record_degraded(&self, label: &str) {
+        let mut entries = self.lock();
+        let entry = entries.entry(label.to_string()).or_default();
+        entry.degraded_since.get_or_insert_with(SystemTime::now);
+    }
+
+    /// Records that refreshing the secret `label` failed with nothing to
+    /// fall back on, e.g. once a stale value is too old to serve.
+    pub fn record_failed(&self, label: &str) {
+        let mut entries = self.lock();
+        let entry = entries.entry(label.to_string()).or_default();
+        entry.degraded_since.get_or_insert_with(SystemTime::now);
+        entry.failed = true;
+    }
+
+    /// Records that the secret `label` refreshed successfully again.
+    pub fn record_recovered(&self, label: &str) {
+        if let Some(entry) = self.lock().get_mut(label) {
+            entry.degraded_since = None;
+            entry.failed = false;
+        }
+    }
+
+    /// Records how the secret `label` is classified, e.g. `"pci"` or `"internal"`.
+    pub fn record_classification(
+        &self,
+        label: impl Into<String>,
+        classification: impl Into<String>,
+    ) {
+        let mut entries = self.lock();
+        entries.entry(label.into()).or_default().classification = Some(classification.into());
+    }
+
+    /// Records that the plaintext of the secret `label` was read.
+    pub fn record_access(&self, label: &str) {
+        let mut entries = self.lock();
+        let entry = entries.entry(label.to_string()).or_default();
+        entry.last_access = Some(SystemTime::now());
+        entry.access_count += 1;
+    }
+
+    /// Reports the health of every tracked secret as of now.
+    pub fn report(&self) -> HealthReport {
+        self.report_at(SystemTime::now())
+    }
+
+    /// Reports the health of every tracked secret as of `now`.
+    pub fn report_at(&self, now: SystemTime) -> HealthReport {
+        let secrets = self
+            .lock()
+            .iter()
+            .map(|(label, entry)| SecretHealth {
+                label: label.clone(),
+                source: entry.source.clone(),
+                last_refreshed: entry.last_refreshed,
+                expires_at: entry.expires_at,
+                last_access: entry.last_access,
+                access_count: entry.access_count,
+                degraded_since: entry.degraded_since,
+                classification: entry.classification.clone(),
+                status: self.status(entry, now),
+            })
+            .collect();
+        HealthReport { secrets }
+    }
+
+    fn status(&self, entry: &Entry, now: SystemTime) -> HealthStatus {
+        match entry.expires_at {
+            Some(expiry) if expiry <= now => HealthStatus::Expired,
+            _ if entry.failed => HealthStatus::Failed,
+            _ if entry.degraded_since.is_some() => HealthStatus::Degraded,
+            Some(expiry) if expiry <= now + self.warn_within => HealthStatus::ExpiringSoon,
+            _ => HealthStatus::Ok,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, Entry>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Records reads of labeled secrets; unlabeled secrets are ignored.
+#[cfg(feature = "audit")]
+impl crate::audit::AccessObserver for HealthRegistry {
+    fn on_access(&self, event: &crate::audit::AccessEvent<'_>) {
+        if let Some(label) = event.label {
+            self.record_access(label);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+pub(crate) fn serialize_time<S>(time: &Option<SystemTime>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match time {
+        Some(time) => serializer.serialize_str(&crate::syslog::rfc3339_utc(*time)),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_from_expiry() {
+        let health = HealthRegistry::new().warn_within(Duration::from_secs(60));
+        let source = SecretSource::Env {
+            name: "TOKEN".into(),
+        };
+        let now = SystemTime::now();
+        health.record_refresh("expired", &source, Some(now - Duration::from_secs(1)));
+        health.record_refresh("soon", &source, Some(now + Duration::from_secs(30)));
+        health.record_refresh("later", &source, Some(now + Duration::from_secs(3600)));
+        health.record_refresh("forever", &source, None);
+
+        let report = health.report_at(now);
+        let statuses: Vec<_> = report
+            .secrets
+            .iter()
+            .map(|secret| (secret.label.as_str(), secret.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("expired", HealthStatus::Expired),
+                ("forever", HealthStatus::Ok),
+                ("later", HealthStatus::Ok),
+                ("soon", HealthStatus::ExpiringSoon),
+            ]
+        );
+        assert!(!report.is_healthy());
+    }
+
+    #[test]
+    fn test_degraded_until_recovered() {
+        const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+        let health = HealthRegistry::new();
+        let source = SecretSource::Env {
+            name: "TOKEN".into(),
+        };
+        let now = SystemTime::now();
+        health.record_refresh("token", &source, Some(now + 7 * DAY));
+        health.record_degraded("token");
+        let since = health.report_at(now).secrets[0].degraded_since;
+        health.record_degraded("token");
+
+        let report = health.report_at(now);
+        assert_eq!(report.secrets[0].status, HealthStatus::Degraded);
+        assert_eq!(report.secrets[0].degraded_since, since);
+        assert!(report.is_healthy());
+        assert_eq!(
+            health.report_at(now + 7 * DAY).secrets[0].status,
+            HealthStatus::Expired
+        );
+
+        health.record_recovered("token");
+        assert_eq!(health.report_at(now).secrets[0].status, HealthStatus::Ok);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_report_serializes_without_secrets() {
+        let health = HealthRegistry::new();
+        let source = SecretSource::Plain {
+            value: crate::SensitiveString::from("hunter2"),
+        };
+        health.record_refresh("inline", &source, None);
+        health.record_access("inline");
+
+        let json = serde_json::to_value(health.report()).unwrap();
+        let entry = &json["secrets"][0];
+        assert_eq!(entry["source"], "plain");
+        assert_eq!(entry["access_count"], 1);
+        assert_eq!(entry["expires_at"], serde_json::Value::Null);
+        assert!(entry["last_access"].as_str().unwrap().ends_with('Z'));
+        assert!(!json.to_string().contains("hunter2"));
+    }
+}
@@ -0,0 +1,118 @@
+//! Putting secrets into HTTP request headers.
+//!
+//! Building an `Authorization` header otherwise means calling `get_value()`,
+//! formatting the plaintext into a temporary `String`, and remembering to
+//! call `set_sensitive(true)` so that HTTP/2 never adds the value to its
+//! HPACK compression tables. With the `http` feature,
+//! [`to_header_value`](SensitiveString::to_header_value),
+//! [`as_bearer_auth`](SensitiveString::as_bearer_auth), and
+//! [`as_basic_auth`](SensitiveString::as_basic_auth) build an
+//! [`http::HeaderValue`](::http::HeaderValue) already marked sensitive, which
+//! `reqwest`, `hyper`, and other `http`-based clients accept as is.
+//!
+//! # Example
+//!
+//! ```
+//! use http::header::AUTHORIZATION;
+//! use http::Request;
+//! use sensitive_string::SensitiveString;
+//!
+//! let token = SensitiveString::from("tok-123");
+//! let request = Request::get("https://api.example.com/")
+//!     .header(AUTHORIZATION, token.as_bearer_auth()?)
+//!     .body(())?;
+//!
+//! let header = &request.headers()[AUTHORIZATION];
+//! assert!(header.is_sensitive());
+//! assert_eq!(format!("{:?}", header), "Sensitive");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Scratch buffers holding the formatted header are zeroed before returning;
+//! the `HeaderValue` itself is not, since `http` owns its storage. Each
+//! helper counts as a plaintext read for auditing and access policies.
+
+use crate::{wipe, SensitiveString};
+use ::base64::engine::general_purpose::STANDARD;
+use ::base64::Engine;
+use ::http::header::{HeaderValue, InvalidHeaderValue};
+
+/// Builds a sensitive header value from `bytes`, then zeroes them.
+fn sensitive_header(mut bytes: Vec<u8>) -> Result<HeaderValue, InvalidHeaderValue> {
+    let value = HeaderValue::from_bytes(&bytes);
+    wipe(&mut bytes);
+    let mut value = value?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+impl SensitiveString {
+    /// Returns the plaintext as a header value marked sensitive.
+    ///
+    /// Fails if the secret contains characters not allowed in a header, such
+    /// as control characters; the error does not include the value.
+    #[track_caller]
+    pub fn to_header_value(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.record_access("to_header_value");
+        sensitive_header(self.value.as_bytes().to_vec())
+    }
+
+    /// Returns `Bearer <secret>` as a header value marked sensitive.
+    #[track_caller]
+    pub fn as_bearer_auth(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.record_access("as_bearer_auth");
+        let mut header = Vec::with_capacity("Bearer ".len() + self.value.len());
+        header.extend_from_slice(b"Bearer ");
+        header.extend_from_slice(self.value.as_bytes());
+        sensitive_header(header)
+    }
+
+    /// Returns `Basic <base64(user:secret)>` as a header value marked
+    /// sensitive, with the secret as the password.
+    #[track_caller]
+    pub fn as_basic_auth(&self, user: &str) -> HeaderValue {
+        self.record_access("as_basic_auth");
+        let mut credentials = Vec::with_capacity(user.len() + 1 + self.value.len());
+        credentials.extend_from_slice(user.as_bytes());
+        credentials.push(b':');
+        credentials.extend_from_slice(self.value.as_bytes());
+        let mut header = String::from("Basic ");
+        STANDARD.encode_string(&credentials, &mut header);
+        wipe(&mut credentials);
+        sensitive_header(header.into_bytes()).expect("base64 is a valid header value")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_values_are_sensitive() {
+        let token = SensitiveString::from("tok-123");
+        let value = token.to_header_value().unwrap();
+        assert!(value.is_sensitive());
+        assert_eq!(value, "tok-123");
+        assert_eq!(format!("{:?}", value), "Sensitive");
+
+        let bearer = token.as_bearer_auth().unwrap();
+        assert!(bearer.is_sensitive());
+        assert_eq!(bearer, "Bearer tok-123");
+    }
+
+    #[test]
+    fn test_basic_auth_encodes_user_and_password() {
+        let password = SensitiveString::from("open sesame");
+        let value = password.as_basic_auth("Aladdin");
+        assert!(value.is_sensitive());
+        assert_eq!(value, "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+    }
+
+    #[test]
+    fn test_invalid_characters_are_rejected() {
+        let error = SensitiveString::from("tok\n123")
+            .to_header_value()
+            .unwrap_err();
+        assert!(!error.to_string().contains("tok"));
+    }
+}
@@ -0,0 +1,183 @@
+//! A redacted inventory of the secrets a process holds.
+//!
+//! Internal admin endpoints want to answer "which credentials does this
+//! service have, where did they come from, and when do they expire" without
+//! ever returning one. [`inventory`] lists every secret in the global
+//! [`SecretRegistry`] by label and fingerprint, and
+//! [`with_health`](Inventory::with_health) joins in the source,
+//! classification, expiry, and status a [`HealthRegistry`] tracks for the
+//! same label. With the `serde` feature an [`Inventory`] serializes as JSON
+//! for a REST or GraphQL resolver to return as is.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::health::HealthRegistry;
+//! use sensitive_string::inventory::inventory;
+//! use sensitive_string::source::SecretSource;
+//! use sensitive_string::SensitiveString;
+//!
+//! let password = SensitiveString::from("hunter2").with_label("db-password").registered();
+//! let health = HealthRegistry::new();
+//! health.record_refresh("db-password", &SecretSource::Env { name: "DB_PASSWORD".into() }, None);
+//! health.record_classification("db-password", "restricted");
+//!
+//! let inventory = inventory().with_health(&health);
+//! let entry = inventory.get("db-password").unwrap();
+//! assert_eq!(entry.source.as_deref(), Some("env:DB_PASSWORD"));
+//! assert_eq!(entry.classification.as_deref(), Some("restricted"));
+//! assert_eq!(entry.fingerprint.as_deref(), Some(password.to_string().as_str()));
+//! ```
+//!
+//! Fingerprints are full digests whatever the redaction policy, like those
+//! printed by [`assert_secret_eq!`](crate::assert_secret_eq), so a
+//! `MaskLast` policy never puts part of a secret into the inventory.
+
+use crate::health::{HealthRegistry, HealthStatus};
+use crate::registry::SecretRegistry;
+use std::time::SystemTime;
+
+/// One secret in an [`Inventory`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct InventoryEntry {
+    /// The secret's label, if it has one.
+    pub label: Option<String>,
+    /// The secret's digest, or `None` for a label only the health registry knows.
+    pub fingerprint: Option<String>,
+    /// Where the secret was last loaded from, e.g. `env:DB_PASSWORD`.
+    pub source: Option<String>,
+    /// How the secret is classified, e.g. `"pci"`.
+    pub classification: Option<String>,
+    /// When the secret stops working, if known.
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::health::serialize_time")
+    )]
+    pub expires_at: Option<SystemTime>,
+    /// Whether the secret is usable, if it is tracked for health.
+    pub status: Option<HealthStatus>,
+}
+
+/// Every known secret, ordered by label, without any secret material.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Inventory {
+    /// One entry per secret; unlabeled secrets come first.
+    pub secrets: Vec<InventoryEntry>,
+}
+
+/// Lists the secrets in the global [`SecretRegistry`].
+pub fn inventory() -> Inventory {
+    Inventory::of(SecretRegistry::global())
+}
+
+impl Inventory {
+    /// Lists the secrets in `registry`.
+    pub fn of(registry: &SecretRegistry) -> Self {
+        let mut secrets: Vec<_> = registry
+            .scrubber()
+            .secrets()
+            .iter()
+            .map(|secret| InventoryEntry {
+                label: secret.label().map(str::to_string),
+                fingerprint: Some(secret.hash_string()),
+                source: None,
+                classification: None,
+                expires_at: None,
+                status: None,
+            })
+            .collect();
+        sort(&mut secrets);
+        Self { secrets }
+    }
+
+    /// Fills in what `health` knows about each label, adding labels it
+    /// tracks that are not registered.
+    pub fn with_health(mut self, health: &HealthRegistry) -> Self {
+        for tracked in health.report().secrets {
+            let mut matched = false;
+            for entry in &mut self.secrets {
+                if entry.label.as_deref() == Some(tracked.label.as_str()) {
+                    entry.source.clone_from(&tracked.source);
+                    entry.classification.clone_from(&tracked.classification);
+                    entry.expires_at = tracked.expires_at;
+                    entry.status = Some(tracked.status);
+                    matched = true;
+                }
+            }
+            if !matched {
+                self.secrets.push(InventoryEntry {
+                    label: Some(tracked.label),
+                    fingerprint: None,
+                    source: tracked.source,
+                    classification: tracked.classification,
+                    expires_at: tracked.expires_at,
+                    status: Some(tracked.status),
+                });
+            }
+        }
+        sort(&mut self.secrets);
+        self
+    }
+
+    /// Returns the first entry for `label`.
+    pub fn get(&self, label: &str) -> Option<&InventoryEntry> {
+        self.secrets
+            .iter()
+            .find(|entry| entry.label.as_deref() == Some(label))
+    }
+}
+
+fn sort(secrets: &mut [InventoryEntry]) {
+    secrets.sort_by(|a, b| (&a.label, &a.fingerprint).cmp(&(&b.label, &b.fingerprint)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::SecretSource;
+    use crate::SensitiveString;
+
+    #[test]
+    fn test_joins_registry_and_health_by_label() {
+        let registry = SecretRegistry::new();
+        registry.register(&SensitiveString::from("hunter2").with_label("db"));
+        registry.register(&SensitiveString::from("unlabeled"));
+        let health = HealthRegistry::new();
+        let source = SecretSource::Env {
+            name: "API_TOKEN".into(),
+        };
+        health.record_refresh("api", &source, None);
+        health.record_classification("db", "restricted");
+
+        let inventory = Inventory::of(&registry).with_health(&health);
+        let labels: Vec<_> = inventory
+            .secrets
+            .iter()
+            .map(|entry| entry.label.as_deref())
+            .collect();
+        assert_eq!(labels, [None, Some("api"), Some("db")]);
+        assert_eq!(inventory.get("api").unwrap().fingerprint, None);
+        assert_eq!(
+            inventory.get("db").unwrap().classification.as_deref(),
+            Some("restricted")
+        );
+        assert_eq!(inventory.get("db").unwrap().status, Some(HealthStatus::Ok));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serializes_without_plaintext() {
+        let registry = SecretRegistry::new();
+        let secret = SensitiveString::from("hunter2")
+            .with_label("db")
+            .with_policy(crate::policy::RedactionPolicy::MaskLast(4));
+        registry.register(&secret);
+
+        let json = serde_json::to_value(Inventory::of(&registry)).unwrap();
+        assert_eq!(json["secrets"][0]["label"], "db");
+        assert_eq!(json["secrets"][0]["fingerprint"], secret.hash_string());
+        assert!(!json.to_string().contains("ter2"));
+    }
+}
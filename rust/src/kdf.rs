@@ -0,0 +1,240 @@
+//! Deriving keys from secrets.
+//!
+//! Turning a wrapped passphrase or master key into an encryption key usually
+//! means calling `get_value()` and handing the plaintext to a KDF crate,
+//! which then returns the key as a bare `Vec<u8>`. The methods here run the
+//! derivation inside the crate and write the output straight into a
+//! [`SensitiveBytes`]:
+//!
+//! - [`derive_key_hkdf`](SensitiveString::derive_key_hkdf): HKDF-SHA256, for
+//!   secrets that are already high-entropy (master keys, API secrets).
+//! - [`derive_key_pbkdf2`](SensitiveString::derive_key_pbkdf2) (with the
+//!   `pbkdf2` feature): PBKDF2-HMAC-SHA256, for passphrases where Argon2 is
+//!   not an option.
+//! - [`derive_key_argon2`](SensitiveString::derive_key_argon2) (with the
+//!   `argon2` feature): Argon2id, the better choice for passphrases.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "hash")]
+//! # {
+//! use sensitive_string::SensitiveString;
+//!
+//! let master = SensitiveString::from("0f3c9a...master-key-material");
+//! let enc_key = master.derive_key_hkdf(b"app-salt", b"orders/encryption", 32);
+//! let mac_key = master.derive_key_hkdf(b"app-salt", b"orders/mac", 32);
+//! assert_eq!(enc_key.len(), 32);
+//! assert_ne!(enc_key, mac_key);
+//! # }
+//! ```
+
+use crate::{SensitiveBytes, SensitiveString};
+use hkdf::Hkdf;
+use sha2::Sha256;
+#[cfg(feature = "argon2")]
+use std::fmt;
+
+/// The largest output HKDF-SHA256 can produce.
+const HKDF_MAX_LEN: usize = 255 * 32;
+
+fn hkdf(ikm: &[u8], salt: &[u8], info: &[u8], len: usize) -> SensitiveBytes {
+    assert!(
+        len <= HKDF_MAX_LEN,
+        "HKDF-SHA256 output is limited to {} bytes",
+        HKDF_MAX_LEN
+    );
+    let mut okm = SensitiveBytes::new(vec![0u8; len]);
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(info, &mut okm.value)
+        .expect("length checked above");
+    okm
+}
+
+impl SensitiveString {
+    /// Derives `len` bytes of key material with HKDF-SHA256.
+    ///
+    /// Use `info` to separate keys for different purposes. HKDF does no key
+    /// stretching, so passphrases should use PBKDF2 or Argon2 instead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is more than 8160 bytes.
+    pub fn derive_key_hkdf(&self, salt: &[u8], info: &[u8], len: usize) -> SensitiveBytes {
+        self.record_access("derive_key_hkdf");
+        hkdf(self.value.as_bytes(), salt, info, len)
+    }
+
+    /// Derives `len` bytes of key material with PBKDF2-HMAC-SHA256.
+    ///
+    /// OWASP recommends at least 600,000 rounds for this construction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rounds` is zero.
+    #[cfg(feature = "pbkdf2")]
+    pub fn derive_key_pbkdf2(&self, salt: &[u8], rounds: u32, len: usize) -> SensitiveBytes {
+        assert!(rounds > 0, "PBKDF2 needs at least one round");
+        self.record_access("derive_key_pbkdf2");
+        let mut key = SensitiveBytes::new(vec![0u8; len]);
+        pbkdf2::pbkdf2_hmac::<Sha256>(self.value.as_bytes(), salt, rounds, &mut key.value);
+        key
+    }
+
+    /// Derives `len` bytes of key material with Argon2id and default parameters.
+    #[cfg(feature = "argon2")]
+    pub fn derive_key_argon2(&self, salt: &[u8], len: usize) -> Result<SensitiveBytes, KdfError> {
+        self.derive_key_argon2_with(salt, len, Argon2Params::default())
+    }
+
+    /// Derives `len` bytes of key material with Argon2id and the given cost parameters.
+    #[cfg(feature = "argon2")]
+    pub fn derive_key_argon2_with(
+        &self,
+        salt: &[u8],
+        len: usize,
+        params: Argon2Params,
+    ) -> Result<SensitiveBytes, KdfError> {
+        self.record_access("derive_key_argon2");
+        let params = argon2::Params::new(
+            params.memory_kib,
+            params.iterations,
+            params.parallelism,
+            Some(len),
+        )
+        .map_err(KdfError::from)?;
+        let argon2 =
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+        let mut key = SensitiveBytes::new(vec![0u8; len]);
+        argon2
+            .hash_password_into(self.value.as_bytes(), salt, &mut key.value)
+            .map_err(KdfError::from)?;
+        Ok(key)
+    }
+}
+
+impl SensitiveBytes {
+    /// Derives `len` bytes of key material from these bytes with HKDF-SHA256.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is more than 8160 bytes.
+    pub fn derive_key_hkdf(&self, salt: &[u8], info: &[u8], len: usize) -> SensitiveBytes {
+        hkdf(&self.value, salt, info, len)
+    }
+}
+
+/// Argon2id cost parameters.
+///
+/// The default (19 MiB, 2 iterations, 1 lane) is the OWASP minimum.
+#[cfg(feature = "argon2")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Params {
+    /// Memory cost in KiB.
+    pub memory_kib: u32,
+    /// Number of passes over memory.
+    pub iterations: u32,
+    /// Degree of parallelism.
+    pub parallelism: u32,
+}
+
+#[cfg(feature = "argon2")]
+impl Default for Argon2Params {
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// Error returned when Argon2 rejects its inputs.
+#[cfg(feature = "argon2")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdfError {
+    /// The salt is shorter than 8 bytes.
+    SaltTooShort,
+    /// The requested key is shorter than 4 bytes or too long.
+    InvalidLength,
+    /// The cost parameters are out of range.
+    InvalidParams,
+}
+
+#[cfg(feature = "argon2")]
+impl From<argon2::Error> for KdfError {
+    fn from(error: argon2::Error) -> Self {
+        match error {
+            argon2::Error::SaltTooShort => KdfError::SaltTooShort,
+            argon2::Error::OutputTooShort | argon2::Error::OutputTooLong => KdfError::InvalidLength,
+            _ => KdfError::InvalidParams,
+        }
+    }
+}
+
+#[cfg(feature = "argon2")]
+impl fmt::Display for KdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdfError::SaltTooShort => write!(f, "salt must be at least 8 bytes"),
+            KdfError::InvalidLength => write!(f, "invalid derived key length"),
+            KdfError::InvalidParams => write!(f, "invalid Argon2 parameters"),
+        }
+    }
+}
+
+#[cfg(feature = "argon2")]
+impl std::error::Error for KdfError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hkdf_matches_rfc5869_case_1() {
+        let ikm = SensitiveBytes::new(vec![0x0b; 22]);
+        let salt = hex::decode("000102030405060708090a0b0c").unwrap();
+        let info = hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap();
+        let okm = ikm.derive_key_hkdf(&salt, &info, 42);
+        assert_eq!(
+            hex::encode(&okm.value),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+
+        let passphrase = SensitiveString::from("\u{b}".repeat(22));
+        assert_eq!(passphrase.derive_key_hkdf(&salt, &info, 42), okm);
+    }
+
+    #[cfg(feature = "pbkdf2")]
+    #[test]
+    fn test_pbkdf2_matches_known_vector() {
+        let key = SensitiveString::from("password").derive_key_pbkdf2(b"salt", 1, 32);
+        assert_eq!(
+            hex::encode(&key.value),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+    }
+
+    #[cfg(feature = "argon2")]
+    #[test]
+    fn test_argon2_depends_on_salt_and_params() {
+        let passphrase = SensitiveString::from("correct horse");
+        let cheap = Argon2Params {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        };
+        let a = passphrase
+            .derive_key_argon2_with(b"salt-one", 32, cheap)
+            .unwrap();
+        let b = passphrase
+            .derive_key_argon2_with(b"salt-two", 32, cheap)
+            .unwrap();
+        assert_eq!(a.len(), 32);
+        assert_ne!(a, b);
+        assert_eq!(
+            passphrase.derive_key_argon2_with(b"short", 32, cheap),
+            Err(KdfError::SaltTooShort)
+        );
+    }
+}
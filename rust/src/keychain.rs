@@ -0,0 +1,294 @@
+//! Storing secrets in the macOS Keychain.
+//!
+//! [`SensitiveString::store_in_keychain`] adds the plaintext to the user's
+//! Keychain as a generic-password item and returns a [`PersistentRef`], an
+//! opaque handle to the item. The handle is not secret: a desktop app can
+//! write it to its preferences or database in place of the plaintext, and
+//! read the secret back later with [`SensitiveString::from_keychain`].
+//!
+//! Items are stored as accessible only while the device is unlocked and are
+//! never synced or migrated to another device.
+//!
+//! The Keychain only exists on macOS. On other platforms the feature still
+//! compiles, and every call returns [`KeychainError::Unsupported`], so
+//! cross-platform code needs no `cfg` of its own.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use sensitive_string::keychain::PersistentRef;
+//! use sensitive_string::SensitiveString;
+//!
+//! let token = SensitiveString::from("ghp_example");
+//! let reference = token.store_in_keychain("com.example.app", "github")?;
+//! std::fs::write("github.ref", reference.as_bytes())?;
+//!
+//! let reference = PersistentRef::from_bytes(std::fs::read("github.ref")?);
+//! let token = SensitiveString::from_keychain(&reference)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::SensitiveString;
+use std::fmt;
+
+/// An opaque reference to a Keychain item, safe to persist.
+///
+/// The bytes identify the item; they do not contain the secret.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct PersistentRef(Vec<u8>);
+
+impl PersistentRef {
+    /// Wraps bytes previously returned by [`as_bytes`](Self::as_bytes).
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Self {
+        Self(bytes.into())
+    }
+
+    /// Returns the reference as bytes, for storing.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for PersistentRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PersistentRef({} bytes)", self.0.len())
+    }
+}
+
+/// Errors from Keychain operations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeychainError {
+    /// The Keychain is only available on macOS.
+    Unsupported,
+    /// An item for this service and account already exists.
+    Duplicate,
+    /// No item matches the reference.
+    NotFound,
+    /// The item's data is not valid UTF-8.
+    InvalidUtf8,
+    /// The Security framework returned another `OSStatus`.
+    Status(i32),
+}
+
+impl fmt::Display for KeychainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeychainError::Unsupported => write!(f, "the Keychain is only available on macOS"),
+            KeychainError::Duplicate => {
+                write!(f, "a Keychain item for this account already exists")
+            }
+            KeychainError::NotFound => write!(f, "Keychain item not found"),
+            KeychainError::InvalidUtf8 => write!(f, "Keychain item is not valid UTF-8"),
+            KeychainError::Status(status) => write!(f, "Keychain error (OSStatus {})", status),
+        }
+    }
+}
+
+impl std::error::Error for KeychainError {}
+
+impl SensitiveString {
+    /// Adds the plaintext to the Keychain as a generic-password item for
+    /// `service` and `account`, returning a reference to persist instead of it.
+    ///
+    /// Fails with [`KeychainError::Duplicate`] if the service and account
+    /// already have an item; remove it with [`delete`] first to replace it.
+    #[track_caller]
+    pub fn store_in_keychain(
+        &self,
+        service: &str,
+        account: &str,
+    ) -> Result<PersistentRef, KeychainError> {
+        self.record_access("store_in_keychain");
+        sys::add(service, account, self.value.as_bytes())
+    }
+
+    /// Reads the secret stored by [`store_in_keychain`](Self::store_in_keychain).
+    ///
+    /// macOS may ask the user to allow access if the item was created by
+    /// another application.
+    pub fn from_keychain(reference: &PersistentRef) -> Result<Self, KeychainError> {
+        String::from_utf8(sys::copy(reference)?)
+            .map(SensitiveString::new)
+            .map_err(|err| {
+                wipe(&mut err.into_bytes());
+                KeychainError::InvalidUtf8
+            })
+    }
+}
+
+/// Removes the item `reference` points to from the Keychain.
+pub fn delete(reference: &PersistentRef) -> Result<(), KeychainError> {
+    sys::delete(reference)
+}
+
+fn wipe(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        // SAFETY: `byte` is a valid, aligned reference into the buffer.
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+#[cfg(target_os = "macos")]
+mod sys {
+    use super::{KeychainError, PersistentRef};
+    use core_foundation::base::{CFType, TCFType};
+    use core_foundation::boolean::CFBoolean;
+    use core_foundation::data::CFData;
+    use core_foundation::dictionary::CFDictionary;
+    use core_foundation::string::{CFString, CFStringRef};
+    use security_framework_sys::access_control::kSecAttrAccessibleWhenUnlockedThisDeviceOnly;
+    use security_framework_sys::item::{
+        kSecAttrAccount, kSecAttrService, kSecClass, kSecClassGenericPassword, kSecReturnData,
+        kSecReturnPersistentRef, kSecValueData,
+    };
+    use security_framework_sys::keychain_item::{SecItemAdd, SecItemCopyMatching, SecItemDelete};
+    use std::ptr;
+
+    const ERR_SEC_SUCCESS: i32 = 0;
+    const ERR_SEC_DUPLICATE_ITEM: i32 = -25299;
+    const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+    // Not declared by `security-framework-sys`.
+    #[link(name = "Security", kind = "framework")]
+    extern "C" {
+        static kSecAttrAccessible: CFStringRef;
+        static kSecValuePersistentRef: CFStringRef;
+    }
+
+    /// Wraps one of the Security framework's constant attribute keys.
+    fn key(name: CFStringRef) -> CFString {
+        // SAFETY: the keys are static CFStrings owned by the framework.
+        unsafe { CFString::wrap_under_get_rule(name) }
+    }
+
+    fn check(status: i32) -> Result<(), KeychainError> {
+        match status {
+            ERR_SEC_SUCCESS => Ok(()),
+            ERR_SEC_DUPLICATE_ITEM => Err(KeychainError::Duplicate),
+            ERR_SEC_ITEM_NOT_FOUND => Err(KeychainError::NotFound),
+            other => Err(KeychainError::Status(other)),
+        }
+    }
+
+    fn by_reference(reference: &PersistentRef) -> (CFString, CFType) {
+        // SAFETY: reading an immutable extern static.
+        let name = key(unsafe { kSecValuePersistentRef });
+        (name, CFData::from_buffer(&reference.0).as_CFType())
+    }
+
+    pub(super) fn add(
+        service: &str,
+        account: &str,
+        secret: &[u8],
+    ) -> Result<PersistentRef, KeychainError> {
+        // SAFETY (for each `unsafe` below): reading immutable extern statics.
+        let attributes = CFDictionary::from_CFType_pairs(&[
+            (
+                key(unsafe { kSecClass }),
+                key(unsafe { kSecClassGenericPassword }).as_CFType(),
+            ),
+            (
+                key(unsafe { kSecAttrService }),
+                CFString::new(service).as_CFType(),
+            ),
+            (
+                key(unsafe { kSecAttrAccount }),
+                CFString::new(account).as_CFType(),
+            ),
+            (
+                key(unsafe { kSecAttrAccessible }),
+                key(unsafe { kSecAttrAccessibleWhenUnlockedThisDeviceOnly }).as_CFType(),
+            ),
+            (
+                key(unsafe { kSecValueData }),
+                CFData::from_buffer(secret).as_CFType(),
+            ),
+            (
+                key(unsafe { kSecReturnPersistentRef }),
+                CFBoolean::true_value().as_CFType(),
+            ),
+        ]);
+        let mut result = ptr::null();
+        // SAFETY: `attributes` is a valid dictionary and `result` a valid out-pointer.
+        check(unsafe { SecItemAdd(attributes.as_concrete_TypeRef(), &mut result) })?;
+        // SAFETY: with kSecReturnPersistentRef, a successful SecItemAdd returns a CFData we own.
+        let reference = unsafe { CFData::wrap_under_create_rule(result as _) };
+        Ok(PersistentRef(reference.bytes().to_vec()))
+    }
+
+    pub(super) fn copy(reference: &PersistentRef) -> Result<Vec<u8>, KeychainError> {
+        let query = CFDictionary::from_CFType_pairs(&[
+            by_reference(reference),
+            (
+                // SAFETY: reading an immutable extern static.
+                key(unsafe { kSecReturnData }),
+                CFBoolean::true_value().as_CFType(),
+            ),
+        ]);
+        let mut result = ptr::null();
+        // SAFETY: `query` is a valid dictionary and `result` a valid out-pointer.
+        check(unsafe { SecItemCopyMatching(query.as_concrete_TypeRef(), &mut result) })?;
+        // SAFETY: with kSecReturnData, a successful match returns a CFData we own.
+        let data = unsafe { CFData::wrap_under_create_rule(result as _) };
+        Ok(data.bytes().to_vec())
+    }
+
+    pub(super) fn delete(reference: &PersistentRef) -> Result<(), KeychainError> {
+        let query = CFDictionary::from_CFType_pairs(&[by_reference(reference)]);
+        // SAFETY: `query` is a valid dictionary.
+        check(unsafe { SecItemDelete(query.as_concrete_TypeRef()) })
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod sys {
+    use super::{KeychainError, PersistentRef};
+
+    pub(super) fn add(_: &str, _: &str, _: &[u8]) -> Result<PersistentRef, KeychainError> {
+        Err(KeychainError::Unsupported)
+    }
+
+    pub(super) fn copy(_: &PersistentRef) -> Result<Vec<u8>, KeychainError> {
+        Err(KeychainError::Unsupported)
+    }
+
+    pub(super) fn delete(_: &PersistentRef) -> Result<(), KeychainError> {
+        Err(KeychainError::Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_store_is_checked_against_access_policies() {
+        let secret = SensitiveString::from("hunter2");
+        crate::access::assert_denied(|| secret.store_in_keychain("svc", "account"));
+    }
+
+    #[test]
+    fn test_persistent_ref_round_trips_bytes() {
+        let reference = PersistentRef::from_bytes(vec![0x67, 0x65, 0x6e, 0x70, 0, 0, 0, 42]);
+        assert_eq!(PersistentRef::from_bytes(reference.as_bytes()), reference);
+        assert_eq!(format!("{:?}", reference), "PersistentRef(8 bytes)");
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_unsupported_off_macos() {
+        let secret = SensitiveString::from("hunter2");
+        assert_eq!(
+            secret.store_in_keychain("svc", "acct"),
+            Err(KeychainError::Unsupported)
+        );
+        let reference = PersistentRef::from_bytes(vec![1, 2, 3]);
+        assert_eq!(
+            SensitiveString::from_keychain(&reference).unwrap_err(),
+            KeychainError::Unsupported
+        );
+        assert_eq!(delete(&reference), Err(KeychainError::Unsupported));
+    }
+}
@@ -0,0 +1,230 @@
+//! Secrets whose kind is part of their type.
+//!
+//! Every secret being a `SensitiveString` means an API key can be passed
+//! where a signing secret was expected and the compiler will not notice.
+//! [`TypedSecret<K>`] wraps a `SensitiveString` with a marker type `K`, so
+//! `TypedSecret<ApiKeyKind>` and `TypedSecret<PasswordKind>` are distinct
+//! types, and shows the kind's name in `Debug` output. [`Password`],
+//! [`ApiKey`], and [`Token`] are provided; [`secret_kind!`](crate::secret_kind)
+//! declares more.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::kind::{ApiKey, TypedSecret};
+//! use sensitive_string::secret_kind;
+//!
+//! secret_kind!(pub WebhookSecret);
+//!
+//! fn sign(payload: &[u8], secret: &TypedSecret<WebhookSecret>) -> usize {
+//!     secret.expose(|secret| payload.len() + secret.len())
+//! }
+//!
+//! let api_key = ApiKey::from("ak-123");
+//! let webhook: TypedSecret<WebhookSecret> = TypedSecret::from("whsec-456");
+//! assert_eq!(sign(b"{}", &webhook), 11);
+//! // sign(b"{}", &api_key); // does not compile: expected `TypedSecret<WebhookSecret>`
+//! assert!(format!("{:?}", api_key).starts_with("ApiKey("));
+//! assert!(format!("{:?}", webhook).starts_with("WebhookSecret("));
+//! ```
+//!
+//! A `TypedSecret` dereferences to its `SensitiveString`, so every accessor
+//! works unchanged and the kind can be dropped explicitly with
+//! [`into_inner`](TypedSecret::into_inner).
+
+use crate::SensitiveString;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+/// A marker type naming a kind of secret.
+pub trait SecretKind {
+    /// The name shown in `Debug` output, e.g. `"ApiKey"`.
+    const NAME: &'static str;
+}
+
+/// Declares a marker type for use with [`TypedSecret`].
+///
+/// ```
+/// sensitive_string::secret_kind!(
+///     /// The HMAC secret shared with the payment provider.
+///     pub PaymentWebhookSecret
+/// );
+/// ```
+#[macro_export]
+macro_rules! secret_kind {
+    ($(#[$meta:meta])* $vis:vis $name:ident $(=> $display:literal)?) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        $vis enum $name {}
+
+        impl $crate::kind::SecretKind for $name {
+            const NAME: &'static str = $crate::secret_kind!(@name $name $($display)?);
+        }
+    };
+    (@name $name:ident) => {
+        ::std::stringify!($name)
+    };
+    (@name $name:ident $display:literal) => {
+        $display
+    };
+}
+
+secret_kind!(
+    /// Marker for [`Password`].
+    pub PasswordKind => "Password"
+);
+secret_kind!(
+    /// Marker for [`ApiKey`].
+    pub ApiKeyKind => "ApiKey"
+);
+secret_kind!(
+    /// Marker for [`Token`].
+    pub TokenKind => "Token"
+);
+
+/// A password.
+pub type Password = TypedSecret<PasswordKind>;
+
+/// An API key.
+pub type ApiKey = TypedSecret<ApiKeyKind>;
+
+/// A bearer, session, or refresh token.
+pub type Token = TypedSecret<TokenKind>;
+
+/// A [`SensitiveString`] tagged with the kind of secret it holds.
+///
+/// Equality and `Hash` compare the plaintext, as for `SensitiveString`.
+pub struct TypedSecret<K> {
+    secret: SensitiveString,
+    kind: PhantomData<fn() -> K>,
+}
+
+impl<K> TypedSecret<K> {
+    /// Tags `secret` with the kind `K`.
+    pub fn new(secret: impl Into<SensitiveString>) -> Self {
+        Self {
+            secret: secret.into(),
+            kind: PhantomData,
+        }
+    }
+
+    /// Returns the untyped secret.
+    pub fn into_inner(self) -> SensitiveString {
+        self.secret
+    }
+}
+
+impl<K> Deref for TypedSecret<K> {
+    type Target = SensitiveString;
+
+    fn deref(&self) -> &SensitiveString {
+        &self.secret
+    }
+}
+
+impl<K> From<SensitiveString> for TypedSecret<K> {
+    fn from(secret: SensitiveString) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl<K> From<String> for TypedSecret<K> {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<K> From<&str> for TypedSecret<K> {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<K> Clone for TypedSecret<K> {
+    fn clone(&self) -> Self {
+        Self::new(self.secret.clone())
+    }
+}
+
+impl<K> PartialEq for TypedSecret<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.secret == other.secret
+    }
+}
+
+impl<K> Eq for TypedSecret<K> {}
+
+impl<K> Hash for TypedSecret<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.secret.hash(state);
+    }
+}
+
+impl<K> fmt::Display for TypedSecret<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.secret, f)
+    }
+}
+
+impl<K: SecretKind> fmt::Debug for TypedSecret<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({})", K::NAME, self.secret)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<K> serde::Serialize for TypedSecret<K> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.secret.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, K> serde::Deserialize<'de> for TypedSecret<K> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        SensitiveString::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_shows_the_kind() {
+        let password = Password::from("hunter2");
+        let shown = format!("{:?}", password);
+        assert_eq!(
+            shown,
+            format!("Password({})", SensitiveString::from("hunter2"))
+        );
+        assert_eq!(password.len(), 7);
+        assert_eq!(
+            password.clone().into_inner(),
+            SensitiveString::from("hunter2")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_is_transparent() {
+        #[derive(serde::Deserialize)]
+        struct Config {
+            token: Token,
+        }
+        let config: Config = serde_json::from_str(r#"{"token": "tok-1"}"#).unwrap();
+        assert_eq!(config.token, Token::from("tok-1"));
+        assert_eq!(
+            serde_json::to_string(&config.token).unwrap(),
+            serde_json::to_string(&SensitiveString::from("tok-1")).unwrap()
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! LDAP simple bind support for the `ldap3` crate.
+//!
+//! `ldap3` takes the bind password as a `&str`, which means callers usually
+//! unwrap the secret into a local and then log the whole connection setup
+//! when something goes wrong. [`BindCredentials`] keeps the password wrapped
+//! until the bind request is built, and its `Debug` output is redacted.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use ldap3::LdapConn;
+//! use sensitive_string::ldap::BindCredentials;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let creds = BindCredentials::new("cn=svc-app,dc=example,dc=com", "service-password");
+//! let mut conn = LdapConn::new("ldap://ldap.example.com")?;
+//! creds.bind(&mut conn)?.success()?;
+//! println!("bound with {:?}", creds); // password shows as a hash
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::SensitiveString;
+use ldap3::result::Result as LdapResultOf;
+use ldap3::{Ldap, LdapConn, LdapResult};
+
+/// A bind DN and password pair for LDAP simple binds.
+///
+/// The DN is not secret and prints normally; the password prints as its hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BindCredentials {
+    /// The distinguished name to bind as.
+    pub dn: String,
+    /// The bind password.
+    pub password: SensitiveString,
+}
+
+impl BindCredentials {
+    /// Creates bind credentials from a DN and a password.
+    pub fn new(dn: impl Into<String>, password: impl Into<SensitiveString>) -> Self {
+        Self {
+            dn: dn.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Performs a simple bind on an async `ldap3` handle.
+    pub async fn bind_async(&self, ldap: &mut Ldap) -> LdapResultOf<LdapResult> {
+        simple_bind(ldap, &self.dn, &self.password).await
+    }
+
+    /// Performs a simple bind on a synchronous `ldap3` connection.
+    #[track_caller]
+    pub fn bind(&self, conn: &mut LdapConn) -> LdapResultOf<LdapResult> {
+        simple_bind_sync(conn, &self.dn, &self.password)
+    }
+}
+
+/// Performs a simple bind on an async `ldap3` handle using a wrapped password.
+pub async fn simple_bind(
+    ldap: &mut Ldap,
+    bind_dn: &str,
+    password: &SensitiveString,
+) -> LdapResultOf<LdapResult> {
+    password.record_access("simple_bind");
+    ldap.simple_bind(bind_dn, &password.value).await
+}
+
+/// Performs a simple bind on a synchronous `ldap3` connection using a wrapped password.
+#[track_caller]
+pub fn simple_bind_sync(
+    conn: &mut LdapConn,
+    bind_dn: &str,
+    password: &SensitiveString,
+) -> LdapResultOf<LdapResult> {
+    password.record_access("simple_bind_sync");
+    conn.simple_bind(bind_dn, &password.value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_credentials_debug_is_redacted() {
+        let creds = BindCredentials::new("cn=admin,dc=example,dc=com", "ldap-password");
+        let debug = format!("{:?}", creds);
+
+        assert!(debug.contains("cn=admin,dc=example,dc=com"));
+        assert!(debug.contains("SensitiveString(sha256:"));
+        assert!(!debug.contains("ldap-password"));
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_bind_is_checked_against_access_policies() {
+        // Nothing is read from the connection before the policy check panics.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ldap://{}", listener.local_addr().unwrap());
+        let mut conn = LdapConn::new(&url).unwrap();
+        let creds = BindCredentials::new("cn=admin,dc=example,dc=com", "ldap-password");
+        crate::access::assert_denied(|| creds.bind(&mut conn));
+    }
+}
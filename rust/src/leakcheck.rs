@@ -0,0 +1,364 @@
+//! Checking that code under test does not print secrets.
+//!
+//! The crate keeps plaintext out of `Display`, `Debug`, and `Serialize`, but
+//! nothing stops a `println!("{}", secret.expose(...))` three layers down.
+//! With the `leakcheck` feature, [`check`] runs a closure while capturing
+//! stdout, stderr, and tracing events, then reports every registered secret
+//! whose plaintext appeared in any of them. [`assert_no_leaks!`] turns that
+//! into an assertion downstream CI suites can run.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{assert_no_leaks, SensitiveString};
+//!
+//! let password = SensitiveString::from("hunter2").registered();
+//! let shown = assert_no_leaks!(|| {
+//!     println!("connecting with {}", password); // prints the digest
+//!     password.to_string()
+//! });
+//! assert!(SensitiveString::looks_redacted(&shown));
+//! ```
+//!
+//! Secrets come from the global [`SecretRegistry`], read when the closure
+//! returns, plus any added with [`LeakCheck::secret`].
+//!
+//! # What is captured
+//!
+//! - stdout and stderr at the file-descriptor level, so writes from any
+//!   thread, C libraries, and child processes are seen. The redirection is
+//!   process-wide: output from other threads while a check runs is checked
+//!   (and hidden) too, and checks run one at a time.
+//! - tracing events on the calling thread, formatted with every field, at
+//!   every level.
+//!
+//! The test harness captures `print!` and `eprint!` from test threads before
+//! they reach file descriptors, so run with `--nocapture` for those to be
+//! checked; direct writes to `io::stdout()`, loggers, and tracing are
+//! checked either way. Unix only.
+
+use crate::registry::SecretRegistry;
+use crate::scrub::Scrubber;
+use crate::SensitiveString;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::io::AsRawFd;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Held while a check runs, since file descriptors are process-wide.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+static CAPTURES: AtomicU64 = AtomicU64::new(0);
+
+/// Where a leaked plaintext was seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+    Tracing,
+}
+
+impl fmt::Display for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stream::Stdout => "stdout",
+            Stream::Stderr => "stderr",
+            Stream::Tracing => "tracing",
+        })
+    }
+}
+
+/// A secret whose plaintext appeared in captured output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leak {
+    /// Where it appeared.
+    pub stream: Stream,
+    /// The secret's digest, never its plaintext.
+    pub fingerprint: String,
+    /// The secret's label, if it has one.
+    pub label: Option<String>,
+}
+
+impl fmt::Display for Leak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.fingerprint)?;
+        if let Some(label) = &self.label {
+            write!(f, " ({})", label)?;
+        }
+        write!(f, " leaked to {}", self.stream)
+    }
+}
+
+/// The result of a leak check.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LeakReport {
+    leaks: Vec<Leak>,
+}
+
+impl LeakReport {
+    /// Returns true if no secret appeared in any captured output.
+    pub fn is_clean(&self) -> bool {
+        self.leaks.is_empty()
+    }
+
+    /// Returns every leak found, in stream order.
+    pub fn leaks(&self) -> &[Leak] {
+        &self.leaks
+    }
+}
+
+impl fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return write!(f, "no secrets leaked");
+        }
+        write!(f, "{} secret leak(s):", self.leaks.len())?;
+        for leak in &self.leaks {
+            write!(f, "\n  {}", leak)?;
+        }
+        Ok(())
+    }
+}
+
+/// A leak check with secrets beyond those in the global registry.
+#[derive(Debug, Clone, Default)]
+pub struct LeakCheck {
+    extra: Scrubber,
+}
+
+impl LeakCheck {
+    /// Creates a check for the globally registered secrets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also checks for `secret`, without registering it globally.
+    pub fn secret(mut self, secret: &SensitiveString) -> Self {
+        self.extra.add(secret);
+        self
+    }
+
+    /// Runs `f` with output captured and returns its result with the report.
+    ///
+    /// If `f` panics, output is restored and the panic continues.
+    ///
+    /// # Panics
+    ///
+    /// Panics if stdout or stderr cannot be redirected.
+    pub fn run<R>(&self, f: impl FnOnce() -> R) -> (R, LeakReport) {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let stdout = Redirect::new(libc::STDOUT_FILENO).expect("redirecting stdout");
+        let stderr = Redirect::new(libc::STDERR_FILENO).expect("redirecting stderr");
+        let events = Buffer::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(events.clone())
+            .with_max_level(tracing_subscriber::filter::LevelFilter::TRACE)
+            .without_time()
+            .finish();
+        let dispatch = tracing_core::Dispatch::new(subscriber);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            tracing_core::dispatcher::with_default(&dispatch, f)
+        }));
+        let stderr = stderr.finish();
+        let stdout = stdout.finish();
+        let result = match result {
+            Ok(result) => result,
+            Err(payload) => panic::resume_unwind(payload),
+        };
+
+        let mut scrubber = SecretRegistry::global().scrubber();
+        for secret in self.extra.secrets() {
+            scrubber.add(secret);
+        }
+        let mut report = LeakReport::default();
+        let events = events.0.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        for (stream, bytes) in [
+            (Stream::Stdout, stdout.expect("reading captured stdout")),
+            (Stream::Stderr, stderr.expect("reading captured stderr")),
+            (Stream::Tracing, events),
+        ] {
+            let text = String::from_utf8_lossy(&bytes);
+            for secret in scrubber.secrets() {
+                if text.contains(&secret.value) {
+                    report.leaks.push(Leak {
+                        stream,
+                        fingerprint: secret.hash_string(),
+                        label: secret.label().map(str::to_string),
+                    });
+                }
+            }
+        }
+        (result, report)
+    }
+}
+
+/// Runs `f` with output captured and reports registered secrets that appeared in it.
+///
+/// Shorthand for `LeakCheck::new().run(f)`.
+pub fn check<R>(f: impl FnOnce() -> R) -> (R, LeakReport) {
+    LeakCheck::new().run(f)
+}
+
+/// Runs a closure with output captured and panics if any registered secret
+/// appeared in it; otherwise returns the closure's result.
+///
+/// The panic message lists leaked secrets by digest and label only.
+/// Requires the `leakcheck` feature.
+///
+/// ```
+/// use sensitive_string::assert_no_leaks;
+///
+/// let total = assert_no_leaks!(|| 2 + 2);
+/// assert_eq!(total, 4);
+/// ```
+#[macro_export]
+macro_rules! assert_no_leaks {
+    ($f:expr $(,)?) => {{
+        let (result, report) = $crate::leakcheck::check($f);
+        if !report.is_clean() {
+            panic!("{}", report);
+        }
+        result
+    }};
+}
+
+/// A file descriptor pointed at an unlinked temporary file until [`finish`](Self::finish).
+struct Redirect {
+    fd: libc::c_int,
+    saved: libc::c_int,
+    file: File,
+}
+
+impl Redirect {
+    fn new(fd: libc::c_int) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "sensitive-string-leakcheck-{}-{}",
+            std::process::id(),
+            CAPTURES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        std::fs::remove_file(&path)?;
+
+        flush_std();
+        // SAFETY: dup and dup2 only operate on descriptors; failures are checked.
+        let saved = unsafe { libc::dup(fd) };
+        if saved < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: as above.
+        if unsafe { libc::dup2(file.as_raw_fd(), fd) } < 0 {
+            let error = io::Error::last_os_error();
+            // SAFETY: `saved` was just returned by dup.
+            unsafe { libc::close(saved) };
+            return Err(error);
+        }
+        Ok(Self { fd, saved, file })
+    }
+
+    fn restore(&mut self) {
+        if self.saved < 0 {
+            return;
+        }
+        flush_std();
+        // SAFETY: `saved` is the descriptor duplicated in `new`, owned by `self`.
+        unsafe {
+            libc::dup2(self.saved, self.fd);
+            libc::close(self.saved);
+        }
+        self.saved = -1;
+    }
+
+    /// Restores the descriptor and returns everything written to it.
+    fn finish(mut self) -> io::Result<Vec<u8>> {
+        self.restore();
+        let mut captured = Vec::new();
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_to_end(&mut captured)?;
+        Ok(captured)
+    }
+}
+
+impl Drop for Redirect {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+fn flush_std() {
+    let _ = io::stdout().flush();
+    let _ = io::stderr().flush();
+}
+
+/// Collects formatted tracing events.
+#[derive(Debug, Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Buffer {
+    type Writer = Buffer;
+
+    fn make_writer(&'a self) -> Buffer {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finds_plaintext_in_stdout_and_tracing() {
+        let password = SensitiveString::from("leakcheck-pw-1").with_label("db");
+        let (value, report) = LeakCheck::new().secret(&password).run(|| {
+            password.expose(|plaintext| {
+                writeln!(io::stdout(), "password={}", plaintext).unwrap();
+                tracing::info!(password = plaintext, "connecting");
+            });
+            7
+        });
+        assert_eq!(value, 7);
+        assert_eq!(
+            report.leaks(),
+            [Stream::Stdout, Stream::Tracing].map(|stream| Leak {
+                stream,
+                fingerprint: password.hash_string(),
+                label: Some("db".to_string()),
+            })
+        );
+        assert!(!report.to_string().contains("leakcheck-pw-1"));
+    }
+
+    #[test]
+    fn test_redacted_output_is_clean() {
+        let password = SensitiveString::from("leakcheck-pw-2").registered();
+        let shown = assert_no_leaks!(|| {
+            writeln!(io::stderr(), "password={}", password).unwrap();
+            tracing::warn!(%password, "rotating");
+            password.to_string()
+        });
+        assert_eq!(shown, password.to_string());
+        SecretRegistry::global().unregister(&password);
+    }
+}
@@ -0,0 +1,158 @@
+//! A temporary shim for call sites that still need plaintext from `Display`.
+//!
+//! Converting a large codebase usually turns up a few places, such as a
+//! template that renders a connection string, where code relied on a
+//! `String` secret displaying as its plaintext. Rather than block adoption
+//! on rewriting them all, the `legacy-display` feature provides
+//! [`legacy_plaintext_display!`](crate::legacy_plaintext_display), which
+//! wraps a secret in a value whose `Display` shows the plaintext. Each use:
+//!
+//! - emits a deprecation warning at compile time, so the remaining call
+//!   sites are listed by every build;
+//! - shows the plaintext only in binaries that call [`enable`], and the
+//!   redacted form otherwise;
+//! - counts as a plaintext read for auditing and access policies, and is
+//!   counted per call site in [`call_sites`].
+//!
+//! # Example
+//!
+//! ```
+//! # #![allow(deprecated)]
+//! use sensitive_string::{legacy, legacy_plaintext_display, SensitiveString};
+//!
+//! let password = SensitiveString::from("hunter2");
+//! assert_eq!(format!("{}", legacy_plaintext_display!(password)), password.to_string());
+//!
+//! legacy::enable(); // once, in main()
+//! let dsn = format!("postgres://app:{}@db/orders", legacy_plaintext_display!(password));
+//! assert_eq!(dsn, "postgres://app:hunter2@db/orders");
+//! assert!(legacy::call_sites().iter().any(|site| site.uses == 1));
+//! ```
+//!
+//! Once the warnings are gone, turning the feature off confirms that no
+//! call site is left.
+
+use crate::SensitiveString;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static CALL_SITES: Mutex<BTreeMap<&'static Location<'static>, u64>> = Mutex::new(BTreeMap::new());
+
+/// Lets [`legacy_plaintext_display!`](crate::legacy_plaintext_display) show
+/// plaintext in this process.
+///
+/// Call it from `main()` of each binary still being migrated, so libraries
+/// containing the shim never show plaintext in binaries that did not opt in.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns true if [`enable`] has been called.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A source location that used the shim, and how many times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallSite {
+    /// Where `legacy_plaintext_display!` was invoked.
+    pub location: &'static Location<'static>,
+    /// How many times it was invoked there.
+    pub uses: u64,
+}
+
+/// Returns every call site that has used the shim, ordered by location.
+pub fn call_sites() -> Vec<CallSite> {
+    CALL_SITES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .map(|(&location, &uses)| CallSite { location, uses })
+        .collect()
+}
+
+/// A secret whose `Display` shows the plaintext if the shim is enabled.
+pub struct LegacyPlaintext<'a> {
+    secret: &'a SensitiveString,
+    plaintext: bool,
+}
+
+impl fmt::Display for LegacyPlaintext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.plaintext {
+            f.write_str(&self.secret.value)
+        } else {
+            fmt::Display::fmt(self.secret, f)
+        }
+    }
+}
+
+impl fmt::Debug for LegacyPlaintext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.secret, f)
+    }
+}
+
+#[doc(hidden)]
+#[deprecated(
+    note = "`legacy_plaintext_display!` is a migration shim; display the redacted form or read the plaintext explicitly"
+)]
+#[track_caller]
+pub fn plaintext_display(secret: &SensitiveString) -> LegacyPlaintext<'_> {
+    *CALL_SITES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .entry(Location::caller())
+        .or_default() += 1;
+    let plaintext = is_enabled();
+    if plaintext {
+        secret.record_access("legacy_plaintext_display");
+    }
+    LegacyPlaintext { secret, plaintext }
+}
+
+/// Wraps a `SensitiveString` so that `Display` shows its plaintext, during a migration.
+///
+/// Every use is a deprecation warning; see the [module docs](crate::legacy).
+#[macro_export]
+macro_rules! legacy_plaintext_display {
+    ($secret:expr $(,)?) => {
+        $crate::__private::legacy_plaintext_display(&$secret)
+    };
+}
+
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plaintext_only_once_enabled() {
+        let secret = SensitiveString::from("hunter2");
+        let before = legacy_plaintext_display!(secret);
+        enable();
+        let after = legacy_plaintext_display!(secret);
+        assert_eq!(before.to_string(), secret.to_string());
+        assert_eq!(after.to_string(), "hunter2");
+        assert!(!format!("{:?}", after).contains("hunter2"));
+    }
+
+    #[test]
+    fn test_counts_uses_per_call_site() {
+        let secret = SensitiveString::from("hunter2");
+        let line = line!() + 2;
+        for _ in 0..3 {
+            let _ = legacy_plaintext_display!(secret);
+        }
+        let site = call_sites()
+            .into_iter()
+            .find(|site| site.location.file() == file!() && site.location.line() == line)
+            .unwrap();
+        assert_eq!(site.uses, 3);
+    }
+}
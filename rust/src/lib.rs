@@ -16,6 +16,8 @@
 //! println!("{:?}", password); // SensitiveString(sha256:...)
 //!
 //! // Intentional access when you actually need the plaintext
+//! let length = password.expose(|plaintext| plaintext.len());
+//! # #[cfg(not(feature = "no-plaintext-getters"))]
 //! let actual_password = password.get_value();
 //! ```
 //!
@@ -45,9 +47,207 @@
 //! // {"username":"user@example.com","password":"sha256:..."}
 //! # }
 //! ```
+//!
+//! # Without hashing
+//!
+//! The `hash` feature (enabled by default) pulls in `sha2`, `hex`, and `hmac`. On
+//! constrained targets, build with `default-features = false` to drop them:
+//! `Display`, `Debug`, and `Serialize` then print `[REDACTED len=N]` instead
+//! of a digest. Features that need real digests (`clipboard`, `snmp`,
+//! `support-bundle`) enable `hash` themselves.
 
+#[cfg(feature = "hash")]
 use sha2::{Digest, Sha256};
 use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+#[cfg(feature = "access-policy")]
+pub mod access;
+#[cfg(feature = "tracing-appender")]
+pub mod appender;
+mod assert;
+#[cfg(feature = "audit")]
+pub mod audit;
+mod borrowed;
+mod bytes;
+pub mod cache;
+#[cfg(feature = "checkpoint")]
+pub mod checkpoint;
+#[cfg(feature = "clap")]
+pub mod clap;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+pub mod command;
+mod compose;
+mod composite;
+#[cfg(feature = "serde")]
+pub mod config;
+#[cfg(all(feature = "serde", feature = "hash"))]
+pub mod conformance;
+#[cfg(feature = "detector")]
+pub mod detector;
+pub mod diff;
+mod embedded;
+#[cfg(feature = "memory-encryption")]
+mod encrypted;
+mod env;
+#[cfg(feature = "envelope")]
+pub mod envelope;
+#[cfg(feature = "windows-eventlog")]
+pub mod eventlog;
+mod expiring;
+mod exposed;
+mod exposure;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod file;
+#[cfg(feature = "rand")]
+pub mod generate;
+#[cfg(all(unix, feature = "handoff"))]
+pub mod handoff;
+#[cfg(feature = "hash")]
+mod hashed;
+pub mod hasher;
+pub mod health;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "terraform")]
+pub mod iac;
+pub mod inventory;
+#[cfg(feature = "hash")]
+mod kdf;
+#[cfg(feature = "keychain")]
+pub mod keychain;
+pub mod kind;
+#[cfg(feature = "ldap3")]
+pub mod ldap;
+#[cfg(all(unix, feature = "leakcheck"))]
+pub mod leakcheck;
+#[cfg(feature = "legacy-display")]
+pub mod legacy;
+#[cfg(feature = "mlock")]
+mod locked;
+mod map;
+pub mod memory;
+#[cfg(all(target_os = "linux", feature = "memscan"))]
+pub mod memscan;
+#[cfg(feature = "migrate")]
+pub mod migrate;
+#[cfg(feature = "digest-ord")]
+mod ord;
+mod os;
+#[cfg(all(unix, feature = "pam"))]
+pub mod pam;
+pub mod parse;
+pub mod pii;
+#[cfg(feature = "pkcs11")]
+pub mod pkcs11;
+pub mod policy;
+#[cfg(feature = "qr")]
+pub mod qr;
+#[cfg(feature = "radius")]
+pub mod radius;
+#[cfg(feature = "hash")]
+mod rate;
+mod redact;
+pub mod registry;
+pub mod reveal;
+pub mod scrub;
+#[cfg(feature = "secrecy")]
+mod secrecy;
+mod sensitive;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "hash")]
+mod shard;
+mod shared;
+#[cfg(feature = "crypto")]
+mod sign;
+mod snapshot;
+#[cfg(feature = "snmp")]
+pub mod snmp;
+pub mod source;
+mod sql;
+#[cfg(feature = "sqlx")]
+mod sqlx;
+#[cfg(feature = "sspi")]
+pub mod sspi;
+#[cfg(feature = "support-bundle")]
+pub mod support_bundle;
+pub mod syslog;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+mod timestamped;
+#[cfg(feature = "tpm")]
+pub mod tpm;
+#[cfg(feature = "tracing")]
+pub mod trace;
+#[cfg(feature = "url")]
+mod url;
+pub mod validate;
+#[cfg(feature = "constant-time")]
+mod verify;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "web")]
+pub mod web;
+
+pub use assert::SecretOperand;
+pub use borrowed::SensitiveStr;
+pub use bytes::SensitiveBytes;
+pub use composite::CompositeSecret;
+pub use embedded::{EmbedArg, InvalidRegion, SensitiveEmbedded, TemplateError};
+#[cfg(feature = "memory-encryption")]
+pub use encrypted::EncryptedSensitiveString;
+pub use env::FromEnvError;
+pub use expiring::{ExpiringSensitiveString, RotatingSecret};
+pub use exposed::ExposedGuard;
+#[cfg(feature = "tokio")]
+pub use exposure::ExposureContext;
+pub use exposure::{current_exposure_reason, is_exposure_active, ExposureGuard, ExposureRequired};
+pub use file::{FromFileError, PermissionCheck, SecretFile};
+#[cfg(feature = "hash")]
+pub use hashed::{HashedSecret, ParseHashedSecretError};
+#[cfg(feature = "argon2")]
+pub use kdf::{Argon2Params, KdfError};
+#[cfg(feature = "mlock")]
+pub use locked::LockedSensitiveString;
+#[cfg(feature = "indexmap")]
+pub use map::OrderedSensitiveMap;
+pub use map::{SensitiveMap, SensitiveVec, ShardedSensitiveMap};
+pub use os::{SensitiveOsString, SensitivePathBuf};
+pub use redact::RedactField;
+pub use sensitive::Sensitive;
+#[cfg(feature = "derive")]
+pub use sensitive_string_derive::Redact;
+pub use shared::SharedSensitiveString;
+pub use snapshot::SendableSecret;
+pub use timestamped::Timestamped;
+#[cfg(feature = "url")]
+pub use url::SensitiveUrl;
+#[cfg(feature = "wasm")]
+pub use wasm::JsSensitiveString;
+
+// Lets the code generated by `#[derive(Redact)]` name this crate in its own tests.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as sensitive_string;
+
+/// Support items for this crate's macros and `#[derive(Redact)]`; not public API.
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "access-policy")]
+    pub use crate::access::{Allowlist, ALLOWLISTS};
+    pub use crate::assert::assert_eq as assert_secret_eq;
+    pub use crate::assert::assert_matches as assert_secret_matches;
+    pub use crate::assert::assert_redacted_eq;
+    pub use crate::embedded::validate_template;
+    #[cfg(feature = "legacy-display")]
+    #[allow(deprecated)]
+    pub use crate::legacy::plaintext_display as legacy_plaintext_display;
+    pub use crate::redact::{Placeholder, Redacted};
+    #[cfg(feature = "access-policy")]
+    pub use linkme;
+}
 
 /// A wrapper for sensitive string values that prevents accidental exposure.
 ///
@@ -57,9 +257,34 @@ use std::fmt;
 ///
 /// The primary goal is to prevent **accidental** exposure. Intentional access
 /// to the plaintext is available via `get_value()` or `value()` methods.
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone)]
 pub struct SensitiveString {
     value: String,
+    hasher: Option<Arc<dyn hasher::Hasher>>,
+    policy: Option<policy::RedactionPolicy>,
+    label: Option<Arc<str>>,
+    #[cfg(feature = "audit")]
+    observer: Option<Arc<dyn audit::AccessObserver>>,
+    validator: Option<Arc<validate::Validator>>,
+    /// The redacted form, computed on first use, and whether a global hasher was installed then.
+    digest: OnceLock<(bool, String)>,
+}
+
+/// Equality compares the plaintext only; attached hashers, policies, and labels do not matter.
+impl PartialEq for SensitiveString {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl Eq for SensitiveString {}
+
+/// Hashes the plaintext. With the `digest-ord` feature, hashes its digest instead.
+#[cfg(not(feature = "digest-ord"))]
+impl std::hash::Hash for SensitiveString {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
 }
 
 impl SensitiveString {
@@ -73,22 +298,44 @@ impl SensitiveString {
     /// let secret = SensitiveString::new("my-secret".to_string());
     /// ```
     pub fn new(value: String) -> Self {
-        Self { value }
+        let secret = Self::unregistered(value);
+        registry::auto_register(&secret);
+        secret
+    }
+
+    /// Wraps `value` without auto-registering it, for values derived from
+    /// another secret and temporaries that are only hashed.
+    pub(crate) fn unregistered(value: String) -> Self {
+        Self {
+            value,
+            hasher: None,
+            policy: None,
+            label: None,
+            #[cfg(feature = "audit")]
+            observer: None,
+            validator: None,
+            digest: OnceLock::new(),
+        }
     }
 
     /// Creates a new `SensitiveString` from a string slice.
     ///
+    /// Deprecated because it shadows [`FromStr::from_str`](std::str::FromStr::from_str);
+    /// use `SensitiveString::from` or `str::parse` instead.
+    ///
     /// # Example
     ///
     /// ```
     /// use sensitive_string::SensitiveString;
     ///
-    /// let secret = SensitiveString::from_str("my-secret");
+    /// let secret = SensitiveString::from("my-secret");
+    /// let parsed: SensitiveString = "my-secret".parse().unwrap();
+    /// assert_eq!(secret, parsed);
     /// ```
+    #[deprecated(note = "use `SensitiveString::from` or `str::parse` instead")]
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(value: &str) -> Self {
-        Self {
-            value: value.to_string(),
-        }
+        Self::new(value.to_string())
     }
 
     /// Explicitly retrieves the plaintext value.
@@ -107,17 +354,100 @@ impl SensitiveString {
     /// let plaintext = secret.get_value();
     /// assert_eq!(plaintext, "password123");
     /// ```
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
     pub fn get_value(&self) -> &str {
+        self.record_access("get_value");
         &self.value
     }
 
     /// Explicitly retrieves the plaintext value (alias for `get_value`).
     ///
     /// This provides a more natural API for some use cases.
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
     pub fn value(&self) -> &str {
+        self.record_access("value");
         &self.value
     }
 
+    /// Gives up the wrapper and returns the plaintext, e.g. to hand ownership
+    /// to an FFI layer.
+    ///
+    /// The returned `String` is the wrapper's own buffer, moved rather than
+    /// copied, so no other copy is left behind. `SensitiveString` does not
+    /// zero its buffer on drop; once moved out, wiping it is the caller's job.
+    ///
+    /// ```
+    /// use sensitive_string::SensitiveString;
+    ///
+    /// let secret = SensitiveString::from("hunter2");
+    /// let plaintext: String = secret.into_plaintext_exposed();
+    /// assert_eq!(plaintext, "hunter2");
+    /// ```
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
+    pub fn into_plaintext_exposed(self) -> String {
+        self.record_access("into_plaintext_exposed");
+        self.value
+    }
+
+    /// Moves the plaintext out, leaving an empty secret behind.
+    ///
+    /// Like [`into_plaintext_exposed`](Self::into_plaintext_exposed), the
+    /// buffer is moved, not copied, so the wrapper holds no trace of the old
+    /// value afterwards; its label, policy, and other settings are kept.
+    ///
+    /// ```
+    /// use sensitive_string::SensitiveString;
+    ///
+    /// let mut secret = SensitiveString::from("hunter2");
+    /// let plaintext = secret.take();
+    /// assert_eq!(plaintext, "hunter2");
+    /// assert!(secret.is_empty());
+    /// ```
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
+    pub fn take(&mut self) -> String {
+        self.record_access("take");
+        self.digest = OnceLock::new();
+        std::mem::take(&mut self.value)
+    }
+
+    /// Names this secret in audit events and diagnostics, e.g. `"db-password"`.
+    ///
+    /// ```
+    /// use sensitive_string::SensitiveString;
+    ///
+    /// let secret = SensitiveString::from("hunter2").with_label("db-password");
+    /// assert_eq!(secret.label(), Some("db-password"));
+    /// ```
+    pub fn with_label(mut self, label: impl Into<Arc<str>>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Returns the label set with [`with_label`](Self::with_label).
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Reports a plaintext read to the audit observer, if the `audit` feature is on.
+    ///
+    /// With the `access-policy` feature, first panics if a policy denies the read.
+    #[track_caller]
+    #[inline]
+    pub(crate) fn record_access(&self, method: &'static str) {
+        #[cfg(feature = "access-policy")]
+        if let Err(denied) = access::check(self, method) {
+            panic!("{}", denied);
+        }
+        #[cfg(feature = "audit")]
+        audit::record(self, method);
+        #[cfg(not(feature = "audit"))]
+        let _ = method;
+    }
+
     /// Returns the length of the underlying value without exposing it.
     ///
     /// # Example
@@ -137,12 +467,106 @@ impl SensitiveString {
         self.value.is_empty()
     }
 
-    /// Computes the SHA256 hash of the value as a hex string.
+    /// Returns the redacted form: the attached hasher, else [`redact_bytes`].
+    ///
+    /// Computed once and cached, since the same secret is often formatted on
+    /// every request. A digest cached before a global hasher was installed is
+    /// not reused afterwards.
     fn hash_string(&self) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(self.value.as_bytes());
-        let result = hasher.finalize();
-        format!("sha256:{}", hex::encode(result))
+        self.with_hash_str(str::to_owned)
+    }
+
+    /// Calls `f` with the digest, borrowing the cached one instead of copying it.
+    fn with_hash_str<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        #[cfg(feature = "test-util")]
+        if let Some(digest) = test_util::deterministic_digest(self) {
+            return f(&digest);
+        }
+        let global = self.hasher.is_none() && hasher::global_hasher().is_some();
+        let (cached_global, digest) = self
+            .digest
+            .get_or_init(|| (global, self.compute_hash_string()));
+        if *cached_global == global {
+            f(digest)
+        } else {
+            f(&self.compute_hash_string())
+        }
+    }
+
+    fn compute_hash_string(&self) -> String {
+        match &self.hasher {
+            Some(hasher) => hasher.redact(self.value.as_bytes()),
+            None => redact_bytes(self.value.as_bytes()),
+        }
+    }
+
+    /// Calls `f` with what `Display`, `Debug`, and `Serialize` show, following
+    /// the redaction policy. Only `MaskLast` allocates; the digest and the
+    /// placeholder are borrowed, so formatting in hot paths does not allocate
+    /// once the digest is cached.
+    fn with_redacted<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        match self.policy.unwrap_or_else(policy::default_policy) {
+            policy::RedactionPolicy::Hash => self.with_hash_str(f),
+            policy::RedactionPolicy::Placeholder => f(policy::PLACEHOLDER),
+            mask => f(&mask.apply(&self.value, || self.hash_string())),
+        }
+    }
+
+    /// Returns true if `value` looks like the redacted form this crate prints.
+    ///
+    /// Matches `sha256:`, `hmac-sha256:`, or `blake3:` followed by 64 lowercase
+    /// hex digits, `sha512:` followed by 128, the output of any
+    /// [truncated](hasher::Digest::truncate) digest configured in this process, the
+    /// `[REDACTED len=N]` form used without the `hash` feature, the
+    /// `[REDACTED]` placeholder, and the `****` of
+    /// [`RedactionPolicy::MaskLast`](policy::RedactionPolicy::MaskLast) followed by
+    /// no more characters than a mask in use shows, plus whatever the global
+    /// [`Hasher`](hasher::Hasher) recognizes. With the `test-util` feature,
+    /// deterministic `sha256:test:<label>` digests match too. The alternate
+    /// form, with the [marker](policy::marker) in front, matches as well.
+    ///
+    /// ```
+    /// use sensitive_string::SensitiveString;
+    ///
+    /// let shown = SensitiveString::from("hunter2").to_string();
+    /// assert!(SensitiveString::looks_redacted(&shown));
+    /// assert!(!SensitiveString::looks_redacted("hunter2"));
+    /// ```
+    pub fn looks_redacted(value: &str) -> bool {
+        if let Some(token) = value
+            .strip_prefix(policy::marker())
+            .and_then(|rest| rest.strip_prefix(' '))
+        {
+            return Self::looks_redacted(token);
+        }
+        if hasher::global_hasher().is_some_and(|hasher| hasher.looks_redacted(value)) {
+            return true;
+        }
+        if is_prefixed_hex_digest(value, "sha256:", 64)
+            || is_prefixed_hex_digest(value, "hmac-sha256:", 64)
+            || is_prefixed_hex_digest(value, "blake3:", 64)
+            || is_prefixed_hex_digest(value, "sha512:", 128)
+        {
+            return true;
+        }
+        #[cfg(feature = "hash")]
+        if hasher::is_truncated_digest(value) {
+            return true;
+        }
+        if value == policy::PLACEHOLDER
+            || value == policy::marker()
+            || policy::is_mask(value)
+        {
+            return true;
+        }
+        #[cfg(feature = "test-util")]
+        if value.starts_with(test_util::DETERMINISTIC_PREFIX) {
+            return true;
+        }
+        value
+            .strip_prefix("[REDACTED len=")
+            .and_then(|rest| rest.strip_suffix(']'))
+            .is_some_and(|len| !len.is_empty() && len.bytes().all(|b| b.is_ascii_digit()))
     }
 
     /// Checks if an object is a `SensitiveString`.
@@ -164,8 +588,11 @@ impl SensitiveString {
     }
 
     /// Extracts the plaintext value from a `SensitiveString`.
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
     pub fn extract_value(value: &SensitiveString) -> &str {
-        value.get_value()
+        value.record_access("extract_value");
+        &value.value
     }
 
     /// Converts a value into a `SensitiveString`.
@@ -176,12 +603,86 @@ impl SensitiveString {
     }
 }
 
+/// Returns true if `value` is `prefix` followed by exactly `len` lowercase hex digits.
+pub(crate) fn is_prefixed_hex_digest(value: &str, prefix: &str, len: usize) -> bool {
+    value.strip_prefix(prefix).is_some_and(|hex| {
+        hex.len() == len && hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    })
+}
+
+/// Computes the redacted form of `bytes` with the global hasher, else the built-in digest.
+pub(crate) fn redact_bytes(bytes: &[u8]) -> String {
+    match hasher::global_hasher() {
+        Some(hasher) => hasher.redact(bytes),
+        None => builtin_redact(bytes),
+    }
+}
+
+/// Redacts an unwrapped plaintext the way a `SensitiveString` with default settings shows it.
+pub(crate) fn redact_plaintext(plaintext: &str) -> String {
+    policy::default_policy().apply(plaintext, || redact_bytes(plaintext.as_bytes()))
+}
+
+/// Overwrites `bytes` with zeros in a way the compiler cannot skip.
+pub(crate) fn wipe(bytes: &mut [u8]) {
+    for byte in bytes {
+        // SAFETY: `byte` is a valid, aligned reference into the buffer.
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+/// Records a plaintext read of a secret held in another type, such as a
+/// `SensitiveBytes` or a borrowed `SensitiveStr`, the way
+/// [`SensitiveString::record_access`] does.
+#[track_caller]
+#[inline]
+pub(crate) fn record_plaintext_read(label: Option<&str>, method: &'static str) {
+    #[cfg(feature = "access-policy")]
+    if let Err(denied) = access::check_label(label, method) {
+        panic!("{}", denied);
+    }
+    #[cfg(feature = "audit")]
+    audit::record_label(None, label, method);
+    #[cfg(not(any(feature = "access-policy", feature = "audit")))]
+    let _ = (label, method);
+}
+
+/// Computes the SHA256 hash of `bytes` as a hex string.
+#[cfg(feature = "hash")]
+fn builtin_redact(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    let result = hasher.finalize();
+    // Hex-encode on the stack so the only allocation is the returned string.
+    let mut hex = [0u8; 64];
+    hex::encode_to_slice(result, &mut hex).expect("a SHA-256 digest is 32 bytes");
+    let mut redacted = String::with_capacity("sha256:".len() + hex.len());
+    redacted.push_str("sha256:");
+    redacted.push_str(std::str::from_utf8(&hex).expect("hex digits are ASCII"));
+    redacted
+}
+
+/// Without the `hash` feature, the redacted form only reveals the length.
+#[cfg(not(feature = "hash"))]
+fn builtin_redact(bytes: &[u8]) -> String {
+    format!("[REDACTED len={}]", bytes.len())
+}
+
 /// Implements `Display` for use with `println!`, `format!`, logging, etc.
 ///
-/// Returns the SHA256 hash instead of the plaintext to prevent accidental exposure.
+/// Returns the SHA256 hash (or whatever the redaction policy chooses) instead
+/// of the plaintext to prevent accidental exposure. The alternate form (`{:#}`)
+/// puts the human-facing [marker](policy::set_marker) in front.
 impl fmt::Display for SensitiveString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.hash_string())
+        self.with_redacted(|redacted| {
+            if f.alternate() {
+                policy::write_with_marker(f, redacted)
+            } else {
+                f.write_str(redacted)
+            }
+        })
     }
 }
 
@@ -190,7 +691,7 @@ impl fmt::Display for SensitiveString {
 /// Returns a debug representation showing the hash, not the plaintext.
 impl fmt::Debug for SensitiveString {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "SensitiveString({})", self.hash_string())
+        self.with_redacted(|redacted| write!(f, "SensitiveString({})", redacted))
     }
 }
 
@@ -204,14 +705,29 @@ impl From<String> for SensitiveString {
 /// Implements `From<&str>` for convenient conversion.
 impl From<&str> for SensitiveString {
     fn from(value: &str) -> Self {
-        Self::from_str(value)
+        Self::new(value.to_string())
+    }
+}
+
+/// Implements `FromStr` so `str::parse` and generic parsers (such as clap's
+/// default value parser) can produce a `SensitiveString`. Never fails.
+///
+/// `TryFrom<&str>` comes from the `From<&str>` impl, also with
+/// [`Infallible`](std::convert::Infallible) as its error.
+impl std::str::FromStr for SensitiveString {
+    type Err = std::convert::Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(value))
     }
 }
 
 #[cfg(feature = "serde")]
 mod serde_impl {
     use super::SensitiveString;
+    use serde::de::{self, Deserialize, Deserializer, Visitor};
     use serde::{Serialize, Serializer};
+    use std::fmt;
 
     /// Implements `Serialize` to work with all serde-based formats.
     ///
@@ -222,15 +738,105 @@ mod serde_impl {
         where
             S: Serializer,
         {
-            serializer.serialize_str(&self.hash_string())
+            // Support bundles find secrets by digest, whatever the policy.
+            #[cfg(feature = "support-bundle")]
+            if crate::support_bundle::note_serialized(self) {
+                return self.with_hash_str(|digest| serializer.serialize_str(digest));
+            }
+            self.with_redacted(|redacted| serializer.serialize_str(redacted))
+        }
+    }
+
+    /// Implements `Deserialize` for loading plaintext secrets from config files.
+    ///
+    /// Values that look like this crate's own redacted output (see
+    /// [`SensitiveString::looks_redacted`]) are rejected, so feeding serialized
+    /// output back in fails loudly instead of storing a hash as the secret.
+    /// Use [`MaybeRedacted`] to accept both. Error messages never include the value.
+    impl<'de> Deserialize<'de> for SensitiveString {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            match MaybeRedacted::deserialize(deserializer)? {
+                MaybeRedacted::Secret(secret) => Ok(secret),
+                MaybeRedacted::Redacted(_) => Err(de::Error::custom(
+                    "expected a plaintext secret, found a redacted digest or mask",
+                )),
+            }
+        }
+    }
+
+    /// A deserialized field that is either a plaintext secret or redacted output.
+    ///
+    /// ```
+    /// use sensitive_string::MaybeRedacted;
+    ///
+    /// let field: MaybeRedacted = serde_json::from_str("\"hunter2\"").unwrap();
+    /// assert!(matches!(field, MaybeRedacted::Secret(_)));
+    /// ```
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum MaybeRedacted {
+        /// A plaintext secret.
+        Secret(SensitiveString),
+        /// A value in the crate's redacted form, e.g. `sha256:<hex>`.
+        Redacted(String),
+    }
+
+    impl MaybeRedacted {
+        /// Returns the secret, or `None` if the value was redacted.
+        pub fn into_secret(self) -> Option<SensitiveString> {
+            match self {
+                MaybeRedacted::Secret(secret) => Some(secret),
+                MaybeRedacted::Redacted(_) => None,
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for MaybeRedacted {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            // Some formats report a type mismatch with the offending value in the
+            // message, so any failure here is replaced with a value-free error.
+            deserializer
+                .deserialize_string(SecretVisitor)
+                .map_err(|_| de::Error::custom("expected a string secret"))
+        }
+    }
+
+    struct SecretVisitor;
+
+    impl<'de> Visitor<'de> for SecretVisitor {
+        type Value = MaybeRedacted;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a string secret")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<MaybeRedacted, E> {
+            self.visit_string(value.to_string())
+        }
+
+        fn visit_string<E: de::Error>(self, value: String) -> Result<MaybeRedacted, E> {
+            if SensitiveString::looks_redacted(&value) {
+                Ok(MaybeRedacted::Redacted(value))
+            } else {
+                Ok(MaybeRedacted::Secret(SensitiveString::new(value)))
+            }
         }
     }
 }
 
+#[cfg(feature = "serde")]
+pub use serde_impl::MaybeRedacted;
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "hash")]
     #[test]
     fn test_display_shows_hash() {
         let secret = SensitiveString::new("my-secret-value".to_string());
@@ -241,6 +847,18 @@ mod tests {
         assert_eq!(result.len(), 71); // "sha256:" (7) + 64 hex chars
     }
 
+    #[cfg(not(feature = "hash"))]
+    #[test]
+    fn test_display_without_hash_shows_length() {
+        let secret = SensitiveString::from("my-secret-value");
+        assert_eq!(secret.to_string(), "[REDACTED len=15]");
+        assert_eq!(
+            format!("{:?}", secret),
+            "SensitiveString([REDACTED len=15])"
+        );
+    }
+
+    #[cfg(feature = "hash")]
     #[test]
     fn test_debug_shows_hash() {
         let secret = SensitiveString::new("my-secret-value".to_string());
@@ -250,18 +868,35 @@ mod tests {
         assert!(!result.contains("my-secret-value"));
     }
 
+    #[cfg(not(feature = "no-plaintext-getters"))]
     #[test]
     fn test_get_value_returns_plaintext() {
         let secret = SensitiveString::new("my-secret-value".to_string());
         assert_eq!(secret.get_value(), "my-secret-value");
     }
 
+    #[cfg(not(feature = "no-plaintext-getters"))]
     #[test]
     fn test_value_returns_plaintext() {
         let secret = SensitiveString::new("my-secret-value".to_string());
         assert_eq!(secret.value(), "my-secret-value");
     }
 
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[test]
+    fn test_take_moves_the_buffer() {
+        let mut secret = SensitiveString::from("my-secret-value").with_label("taken");
+        let buffer = secret.value.as_ptr();
+        let shown = secret.to_string();
+
+        let plaintext = secret.take();
+        assert_eq!(plaintext.as_ptr(), buffer);
+        assert!(secret.is_empty());
+        assert_eq!(secret.label(), Some("taken"));
+        assert_ne!(secret.to_string(), shown);
+        assert_eq!(secret.into_plaintext_exposed(), "");
+    }
+
     #[test]
     fn test_len() {
         let secret = SensitiveString::new("12345".to_string());
@@ -306,15 +941,26 @@ mod tests {
     #[test]
     fn test_from_string() {
         let secret: SensitiveString = "my-secret".to_string().into();
-        assert_eq!(secret.get_value(), "my-secret");
+        assert_eq!(secret.value, "my-secret");
     }
 
     #[test]
     fn test_from_str() {
         let secret: SensitiveString = "my-secret".into();
-        assert_eq!(secret.get_value(), "my-secret");
+        assert_eq!(secret.value, "my-secret");
     }
 
+    #[test]
+    fn test_parse_and_try_from() {
+        fn convert<T: TryFrom<&'static str>>(value: &'static str) -> Option<T> {
+            T::try_from(value).ok()
+        }
+        let parsed: SensitiveString = "my-secret".parse().unwrap();
+        assert_eq!(parsed.value, "my-secret");
+        assert_eq!(convert::<SensitiveString>("my-secret"), Some(parsed));
+    }
+
+    #[cfg(not(feature = "no-plaintext-getters"))]
     #[test]
     fn test_extract_value() {
         let secret = SensitiveString::new("secret".to_string());
@@ -324,13 +970,63 @@ mod tests {
     #[test]
     fn test_sensitive() {
         let secret = SensitiveString::sensitive("plain");
-        assert_eq!(secret.get_value(), "plain");
+        assert_eq!(secret.value, "plain");
     }
 
-    #[cfg(feature = "serde")]
+    #[cfg(all(feature = "serde", feature = "hash"))]
     mod serde_tests {
         use super::*;
-        use serde::Serialize;
+        use ::serde::Serialize;
+
+        #[derive(Debug, ::serde::Deserialize)]
+        struct Config {
+            user: String,
+            password: SensitiveString,
+        }
+
+        #[test]
+        fn test_deserialize_reads_plaintext() {
+            let config: Config =
+                serde_json::from_str(r#"{"user":"app","password":"hunter2"}"#).unwrap();
+            assert_eq!(config.user, "app");
+            assert_eq!(config.password.value, "hunter2");
+
+            let config: Config = toml::from_str("user = \"app\"\npassword = \"hunter2\"").unwrap();
+            assert_eq!(config.password.value, "hunter2");
+        }
+
+        #[test]
+        fn test_deserialize_rejects_round_tripped_digest() {
+            let serialized = serde_json::to_string(&SensitiveString::from("hunter2")).unwrap();
+            let error = serde_json::from_str::<SensitiveString>(&serialized).unwrap_err();
+            assert!(error.to_string().contains("redacted digest"));
+
+            let field: MaybeRedacted = serde_json::from_str(&serialized).unwrap();
+            assert!(matches!(field, MaybeRedacted::Redacted(_)));
+            assert_eq!(field.into_secret(), None);
+        }
+
+        #[test]
+        fn test_deserialize_rejects_round_tripped_mask() {
+            let card = SensitiveString::from("4111111111111111")
+                .with_policy(policy::RedactionPolicy::MaskLast(4));
+            let serialized = serde_json::to_string(&card).unwrap();
+            assert_eq!(serialized, "\"****1111\"");
+            assert!(serde_json::from_str::<SensitiveString>(&serialized).is_err());
+
+            let field: MaybeRedacted = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(field, MaybeRedacted::Redacted("****1111".to_string()));
+            let short = SensitiveString::from("a").with_policy(policy::RedactionPolicy::MaskLast(4));
+            assert!(SensitiveString::looks_redacted(&short.to_string()));
+        }
+
+        #[test]
+        fn test_deserialize_errors_do_not_echo_values() {
+            let error = serde_json::from_str::<Config>(r#"{"user":"app","password":918273}"#)
+                .unwrap_err()
+                .to_string();
+            assert!(!error.contains("918273"));
+        }
 
         #[test]
         fn test_json_serialization() {
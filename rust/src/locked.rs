@@ -0,0 +1,300 @@
+//! Secrets in page-locked memory.
+//!
+//! A heap-allocated plaintext can be written to swap under memory pressure
+//! and appears in core dumps. With the `mlock` feature,
+//! [`LockedSensitiveString`] keeps its plaintext in pages of its own, mapped
+//! for it alone, locked into RAM with `mlock` and, on Linux, excluded from
+//! core dumps with `madvise(MADV_DONTDUMP)`, in the way libsodium's
+//! `sodium_malloc` does. The pages are zeroed before they are unmapped.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::LockedSensitiveString;
+//!
+//! let key = LockedSensitiveString::new("hunter2");
+//! key.expose(|plaintext| assert_eq!(plaintext, "hunter2"));
+//! if !key.is_locked() {
+//!     eprintln!("warning: secret memory could not be locked");
+//! }
+//! ```
+//!
+//! Protection is best effort. When pages cannot be mapped or locked (on
+//! platforms without `mlock`, or past `RLIMIT_MEMLOCK`), the secret is kept
+//! in an ordinary buffer that is still zeroed on drop, and
+//! [`is_locked`](LockedSensitiveString::is_locked) /
+//! [`is_excluded_from_dumps`](LockedSensitiveString::is_excluded_from_dumps)
+//! report what was achieved. Each secret takes at least one page.
+
+use crate::{wipe, SensitiveString};
+use std::fmt;
+
+/// Pages mapped for one secret.
+#[cfg(unix)]
+struct Pages {
+    ptr: *mut u8,
+    map_len: usize,
+    locked: bool,
+    dontdump: bool,
+}
+
+#[cfg(unix)]
+impl Pages {
+    /// Maps, locks, and marks enough pages for `len` bytes.
+    fn map(len: usize) -> Option<Self> {
+        // SAFETY: sysconf has no preconditions.
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        let page = usize::try_from(page).ok().filter(|&page| page > 0)?;
+        let map_len = len.max(1).div_ceil(page) * page;
+        // SAFETY: an anonymous private mapping of a non-zero length with no address hint.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                map_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANON,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return None;
+        }
+        // SAFETY: `ptr..ptr + map_len` is the mapping created above.
+        let locked = unsafe { libc::mlock(ptr, map_len) } == 0;
+        #[cfg(target_os = "linux")]
+        // SAFETY: as above; MADV_DONTDUMP only changes core dump behavior.
+        let dontdump = unsafe { libc::madvise(ptr, map_len, libc::MADV_DONTDUMP) } == 0;
+        #[cfg(not(target_os = "linux"))]
+        let dontdump = false;
+        Some(Self {
+            ptr: ptr.cast(),
+            map_len,
+            locked,
+            dontdump,
+        })
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        // SAFETY: the mapping is `map_len` readable and writable bytes owned by `self`.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.map_len) }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: as in `bytes_mut`.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.map_len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Pages {
+    fn drop(&mut self) {
+        wipe(self.bytes_mut());
+        // SAFETY: unlocking and unmapping the mapping created in `map`, which
+        // nothing refers to any more.
+        unsafe {
+            if self.locked {
+                libc::munlock(self.ptr.cast(), self.map_len);
+            }
+            libc::munmap(self.ptr.cast(), self.map_len);
+        }
+    }
+}
+
+// SAFETY: `Pages` exclusively owns its mapping, like a `Box<[u8]>`.
+#[cfg(unix)]
+unsafe impl Send for Pages {}
+// SAFETY: shared access is read-only.
+#[cfg(unix)]
+unsafe impl Sync for Pages {}
+
+enum Storage {
+    #[cfg(unix)]
+    Pages(Pages),
+    Heap(Vec<u8>),
+}
+
+/// A secret string kept in locked memory that is excluded from core dumps.
+pub struct LockedSensitiveString {
+    storage: Storage,
+    len: usize,
+}
+
+impl LockedSensitiveString {
+    /// Moves `value` into locked pages, zeroing the original buffer.
+    pub fn new(value: impl Into<String>) -> Self {
+        let mut value = value.into().into_bytes();
+        let secret = Self::from_bytes(&value);
+        wipe(&mut value);
+        secret
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        #[cfg(unix)]
+        if let Some(mut pages) = Pages::map(bytes.len()) {
+            pages.bytes_mut()[..bytes.len()].copy_from_slice(bytes);
+            return Self {
+                storage: Storage::Pages(pages),
+                len: bytes.len(),
+            };
+        }
+        Self {
+            storage: Storage::Heap(bytes.to_vec()),
+            len: bytes.len(),
+        }
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        match &self.storage {
+            #[cfg(unix)]
+            Storage::Pages(pages) => &pages.bytes()[..self.len],
+            Storage::Heap(bytes) => bytes,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(self.as_bytes()).expect("created from a String")
+    }
+
+    /// Runs `f` with the plaintext and returns its result.
+    #[track_caller]
+    pub fn expose<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        crate::record_plaintext_read(None, "expose");
+        f(self.as_str())
+    }
+
+    /// Copies the plaintext into an ordinary [`SensitiveString`].
+    pub fn to_sensitive_string(&self) -> SensitiveString {
+        SensitiveString::from(self.as_str())
+    }
+
+    /// Returns the length of the plaintext in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the plaintext is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if the plaintext's pages are locked into RAM.
+    pub fn is_locked(&self) -> bool {
+        match &self.storage {
+            #[cfg(unix)]
+            Storage::Pages(pages) => pages.locked,
+            Storage::Heap(_) => false,
+        }
+    }
+
+    /// Returns true if the plaintext's pages are excluded from core dumps.
+    pub fn is_excluded_from_dumps(&self) -> bool {
+        match &self.storage {
+            #[cfg(unix)]
+            Storage::Pages(pages) => pages.dontdump,
+            Storage::Heap(_) => false,
+        }
+    }
+}
+
+impl Drop for LockedSensitiveString {
+    fn drop(&mut self) {
+        // Pages wipe themselves.
+        if let Storage::Heap(bytes) = &mut self.storage {
+            wipe(bytes);
+        }
+    }
+}
+
+impl Clone for LockedSensitiveString {
+    fn clone(&self) -> Self {
+        Self::from_bytes(self.as_bytes())
+    }
+}
+
+impl From<SensitiveString> for LockedSensitiveString {
+    fn from(secret: SensitiveString) -> Self {
+        Self::new(secret.value)
+    }
+}
+
+impl From<&str> for LockedSensitiveString {
+    fn from(value: &str) -> Self {
+        Self::from_bytes(value.as_bytes())
+    }
+}
+
+impl From<String> for LockedSensitiveString {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl PartialEq for LockedSensitiveString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for LockedSensitiveString {}
+
+/// Shows the redacted form `SensitiveString` would.
+impl fmt::Display for LockedSensitiveString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plaintext = self.as_str();
+        let redacted = crate::policy::default_policy()
+            .apply(plaintext, || crate::redact_bytes(plaintext.as_bytes()));
+        f.write_str(&redacted)
+    }
+}
+
+impl fmt::Debug for LockedSensitiveString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LockedSensitiveString({})", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_and_formats_like_sensitive_string() {
+        let secret = LockedSensitiveString::new("hunter2");
+        assert_eq!(secret.len(), 7);
+        assert_eq!(
+            secret.to_sensitive_string(),
+            SensitiveString::from("hunter2")
+        );
+        assert_eq!(secret, secret.clone());
+        assert_ne!(secret, LockedSensitiveString::from("hunter3"));
+        assert_eq!(
+            secret.to_string(),
+            SensitiveString::from("hunter2").to_string()
+        );
+        assert!(format!("{:?}", secret).starts_with("LockedSensitiveString("));
+        assert!(LockedSensitiveString::from("").is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_uses_dedicated_pages_on_linux() {
+        let secret = LockedSensitiveString::new("x".repeat(10_000));
+        match &secret.storage {
+            Storage::Pages(pages) => {
+                assert!(pages.map_len >= 10_000);
+                assert!(pages.dontdump);
+                assert_eq!(secret.is_excluded_from_dumps(), pages.dontdump);
+            }
+            Storage::Heap(_) => panic!("expected a page mapping"),
+        }
+        secret.expose(|plaintext| assert_eq!(plaintext.len(), 10_000));
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_expose_is_checked_against_access_policies() {
+        let secret = LockedSensitiveString::new("hunter2");
+        crate::access::assert_denied(|| secret.expose(|plaintext| plaintext.len()));
+    }
+}
@@ -0,0 +1,787 @@
+//! A map of secrets.
+//!
+//! [`SensitiveMap`] holds named secrets, such as the result of
+//! [`resolve_all`](crate::source::resolve_all). Every value is a
+//! [`SensitiveString`], so `Debug` output of the map is redacted.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{SensitiveMap, SensitiveString};
+//!
+//! let mut secrets: SensitiveMap = SensitiveMap::new();
+//! secrets.insert("db", SensitiveString::from("hunter2"));
+//!
+//! assert_eq!(secrets.get("db"), Some(&SensitiveString::from("hunter2")));
+//! assert!(!format!("{:?}", secrets).contains("hunter2"));
+//! ```
+//!
+//! [`ShardedSensitiveMap`] is the concurrent variant for hot lookup paths: it
+//! splits the entries across independently locked shards, so lookups of
+//! different keys rarely contend, and
+//! [`replace_all`](ShardedSensitiveMap::replace_all) swaps in a rotated set
+//! of credentials in one step.
+//!
+//! ```
+//! use sensitive_string::{SensitiveMap, SensitiveString, ShardedSensitiveMap};
+//!
+//! let keys: ShardedSensitiveMap = ShardedSensitiveMap::new();
+//! keys.insert("acme", SensitiveString::from("sk-old"));
+//!
+//! let rotated: SensitiveMap = [("acme".to_string(), SensitiveString::from("sk-new"))]
+//!     .into_iter()
+//!     .collect();
+//! let old = keys.replace_all(rotated);
+//!
+//! assert_eq!(old.get("acme"), Some(&SensitiveString::from("sk-old")));
+//! assert_eq!(keys.get_with("acme", |key| key.len()), Some(6));
+//! ```
+//!
+//! Secrets that arrive as plain strings, such as a `HashMap<String,
+//! String>` read from a vault or a list of rotated API keys, convert in one
+//! step with [`SensitiveMap::from_plain`], `From<HashMap<K, String>>`, or
+//! [`SensitiveVec::from_plain`]. With the `serde` feature both serialize
+//! their values redacted and deserialize from plaintext.
+//!
+//! ```
+//! use sensitive_string::{SensitiveString, SensitiveVec};
+//!
+//! let keys = SensitiveVec::from_plain(["sk-1", "sk-2"]);
+//! assert_eq!(keys.get(0), Some(&SensitiveString::from("sk-1")));
+//! assert!(!format!("{:?}", keys).contains("sk-1"));
+//! ```
+//!
+//! With the `indexmap` feature, [`OrderedSensitiveMap`] keeps entries in
+//! insertion order, and serializes (redacted) and deserializes in that
+//! order, so redacted config dumps come out the same way every run and can
+//! be diffed.
+//!
+//! ```
+//! # #[cfg(feature = "indexmap")] {
+//! use sensitive_string::{OrderedSensitiveMap, SensitiveString};
+//!
+//! let mut dump: OrderedSensitiveMap = OrderedSensitiveMap::new();
+//! dump.insert("zeta", SensitiveString::from("z"));
+//! dump.insert("alpha", SensitiveString::from("a"));
+//! assert_eq!(dump.keys().collect::<Vec<_>>(), ["zeta", "alpha"]);
+//! # }
+//! ```
+
+use crate::SensitiveString;
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap;
+use std::borrow::Borrow;
+use std::collections::hash_map::{self, HashMap, RandomState};
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A map from keys to secrets.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        transparent,
+        bound(
+            serialize = "K: serde::Serialize",
+            deserialize = "K: serde::Deserialize<'de> + Eq + Hash"
+        )
+    )
+)]
+pub struct SensitiveMap<K = String> {
+    pub(crate) entries: HashMap<K, SensitiveString>,
+}
+
+impl<K> Default for SensitiveMap<K> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash> PartialEq for SensitiveMap<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl<K: Eq + Hash> Eq for SensitiveMap<K> {}
+
+impl<K: Eq + Hash> SensitiveMap<K> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a map from plaintext entries, wrapping each value.
+    pub fn from_plain<I, V>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        V: Into<SensitiveString>,
+    {
+        entries
+            .into_iter()
+            .map(|(key, value)| (key, value.into()))
+            .collect()
+    }
+
+    /// Inserts a secret, returning the previous one for `key`.
+    pub fn insert(
+        &mut self,
+        key: impl Into<K>,
+        secret: impl Into<SensitiveString>,
+    ) -> Option<SensitiveString> {
+        self.entries.insert(key.into(), secret.into())
+    }
+
+    /// Returns the secret for `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&SensitiveString>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.get(key)
+    }
+
+    /// Removes and returns the secret for `key`.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<SensitiveString>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.remove(key)
+    }
+
+    /// Returns true if there is a secret for `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns the number of secrets.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the keys, in arbitrary order.
+    pub fn keys(&self) -> hash_map::Keys<'_, K, SensitiveString> {
+        self.entries.keys()
+    }
+
+    /// Iterates over the secrets, in arbitrary order.
+    pub fn values(&self) -> hash_map::Values<'_, K, SensitiveString> {
+        self.entries.values()
+    }
+
+    /// Iterates over the entries, in arbitrary order.
+    pub fn iter(&self) -> hash_map::Iter<'_, K, SensitiveString> {
+        self.entries.iter()
+    }
+}
+
+impl<K: Eq + Hash> FromIterator<(K, SensitiveString)> for SensitiveMap<K> {
+    fn from_iter<I: IntoIterator<Item = (K, SensitiveString)>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<K: Eq + Hash> From<HashMap<K, String>> for SensitiveMap<K> {
+    fn from(entries: HashMap<K, String>) -> Self {
+        Self::from_plain(entries)
+    }
+}
+
+impl<K: Eq + Hash> From<HashMap<K, SensitiveString>> for SensitiveMap<K> {
+    fn from(entries: HashMap<K, SensitiveString>) -> Self {
+        Self { entries }
+    }
+}
+
+impl<'a, K> IntoIterator for &'a SensitiveMap<K> {
+    type Item = (&'a K, &'a SensitiveString);
+    type IntoIter = hash_map::Iter<'a, K, SensitiveString>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl<K> IntoIterator for SensitiveMap<K> {
+    type Item = (K, SensitiveString);
+    type IntoIter = hash_map::IntoIter<K, SensitiveString>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// A list of secrets.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct SensitiveVec {
+    items: Vec<SensitiveString>,
+}
+
+impl SensitiveVec {
+    /// Creates an empty list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a list from plaintext values, wrapping each one.
+    pub fn from_plain<I, V>(values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<SensitiveString>,
+    {
+        values.into_iter().map(Into::into).collect()
+    }
+
+    /// Appends a secret.
+    pub fn push(&mut self, secret: impl Into<SensitiveString>) {
+        self.items.push(secret.into());
+    }
+
+    /// Returns the secret at `index`.
+    pub fn get(&self, index: usize) -> Option<&SensitiveString> {
+        self.items.get(index)
+    }
+
+    /// Removes and returns the last secret.
+    pub fn pop(&mut self) -> Option<SensitiveString> {
+        self.items.pop()
+    }
+
+    /// Returns the number of secrets.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns true if the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Iterates over the secrets in order.
+    pub fn iter(&self) -> std::slice::Iter<'_, SensitiveString> {
+        self.items.iter()
+    }
+
+    /// Returns the secrets as a slice.
+    pub fn as_slice(&self) -> &[SensitiveString] {
+        &self.items
+    }
+}
+
+impl FromIterator<SensitiveString> for SensitiveVec {
+    fn from_iter<I: IntoIterator<Item = SensitiveString>>(iter: I) -> Self {
+        Self {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl From<Vec<String>> for SensitiveVec {
+    fn from(values: Vec<String>) -> Self {
+        Self::from_plain(values)
+    }
+}
+
+impl From<Vec<SensitiveString>> for SensitiveVec {
+    fn from(items: Vec<SensitiveString>) -> Self {
+        Self { items }
+    }
+}
+
+impl<'a> IntoIterator for &'a SensitiveVec {
+    type Item = &'a SensitiveString;
+    type IntoIter = std::slice::Iter<'a, SensitiveString>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl IntoIterator for SensitiveVec {
+    type Item = SensitiveString;
+    type IntoIter = std::vec::IntoIter<SensitiveString>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+/// A map from keys to secrets that remembers insertion order.
+///
+/// Equality ignores order, like [`SensitiveMap`]'s. Converting from a
+/// `SensitiveMap` sorts the entries by key, since its order is arbitrary.
+#[cfg(feature = "indexmap")]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        transparent,
+        bound(
+            serialize = "K: serde::Serialize",
+            deserialize = "K: serde::Deserialize<'de> + Eq + Hash"
+        )
+    )
+)]
+pub struct OrderedSensitiveMap<K = String> {
+    entries: IndexMap<K, SensitiveString>,
+}
+
+#[cfg(feature = "indexmap")]
+impl<K> Default for OrderedSensitiveMap<K> {
+    fn default() -> Self {
+        Self {
+            entries: IndexMap::default(),
+        }
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K: Eq + Hash> PartialEq for OrderedSensitiveMap<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K: Eq + Hash> Eq for OrderedSensitiveMap<K> {}
+
+#[cfg(feature = "indexmap")]
+impl<K: Eq + Hash> OrderedSensitiveMap<K> {
+    /// Creates an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a secret, returning the previous one for `key`.
+    ///
+    /// A new key goes last; an existing key keeps its position.
+    pub fn insert(
+        &mut self,
+        key: impl Into<K>,
+        secret: impl Into<SensitiveString>,
+    ) -> Option<SensitiveString> {
+        self.entries.insert(key.into(), secret.into())
+    }
+
+    /// Returns the secret for `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<&SensitiveString>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.get(key)
+    }
+
+    /// Removes and returns the secret for `key`, keeping the order of the rest.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<SensitiveString>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.shift_remove(key)
+    }
+
+    /// Returns true if there is a secret for `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns the number of secrets.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sorts the entries by key.
+    pub fn sort_keys(&mut self)
+    where
+        K: Ord,
+    {
+        self.entries.sort_keys();
+    }
+
+    /// Iterates over the keys, in order.
+    pub fn keys(&self) -> indexmap::map::Keys<'_, K, SensitiveString> {
+        self.entries.keys()
+    }
+
+    /// Iterates over the secrets, in order.
+    pub fn values(&self) -> indexmap::map::Values<'_, K, SensitiveString> {
+        self.entries.values()
+    }
+
+    /// Iterates over the entries, in order.
+    pub fn iter(&self) -> indexmap::map::Iter<'_, K, SensitiveString> {
+        self.entries.iter()
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K: Eq + Hash> FromIterator<(K, SensitiveString)> for OrderedSensitiveMap<K> {
+    fn from_iter<I: IntoIterator<Item = (K, SensitiveString)>>(iter: I) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K: Eq + Hash + Ord> From<SensitiveMap<K>> for OrderedSensitiveMap<K> {
+    fn from(secrets: SensitiveMap<K>) -> Self {
+        let mut map: Self = secrets.into_iter().collect();
+        map.sort_keys();
+        map
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<'a, K> IntoIterator for &'a OrderedSensitiveMap<K> {
+    type Item = (&'a K, &'a SensitiveString);
+    type IntoIter = indexmap::map::Iter<'a, K, SensitiveString>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(feature = "indexmap")]
+impl<K> IntoIterator for OrderedSensitiveMap<K> {
+    type Item = (K, SensitiveString);
+    type IntoIter = indexmap::map::IntoIter<K, SensitiveString>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// The number of shards used by [`ShardedSensitiveMap::new`].
+const DEFAULT_SHARDS: usize = 16;
+
+/// A map from keys to secrets that can be shared between threads.
+///
+/// Entries are spread over shards that are locked separately. Lookups return
+/// clones, or borrow the secret inside [`get_with`](Self::get_with) to avoid
+/// copying the plaintext.
+pub struct ShardedSensitiveMap<K = String> {
+    shards: Box<[RwLock<HashMap<K, SensitiveString>>]>,
+    hasher: RandomState,
+}
+
+impl<K: Eq + Hash> Default for ShardedSensitiveMap<K> {
+    fn default() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+}
+
+impl<K: Eq + Hash> ShardedSensitiveMap<K> {
+    /// Creates an empty map with the default number of shards.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty map with `shards` shards (at least one).
+    pub fn with_shards(shards: usize) -> Self {
+        Self {
+            shards: (0..shards.max(1))
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Inserts a secret, returning the previous one for `key`.
+    pub fn insert(
+        &self,
+        key: impl Into<K>,
+        secret: impl Into<SensitiveString>,
+    ) -> Option<SensitiveString> {
+        let key = key.into();
+        write(self.shard(&key)).insert(key, secret.into())
+    }
+
+    /// Returns a clone of the secret for `key`.
+    pub fn get<Q>(&self, key: &Q) -> Option<SensitiveString>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_with(key, SensitiveString::clone)
+    }
+
+    /// Calls `f` with the secret for `key` while its shard is read-locked.
+    pub fn get_with<Q, R>(&self, key: &Q, f: impl FnOnce(&SensitiveString) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        read(self.shard(key)).get(key).map(f)
+    }
+
+    /// Removes and returns the secret for `key`.
+    pub fn remove<Q>(&self, key: &Q) -> Option<SensitiveString>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        write(self.shard(key)).remove(key)
+    }
+
+    /// Returns true if there is a secret for `key`.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        read(self.shard(key)).contains_key(key)
+    }
+
+    /// Returns the number of secrets.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| read(shard).len()).sum()
+    }
+
+    /// Returns true if the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| read(shard).is_empty())
+    }
+
+    /// Replaces every entry with those of `secrets` and returns the old entries.
+    ///
+    /// All shards are locked for the swap, so no reader sees a mix of old and
+    /// new secrets, and [`snapshot`](Self::snapshot) never sees a half-replaced map.
+    pub fn replace_all(&self, secrets: SensitiveMap<K>) -> SensitiveMap<K> {
+        let mut incoming: Vec<HashMap<K, SensitiveString>> =
+            self.shards.iter().map(|_| HashMap::new()).collect();
+        for (key, secret) in secrets {
+            let index = self.index(&key);
+            incoming[index].insert(key, secret);
+        }
+
+        let mut guards: Vec<_> = self.shards.iter().map(write).collect();
+        let mut old = SensitiveMap::new();
+        for (guard, entries) in guards.iter_mut().zip(incoming) {
+            old.entries.extend(std::mem::replace(&mut **guard, entries));
+        }
+        old
+    }
+
+    /// Copies every entry into a [`SensitiveMap`] at a single point in time.
+    pub fn snapshot(&self) -> SensitiveMap<K>
+    where
+        K: Clone,
+    {
+        let guards: Vec<_> = self.shards.iter().map(read).collect();
+        guards
+            .iter()
+            .flat_map(|guard| guard.iter())
+            .map(|(key, secret)| (key.clone(), secret.clone()))
+            .collect()
+    }
+
+    fn index<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        (self.hasher.hash_one(key) % self.shards.len() as u64) as usize
+    }
+
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> &RwLock<HashMap<K, SensitiveString>> {
+        &self.shards[self.index(key)]
+    }
+}
+
+impl<K: Eq + Hash> From<SensitiveMap<K>> for ShardedSensitiveMap<K> {
+    fn from(secrets: SensitiveMap<K>) -> Self {
+        let map = Self::new();
+        map.replace_all(secrets);
+        map
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for ShardedSensitiveMap<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let guards: Vec<_> = self.shards.iter().map(read).collect();
+        f.debug_map()
+            .entries(guards.iter().flat_map(|guard| guard.iter()))
+            .finish()
+    }
+}
+
+fn read<K>(
+    shard: &RwLock<HashMap<K, SensitiveString>>,
+) -> RwLockReadGuard<'_, HashMap<K, SensitiveString>> {
+    shard
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn write<K>(
+    shard: &RwLock<HashMap<K, SensitiveString>>,
+) -> RwLockWriteGuard<'_, HashMap<K, SensitiveString>> {
+    shard
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut map: SensitiveMap = SensitiveMap::new();
+        assert!(map.insert("a", "1").is_none());
+        assert_eq!(map.insert("a", "2"), Some(SensitiveString::from("1")));
+        assert!(map.contains_key("a"));
+        assert_eq!(map.remove("a"), Some(SensitiveString::from("2")));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let map: SensitiveMap<u8> = [(1, SensitiveString::from("hunter2"))]
+            .into_iter()
+            .collect();
+        let debug = format!("{:?}", map);
+        assert!(debug.contains("SensitiveString("));
+        assert!(!debug.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_from_plain_wraps_values() {
+        let plain: HashMap<String, String> = [("db".to_string(), "hunter2".to_string())].into();
+        let map = SensitiveMap::from(plain);
+        assert_eq!(map.get("db"), Some(&SensitiveString::from("hunter2")));
+        assert!(!format!("{:?}", map).contains("hunter2"));
+
+        let list = SensitiveVec::from_plain(["hunter2", "hunter3"]);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.get(1), Some(&SensitiveString::from("hunter3")));
+        let debug = format!("{:?}", list);
+        assert!(!debug.contains("hunter2") && !debug.contains("hunter3"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip_is_redacted() {
+        let map: SensitiveMap = SensitiveMap::from_plain([("db".to_string(), "hunter2")]);
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(
+            json,
+            format!(r#"{{"db":"{}"}}"#, SensitiveString::from("hunter2"))
+        );
+        let parsed: SensitiveMap = serde_json::from_str(r#"{"db":"hunter2"}"#).unwrap();
+        assert_eq!(parsed, map);
+
+        let list: SensitiveVec = serde_json::from_str(r#"["a","b"]"#).unwrap();
+        assert_eq!(list, SensitiveVec::from_plain(["a", "b"]));
+        assert!(!serde_json::to_string(&list).unwrap().contains("\"a\""));
+    }
+
+    #[test]
+    fn test_sharded_replace_all_is_visible_everywhere() {
+        let map: ShardedSensitiveMap<u32> = ShardedSensitiveMap::with_shards(4);
+        for key in 0..100u32 {
+            map.insert(key, "old");
+        }
+        let rotated: SensitiveMap<u32> = (50..150).map(|key| (key, "new".into())).collect();
+
+        let old = map.replace_all(rotated);
+        assert_eq!(old.len(), 100);
+        assert_eq!(map.len(), 100);
+        assert!(!map.contains_key(&0));
+        assert_eq!(map.get(&120), Some(SensitiveString::from("new")));
+        assert_eq!(map.snapshot().len(), 100);
+    }
+
+    #[test]
+    fn test_sharded_concurrent_lookups() {
+        let map: std::sync::Arc<ShardedSensitiveMap> = std::sync::Arc::new(
+            [("a".to_string(), SensitiveString::from("1"))]
+                .into_iter()
+                .collect::<SensitiveMap>()
+                .into(),
+        );
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let map = map.clone();
+                std::thread::spawn(move || {
+                    (0..1000).all(|_| map.get_with("a", |secret| secret.len()).is_some())
+                })
+            })
+            .collect();
+        for i in 0..100 {
+            map.replace_all(
+                [("a".to_string(), SensitiveString::new(i.to_string()))]
+                    .into_iter()
+                    .collect(),
+            );
+        }
+        assert!(readers.into_iter().all(|reader| reader.join().unwrap()));
+        assert!(!format!("{:?}", map).contains("\"99\""));
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_ordered_keeps_insertion_order() {
+        let mut map: OrderedSensitiveMap = ["c", "a", "b"]
+            .into_iter()
+            .map(|key| (key.to_string(), SensitiveString::from(key)))
+            .collect();
+        map.insert("a", "again");
+        map.remove("c");
+        map.insert("c", "last");
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["a", "b", "c"]);
+
+        let unordered: SensitiveMap = map.clone().into_iter().rev().collect();
+        assert_eq!(OrderedSensitiveMap::from(unordered), map);
+    }
+
+    #[cfg(all(feature = "indexmap", feature = "serde"))]
+    #[test]
+    fn test_ordered_serializes_in_order_and_redacted() {
+        let mut map: OrderedSensitiveMap = OrderedSensitiveMap::new();
+        map.insert("zeta", "hunter2");
+        map.insert("alpha", "hunter3");
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"zeta":"{}","alpha":"{}"}}"#,
+                SensitiveString::from("hunter2"),
+                SensitiveString::from("hunter3")
+            )
+        );
+
+        let parsed: OrderedSensitiveMap = serde_json::from_str(r#"{"b":"2","a":"1"}"#).unwrap();
+        assert_eq!(parsed.keys().collect::<Vec<_>>(), ["b", "a"]);
+        assert_eq!(parsed.get("a"), Some(&SensitiveString::from("1")));
+    }
+}
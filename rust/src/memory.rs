@@ -0,0 +1,147 @@
+//! Approximate memory usage of held secrets.
+//!
+//! Services holding credentials for hundreds of thousands of tenants need to
+//! watch how much memory those credentials take. `approx_memory_bytes()` on
+//! [`SensitiveString`], [`SensitiveMap`], and
+//! [`SecretRegistry`](crate::registry::SecretRegistry) estimates the bytes
+//! each holds, and [`MemoryReport`] aggregates counts and sizes for a
+//! metrics endpoint.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{SensitiveMap, SensitiveString};
+//!
+//! let mut tenants: SensitiveMap = SensitiveMap::new();
+//! tenants.insert("acme", SensitiveString::from("sk-acme"));
+//! tenants.insert("globex", SensitiveString::from("sk-globex"));
+//!
+//! let report = tenants.memory_report();
+//! assert_eq!(report.secrets, 2);
+//! assert_eq!(report.plaintext_bytes, 16);
+//! assert!(tenants.approx_memory_bytes() > report.plaintext_bytes);
+//! ```
+//!
+//! Estimates count the wrapper, the plaintext buffer, the cached digest, and
+//! the label, but not allocator overhead or attached hashers and observers.
+//! A label shared between clones is counted once per clone.
+
+use crate::{SensitiveMap, SensitiveString};
+use std::iter::Sum;
+use std::mem::size_of;
+use std::ops::{Add, AddAssign};
+
+/// Aggregate memory use of a set of secrets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MemoryReport {
+    /// The number of secrets.
+    pub secrets: usize,
+    /// The total length of their plaintexts.
+    pub plaintext_bytes: usize,
+    /// The approximate total bytes they occupy.
+    pub approx_bytes: usize,
+}
+
+impl MemoryReport {
+    /// Reports on `secrets`.
+    pub fn of<'a>(secrets: impl IntoIterator<Item = &'a SensitiveString>) -> Self {
+        secrets.into_iter().map(MemoryReport::from).sum()
+    }
+}
+
+impl From<&SensitiveString> for MemoryReport {
+    fn from(secret: &SensitiveString) -> Self {
+        MemoryReport {
+            secrets: 1,
+            plaintext_bytes: secret.len(),
+            approx_bytes: secret.approx_memory_bytes(),
+        }
+    }
+}
+
+impl Add for MemoryReport {
+    type Output = MemoryReport;
+
+    fn add(self, other: MemoryReport) -> MemoryReport {
+        MemoryReport {
+            secrets: self.secrets + other.secrets,
+            plaintext_bytes: self.plaintext_bytes + other.plaintext_bytes,
+            approx_bytes: self.approx_bytes + other.approx_bytes,
+        }
+    }
+}
+
+impl AddAssign for MemoryReport {
+    fn add_assign(&mut self, other: MemoryReport) {
+        *self = *self + other;
+    }
+}
+
+impl Sum for MemoryReport {
+    fn sum<I: Iterator<Item = MemoryReport>>(iter: I) -> Self {
+        iter.fold(MemoryReport::default(), Add::add)
+    }
+}
+
+impl SensitiveString {
+    /// Estimates the bytes this secret occupies, including the wrapper itself.
+    pub fn approx_memory_bytes(&self) -> usize {
+        size_of::<Self>()
+            + self.value.capacity()
+            + self.label.as_ref().map_or(0, |label| label.len())
+            + self.digest.get().map_or(0, |(_, digest)| digest.capacity())
+    }
+}
+
+impl<K> SensitiveMap<K> {
+    /// Estimates the bytes this map occupies, including its table.
+    ///
+    /// Heap memory owned by keys (e.g. `String` contents) is not counted.
+    pub fn approx_memory_bytes(&self) -> usize {
+        size_of::<Self>() + self.table_bytes() + self.heap_bytes()
+    }
+
+    /// Reports on the secrets in this map.
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut report = MemoryReport::of(self.entries.values());
+        report.approx_bytes = self.table_bytes() + self.heap_bytes();
+        report
+    }
+
+    fn table_bytes(&self) -> usize {
+        self.entries.capacity() * (size_of::<(K, SensitiveString)>() + 1)
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.entries
+            .values()
+            .map(|secret| secret.approx_memory_bytes() - size_of::<SensitiveString>())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_size_counts_plaintext_label_and_digest() {
+        let secret = SensitiveString::new(String::with_capacity(64)).with_label("db");
+        let base = size_of::<SensitiveString>() + 64 + 2;
+        assert_eq!(secret.approx_memory_bytes(), base);
+
+        let _ = secret.to_string();
+        assert!(secret.approx_memory_bytes() > base);
+    }
+
+    #[test]
+    fn test_reports_add_up() {
+        let a = SensitiveString::from("abc");
+        let b = SensitiveString::from("de");
+        let report = MemoryReport::of([&a, &b]);
+        assert_eq!(report.secrets, 2);
+        assert_eq!(report.plaintext_bytes, 5);
+        assert_eq!(report, MemoryReport::from(&a) + MemoryReport::from(&b));
+    }
+}
@@ -0,0 +1,227 @@
+//! Scanning the process's own memory for plaintext that should be gone.
+//!
+//! Storage backends that zero their buffers on drop ([`SharedSensitiveString`],
+//! `LockedSensitiveString`, `EncryptedSensitiveString`'s scratch buffers) can
+//! regress silently: an extra `clone()` or a `String` that is moved instead of
+//! wiped leaves the plaintext in freed heap memory, and nothing fails. With
+//! the `memscan` feature, [`check_wiped`] hands a closure a freshly generated
+//! plaintext, lets it store and drop it, then reads every writable mapping
+//! of the process through `/proc/self/mem` and reports where the plaintext
+//! is still found.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::memscan::check_wiped;
+//! use sensitive_string::SharedSensitiveString;
+//!
+//! let report = check_wiped(|plaintext| drop(SharedSensitiveString::new(plaintext)));
+//! assert!(report.is_clean(), "{}", report);
+//! ```
+//!
+//! The closure should move the plaintext straight into the storage under
+//! test; any copy it makes itself will be found too. Plaintext is generated
+//! at run time and searched for by its tail, since allocators overwrite the
+//! first bytes of a freed block with their own bookkeeping. Linux only.
+//!
+//! [`SharedSensitiveString`]: crate::SharedSensitiveString
+
+use crate::wipe;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The length of each generated plaintext.
+const PLAINTEXT_LEN: usize = 64;
+
+/// Bytes at the start of the plaintext that are not searched for.
+const SKIPPED_PREFIX: usize = 24;
+
+/// How much of a mapping is read at a time.
+const CHUNK: usize = 1 << 20;
+
+static SEEDS: AtomicU64 = AtomicU64::new(0);
+
+/// Where plaintext was still found after a [`check_wiped`] closure returned.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanReport {
+    found_at: Vec<usize>,
+    bytes_scanned: usize,
+}
+
+impl ScanReport {
+    /// Returns true if the plaintext was found nowhere.
+    pub fn is_clean(&self) -> bool {
+        self.found_at.is_empty()
+    }
+
+    /// Returns the addresses at which the plaintext's tail was found.
+    pub fn found_at(&self) -> &[usize] {
+        &self.found_at
+    }
+
+    /// Returns how many bytes of memory were read.
+    pub fn bytes_scanned(&self) -> usize {
+        self.bytes_scanned
+    }
+}
+
+impl fmt::Display for ScanReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return write!(f, "plaintext not found in {} bytes", self.bytes_scanned);
+        }
+        write!(f, "plaintext found {} time(s) at", self.found_at.len())?;
+        for address in &self.found_at {
+            write!(f, " {:#x}", address)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs `f` with a fresh plaintext, then scans memory for any copy left behind.
+///
+/// # Panics
+///
+/// Panics if `/proc/self/maps` or `/proc/self/mem` cannot be read.
+pub fn check_wiped(f: impl FnOnce(&str)) -> ScanReport {
+    let seed = next_seed();
+
+    let mut plaintext = vec![0; PLAINTEXT_LEN];
+    generate(seed, &mut plaintext);
+    f(std::str::from_utf8(&plaintext).expect("generated as hex"));
+    wipe(&mut plaintext);
+    drop(plaintext);
+
+    let mut needle = vec![0; PLAINTEXT_LEN];
+    generate(seed, &mut needle);
+    let report =
+        scan(&needle[SKIPPED_PREFIX..], address_range(&needle)).expect("scanning /proc/self/mem");
+    wipe(&mut needle);
+    report
+}
+
+/// Returns a seed no other check in this process has used.
+fn next_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    let stack = &nanos as *const u64 as u64;
+    nanos ^ stack.rotate_left(32) ^ SEEDS.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Fills `out` with hex digits derived from `seed` (SplitMix64).
+///
+/// The plaintext is regenerated rather than kept, so that no copy of it
+/// exists in memory to be found by the scan except the one being searched for.
+fn generate(seed: u64, out: &mut [u8]) {
+    let mut state = seed;
+    for byte in out {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        *byte = b"0123456789abcdef"[(z & 0xf) as usize];
+    }
+}
+
+fn address_range(bytes: &[u8]) -> Range<usize> {
+    let start = bytes.as_ptr() as usize;
+    start..start + bytes.len()
+}
+
+/// Returns the readable, writable, private mappings listed in `/proc/self/maps`.
+fn writable_mappings() -> io::Result<Vec<Range<usize>>> {
+    let mut mappings = Vec::new();
+    for line in BufReader::new(File::open("/proc/self/maps")?).lines() {
+        let line = line?;
+        let mut fields = line.split_whitespace();
+        let (Some(range), Some(perms)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let name = fields.nth(3).unwrap_or("");
+        if !perms.starts_with("rw") || !perms.ends_with('p') || name.starts_with("[v") {
+            continue;
+        }
+        let Some((start, end)) = range.split_once('-') else {
+            continue;
+        };
+        if let (Ok(start), Ok(end)) = (
+            usize::from_str_radix(start, 16),
+            usize::from_str_radix(end, 16),
+        ) {
+            mappings.push(start..end);
+        }
+    }
+    Ok(mappings)
+}
+
+/// Searches every writable mapping for `needle`, ignoring matches inside `exclude` or the read buffer.
+fn scan(needle: &[u8], exclude: Range<usize>) -> io::Result<ScanReport> {
+    let mappings = writable_mappings()?;
+    let mut mem = File::open("/proc/self/mem")?;
+    let mut buf = vec![0; CHUNK + needle.len()];
+    let own = address_range(&buf);
+    let mut report = ScanReport::default();
+
+    for mapping in mappings {
+        let mut offset = mapping.start;
+        while offset < mapping.end {
+            let len = (mapping.end - offset).min(buf.len());
+            let read = mem
+                .seek(SeekFrom::Start(offset as u64))
+                .and_then(|_| mem.read(&mut buf[..len]));
+            let read = match read {
+                Ok(read) if read > 0 => read,
+                // Guard pages and mappings that changed since they were listed.
+                _ => break,
+            };
+            report.bytes_scanned += read;
+            for (at, window) in buf[..read].windows(needle.len()).enumerate() {
+                let address = offset + at;
+                if window == needle
+                    && !exclude.contains(&address)
+                    && !own.contains(&address)
+                    && !report.found_at.contains(&address)
+                {
+                    report.found_at.push(address);
+                }
+            }
+            if offset + read >= mapping.end {
+                break;
+            }
+            // Overlap chunks so a match across a boundary is still found.
+            offset += read.saturating_sub(needle.len() - 1).max(1);
+        }
+    }
+    wipe(&mut buf);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SharedSensitiveString;
+
+    #[test]
+    fn test_wiped_storage_is_clean() {
+        let report = check_wiped(|plaintext| {
+            let shared = SharedSensitiveString::new(plaintext);
+            drop(shared.clone());
+            drop(shared);
+        });
+        assert!(report.is_clean(), "{}", report);
+        assert!(report.bytes_scanned() > 0);
+    }
+
+    #[test]
+    fn test_finds_a_copy_left_behind() {
+        let mut kept = String::new();
+        let report = check_wiped(|plaintext| kept = plaintext.to_string());
+        assert_eq!(report.found_at(), [kept.as_ptr() as usize + SKIPPED_PREFIX]);
+    }
+}
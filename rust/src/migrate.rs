@@ -0,0 +1,525 @@
+//! Moving plaintext secrets out of existing config files.
+//!
+//! [`migrate_config`] is the core of a "secure my config" tool: it reads a
+//! JSON, YAML, or TOML document, replaces each plaintext secret with a
+//! [`SecretSource`](crate::source::SecretSource) table pointing at where the
+//! secret should live instead, and writes the result in any of the three
+//! formats. The rewritten file deserializes straight into `SecretSource`
+//! fields.
+//!
+//! Which fields count as secrets is decided by [`MigrationRules`]: string
+//! values under keys that look sensitive (see
+//! [`DEFAULT_SENSITIVE_KEYS`](crate::diff::DEFAULT_SENSITIVE_KEYS)) plus any
+//! explicitly listed paths. Where they go is decided by a [`SecretTarget`]:
+//!
+//! - [`Env`](SecretTarget::Env): `{ source = "env", name = "DATABASE_PASSWORD" }`
+//! - [`Provider`](SecretTarget::Provider): `{ source = "provider", name = "vault", key = "app/database.password" }`
+//! - [`Envelope`](SecretTarget::Envelope) (with the `envelope` feature):
+//!   `{ source = "envelope", blob = "ssenv1:..." }`, sealed in place
+//!
+//! For env and provider targets the plaintexts are returned in
+//! [`Migration::secrets`], keyed by variable name or provider key, so the
+//! tool can store them before the old file is replaced.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::migrate::{migrate_config, ConfigFormat, MigrationRules, SecretTarget};
+//! use sensitive_string::SensitiveString;
+//!
+//! let input = r#"
+//! [database]
+//! host = "db1"
+//! password = "hunter2"
+//! "#;
+//!
+//! let rules = MigrationRules::new(SecretTarget::env("APP_"));
+//! let migration = migrate_config(input, ConfigFormat::Toml, ConfigFormat::Yaml, &rules).unwrap();
+//!
+//! assert!(migration.document.contains("source: env"));
+//! assert!(migration.document.contains("name: APP_DATABASE_PASSWORD"));
+//! assert!(!migration.document.contains("hunter2"));
+//! assert_eq!(
+//!     migration.secrets.get("APP_DATABASE_PASSWORD").unwrap(),
+//!     &SensitiveString::from("hunter2")
+//! );
+//! ```
+//!
+//! Documents are transcoded through a `serde_json::Value`, so comments are
+//! dropped and object keys come out sorted. Parse errors report a line and
+//! column, never the offending text.
+
+use crate::diff::DEFAULT_SENSITIVE_KEYS;
+#[cfg(feature = "envelope")]
+use crate::envelope::{EnvelopeError, Keyring};
+use crate::{SensitiveMap, SensitiveString};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// A config file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// JSON.
+    Json,
+    /// YAML.
+    Yaml,
+    /// TOML.
+    Toml,
+}
+
+impl ConfigFormat {
+    /// Guesses the format from a file extension (`json`, `yaml`, `yml`, `toml`).
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(ConfigFormat::Json),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            "toml" => Some(ConfigFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigFormat::Json => write!(f, "JSON"),
+            ConfigFormat::Yaml => write!(f, "YAML"),
+            ConfigFormat::Toml => write!(f, "TOML"),
+        }
+    }
+}
+
+/// Where migrated secrets are moved to.
+#[derive(Debug, Clone)]
+pub enum SecretTarget {
+    /// Environment variables named after the field path, e.g. `DATABASE_PASSWORD`.
+    Env {
+        /// Prepended to every variable name, e.g. `APP_`.
+        prefix: String,
+    },
+    /// Keys in a named provider, built from the field path, e.g. `database.password`.
+    Provider {
+        /// The provider name the config will resolve through.
+        name: String,
+        /// Prepended to every key, e.g. `kv/app/`.
+        key_prefix: String,
+    },
+    /// Envelopes sealed for `key_id`, kept in the config itself.
+    #[cfg(feature = "envelope")]
+    Envelope {
+        /// The recipient key id.
+        key_id: String,
+        /// A keyring holding `key_id`.
+        keyring: Keyring,
+    },
+}
+
+impl SecretTarget {
+    /// Moves secrets to environment variables whose names start with `prefix`.
+    pub fn env(prefix: impl Into<String>) -> Self {
+        SecretTarget::Env {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// Moves secrets to provider `name`, under keys starting with `key_prefix`.
+    pub fn provider(name: impl Into<String>, key_prefix: impl Into<String>) -> Self {
+        SecretTarget::Provider {
+            name: name.into(),
+            key_prefix: key_prefix.into(),
+        }
+    }
+
+    /// Seals secrets in place for `key_id`.
+    #[cfg(feature = "envelope")]
+    pub fn envelope(key_id: impl Into<String>, keyring: Keyring) -> Self {
+        SecretTarget::Envelope {
+            key_id: key_id.into(),
+            keyring,
+        }
+    }
+}
+
+/// Rules deciding which fields are migrated, and where to.
+#[derive(Debug, Clone)]
+pub struct MigrationRules {
+    target: SecretTarget,
+    sensitive_keys: Vec<String>,
+    paths: Vec<String>,
+}
+
+impl MigrationRules {
+    /// Migrates string values of keys containing any of `DEFAULT_SENSITIVE_KEYS`.
+    pub fn new(target: SecretTarget) -> Self {
+        Self {
+            target,
+            sensitive_keys: DEFAULT_SENSITIVE_KEYS
+                .iter()
+                .map(|key| key.to_string())
+                .collect(),
+            paths: Vec::new(),
+        }
+    }
+
+    /// Migrates nothing until keys or paths are added.
+    pub fn empty(target: SecretTarget) -> Self {
+        Self {
+            target,
+            sensitive_keys: Vec::new(),
+            paths: Vec::new(),
+        }
+    }
+
+    /// Also migrates values of any key whose name contains `fragment` (case-insensitive).
+    pub fn sensitive_key(mut self, fragment: &str) -> Self {
+        self.sensitive_keys.push(fragment.to_ascii_lowercase());
+        self
+    }
+
+    /// Also migrates the value at a dotted path, e.g. `database.url` or `servers.0.dsn`.
+    pub fn path(mut self, path: &str) -> Self {
+        self.paths.push(path.to_string());
+        self
+    }
+
+    fn applies_to(&self, path: &[String]) -> bool {
+        let dotted = path.join(".");
+        if self.paths.contains(&dotted) {
+            return true;
+        }
+        let key = match path.last() {
+            Some(key) => key.to_ascii_lowercase(),
+            None => return false,
+        };
+        self.sensitive_keys
+            .iter()
+            .any(|fragment| key.contains(fragment.as_str()))
+    }
+}
+
+/// The result of [`migrate_config`].
+#[derive(Debug)]
+pub struct Migration {
+    /// The rewritten document.
+    pub document: String,
+    /// Plaintexts to store, keyed by environment variable name or provider key.
+    ///
+    /// Empty for envelope targets, whose ciphertext is in the document.
+    pub secrets: SensitiveMap,
+    /// Dotted paths of the migrated fields, in document order.
+    pub migrated: Vec<String>,
+}
+
+/// Error returned by [`migrate_config`].
+#[derive(Debug)]
+pub enum MigrateError {
+    /// The input is not valid in its format.
+    Parse {
+        /// The input format.
+        format: ConfigFormat,
+        /// 1-based line and column, when the parser reports one.
+        location: Option<(usize, usize)>,
+    },
+    /// The migrated document cannot be written in the output format,
+    /// e.g. a `null` in TOML.
+    Serialize {
+        /// The output format.
+        format: ConfigFormat,
+        /// The serializer's message.
+        message: String,
+    },
+    /// Two fields map to the same variable name or provider key.
+    DuplicateReference(String),
+    /// Sealing a field failed.
+    #[cfg(feature = "envelope")]
+    Seal {
+        /// The dotted path of the field.
+        path: String,
+        /// The underlying error.
+        source: EnvelopeError,
+    },
+}
+
+impl fmt::Display for MigrateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrateError::Parse {
+                format,
+                location: Some((line, column)),
+            } => write!(f, "invalid {} at line {}, column {}", format, line, column),
+            MigrateError::Parse {
+                format,
+                location: None,
+            } => write!(f, "invalid {}", format),
+            MigrateError::Serialize { format, message } => {
+                write!(f, "cannot write {}: {}", format, message)
+            }
+            MigrateError::DuplicateReference(reference) => {
+                write!(f, "more than one field maps to {}", reference)
+            }
+            #[cfg(feature = "envelope")]
+            MigrateError::Seal { path, source } => {
+                write!(f, "cannot seal {}: {}", path, source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "envelope")]
+            MigrateError::Seal { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Transcodes a config document from `input_format` to `output_format`,
+/// replacing plaintext secrets with secret source references per `rules`.
+pub fn migrate_config(
+    input: &str,
+    input_format: ConfigFormat,
+    output_format: ConfigFormat,
+    rules: &MigrationRules,
+) -> Result<Migration, MigrateError> {
+    let mut document = parse(input, input_format)?;
+    let mut migration = Migration {
+        document: String::new(),
+        secrets: SensitiveMap::new(),
+        migrated: Vec::new(),
+    };
+    walk(&mut document, &mut Vec::new(), rules, &mut migration)?;
+    migration.document = write(&document, output_format)?;
+    Ok(migration)
+}
+
+fn parse(input: &str, format: ConfigFormat) -> Result<Value, MigrateError> {
+    let location = match format {
+        ConfigFormat::Json => match serde_json::from_str(input) {
+            Ok(document) => return Ok(document),
+            Err(error) => Some((error.line(), error.column())),
+        },
+        ConfigFormat::Yaml => match serde_yaml::from_str(input) {
+            Ok(document) => return Ok(document),
+            Err(error) => error
+                .location()
+                .map(|location| (location.line(), location.column())),
+        },
+        ConfigFormat::Toml => match toml::from_str(input) {
+            Ok(document) => return Ok(document),
+            Err(error) => error.span().map(|span| line_and_column(input, span.start)),
+        },
+    };
+    Err(MigrateError::Parse { format, location })
+}
+
+fn write(document: &Value, format: ConfigFormat) -> Result<String, MigrateError> {
+    let result = match format {
+        ConfigFormat::Json => serde_json::to_string_pretty(document).map_err(|e| e.to_string()),
+        ConfigFormat::Yaml => serde_yaml::to_string(document).map_err(|e| e.to_string()),
+        ConfigFormat::Toml => toml::to_string_pretty(document).map_err(|e| e.to_string()),
+    };
+    result.map_err(|message| MigrateError::Serialize { format, message })
+}
+
+/// Converts a byte offset into a 1-based line and column.
+fn line_and_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset.min(input.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+fn walk(
+    value: &mut Value,
+    path: &mut Vec<String>,
+    rules: &MigrationRules,
+    migration: &mut Migration,
+) -> Result<(), MigrateError> {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                path.push(key.clone());
+                walk(field, path, rules, migration)?;
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                path.push(index.to_string());
+                walk(item, path, rules, migration)?;
+                path.pop();
+            }
+        }
+        Value::String(text) if !text.is_empty() && rules.applies_to(path) => {
+            let secret = SensitiveString::new(std::mem::take(text));
+            *value = reference(secret, path, &rules.target, migration)?;
+            migration.migrated.push(path.join("."));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Builds the `SecretSource` table replacing `secret`.
+fn reference(
+    secret: SensitiveString,
+    path: &[String],
+    target: &SecretTarget,
+    migration: &mut Migration,
+) -> Result<Value, MigrateError> {
+    let mut table = Map::new();
+    match target {
+        SecretTarget::Env { prefix } => {
+            let name = format!("{}{}", prefix, env_name(path));
+            store(&mut migration.secrets, &name, secret)?;
+            table.insert("source".into(), "env".into());
+            table.insert("name".into(), name.into());
+        }
+        SecretTarget::Provider { name, key_prefix } => {
+            let key = format!("{}{}", key_prefix, path.join("."));
+            store(&mut migration.secrets, &key, secret)?;
+            table.insert("source".into(), "provider".into());
+            table.insert("name".into(), name.as_str().into());
+            table.insert("key".into(), key.into());
+        }
+        #[cfg(feature = "envelope")]
+        SecretTarget::Envelope { key_id, keyring } => {
+            let blob = secret
+                .seal_for(key_id, keyring)
+                .map_err(|source| MigrateError::Seal {
+                    path: path.join("."),
+                    source,
+                })?;
+            table.insert("source".into(), "envelope".into());
+            table.insert("blob".into(), blob.into());
+        }
+    }
+    Ok(Value::Object(table))
+}
+
+fn store(
+    secrets: &mut SensitiveMap,
+    reference: &str,
+    secret: SensitiveString,
+) -> Result<(), MigrateError> {
+    if secrets.contains_key(reference) {
+        return Err(MigrateError::DuplicateReference(reference.to_string()));
+    }
+    secrets.insert(reference, secret);
+    Ok(())
+}
+
+/// Turns `["database", "primary-password"]` into `DATABASE_PRIMARY_PASSWORD`.
+fn env_name(path: &[String]) -> String {
+    path.iter()
+        .map(|segment| {
+            segment
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::SecretSource;
+
+    #[test]
+    fn test_migrated_json_reads_back_as_secret_sources() {
+        #[derive(serde::Deserialize)]
+        struct Config {
+            host: String,
+            api_token: SecretSource,
+            upstreams: Vec<Upstream>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Upstream {
+            dsn: SecretSource,
+        }
+
+        let input = r#"{"host":"db1","api_token":"tok-1","upstreams":[{"dsn":"pg://a"}]}"#;
+        let rules =
+            MigrationRules::new(SecretTarget::provider("vault", "kv/app/")).path("upstreams.0.dsn");
+        let migration =
+            migrate_config(input, ConfigFormat::Json, ConfigFormat::Toml, &rules).unwrap();
+        assert_eq!(migration.migrated, ["api_token", "upstreams.0.dsn"]);
+        assert!(!migration.document.contains("tok-1"));
+
+        let config: Config = toml::from_str(&migration.document).unwrap();
+        assert_eq!(config.host, "db1");
+        assert_eq!(
+            config.api_token,
+            SecretSource::Provider {
+                name: "vault".into(),
+                key: "kv/app/api_token".into()
+            }
+        );
+        assert_eq!(
+            config.upstreams[0].dsn.to_string(),
+            "provider:vault:kv/app/upstreams.0.dsn"
+        );
+        assert_eq!(
+            migration.secrets.get("kv/app/upstreams.0.dsn"),
+            Some(&SensitiveString::from("pg://a"))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_report_location_only() {
+        let rules = MigrationRules::new(SecretTarget::env(""));
+        let input = "host = \"db1\"\npassword = hunter2\n";
+        let error =
+            migrate_config(input, ConfigFormat::Toml, ConfigFormat::Json, &rules).unwrap_err();
+        assert!(matches!(
+            error,
+            MigrateError::Parse {
+                format: ConfigFormat::Toml,
+                location: Some((2, _))
+            }
+        ));
+        assert!(!error.to_string().contains("hunter2"));
+
+        let input = "{\"a-b\":{\"token\":\"x\"},\"a_b\":{\"token\":\"y\"}}";
+        assert!(matches!(
+            migrate_config(input, ConfigFormat::Json, ConfigFormat::Json, &rules),
+            Err(MigrateError::DuplicateReference(name)) if name == "A_B_TOKEN"
+        ));
+    }
+
+    #[cfg(feature = "envelope")]
+    #[test]
+    fn test_envelope_target_seals_in_place() {
+        let mut keyring = Keyring::new();
+        keyring
+            .insert("k1", crate::SensitiveBytes::new(vec![7u8; 32]))
+            .unwrap();
+        let rules = MigrationRules::new(SecretTarget::envelope("k1", keyring.clone()));
+        let migration = migrate_config(
+            "password: hunter2\n",
+            ConfigFormat::Yaml,
+            ConfigFormat::Json,
+            &rules,
+        )
+        .unwrap();
+        assert!(migration.secrets.is_empty());
+
+        let document: Value = serde_json::from_str(&migration.document).unwrap();
+        let blob = document["password"]["blob"].as_str().unwrap();
+        assert_eq!(
+            SensitiveString::open_with(blob, &keyring).unwrap(),
+            SensitiveString::from("hunter2")
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Ordering and hashing by digest, for secrets used as map keys.
+//!
+//! With the `digest-ord` feature, `SensitiveString` implements `Ord` so it
+//! can key a `BTreeMap` or be sorted, without the order revealing anything
+//! about the plaintexts: secrets compare by the SHA-256 of their plaintext,
+//! so `"a" < "b"` is no more likely than not. `Hash` feeds the same digest
+//! to the hasher instead of the plaintext.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::SensitiveString;
+//! use std::collections::BTreeMap;
+//!
+//! let mut owners = BTreeMap::new();
+//! owners.insert(SensitiveString::from("sk-live-2"), "billing");
+//! owners.insert(SensitiveString::from("sk-live-1"), "search");
+//!
+//! assert_eq!(owners[&SensitiveString::from("sk-live-1")], "search");
+//! ```
+//!
+//! # Semantics
+//!
+//! - The order is that of the raw SHA-256 bytes, regardless of any attached
+//!   or global [`Hasher`](crate::hasher::Hasher) or redaction policy, so it
+//!   is the same in every process and build and sorted output is
+//!   deterministic.
+//! - It agrees with `Eq`, which compares plaintexts: equal secrets have equal
+//!   digests, and in the astronomically unlikely case of two different
+//!   plaintexts with the same digest, they are ordered by plaintext.
+//! - Each comparison hashes both plaintexts. To sort a long list, prefer
+//!   `sort_by_cached_key(SensitiveString::ordering_key)`.
+
+use crate::SensitiveString;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+impl SensitiveString {
+    /// Returns the SHA-256 of the plaintext, the key `Ord` and `Hash` use.
+    pub fn ordering_key(&self) -> [u8; 32] {
+        Sha256::digest(self.value.as_bytes()).into()
+    }
+}
+
+/// Orders by the SHA-256 of the plaintext; see [the module docs](self).
+impl Ord for SensitiveString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.ordering_key()
+            .cmp(&other.ordering_key())
+            .then_with(|| self.value.cmp(&other.value))
+    }
+}
+
+impl PartialOrd for SensitiveString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Hashes the SHA-256 of the plaintext rather than the plaintext itself.
+impl Hash for SensitiveString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ordering_key().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    #[test]
+    fn test_orders_by_digest_not_plaintext() {
+        let mut secrets: Vec<SensitiveString> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(SensitiveString::from)
+            .collect();
+        secrets.sort();
+        let mut digests: Vec<_> = ["a", "b", "c", "d"]
+            .into_iter()
+            .map(|value| Sha256::digest(value.as_bytes()))
+            .collect();
+        digests.sort();
+        let sorted: Vec<_> = secrets.iter().map(SensitiveString::ordering_key).collect();
+        assert!(sorted.iter().zip(&digests).all(|(a, b)| a[..] == b[..]));
+        // "a" hashes above "b", "c", and "d".
+        assert_eq!(secrets.last(), Some(&SensitiveString::from("a")));
+    }
+
+    #[test]
+    fn test_hash_agrees_with_eq() {
+        let hash = |secret: &SensitiveString| {
+            let mut state = DefaultHasher::new();
+            secret.hash(&mut state);
+            state.finish()
+        };
+        let a = SensitiveString::from("hunter2");
+        let b = SensitiveString::from("hunter2").with_label("copy");
+        assert_eq!(a, b);
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        assert_eq!(hash(&a), hash(&b));
+    }
+}
@@ -0,0 +1,236 @@
+//! Secrets and sensitive locations held as platform strings.
+//!
+//! Environment variables and file names are `OsString`s, which need not be
+//! valid Unicode, and on Windows a credential read from the environment may
+//! not convert to a `String` at all. [`SensitiveOsString`] wraps an
+//! `OsString` with the redaction of `SensitiveString`, and
+//! [`SensitivePathBuf`] does the same for paths whose location is itself
+//! sensitive, such as per-tenant key files.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{SensitiveOsString, SensitivePathBuf, SensitiveString};
+//!
+//! std::env::set_var("EXAMPLE_OS_TOKEN", "tok-123");
+//! let token = SensitiveOsString::from_env("EXAMPLE_OS_TOKEN").unwrap();
+//! assert_eq!(token.to_string(), SensitiveString::from("tok-123").to_string());
+//! assert_eq!(token.into_sensitive_string().unwrap(), SensitiveString::from("tok-123"));
+//!
+//! let key_file = SensitivePathBuf::from("/srv/tenants/acme/signing.key");
+//! assert!(!format!("{:?}", key_file).contains("acme"));
+//! ```
+//!
+//! Values that are valid Unicode are shown exactly as the equivalent
+//! `SensitiveString` with default settings. Others are shown as a hash of
+//! their platform encoding, or the placeholder when the process-wide policy
+//! is not [`Hash`](RedactionPolicy::Hash).
+
+use crate::policy::{default_policy, RedactionPolicy, PLACEHOLDER};
+use crate::{redact_bytes, redact_plaintext, SensitiveString};
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Redacts a platform string like a `SensitiveString` where possible.
+fn redact_os_str(value: &OsStr) -> String {
+    if let Some(text) = value.to_str() {
+        return redact_plaintext(text);
+    }
+    match default_policy() {
+        RedactionPolicy::Hash => redact_bytes(value.as_encoded_bytes()),
+        RedactionPolicy::Placeholder | RedactionPolicy::MaskLast(_) => PLACEHOLDER.to_string(),
+    }
+}
+
+/// A wrapper for `OsString` secrets that prevents accidental exposure.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SensitiveOsString {
+    pub(crate) value: OsString,
+}
+
+impl SensitiveOsString {
+    /// Creates a new `SensitiveOsString` from the given value.
+    pub fn new(value: OsString) -> Self {
+        Self { value }
+    }
+
+    /// Explicitly retrieves the plaintext.
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
+    pub fn get_value(&self) -> &OsStr {
+        crate::record_plaintext_read(None, "get_value");
+        &self.value
+    }
+
+    /// Returns the length of the platform encoding without exposing the value.
+    pub fn len(&self) -> usize {
+        self.value.len()
+    }
+
+    /// Returns true if the value is empty.
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// Converts to a `SensitiveString`, or returns `self` if the value is not valid Unicode.
+    pub fn into_sensitive_string(self) -> Result<SensitiveString, Self> {
+        self.value
+            .into_string()
+            .map(SensitiveString::new)
+            .map_err(Self::new)
+    }
+}
+
+impl fmt::Display for SensitiveOsString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&redact_os_str(&self.value))
+    }
+}
+
+impl fmt::Debug for SensitiveOsString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SensitiveOsString({})", self)
+    }
+}
+
+impl From<OsString> for SensitiveOsString {
+    fn from(value: OsString) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&OsStr> for SensitiveOsString {
+    fn from(value: &OsStr) -> Self {
+        Self::new(value.to_os_string())
+    }
+}
+
+impl From<SensitiveString> for SensitiveOsString {
+    fn from(secret: SensitiveString) -> Self {
+        Self::new(OsString::from(secret.value))
+    }
+}
+
+impl TryFrom<SensitiveOsString> for SensitiveString {
+    type Error = SensitiveOsString;
+
+    fn try_from(value: SensitiveOsString) -> Result<Self, SensitiveOsString> {
+        value.into_sensitive_string()
+    }
+}
+
+/// A path whose location is sensitive, e.g. a per-tenant key file.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SensitivePathBuf {
+    value: PathBuf,
+}
+
+impl SensitivePathBuf {
+    /// Creates a new `SensitivePathBuf` from the given path.
+    pub fn new(value: PathBuf) -> Self {
+        Self { value }
+    }
+
+    /// Explicitly retrieves the path.
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
+    pub fn get_value(&self) -> &Path {
+        crate::record_plaintext_read(None, "get_value");
+        &self.value
+    }
+
+    /// Returns a copy with `component` appended, like [`Path::join`].
+    pub fn join(&self, component: impl AsRef<Path>) -> Self {
+        Self::new(self.value.join(component))
+    }
+}
+
+impl fmt::Display for SensitivePathBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&redact_os_str(self.value.as_os_str()))
+    }
+}
+
+impl fmt::Debug for SensitivePathBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SensitivePathBuf({})", self)
+    }
+}
+
+impl From<PathBuf> for SensitivePathBuf {
+    fn from(value: PathBuf) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&Path> for SensitivePathBuf {
+    fn from(value: &Path) -> Self {
+        Self::new(value.to_path_buf())
+    }
+}
+
+impl From<&str> for SensitivePathBuf {
+    fn from(value: &str) -> Self {
+        Self::new(PathBuf::from(value))
+    }
+}
+
+impl From<SensitiveOsString> for SensitivePathBuf {
+    fn from(value: SensitiveOsString) -> Self {
+        Self::new(PathBuf::from(value.value))
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::{SensitiveOsString, SensitivePathBuf};
+    use serde::{Serialize, Serializer};
+
+    impl Serialize for SensitiveOsString {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl Serialize for SensitivePathBuf {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions_are_lossless() {
+        let secret = SensitiveString::from("hunter2");
+        let os = SensitiveOsString::from(secret.clone());
+        assert_eq!(os.len(), 7);
+        assert_eq!(os.to_string(), secret.to_string());
+        assert_eq!(SensitiveString::try_from(os).unwrap(), secret);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_unicode_is_kept() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let os = SensitiveOsString::from(OsString::from_vec(b"pass\xffword".to_vec()));
+        assert!(!format!("{:?}", os).contains("pass"));
+        assert!(SensitiveString::looks_redacted(&os.to_string()));
+        let os = os.into_sensitive_string().unwrap_err();
+        assert_eq!(os.len(), 9);
+    }
+
+    #[cfg(all(feature = "access-policy", not(feature = "no-plaintext-getters")))]
+    #[test]
+    fn test_get_value_is_checked_against_access_policies() {
+        let os = SensitiveOsString::from(OsStr::new("hunter2"));
+        crate::access::assert_denied(|| os.get_value().len());
+        let path = SensitivePathBuf::from("/srv/tenant-a/key.pem");
+        crate::access::assert_denied(|| path.get_value().exists());
+    }
+}
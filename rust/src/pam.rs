@@ -0,0 +1,211 @@
+//! PAM conversation helpers.
+//!
+//! A PAM conversation function answers prompts by returning an array of
+//! `struct pam_response` allocated with `malloc`, each holding a
+//! `malloc`-allocated, NUL-terminated string. PAM takes ownership of that
+//! memory and frees it. These helpers build that array directly from
+//! `SensitiveString`s, and free it with zeroization when the conversation
+//! fails and the application is responsible for cleanup.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::pam::{self, PamResponse};
+//! use sensitive_string::SensitiveString;
+//!
+//! let password = SensitiveString::from("hunter2");
+//! let responses: *mut PamResponse = pam::alloc_responses(&[Some(&password)]).unwrap();
+//!
+//! // Normally `responses` is handed to PAM through the conversation callback.
+//! // If the conversation is aborted instead, release it ourselves:
+//! unsafe { pam::free_responses(responses, 1) };
+//! ```
+
+use crate::SensitiveString;
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+/// Mirror of `struct pam_response` from `<security/pam_appl.h>`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PamResponse {
+    /// The response text, allocated with `malloc`, or null.
+    pub resp: *mut c_char,
+    /// Unused by Linux-PAM and OpenPAM; always zero.
+    pub resp_retcode: c_int,
+}
+
+/// Errors from building PAM response buffers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PamBufferError {
+    /// The secret contains an interior NUL byte and cannot be a C string.
+    InteriorNul,
+    /// `malloc`/`calloc` returned null.
+    AllocationFailed,
+}
+
+impl fmt::Display for PamBufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PamBufferError::InteriorNul => write!(f, "secret contains an interior NUL byte"),
+            PamBufferError::AllocationFailed => write!(f, "failed to allocate PAM response"),
+        }
+    }
+}
+
+impl std::error::Error for PamBufferError {}
+
+impl SensitiveString {
+    /// Copies the plaintext into a `malloc`-allocated, NUL-terminated buffer.
+    ///
+    /// Ownership of the buffer passes to the caller, normally to be placed in
+    /// a [`PamResponse`] and handed to PAM, which frees it. Release it with
+    /// [`free_response_text`] if it is never handed over.
+    #[track_caller]
+    pub fn to_pam_response_text(&self) -> Result<*mut c_char, PamBufferError> {
+        self.record_access("to_pam_response_text");
+        response_text(self)
+    }
+}
+
+fn response_text(secret: &SensitiveString) -> Result<*mut c_char, PamBufferError> {
+    let bytes = secret.value.as_bytes();
+    if bytes.contains(&0) {
+        return Err(PamBufferError::InteriorNul);
+    }
+
+    // SAFETY: we allocate len + 1 bytes and write exactly that many.
+    unsafe {
+        let buf = libc::malloc(bytes.len() + 1) as *mut u8;
+        if buf.is_null() {
+            return Err(PamBufferError::AllocationFailed);
+        }
+        ptr::copy_nonoverlapping(bytes.as_ptr(), buf, bytes.len());
+        *buf.add(bytes.len()) = 0;
+        Ok(buf as *mut c_char)
+    }
+}
+
+/// Builds a `calloc`-allocated response array for a PAM conversation.
+///
+/// Each entry answers the prompt at the same index; `None` leaves the
+/// response text null, as PAM expects for `PAM_TEXT_INFO` and
+/// `PAM_ERROR_MSG` messages.
+///
+/// Every answer is checked against access policies before anything is
+/// allocated, so a denied read cannot leave copies behind.
+#[track_caller]
+pub fn alloc_responses(
+    answers: &[Option<&SensitiveString>],
+) -> Result<*mut PamResponse, PamBufferError> {
+    for secret in answers.iter().flatten() {
+        secret.record_access("alloc_responses");
+    }
+    // SAFETY: calloc zero-initializes, which is a valid PamResponse (null, 0).
+    let responses = unsafe {
+        libc::calloc(answers.len().max(1), std::mem::size_of::<PamResponse>()) as *mut PamResponse
+    };
+    if responses.is_null() {
+        return Err(PamBufferError::AllocationFailed);
+    }
+
+    for (index, answer) in answers.iter().enumerate() {
+        let Some(secret) = answer else { continue };
+        match response_text(secret) {
+            // SAFETY: index is within the answers.len() entries allocated above.
+            Ok(text) => unsafe { (*responses.add(index)).resp = text },
+            Err(err) => {
+                // SAFETY: the array and any texts written so far were allocated above.
+                unsafe { free_responses(responses, answers.len()) };
+                return Err(err);
+            }
+        }
+    }
+    Ok(responses)
+}
+
+/// Zeroizes and frees a response text allocated by [`SensitiveString::to_pam_response_text`].
+///
+/// # Safety
+///
+/// `text` must be null or a NUL-terminated buffer allocated with `malloc`
+/// that is not used afterwards.
+pub unsafe fn free_response_text(text: *mut c_char) {
+    if text.is_null() {
+        return;
+    }
+    let len = libc::strlen(text);
+    for offset in 0..len {
+        ptr::write_volatile(text.add(offset), 0);
+    }
+    libc::free(text as *mut libc::c_void);
+}
+
+/// Zeroizes and frees a response array and all of its texts.
+///
+/// Only call this when the array was never handed to PAM.
+///
+/// # Safety
+///
+/// `responses` must be null or an array of `count` entries allocated with
+/// `malloc`/`calloc` (such as one from [`alloc_responses`]) that is not used
+/// afterwards.
+pub unsafe fn free_responses(responses: *mut PamResponse, count: usize) {
+    if responses.is_null() {
+        return;
+    }
+    for index in 0..count {
+        free_response_text((*responses.add(index)).resp);
+    }
+    libc::free(responses as *mut libc::c_void);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_response_text_is_nul_terminated_copy() {
+        let secret = SensitiveString::from("pam-secret");
+        let text = secret.to_pam_response_text().unwrap();
+        unsafe {
+            assert_eq!(CStr::from_ptr(text).to_bytes(), b"pam-secret");
+            free_response_text(text);
+        }
+    }
+
+    #[test]
+    fn test_alloc_responses_leaves_none_null() {
+        let secret = SensitiveString::from("pam-secret");
+        let responses = alloc_responses(&[None, Some(&secret)]).unwrap();
+        unsafe {
+            assert!((*responses).resp.is_null());
+            assert_eq!(
+                CStr::from_ptr((*responses.add(1)).resp).to_bytes(),
+                b"pam-secret"
+            );
+            assert_eq!((*responses.add(1)).resp_retcode, 0);
+            free_responses(responses, 2);
+        }
+    }
+
+    #[test]
+    fn test_interior_nul_rejected() {
+        let ok = SensitiveString::from("fine");
+        let bad = SensitiveString::from("bad\0secret");
+        assert_eq!(
+            alloc_responses(&[Some(&ok), Some(&bad)]).unwrap_err(),
+            PamBufferError::InteriorNul
+        );
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_copies_are_checked_against_access_policies() {
+        let secret = SensitiveString::from("pam-secret");
+        crate::access::assert_denied(|| secret.to_pam_response_text());
+        crate::access::assert_denied(|| alloc_responses(&[None, Some(&secret)]));
+    }
+}
@@ -0,0 +1,317 @@
+//! Personal data whose structure is still useful for debugging.
+//!
+//! Email addresses and phone numbers identify people, but the domain of an
+//! address or the country code of a number is often exactly what a bug
+//! report needs. [`SensitiveEmail`] and [`SensitivePhone`] keep those parts
+//! readable in `Display`, `Debug`, and `Serialize`, and show the identifying
+//! part (the local part of an address, the subscriber number of a phone
+//! number) the way a [`SensitiveString`] is shown, so redaction policies,
+//! hashers, and the marker apply to it unchanged.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::pii::{SensitiveEmail, SensitivePhone};
+//! use sensitive_string::SensitiveString;
+//!
+//! let email: SensitiveEmail = "Jane.Doe@Example.com".parse()?;
+//! assert_eq!(email.domain(), "example.com");
+//! assert_eq!(email.to_string(), format!("{}@example.com", SensitiveString::from("Jane.Doe")));
+//!
+//! let phone: SensitivePhone = "+44 20 7946 0958".parse()?;
+//! assert_eq!(phone.country_code(), "44");
+//! assert_eq!(phone.to_string(), format!("+44 {}", SensitiveString::from("2079460958")));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! Domains are lowercased, and phone numbers are kept as their E.164 digits
+//! only, so equal values also redact to the same string.
+
+use crate::policy::RedactionPolicy;
+use crate::SensitiveString;
+use std::fmt;
+
+/// An email address whose local part is redacted and domain is shown.
+///
+/// Equality compares the local part exactly and the domain ignoring case.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SensitiveEmail {
+    local: SensitiveString,
+    domain: String,
+}
+
+/// Error returned when a string is not an email address.
+///
+/// The message never includes the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidEmail;
+
+impl fmt::Display for InvalidEmail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected an email address of the form local@domain")
+    }
+}
+
+impl std::error::Error for InvalidEmail {}
+
+impl SensitiveEmail {
+    /// Parses `address`, splitting it at its last `@`.
+    pub fn parse(address: &str) -> Result<Self, InvalidEmail> {
+        let (local, domain) = address.trim().rsplit_once('@').ok_or(InvalidEmail)?;
+        if local.is_empty()
+            || domain.is_empty()
+            || domain.starts_with('.')
+            || domain.ends_with('.')
+            || domain.chars().any(|c| c.is_whitespace() || c == '@')
+        {
+            return Err(InvalidEmail);
+        }
+        Ok(Self {
+            local: SensitiveString::from(local),
+            domain: domain.to_lowercase(),
+        })
+    }
+
+    /// Sets how the local part is shown, overriding the process-wide default.
+    pub fn with_policy(mut self, policy: RedactionPolicy) -> Self {
+        self.local = self.local.with_policy(policy);
+        self
+    }
+
+    /// Returns the lowercased domain, e.g. `example.com`.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Returns the part before the `@`.
+    pub fn local_part(&self) -> &SensitiveString {
+        &self.local
+    }
+
+    /// Returns the whole address as a `SensitiveString`.
+    pub fn to_sensitive_string(&self) -> SensitiveString {
+        SensitiveString::unregistered(format!("{}@{}", self.local.value, self.domain))
+    }
+}
+
+impl std::str::FromStr for SensitiveEmail {
+    type Err = InvalidEmail;
+
+    fn from_str(address: &str) -> Result<Self, InvalidEmail> {
+        Self::parse(address)
+    }
+}
+
+impl fmt::Display for SensitiveEmail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.local, self.domain)
+    }
+}
+
+impl fmt::Debug for SensitiveEmail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SensitiveEmail({})", self)
+    }
+}
+
+/// A phone number whose country code is shown and subscriber number redacted.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SensitivePhone {
+    country_code: String,
+    subscriber: SensitiveString,
+}
+
+/// Error returned when a string is not an international phone number.
+///
+/// The message never includes the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidPhone;
+
+impl fmt::Display for InvalidPhone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected an international phone number: '+', then 8 to 15 digits"
+        )
+    }
+}
+
+impl std::error::Error for InvalidPhone {}
+
+impl SensitivePhone {
+    /// Parses a number in international format, e.g. `+1 (555) 010-0199`.
+    ///
+    /// Spaces, dashes, dots, and parentheses are ignored. Numbers without a
+    /// leading `+` are rejected, since their country cannot be known.
+    pub fn parse(number: &str) -> Result<Self, InvalidPhone> {
+        let rest = number.trim().strip_prefix('+').ok_or(InvalidPhone)?;
+        let mut digits = String::with_capacity(rest.len());
+        for c in rest.chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                ' ' | '-' | '.' | '(' | ')' => {}
+                _ => return Err(InvalidPhone),
+            }
+        }
+        if !(8..=15).contains(&digits.len()) {
+            return Err(InvalidPhone);
+        }
+        let split = country_code_len(&digits);
+        Ok(Self {
+            country_code: digits[..split].to_string(),
+            subscriber: SensitiveString::from(&digits[split..]),
+        })
+    }
+
+    /// Sets how the subscriber number is shown, overriding the process-wide default.
+    pub fn with_policy(mut self, policy: RedactionPolicy) -> Self {
+        self.subscriber = self.subscriber.with_policy(policy);
+        self
+    }
+
+    /// Returns the country calling code without the `+`, e.g. `44`.
+    pub fn country_code(&self) -> &str {
+        &self.country_code
+    }
+
+    /// Returns the digits after the country code.
+    pub fn subscriber_number(&self) -> &SensitiveString {
+        &self.subscriber
+    }
+
+    /// Returns the number in E.164 form, e.g. `+442079460958`.
+    pub fn to_sensitive_string(&self) -> SensitiveString {
+        SensitiveString::unregistered(format!("+{}{}", self.country_code, self.subscriber.value))
+    }
+}
+
+/// Returns the length of the E.164 country calling code at the start of `digits`.
+///
+/// Codes are prefix-free: `1` and `7` are one digit, the zones below list
+/// their two-digit codes, and every other code is three digits.
+fn country_code_len(digits: &str) -> usize {
+    let two_digit: &[&str] = match &digits[..1] {
+        "1" | "7" => return 1,
+        "2" => &["20", "27"],
+        "3" => &["30", "31", "32", "33", "34", "36", "39"],
+        "4" => &["40", "41", "43", "44", "45", "46", "47", "48", "49"],
+        "5" => &["51", "52", "53", "54", "55", "56", "57", "58"],
+        "6" => &["60", "61", "62", "63", "64", "65", "66"],
+        "8" => &["81", "82", "84", "86"],
+        "9" => &["90", "91", "92", "93", "94", "95", "98"],
+        _ => &[],
+    };
+    if two_digit.contains(&&digits[..2]) {
+        2
+    } else {
+        3
+    }
+}
+
+impl std::str::FromStr for SensitivePhone {
+    type Err = InvalidPhone;
+
+    fn from_str(number: &str) -> Result<Self, InvalidPhone> {
+        Self::parse(number)
+    }
+}
+
+impl fmt::Display for SensitivePhone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "+{} {}", self.country_code, self.subscriber)
+    }
+}
+
+impl fmt::Debug for SensitivePhone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SensitivePhone({})", self)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::{SensitiveEmail, SensitivePhone};
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::{Serialize, Serializer};
+
+    impl Serialize for SensitiveEmail {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SensitiveEmail {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let address = String::deserialize(deserializer)?;
+            SensitiveEmail::parse(&address).map_err(de::Error::custom)
+        }
+    }
+
+    impl Serialize for SensitivePhone {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SensitivePhone {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let number = String::deserialize(deserializer)?;
+            SensitivePhone::parse(&number).map_err(de::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_keeps_domain() {
+        let email = SensitiveEmail::parse(" \"a@b\"@Mail.Example.COM ")
+            .unwrap()
+            .with_policy(RedactionPolicy::MaskLast(2));
+        assert_eq!(email.domain(), "mail.example.com");
+        assert_eq!(email.to_string(), "****b\"@mail.example.com");
+        assert_eq!(
+            email.to_sensitive_string(),
+            SensitiveString::from("\"a@b\"@mail.example.com")
+        );
+        assert_eq!(SensitiveEmail::parse("nobody@"), Err(InvalidEmail));
+        assert_eq!(SensitiveEmail::parse("@example.com"), Err(InvalidEmail));
+        assert_eq!(SensitiveEmail::parse("a@b c"), Err(InvalidEmail));
+    }
+
+    #[test]
+    fn test_phone_keeps_country_code() {
+        let code = |number: &str| SensitivePhone::parse(number).unwrap().country_code;
+        assert_eq!(code("+1 (555) 010-0199"), "1");
+        assert_eq!(code("+44 20 7946 0958"), "44");
+        assert_eq!(code("+353 1 234 5678"), "353");
+        assert_eq!(code("+81 3-1234-5678"), "81");
+        assert_eq!(code("+880 17 1234 5678"), "880");
+
+        let phone = SensitivePhone::parse("+49 30 1234567").unwrap();
+        assert_eq!(
+            phone.subscriber_number(),
+            &SensitiveString::from("301234567")
+        );
+        assert_eq!(
+            phone.to_sensitive_string(),
+            SensitiveString::from("+49301234567")
+        );
+        assert!(!format!("{:?}", phone).contains("301234567"));
+        assert_eq!(SensitivePhone::parse("030 1234567"), Err(InvalidPhone));
+        assert_eq!(SensitivePhone::parse("+49 30 12x4567"), Err(InvalidPhone));
+        assert_eq!(SensitivePhone::parse("+1 555"), Err(InvalidPhone));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let email: SensitiveEmail = serde_json::from_str("\"jane@example.com\"").unwrap();
+        let json = serde_json::to_string(&email).unwrap();
+        assert!(json.ends_with("@example.com\""));
+        assert!(!json.contains("jane"));
+        assert!(serde_json::from_str::<SensitivePhone>("\"12345\"").is_err());
+    }
+}
@@ -0,0 +1,523 @@
+//! Keys that stay on a PKCS#11 token.
+//!
+//! A [`SecretRef`] names a secret by where it lives instead of holding it.
+//! [`SecretRef::Pkcs11`] points at a private key on an HSM or smart card;
+//! [`SecretRef::open`] logs in with the PIN and returns an [`HsmKey`] whose
+//! [`sign`](HsmKey::sign) and [`decrypt`](HsmKey::decrypt) run on the
+//! token. There is no way to read the key itself: the token keeps it, and
+//! this module never asks for its value.
+//!
+//! The PKCS#11 module (the vendor's `.so`/`.dll`, or SoftHSM for testing) is
+//! loaded at run time with [`Pkcs11Module::load`], so nothing links against
+//! a vendor library at build time.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use sensitive_string::pkcs11::{Mechanism, Pkcs11Module, SecretRef};
+//! use sensitive_string::SensitiveString;
+//!
+//! let module = Pkcs11Module::load("/usr/lib/softhsm/libsofthsm2.so")?;
+//! let signing_key = SecretRef::Pkcs11 {
+//!     slot: 0,
+//!     label: "release-signing".to_string(),
+//!     pin: SensitiveString::from("1234"),
+//! };
+//! let key = signing_key.open(&module)?;
+//! let signature = key.sign(Mechanism::EcdsaSha256, b"release-1.4.2.tar.gz digest")?;
+//! # Ok::<(), sensitive_string::pkcs11::Pkcs11Error>(())
+//! ```
+
+use crate::{SensitiveBytes, SensitiveString};
+use libloading::Library;
+use std::ffi::{c_ulong, c_void, OsStr};
+use std::fmt;
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+use std::sync::{Arc, Mutex};
+
+type CkUlong = c_ulong;
+type CkRv = CkUlong;
+
+const CKR_OK: CkRv = 0;
+const CKR_USER_ALREADY_LOGGED_IN: CkRv = 0x100;
+const CKR_CRYPTOKI_ALREADY_INITIALIZED: CkRv = 0x191;
+
+const CKF_OS_LOCKING_OK: CkUlong = 0x2;
+const CKF_SERIAL_SESSION: CkUlong = 0x4;
+const CKU_USER: CkUlong = 1;
+const CKA_CLASS: CkUlong = 0x0;
+const CKA_LABEL: CkUlong = 0x3;
+const CKO_PRIVATE_KEY: CkUlong = 0x3;
+
+// PKCS#11 structures are packed to 1 byte on Windows and use natural
+// alignment elsewhere.
+#[cfg_attr(windows, repr(C, packed))]
+#[cfg_attr(not(windows), repr(C))]
+struct CkInitializeArgs {
+    create_mutex: *mut c_void,
+    destroy_mutex: *mut c_void,
+    lock_mutex: *mut c_void,
+    unlock_mutex: *mut c_void,
+    flags: CkUlong,
+    reserved: *mut c_void,
+}
+
+#[cfg_attr(windows, repr(C, packed))]
+#[cfg_attr(not(windows), repr(C))]
+struct CkAttribute {
+    kind: CkUlong,
+    value: *const c_void,
+    len: CkUlong,
+}
+
+#[cfg_attr(windows, repr(C, packed))]
+#[cfg_attr(not(windows), repr(C))]
+struct CkMechanism {
+    mechanism: CkUlong,
+    parameter: *const c_void,
+    len: CkUlong,
+}
+
+/// Errors from loading a module or using a token.
+#[derive(Debug)]
+pub enum Pkcs11Error {
+    /// The module could not be loaded or lacks a required function.
+    Load(libloading::Error),
+    /// A PKCS#11 function returned this `CKR_*` value.
+    Call {
+        /// The function, e.g. `C_Login`.
+        function: &'static str,
+        /// The return value.
+        rv: c_ulong,
+    },
+    /// No private key on the token has the label.
+    KeyNotFound,
+    /// More than one private key on the token has the label.
+    AmbiguousLabel,
+    /// A slot, PIN, or input does not fit the platform's `CK_ULONG`.
+    TooLarge,
+}
+
+impl fmt::Display for Pkcs11Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pkcs11Error::Load(err) => write!(f, "could not load PKCS#11 module: {}", err),
+            Pkcs11Error::Call { function, rv } => {
+                write!(f, "{} failed with CKR {:#x}", function, rv)
+            }
+            Pkcs11Error::KeyNotFound => write!(f, "no private key with that label on the token"),
+            Pkcs11Error::AmbiguousLabel => {
+                write!(f, "more than one private key with that label on the token")
+            }
+            Pkcs11Error::TooLarge => write!(f, "value does not fit in a CK_ULONG"),
+        }
+    }
+}
+
+impl std::error::Error for Pkcs11Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Pkcs11Error::Load(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<libloading::Error> for Pkcs11Error {
+    fn from(err: libloading::Error) -> Self {
+        Pkcs11Error::Load(err)
+    }
+}
+
+fn check(function: &'static str, rv: CkRv) -> Result<(), Pkcs11Error> {
+    if rv == CKR_OK {
+        Ok(())
+    } else {
+        Err(Pkcs11Error::Call { function, rv })
+    }
+}
+
+fn ulong<T: TryInto<CkUlong>>(value: T) -> Result<CkUlong, Pkcs11Error> {
+    value.try_into().map_err(|_| Pkcs11Error::TooLarge)
+}
+
+/// A signing or decryption mechanism without parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Mechanism {
+    /// `CKM_RSA_PKCS`: PKCS #1 v1.5 over data the caller has already hashed
+    /// and DigestInfo-encoded, or decryption of PKCS #1 v1.5 ciphertext.
+    RsaPkcs,
+    /// `CKM_SHA256_RSA_PKCS`: hashes the data with SHA-256, then signs.
+    Sha256RsaPkcs,
+    /// `CKM_ECDSA`: signs a digest the caller computed.
+    Ecdsa,
+    /// `CKM_ECDSA_SHA256`: hashes the data with SHA-256, then signs.
+    EcdsaSha256,
+    /// Any other `CKM_*` value that takes no parameters.
+    Raw(u64),
+}
+
+impl Mechanism {
+    /// Returns the `CKM_*` value.
+    pub fn code(self) -> u64 {
+        match self {
+            Mechanism::RsaPkcs => 0x1,
+            Mechanism::Sha256RsaPkcs => 0x40,
+            Mechanism::Ecdsa => 0x1041,
+            Mechanism::EcdsaSha256 => 0x1044,
+            Mechanism::Raw(code) => code,
+        }
+    }
+}
+
+/// The functions of a loaded module that this crate calls.
+struct Functions {
+    finalize: unsafe extern "C" fn(*mut c_void) -> CkRv,
+    open_session:
+        unsafe extern "C" fn(CkUlong, CkUlong, *mut c_void, *mut c_void, *mut CkUlong) -> CkRv,
+    close_session: unsafe extern "C" fn(CkUlong) -> CkRv,
+    login: unsafe extern "C" fn(CkUlong, CkUlong, *const u8, CkUlong) -> CkRv,
+    find_objects_init: unsafe extern "C" fn(CkUlong, *const CkAttribute, CkUlong) -> CkRv,
+    find_objects: unsafe extern "C" fn(CkUlong, *mut CkUlong, CkUlong, *mut CkUlong) -> CkRv,
+    find_objects_final: unsafe extern "C" fn(CkUlong) -> CkRv,
+    sign_init: unsafe extern "C" fn(CkUlong, *const CkMechanism, CkUlong) -> CkRv,
+    sign: unsafe extern "C" fn(CkUlong, *const u8, CkUlong, *mut u8, *mut CkUlong) -> CkRv,
+    decrypt_init: unsafe extern "C" fn(CkUlong, *const CkMechanism, CkUlong) -> CkRv,
+    decrypt: unsafe extern "C" fn(CkUlong, *const u8, CkUlong, *mut u8, *mut CkUlong) -> CkRv,
+}
+
+/// A loaded and initialized PKCS#11 module.
+pub struct Pkcs11Module {
+    functions: Functions,
+    /// Whether this load initialized the module, and so must finalize it.
+    initialized: bool,
+    _library: Library,
+}
+
+impl fmt::Debug for Pkcs11Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pkcs11Module").finish_non_exhaustive()
+    }
+}
+
+impl Pkcs11Module {
+    /// Loads the module at `path` and initializes it for use from several threads.
+    pub fn load(path: impl AsRef<OsStr>) -> Result<Arc<Self>, Pkcs11Error> {
+        // SAFETY: loading a PKCS#11 module runs its initializers, which is
+        // what the caller asked for by naming it.
+        let library = unsafe { Library::new(path.as_ref())? };
+        // SAFETY (for each `get` below): the types are the PKCS#11 v2.40
+        // signatures of the named functions.
+        let functions = unsafe {
+            Functions {
+                finalize: *library.get(b"C_Finalize\0")?,
+                open_session: *library.get(b"C_OpenSession\0")?,
+                close_session: *library.get(b"C_CloseSession\0")?,
+                login: *library.get(b"C_Login\0")?,
+                find_objects_init: *library.get(b"C_FindObjectsInit\0")?,
+                find_objects: *library.get(b"C_FindObjects\0")?,
+                find_objects_final: *library.get(b"C_FindObjectsFinal\0")?,
+                sign_init: *library.get(b"C_SignInit\0")?,
+                sign: *library.get(b"C_Sign\0")?,
+                decrypt_init: *library.get(b"C_DecryptInit\0")?,
+                decrypt: *library.get(b"C_Decrypt\0")?,
+            }
+        };
+        // SAFETY: as above.
+        let initialize: unsafe extern "C" fn(*mut c_void) -> CkRv =
+            unsafe { *library.get(b"C_Initialize\0")? };
+        let mut args = CkInitializeArgs {
+            create_mutex: ptr::null_mut(),
+            destroy_mutex: ptr::null_mut(),
+            lock_mutex: ptr::null_mut(),
+            unlock_mutex: ptr::null_mut(),
+            flags: CKF_OS_LOCKING_OK,
+            reserved: ptr::null_mut(),
+        };
+        // SAFETY: `args` is a valid CK_C_INITIALIZE_ARGS for the call.
+        let rv = unsafe { initialize(ptr::addr_of_mut!(args).cast()) };
+        let initialized = rv != CKR_CRYPTOKI_ALREADY_INITIALIZED;
+        if initialized {
+            check("C_Initialize", rv)?;
+        }
+        Ok(Arc::new(Self {
+            functions,
+            initialized,
+            _library: library,
+        }))
+    }
+}
+
+impl Drop for Pkcs11Module {
+    fn drop(&mut self) {
+        if self.initialized {
+            // SAFETY: the module was initialized by `load`, and every
+            // `HsmKey` holds an `Arc` to it, so no session is still open.
+            unsafe { (self.functions.finalize)(ptr::null_mut()) };
+        }
+    }
+}
+
+/// A reference to a secret that stays where it is stored.
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum SecretRef {
+    /// A private key on a PKCS#11 token, found by its `CKA_LABEL`.
+    Pkcs11 {
+        /// The slot ID the token is in.
+        slot: u64,
+        /// The key's label.
+        label: String,
+        /// The user PIN.
+        pin: SensitiveString,
+    },
+}
+
+impl fmt::Debug for SecretRef {
+    /// Omits the PIN entirely: even its hash would be easy to brute-force.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretRef::Pkcs11 { slot, label, .. } => f
+                .debug_struct("Pkcs11")
+                .field("slot", slot)
+                .field("label", label)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl SecretRef {
+    /// Opens a session on the token, logs in, and finds the key.
+    ///
+    /// Passing the PIN to the module counts as a plaintext read for
+    /// auditing and access policies.
+    #[track_caller]
+    pub fn open(&self, module: &Arc<Pkcs11Module>) -> Result<HsmKey, Pkcs11Error> {
+        let SecretRef::Pkcs11 { slot, label, pin } = self;
+        pin.record_access("pkcs11_login");
+        let functions = &module.functions;
+        let mut session = 0;
+        // SAFETY: no application data or notification callback is passed,
+        // and `session` is a valid out-pointer.
+        check("C_OpenSession", unsafe {
+            (functions.open_session)(
+                ulong(*slot)?,
+                CKF_SERIAL_SESSION,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut session,
+            )
+        })?;
+        // Closing the session on error (and in `Drop`) also logs out once
+        // it is the application's last session on the token.
+        let mut key = HsmKey {
+            module: Arc::clone(module),
+            session: Mutex::new(session),
+            object: 0,
+        };
+        let pin = pin.value.as_bytes();
+        // SAFETY: `pin` is valid for its length for the duration of the call.
+        let rv = unsafe { (functions.login)(session, CKU_USER, pin.as_ptr(), ulong(pin.len())?) };
+        if rv != CKR_USER_ALREADY_LOGGED_IN {
+            check("C_Login", rv)?;
+        }
+        key.object = find_private_key(functions, session, label)?;
+        Ok(key)
+    }
+}
+
+fn find_private_key(
+    functions: &Functions,
+    session: CkUlong,
+    label: &str,
+) -> Result<CkUlong, Pkcs11Error> {
+    let class = CKO_PRIVATE_KEY;
+    let template = [
+        CkAttribute {
+            kind: CKA_CLASS,
+            value: ptr::addr_of!(class).cast(),
+            len: ulong(std::mem::size_of::<CkUlong>())?,
+        },
+        CkAttribute {
+            kind: CKA_LABEL,
+            value: label.as_ptr().cast(),
+            len: ulong(label.len())?,
+        },
+    ];
+    // SAFETY: the template's pointers are valid for the duration of the call.
+    check("C_FindObjectsInit", unsafe {
+        (functions.find_objects_init)(session, template.as_ptr(), 2)
+    })?;
+    let mut objects = [0; 2];
+    let mut count = 0;
+    // SAFETY: `objects` has room for the 2 handles asked for.
+    let found = check("C_FindObjects", unsafe {
+        (functions.find_objects)(session, objects.as_mut_ptr(), 2, &mut count)
+    });
+    // SAFETY: a search is active on the session.
+    let finished = check("C_FindObjectsFinal", unsafe {
+        (functions.find_objects_final)(session)
+    });
+    found.and(finished)?;
+    match count {
+        0 => Err(Pkcs11Error::KeyNotFound),
+        1 => Ok(objects[0]),
+        _ => Err(Pkcs11Error::AmbiguousLabel),
+    }
+}
+
+/// A private key on a token, usable only through operations the token runs.
+pub struct HsmKey {
+    module: Arc<Pkcs11Module>,
+    /// Operations on one session must not interleave.
+    session: Mutex<CkUlong>,
+    object: CkUlong,
+}
+
+impl fmt::Debug for HsmKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HsmKey").finish_non_exhaustive()
+    }
+}
+
+type Init = unsafe extern "C" fn(CkUlong, *const CkMechanism, CkUlong) -> CkRv;
+type Operation = unsafe extern "C" fn(CkUlong, *const u8, CkUlong, *mut u8, *mut CkUlong) -> CkRv;
+
+impl HsmKey {
+    /// Signs `data` on the token.
+    pub fn sign(&self, mechanism: Mechanism, data: &[u8]) -> Result<Vec<u8>, Pkcs11Error> {
+        let functions = &self.module.functions;
+        self.run(
+            mechanism,
+            data,
+            (functions.sign_init, "C_SignInit"),
+            (functions.sign, "C_Sign"),
+        )
+    }
+
+    /// Decrypts `ciphertext` on the token.
+    pub fn decrypt(
+        &self,
+        mechanism: Mechanism,
+        ciphertext: &[u8],
+    ) -> Result<SensitiveBytes, Pkcs11Error> {
+        let functions = &self.module.functions;
+        self.run(
+            mechanism,
+            ciphertext,
+            (functions.decrypt_init, "C_DecryptInit"),
+            (functions.decrypt, "C_Decrypt"),
+        )
+        .map(SensitiveBytes::new)
+    }
+
+    /// Runs a single-part operation, asking the token for the output length first.
+    fn run(
+        &self,
+        mechanism: Mechanism,
+        input: &[u8],
+        (init, init_name): (Init, &'static str),
+        (operation, name): (Operation, &'static str),
+    ) -> Result<Vec<u8>, Pkcs11Error> {
+        let guard = self.session.lock().unwrap_or_else(|err| err.into_inner());
+        let session = *guard;
+        let mechanism = CkMechanism {
+            mechanism: ulong(mechanism.code())?,
+            parameter: ptr::null(),
+            len: 0,
+        };
+        let input_len = ulong(input.len())?;
+        // SAFETY: `mechanism` takes no parameters and `object` is a key
+        // handle on this session.
+        check(init_name, unsafe { init(session, &mechanism, self.object) })?;
+        let mut len = 0;
+        // SAFETY: a null output pointer asks only for the length, which
+        // leaves the operation active.
+        check(name, unsafe {
+            operation(
+                session,
+                input.as_ptr(),
+                input_len,
+                ptr::null_mut(),
+                &mut len,
+            )
+        })?;
+        let mut output = vec![0u8; usize::try_from(len).map_err(|_| Pkcs11Error::TooLarge)?];
+        // SAFETY: `output` has room for `len` bytes.
+        let rv = unsafe {
+            operation(
+                session,
+                input.as_ptr(),
+                input_len,
+                output.as_mut_ptr(),
+                &mut len,
+            )
+        };
+        if let Err(err) = check(name, rv) {
+            wipe(&mut output);
+            return Err(err);
+        }
+        output.truncate(usize::try_from(len).unwrap_or(0));
+        Ok(output)
+    }
+}
+
+impl Drop for HsmKey {
+    fn drop(&mut self) {
+        let session = *self
+            .session
+            .get_mut()
+            .unwrap_or_else(|err| err.into_inner());
+        // SAFETY: the session was opened by `SecretRef::open` and is closed once.
+        unsafe { (self.module.functions.close_session)(session) };
+    }
+}
+
+fn wipe(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        // SAFETY: `byte` is a valid, aligned reference into the buffer.
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_reports_missing_module() {
+        let err = Pkcs11Module::load("/nonexistent/libpkcs11-module.so").unwrap_err();
+        assert!(matches!(err, Pkcs11Error::Load(_)));
+        assert!(err.to_string().starts_with("could not load PKCS#11 module"));
+    }
+
+    #[test]
+    fn test_debug_omits_pin() {
+        let reference = SecretRef::Pkcs11 {
+            slot: 3,
+            label: "release-signing".to_string(),
+            pin: SensitiveString::from("123456"),
+        };
+        let debug = format!("{:?}", reference);
+        assert_eq!(debug, r#"Pkcs11 { slot: 3, label: "release-signing", .. }"#);
+        assert!(!debug.contains(&SensitiveString::from("123456").to_string()));
+    }
+
+    #[test]
+    fn test_mechanism_codes() {
+        assert_eq!(Mechanism::RsaPkcs.code(), 0x1);
+        assert_eq!(Mechanism::Sha256RsaPkcs.code(), 0x40);
+        assert_eq!(Mechanism::Ecdsa.code(), 0x1041);
+        assert_eq!(Mechanism::EcdsaSha256.code(), 0x1044);
+        assert_eq!(Mechanism::Raw(0x1087).code(), 0x1087);
+    }
+
+    #[test]
+    fn test_call_error_names_function_only() {
+        let err = check("C_Login", 0xA0).unwrap_err();
+        assert_eq!(err.to_string(), "C_Login failed with CKR 0xa0");
+    }
+}
@@ -0,0 +1,231 @@
+//! Choosing what redacted output looks like.
+//!
+//! A digest lets redacted logs still be correlated, but not every consumer
+//! wants one: some compliance regimes require a fixed `[REDACTED]`, and support
+//! teams often ask for last-four masking like `****cdef`. A [`RedactionPolicy`]
+//! picks the form used by `Display`, `Debug`, and `Serialize`. It can be set
+//! once for the whole process with [`set_default_policy`] or per secret with
+//! [`SensitiveString::with_policy`].
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::policy::RedactionPolicy;
+//! use sensitive_string::SensitiveString;
+//!
+//! let card = SensitiveString::from("4111111111111111").with_policy(RedactionPolicy::MaskLast(4));
+//! assert_eq!(card.to_string(), "****1111");
+//! assert_eq!(format!("{:?}", card), "SensitiveString(****1111)");
+//!
+//! let token = SensitiveString::from("hunter2").with_policy(RedactionPolicy::Placeholder);
+//! assert_eq!(token.to_string(), "[REDACTED]");
+//! ```
+//!
+//! The alternate form (`{:#}`) puts a human-facing marker in front of the
+//! redacted form, e.g. `[REDACTED] sha256:…`. Products whose logs are read by
+//! non-English operators can localize the marker once with [`set_marker`]
+//! (`«masqué»`, `[編集済み]`) without changing the token that tools parse.
+//!
+//! ```
+//! use sensitive_string::SensitiveString;
+//!
+//! let password = SensitiveString::from("hunter2");
+//! assert_eq!(format!("{:#}", password), format!("[REDACTED] {}", password));
+//! ```
+//!
+//! Features that must match a secret by its digest (support bundles,
+//! clipboard verification, scrubbers) keep using the digest regardless of
+//! policy.
+
+use crate::SensitiveString;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+static DEFAULT: OnceLock<RedactionPolicy> = OnceLock::new();
+static MARKER: OnceLock<String> = OnceLock::new();
+/// One more than the most characters any [`RedactionPolicy::MaskLast`] in
+/// use shows, or zero while none is in use.
+static MASK_SHOWN: AtomicUsize = AtomicUsize::new(0);
+
+/// The fixed string shown by [`RedactionPolicy::Placeholder`].
+pub const PLACEHOLDER: &str = "[REDACTED]";
+
+/// The prefix of every value shown by [`RedactionPolicy::MaskLast`].
+pub const MASK: &str = "****";
+
+/// How a secret is shown in place of its plaintext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedactionPolicy {
+    /// A digest of the plaintext, e.g. `sha256:<hex>`. The default.
+    #[default]
+    Hash,
+    /// The fixed string `[REDACTED]`.
+    Placeholder,
+    /// [`MASK`] followed by up to the last N characters.
+    ///
+    /// At most half of the secret is ever shown, so short secrets reveal
+    /// fewer characters (or none).
+    MaskLast(usize),
+}
+
+impl RedactionPolicy {
+    /// Applies this policy to `plaintext`, calling `digest` only for [`RedactionPolicy::Hash`].
+    pub(crate) fn apply(self, plaintext: &str, digest: impl FnOnce() -> String) -> String {
+        match self {
+            RedactionPolicy::Hash => digest(),
+            RedactionPolicy::Placeholder => PLACEHOLDER.to_string(),
+            RedactionPolicy::MaskLast(visible) => {
+                let chars = plaintext.chars().count();
+                let visible = visible.min(chars / 2);
+                let tail: String = plaintext.chars().skip(chars - visible).collect();
+                format!("{}{}", MASK, tail)
+            }
+        }
+    }
+}
+
+/// Error returned by [`set_default_policy`] when a default policy is already installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefaultPolicyAlreadySet;
+
+impl fmt::Display for DefaultPolicyAlreadySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a default redaction policy is already installed")
+    }
+}
+
+impl std::error::Error for DefaultPolicyAlreadySet {}
+
+/// Installs the policy used by every secret without a per-instance policy.
+///
+/// Can only be called once per process, so that the redacted form of a secret
+/// cannot change halfway through a log.
+pub fn set_default_policy(policy: RedactionPolicy) -> Result<(), DefaultPolicyAlreadySet> {
+    DEFAULT.set(policy).map_err(|_| DefaultPolicyAlreadySet)?;
+    note_mask(policy);
+    Ok(())
+}
+
+/// Returns the process-wide default policy ([`RedactionPolicy::Hash`] unless set).
+pub fn default_policy() -> RedactionPolicy {
+    DEFAULT.get().copied().unwrap_or_default()
+}
+
+/// Error returned by [`set_marker`] when a marker is already installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkerAlreadySet;
+
+impl fmt::Display for MarkerAlreadySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a redaction marker is already installed")
+    }
+}
+
+impl std::error::Error for MarkerAlreadySet {}
+
+/// Installs the human-facing marker shown by the alternate form (`{:#}`).
+///
+/// Only the marker changes: the plain form, `Serialize`, and
+/// [`RedactionPolicy::Placeholder`] keep their machine-parseable output. Can
+/// only be called once per process, like [`set_default_policy`].
+///
+/// ```no_run
+/// use sensitive_string::policy::set_marker;
+///
+/// set_marker("«masqué»").unwrap();
+/// ```
+pub fn set_marker(marker: impl Into<String>) -> Result<(), MarkerAlreadySet> {
+    MARKER.set(marker.into()).map_err(|_| MarkerAlreadySet)
+}
+
+/// Returns the marker shown by the alternate form ([`PLACEHOLDER`] unless set).
+pub fn marker() -> &'static str {
+    MARKER.get().map_or(PLACEHOLDER, String::as_str)
+}
+
+/// Remembers how many characters `policy` shows, if it is a mask.
+fn note_mask(policy: RedactionPolicy) {
+    if let RedactionPolicy::MaskLast(visible) = policy {
+        MASK_SHOWN.fetch_max(visible.saturating_add(1), Ordering::Relaxed);
+    }
+}
+
+/// Returns true if `value` is [`MASK`] followed by no more characters than a
+/// [`RedactionPolicy::MaskLast`] in use shows.
+pub(crate) fn is_mask(value: &str) -> bool {
+    value
+        .strip_prefix(MASK)
+        .is_some_and(|tail| tail.chars().count() < MASK_SHOWN.load(Ordering::Relaxed))
+}
+
+/// Puts the marker in front of `redacted`, unless it is already just the placeholder.
+pub(crate) fn write_with_marker(f: &mut fmt::Formatter<'_>, redacted: &str) -> fmt::Result {
+    if redacted == PLACEHOLDER {
+        f.write_str(marker())
+    } else {
+        write!(f, "{} {}", marker(), redacted)
+    }
+}
+
+impl SensitiveString {
+    /// Sets how this secret is shown, overriding the process-wide default.
+    pub fn with_policy(mut self, policy: RedactionPolicy) -> Self {
+        note_mask(policy);
+        self.policy = Some(policy);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_last_never_shows_more_than_half() {
+        let mask = |value: &str| {
+            SensitiveString::from(value)
+                .with_policy(RedactionPolicy::MaskLast(4))
+                .to_string()
+        };
+        assert_eq!(mask("abcdefghcdef"), "****cdef");
+        assert_eq!(mask("abcdef"), "****def");
+        assert_eq!(mask("a"), "****");
+        assert_eq!(mask("pässwörd"), "****wörd");
+    }
+
+    #[test]
+    fn test_only_masks_in_use_look_redacted() {
+        let card =
+            SensitiveString::from("4111111111111111").with_policy(RedactionPolicy::MaskLast(4));
+        assert!(SensitiveString::looks_redacted(&card.to_string()));
+        assert!(SensitiveString::looks_redacted("****"));
+        assert!(!SensitiveString::looks_redacted("****4111111111111111"));
+        assert!(!SensitiveString::looks_redacted("**** not a mask"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_policy_applies_to_serialize() {
+        let secret = SensitiveString::from("hunter2").with_policy(RedactionPolicy::Placeholder);
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"[REDACTED]\"");
+        assert!(SensitiveString::looks_redacted(&secret.to_string()));
+    }
+
+    #[test]
+    fn test_alternate_form_adds_marker() {
+        let secret = SensitiveString::from("hunter2");
+        let alternate = format!("{:#}", secret);
+        assert_eq!(alternate, format!("{} {}", marker(), secret));
+        assert!(SensitiveString::looks_redacted(&alternate));
+
+        let placeholder = secret.with_policy(RedactionPolicy::Placeholder);
+        assert_eq!(format!("{:#}", placeholder), marker());
+    }
+
+    #[test]
+    fn test_policy_does_not_affect_equality() {
+        let masked = SensitiveString::from("abc").with_policy(RedactionPolicy::Placeholder);
+        assert_eq!(masked, SensitiveString::from("abc"));
+    }
+}
@@ -0,0 +1,131 @@
+//! QR code rendering for secret provisioning.
+//!
+//! Provisioning flows (TOTP `otpauth://` URIs, Wi-Fi credentials, device
+//! enrollment tokens) often need to hand a secret to a phone. Rendering it as
+//! a QR code avoids printing the seed as copyable text. Like other operations
+//! that move plaintext out of the wrapper, rendering requires an active
+//! [`ExposureGuard`](crate::ExposureGuard).
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{ExposureGuard, SensitiveString};
+//!
+//! let uri = SensitiveString::from("otpauth://totp/Example:alice?secret=JBSWY3DPEHPK3PXP");
+//! let _guard = ExposureGuard::new("TOTP enrollment");
+//! let art = uri.qr_terminal().unwrap();
+//! assert!(!art.contains("JBSWY3DPEHPK3PXP"));
+//! ```
+
+use crate::exposure::{require_exposure, ExposureRequired};
+use crate::SensitiveString;
+use qrcode::render::{svg, unicode};
+use qrcode::types::QrError;
+use qrcode::QrCode;
+use std::fmt;
+
+/// Errors from QR rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrRenderError {
+    /// No [`ExposureGuard`](crate::ExposureGuard) was active.
+    ExposureRequired(ExposureRequired),
+    /// The secret could not be encoded (usually because it is too long).
+    Encode(QrError),
+}
+
+impl fmt::Display for QrRenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QrRenderError::ExposureRequired(err) => err.fmt(f),
+            QrRenderError::Encode(err) => write!(f, "QR encoding failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for QrRenderError {}
+
+impl From<ExposureRequired> for QrRenderError {
+    fn from(err: ExposureRequired) -> Self {
+        QrRenderError::ExposureRequired(err)
+    }
+}
+
+impl From<QrError> for QrRenderError {
+    fn from(err: QrError) -> Self {
+        QrRenderError::Encode(err)
+    }
+}
+
+impl SensitiveString {
+    /// Encodes the plaintext as a QR code.
+    ///
+    /// Use this to render into an image buffer with `qrcode`'s renderers.
+    #[track_caller]
+    pub fn qr_code(&self) -> Result<QrCode, QrRenderError> {
+        require_exposure("qr_code")?;
+        self.record_access("qr_code");
+        Ok(QrCode::new(self.value.as_bytes())?)
+    }
+
+    /// Renders the plaintext as a QR code made of Unicode half blocks, for terminals.
+    #[track_caller]
+    pub fn qr_terminal(&self) -> Result<String, QrRenderError> {
+        require_exposure("qr_terminal")?;
+        self.record_access("qr_terminal");
+        let code = QrCode::new(self.value.as_bytes())?;
+        Ok(code
+            .render::<unicode::Dense1x2>()
+            .dark_color(unicode::Dense1x2::Light)
+            .light_color(unicode::Dense1x2::Dark)
+            .build())
+    }
+
+    /// Renders the plaintext as a QR code in an SVG document.
+    #[track_caller]
+    pub fn qr_svg(&self) -> Result<String, QrRenderError> {
+        require_exposure("qr_svg")?;
+        self.record_access("qr_svg");
+        let code = QrCode::new(self.value.as_bytes())?;
+        Ok(code.render::<svg::Color<'_>>().build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExposureGuard;
+
+    #[test]
+    fn test_qr_requires_exposure() {
+        let secret = SensitiveString::from("seed");
+        assert!(matches!(
+            secret.qr_terminal(),
+            Err(QrRenderError::ExposureRequired(_))
+        ));
+        assert!(secret.qr_code().is_err());
+        assert!(secret.qr_svg().is_err());
+    }
+
+    #[test]
+    fn test_qr_output_has_no_plaintext() {
+        let secret = SensitiveString::from("JBSWY3DPEHPK3PXP");
+        let _guard = ExposureGuard::new("test");
+
+        let terminal = secret.qr_terminal().unwrap();
+        let svg = secret.qr_svg().unwrap();
+
+        assert!(!terminal.contains("JBSWY3DPEHPK3PXP"));
+        assert!(svg.starts_with("<?xml"));
+        assert!(!svg.contains("JBSWY3DPEHPK3PXP"));
+        assert!(secret.qr_code().unwrap().width() >= 21);
+    }
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_qr_is_checked_against_access_policies() {
+        let secret = SensitiveString::from("JBSWY3DPEHPK3PXP");
+        let _guard = ExposureGuard::new("test");
+        crate::access::assert_denied(|| secret.qr_code());
+        crate::access::assert_denied(|| secret.qr_terminal());
+        crate::access::assert_denied(|| secret.qr_svg());
+    }
+}
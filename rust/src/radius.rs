@@ -0,0 +1,206 @@
+//! RADIUS shared-secret helpers.
+//!
+//! Implements User-Password attribute hiding from RFC 2865, section 5.2, with
+//! both the password and the shared secret held as `SensitiveString`s.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::radius;
+//! use sensitive_string::SensitiveString;
+//!
+//! let secret = SensitiveString::from("shared-secret");
+//! let password = SensitiveString::from("user-password");
+//! let authenticator = [7u8; 16];
+//!
+//! let hidden = radius::hide_user_password(&password, &secret, &authenticator).unwrap();
+//! let revealed = radius::reveal_user_password(&hidden, &secret, &authenticator).unwrap();
+//! assert_eq!(revealed, password);
+//! ```
+
+use crate::SensitiveString;
+use md5::{Digest, Md5};
+use std::fmt;
+
+/// Length of the Request Authenticator field.
+pub const AUTHENTICATOR_LEN: usize = 16;
+
+/// Maximum length of a User-Password attribute value.
+pub const MAX_PASSWORD_LEN: usize = 128;
+
+/// Errors from User-Password hiding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadiusError {
+    /// The password is longer than [`MAX_PASSWORD_LEN`] bytes.
+    PasswordTooLong,
+    /// The hidden value is empty, too long, or not a multiple of 16 bytes.
+    InvalidHiddenLength,
+    /// The revealed password is not valid UTF-8.
+    InvalidUtf8,
+}
+
+impl fmt::Display for RadiusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RadiusError::PasswordTooLong => write!(
+                f,
+                "RADIUS User-Password must be at most {} bytes",
+                MAX_PASSWORD_LEN
+            ),
+            RadiusError::InvalidHiddenLength => write!(
+                f,
+                "hidden User-Password must be a non-empty multiple of 16 bytes"
+            ),
+            RadiusError::InvalidUtf8 => write!(f, "revealed User-Password is not valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for RadiusError {}
+
+/// Hides `password` for the User-Password attribute of an Access-Request.
+#[track_caller]
+pub fn hide_user_password(
+    password: &SensitiveString,
+    shared_secret: &SensitiveString,
+    authenticator: &[u8; AUTHENTICATOR_LEN],
+) -> Result<Vec<u8>, RadiusError> {
+    password.record_access("hide_user_password");
+    shared_secret.record_access("hide_user_password");
+    let plain = password.value.as_bytes();
+    if plain.len() > MAX_PASSWORD_LEN {
+        return Err(RadiusError::PasswordTooLong);
+    }
+
+    let padded_len = plain.len().div_ceil(16).max(1) * 16;
+    let mut hidden = vec![0u8; padded_len];
+    hidden[..plain.len()].copy_from_slice(plain);
+
+    let mut previous: [u8; 16] = *authenticator;
+    for chunk in hidden.chunks_mut(16) {
+        let pad = keystream_block(shared_secret, &previous);
+        for (byte, key) in chunk.iter_mut().zip(pad.iter()) {
+            *byte ^= key;
+        }
+        previous.copy_from_slice(chunk);
+    }
+    Ok(hidden)
+}
+
+/// Recovers a password hidden with [`hide_user_password`].
+#[track_caller]
+pub fn reveal_user_password(
+    hidden: &[u8],
+    shared_secret: &SensitiveString,
+    authenticator: &[u8; AUTHENTICATOR_LEN],
+) -> Result<SensitiveString, RadiusError> {
+    shared_secret.record_access("reveal_user_password");
+    if hidden.is_empty() || !hidden.len().is_multiple_of(16) || hidden.len() > MAX_PASSWORD_LEN {
+        return Err(RadiusError::InvalidHiddenLength);
+    }
+
+    let mut plain = Vec::with_capacity(hidden.len());
+    let mut previous: [u8; 16] = *authenticator;
+    for chunk in hidden.chunks(16) {
+        let pad = keystream_block(shared_secret, &previous);
+        plain.extend(chunk.iter().zip(pad.iter()).map(|(byte, key)| byte ^ key));
+        previous.copy_from_slice(chunk);
+    }
+
+    while plain.last() == Some(&0) {
+        plain.pop();
+    }
+    String::from_utf8(plain)
+        .map(SensitiveString::new)
+        .map_err(|_| RadiusError::InvalidUtf8)
+}
+
+fn keystream_block(shared_secret: &SensitiveString, previous: &[u8; 16]) -> [u8; 16] {
+    let mut hasher = Md5::new();
+    hasher.update(shared_secret.value.as_bytes());
+    hasher.update(previous);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let secret = SensitiveString::from("xyzzy5461");
+        let authenticator = [0x42u8; 16];
+        for password in [
+            "",
+            "a",
+            "exactly-16-bytes",
+            "a password longer than sixteen bytes",
+        ] {
+            let password = SensitiveString::from(password);
+            let hidden = hide_user_password(&password, &secret, &authenticator).unwrap();
+            assert!(hidden.len().is_multiple_of(16));
+            assert!(!hidden.is_empty());
+            let revealed = reveal_user_password(&hidden, &secret, &authenticator).unwrap();
+            assert_eq!(revealed, password);
+        }
+    }
+
+    /// The Access-Request in RFC 2865, section 7.1, hides "arctangent" as
+    /// section 5.2 describes.
+    #[test]
+    fn test_rfc2865_vector() {
+        let secret = SensitiveString::from("xyzzy5461");
+        let password = SensitiveString::from("arctangent");
+        let authenticator = [
+            0x0f, 0x40, 0x3f, 0x94, 0x73, 0x97, 0x80, 0x57, 0xbd, 0x83, 0xd5, 0xcb, 0x98, 0xf4,
+            0x22, 0x7a,
+        ];
+        let hidden = hide_user_password(&password, &secret, &authenticator).unwrap();
+        assert_eq!(
+            hidden,
+            [
+                0x0d, 0xbe, 0x70, 0x8d, 0x93, 0xd4, 0x13, 0xce, 0x31, 0x96, 0xe4, 0x3f, 0x78, 0x2a,
+                0x0a, 0xee,
+            ]
+        );
+        let revealed = reveal_user_password(&hidden, &secret, &authenticator).unwrap();
+        assert_eq!(revealed, password);
+    }
+
+    #[test]
+    fn test_hidden_does_not_contain_plaintext() {
+        let secret = SensitiveString::from("xyzzy5461");
+        let password = SensitiveString::from("arctangent");
+        let hidden = hide_user_password(&password, &secret, &[0u8; 16]).unwrap();
+        assert!(!hidden.windows(10).any(|w| w == b"arctangent"));
+    }
+
+    #[test]
+    fn test_rejects_long_password() {
+        let secret = SensitiveString::from("s");
+        let password = SensitiveString::new("x".repeat(MAX_PASSWORD_LEN + 1));
+        assert_eq!(
+            hide_user_password(&password, &secret, &[0u8; 16]),
+            Err(RadiusError::PasswordTooLong)
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_hidden_length() {
+        let secret = SensitiveString::from("s");
+        assert_eq!(
+            reveal_user_password(&[0u8; 15], &secret, &[0u8; 16]).unwrap_err(),
+            RadiusError::InvalidHiddenLength
+        );
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_hiding_is_checked_against_access_policies() {
+        let secret = SensitiveString::from("xyzzy5461");
+        let password = SensitiveString::from("arctangent");
+        let hidden = hide_user_password(&password, &secret, &[0u8; 16]).unwrap();
+        crate::access::assert_denied(|| hide_user_password(&password, &secret, &[0u8; 16]));
+        crate::access::assert_denied(|| reveal_user_password(&hidden, &secret, &[0u8; 16]));
+    }
+}
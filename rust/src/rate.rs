@@ -0,0 +1,82 @@
+//! Rate-limiter keys derived from secrets.
+//!
+//! Per-token rate limiting keys the limiter's state by token, which leaves
+//! every recently seen token in memory and in any dump of that state.
+//! [`SensitiveString::rate_key`] returns a fixed-size digest to key it by
+//! instead; it is `Copy + Eq + Hash`, as keyed limiters such as `governor`'s
+//! `RateLimiter::keyed` require.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "hash")]
+//! # {
+//! use sensitive_string::SensitiveString;
+//! use std::collections::HashMap;
+//!
+//! let mut requests: HashMap<[u8; 32], u32> = HashMap::new();
+//! for token in ["tok-a", "tok-b", "tok-a"] {
+//!     *requests.entry(SensitiveString::from(token).rate_key()).or_default() += 1;
+//! }
+//! assert_eq!(requests[&SensitiveString::from("tok-a").rate_key()], 2);
+//! # }
+//! ```
+//!
+//! The key is a domain-separated SHA-256, so it does not match the digest
+//! shown in logs, and is stable across processes for limiters shared through
+//! Redis or similar. For low-entropy secrets, use
+//! [`rate_key_with`](SensitiveString::rate_key_with) and a key of your own so
+//! that limiter state cannot be brute-forced.
+
+use crate::SensitiveString;
+use hmac::Mac;
+use sha2::{Digest, Sha256};
+
+/// Prepended to the plaintext so rate keys never equal `sha256:` log digests.
+const DOMAIN: &[u8] = b"sensitive-string/rate-key\0";
+
+impl SensitiveString {
+    /// Returns a digest of this secret to key rate-limiter state by.
+    pub fn rate_key(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(DOMAIN);
+        hasher.update(self.value.as_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Returns an HMAC-SHA256 of this secret keyed with `key`, to key rate-limiter state by.
+    pub fn rate_key_with(&self, key: &[u8]) -> [u8; 32] {
+        let mut mac =
+            hmac::Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(DOMAIN);
+        mac.update(self.value.as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_key_differs_from_log_digest() {
+        let token = SensitiveString::from("tok-123");
+        let plain: [u8; 32] = Sha256::digest(b"tok-123").into();
+        assert_ne!(token.rate_key(), plain);
+        assert_eq!(
+            token.rate_key(),
+            SensitiveString::from("tok-123").rate_key()
+        );
+        assert_ne!(
+            token.rate_key(),
+            SensitiveString::from("tok-124").rate_key()
+        );
+    }
+
+    #[test]
+    fn test_keyed_rate_key_depends_on_key() {
+        let token = SensitiveString::from("1234");
+        assert_ne!(token.rate_key_with(b"a"), token.rate_key_with(b"b"));
+        assert_ne!(token.rate_key_with(b"a"), token.rate_key());
+    }
+}
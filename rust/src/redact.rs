@@ -0,0 +1,174 @@
+//! Redacted `Debug` output for whole structs.
+//!
+//! Wrapping every field of a config struct in `SensitiveString` is not
+//! always possible, and hand-writing `Debug` to hide a few fields is tedious.
+//! With the `derive` feature, `#[derive(Redact)]` generates `Debug` and
+//! `Display` impls that print like `#[derive(Debug)]`, except for fields
+//! marked `#[sensitive]`:
+//!
+//! - `#[sensitive]` shows the field's redacted form, through its [`RedactField`]
+//!   impl. Strings are shown like the equivalent `SensitiveString`, following
+//!   the process-wide [`RedactionPolicy`](policy::RedactionPolicy).
+//! - `#[sensitive(placeholder)]` shows `[REDACTED]`, for fields of any type.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "derive")]
+//! # {
+//! use sensitive_string::{Redact, SensitiveString};
+//!
+//! #[derive(Redact)]
+//! struct DbConfig {
+//!     host: String,
+//!     #[sensitive]
+//!     password: String,
+//!     #[sensitive(placeholder)]
+//!     pin: u32,
+//! }
+//!
+//! let config = DbConfig {
+//!     host: "db.internal".into(),
+//!     password: "hunter2".into(),
+//!     pin: 1234,
+//! };
+//! assert_eq!(
+//!     format!("{:?}", config),
+//!     format!(
+//!         "DbConfig {{ host: \"db.internal\", password: {}, pin: [REDACTED] }}",
+//!         SensitiveString::from("hunter2")
+//!     )
+//! );
+//! # }
+//! ```
+//!
+//! Enums and tuple structs are supported. Type parameters must implement
+//! `Debug`, as with `#[derive(Debug)]`.
+
+use crate::{policy, Sensitive, SensitiveBytes, SensitiveString};
+use std::fmt;
+
+/// A type that can print itself in redacted form, for `#[sensitive]` fields.
+pub trait RedactField {
+    /// Writes the redacted form, such as a digest, to `f`.
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+impl RedactField for str {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&crate::redact_plaintext(self))
+    }
+}
+
+impl RedactField for String {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt_redacted(f)
+    }
+}
+
+impl RedactField for [u8] {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&crate::redact_bytes(self))
+    }
+}
+
+impl RedactField for Vec<u8> {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_slice().fmt_redacted(f)
+    }
+}
+
+impl RedactField for SensitiveString {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl RedactField for SensitiveBytes {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<T> RedactField for Sensitive<T> {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl<T: RedactField> RedactField for Option<T> {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Some(value) => f.debug_tuple("Some").field(&Redacted(value)).finish(),
+            None => f.write_str("None"),
+        }
+    }
+}
+
+impl<T: RedactField + ?Sized> RedactField for &T {
+    fn fmt_redacted(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt_redacted(f)
+    }
+}
+
+/// Formats a `#[sensitive]` field with its [`RedactField`] impl.
+#[doc(hidden)]
+pub struct Redacted<'a, T: ?Sized>(pub &'a T);
+
+impl<T: RedactField + ?Sized> fmt::Debug for Redacted<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_redacted(f)
+    }
+}
+
+/// Formats a `#[sensitive(placeholder)]` field.
+#[doc(hidden)]
+pub struct Placeholder;
+
+impl fmt::Debug for Placeholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(policy::PLACEHOLDER)
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use crate::{Redact, SensitiveString};
+
+    #[derive(Redact)]
+    enum Credential<T> {
+        Token(#[sensitive] Option<String>, T),
+        Basic {
+            user: String,
+            #[sensitive]
+            password: SensitiveString,
+        },
+        Anonymous,
+    }
+
+    #[test]
+    fn test_derived_output_redacts_marked_fields() {
+        let token: Credential<u8> = Credential::Token(Some("tok".into()), 7);
+        assert_eq!(
+            format!("{:?}", token),
+            format!("Token(Some({}), 7)", SensitiveString::from("tok"))
+        );
+
+        let basic: Credential<u8> = Credential::Basic {
+            user: "ann".into(),
+            password: "hunter2".into(),
+        };
+        let shown = basic.to_string();
+        assert!(shown.starts_with("Basic { user: \"ann\", password: "));
+        assert!(!shown.contains("hunter2"));
+        assert_eq!(Credential::<u8>::Anonymous.to_string(), "Anonymous");
+    }
+
+    #[test]
+    fn test_pretty_debug_is_redacted() {
+        #[derive(Redact)]
+        struct Pin(#[sensitive(placeholder)] u32);
+
+        assert_eq!(format!("{:#?}", Pin(1234)), "Pin(\n    [REDACTED],\n)");
+    }
+}
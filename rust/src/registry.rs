@@ -0,0 +1,318 @@
+//! A process-wide registry of secrets for last-line redaction.
+//!
+//! Error messages and panic payloads are built by code that never saw a
+//! `SensitiveString`, and they are sent to crash reporters such as Sentry.
+//! [`SecretRegistry::global`] collects the secrets a process knows about so
+//! that any string can be scrubbed with [`SecretRegistry::redact_str`] just
+//! before it leaves. Secrets join with [`SensitiveString::registered`], or
+//! all at once with [`SecretRegistry::set_auto_register`].
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::registry::SecretRegistry;
+//! use sensitive_string::SensitiveString;
+//!
+//! let token = SensitiveString::from("tok-123").registered();
+//!
+//! let message = format!("request failed: bad token tok-123");
+//! let redacted = SecretRegistry::global().redact_str(&message);
+//! assert_eq!(redacted, format!("request failed: bad token {}", token));
+//! ```
+//!
+//! The registry keeps a copy of every registered plaintext until it is
+//! [unregistered](SecretRegistry::unregister), so register long-lived
+//! credentials rather than per-request values.
+//!
+//! # Panics and error chains
+//!
+//! [`install_panic_scrubber`] scrubs panic messages before they are printed,
+//! and [`RedactedError`] scrubs the `Display` and `Debug` output of an error,
+//! such as an `anyhow::Error` whose chain includes a third-party message:
+//!
+//! ```
+//! use sensitive_string::registry::RedactedError;
+//! use sensitive_string::SensitiveString;
+//!
+//! let token = SensitiveString::from("tok-456").registered();
+//! let error = std::io::Error::other("upstream rejected tok-456");
+//!
+//! let redacted = RedactedError::new(error);
+//! assert_eq!(redacted.to_string(), format!("upstream rejected {}", token));
+//! assert!(!format!("{:?}", redacted).contains("tok-456"));
+//! ```
+
+use crate::memory::MemoryReport;
+use crate::scrub::Scrubber;
+use crate::SensitiveString;
+use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Once, OnceLock, RwLock};
+
+static GLOBAL: OnceLock<SecretRegistry> = OnceLock::new();
+static AUTO_REGISTER: AtomicBool = AtomicBool::new(false);
+
+/// A set of secrets that arbitrary strings can be scrubbed of.
+#[derive(Debug, Default)]
+pub struct SecretRegistry {
+    scrubber: RwLock<Scrubber>,
+}
+
+impl SecretRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the process-wide registry.
+    pub fn global() -> &'static SecretRegistry {
+        GLOBAL.get_or_init(SecretRegistry::new)
+    }
+
+    /// Registers every `SensitiveString` created from now on with the global
+    /// registry, or stops doing so.
+    ///
+    /// Secrets the crate derives from others, such as the results of
+    /// [`trim`](SensitiveString::trim) and [`concat`](SensitiveString::concat),
+    /// are not registered, since scrubbing the secrets they came from covers them.
+    pub fn set_auto_register(enabled: bool) {
+        AUTO_REGISTER.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Adds `secret`. Empty secrets are ignored.
+    pub fn register(&self, secret: &SensitiveString) {
+        self.scrubber
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .add(secret);
+    }
+
+    /// Removes `secret`, e.g. after it has been rotated out. Returns true if it was registered.
+    pub fn unregister(&self, secret: &SensitiveString) -> bool {
+        self.scrubber
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(secret)
+    }
+
+    /// Estimates the bytes held by the registered secrets.
+    pub fn approx_memory_bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + self.memory_report().approx_bytes
+    }
+
+    /// Reports on the registered secrets.
+    pub fn memory_report(&self) -> MemoryReport {
+        MemoryReport::of(
+            self.scrubber
+                .read()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .secrets(),
+        )
+    }
+
+    /// Returns a copy of the registered secrets.
+    pub(crate) fn scrubber(&self) -> Scrubber {
+        self.scrubber
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Replaces every registered secret in `text` with its redacted form.
+    pub fn redact_str(&self, text: &str) -> String {
+        self.scrubber
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .scrub(text)
+            .into_owned()
+    }
+}
+
+/// Registers `secret` with the global registry if auto-registration is on.
+pub(crate) fn auto_register(secret: &SensitiveString) {
+    if AUTO_REGISTER.load(Ordering::Relaxed) {
+        SecretRegistry::global().register(secret);
+    }
+}
+
+impl SensitiveString {
+    /// Registers this secret with the global [`SecretRegistry`] and returns it.
+    pub fn registered(self) -> Self {
+        SecretRegistry::global().register(&self);
+        self
+    }
+}
+
+/// Wraps the panic hook so that registered secrets are scrubbed from panic messages.
+///
+/// Panics whose message contains no registered secret go to the previous
+/// hook unchanged, so crash reporters installed earlier keep working. The
+/// others are printed to stderr, scrubbed, in the format of the default hook
+/// instead, since the previous hook can only be given the original message.
+/// The payload seen by `catch_unwind` and `JoinHandle::join` is not changed.
+///
+/// Calling this more than once has no further effect.
+pub fn install_panic_scrubber() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let message = match panic_message(info.payload()) {
+                Some(message) => message,
+                None => return previous(info),
+            };
+            let scrubbed = SecretRegistry::global().redact_str(message);
+            if scrubbed == message {
+                return previous(info);
+            }
+            let thread = std::thread::current();
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(
+                stderr,
+                "\nthread '{}' panicked",
+                thread.name().unwrap_or("<unnamed>")
+            );
+            if let Some(location) = info.location() {
+                let _ = write!(stderr, " at {}", location);
+            }
+            let _ = writeln!(stderr, ":\n{}", scrubbed);
+            let backtrace = Backtrace::capture();
+            match backtrace.status() {
+                BacktraceStatus::Captured => {
+                    let _ = writeln!(stderr, "stack backtrace:\n{}", backtrace);
+                }
+                _ => {
+                    let _ = writeln!(
+                        stderr,
+                        "note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"
+                    );
+                }
+            }
+        }));
+    });
+}
+
+/// Returns the message of a `panic!` payload.
+fn panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+}
+
+/// An error whose `Display` and `Debug` output is scrubbed of registered secrets.
+///
+/// Works with any error that is `Display`, including `anyhow::Error`, whose
+/// `{:#}` and `{:?}` forms print the whole chain. `source()` returns `None`,
+/// since a reporter walking the causes would print them unscrubbed.
+pub struct RedactedError<E> {
+    error: E,
+}
+
+impl<E> RedactedError<E> {
+    /// Wraps `error`.
+    pub fn new(error: E) -> Self {
+        Self { error }
+    }
+
+    /// Returns the wrapped error.
+    pub fn get_ref(&self) -> &E {
+        &self.error
+    }
+
+    /// Returns the wrapped error.
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+}
+
+impl<E> From<E> for RedactedError<E> {
+    fn from(error: E) -> Self {
+        Self::new(error)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for RedactedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let display = if f.alternate() {
+            format!("{:#}", self.error)
+        } else {
+            self.error.to_string()
+        };
+        f.write_str(&SecretRegistry::global().redact_str(&display))
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for RedactedError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let debug = if f.alternate() {
+            format!("{:#?}", self.error)
+        } else {
+            format!("{:?}", self.error)
+        };
+        f.write_str(&SecretRegistry::global().redact_str(&debug))
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for RedactedError<E> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_unregister() {
+        let registry = SecretRegistry::new();
+        let old = SensitiveString::from("old-key");
+        let new = SensitiveString::from("new-key");
+        registry.register(&old);
+        registry.register(&new);
+
+        assert_eq!(
+            registry.redact_str("old-key new-key"),
+            format!("{} {}", old, new)
+        );
+        assert_eq!(registry.memory_report().secrets, 2);
+        assert!(registry.unregister(&old));
+        assert!(!registry.unregister(&old));
+        assert_eq!(
+            registry.redact_str("old-key new-key"),
+            format!("old-key {}", new)
+        );
+    }
+
+    #[test]
+    fn test_redacted_error_scrubs_output() {
+        struct Chain;
+        impl fmt::Display for Chain {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                if f.alternate() {
+                    write!(f, "login failed: bad chain-secret-41c2")
+                } else {
+                    write!(f, "login failed")
+                }
+            }
+        }
+        impl fmt::Debug for Chain {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "login failed\n\nCaused by:\n    bad chain-secret-41c2")
+            }
+        }
+
+        let secret = SensitiveString::from("chain-secret-41c2").registered();
+        let error = RedactedError::from(Chain);
+        assert_eq!(error.to_string(), "login failed");
+        assert_eq!(
+            format!("{:#}", error),
+            format!("login failed: bad {}", secret)
+        );
+        assert!(!format!("{:?}", error).contains("chain-secret-41c2"));
+
+        let io = RedactedError::new(std::io::Error::other("bad chain-secret-41c2"));
+        assert_eq!(io.to_string(), format!("bad {}", secret));
+        assert!(std::error::Error::source(&io).is_none());
+    }
+}
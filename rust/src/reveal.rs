@@ -0,0 +1,236 @@
+//! Showing part of a secret to a person.
+//!
+//! Support UIs need to let an agent confirm which key a customer is talking
+//! about ("the one ending in 4f2a") without showing the key.
+//! [`SensitiveString::reveal`] returns a masked `String` with only the
+//! requested ends visible. Policies added with [`add_reveal_policy`] can
+//! refuse even that for labels that must never be partially shown.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::reveal::{add_reveal_policy, PartialReveal};
+//! use sensitive_string::SensitiveString;
+//!
+//! let key = SensitiveString::from("sk_live_51Hx9f3e4f2a");
+//! assert_eq!(key.reveal(PartialReveal::FirstLast { first: 3, last: 4 }), "sk_…4f2a");
+//!
+//! // Root credentials are never partially shown.
+//! add_reveal_policy(|request| request.label != Some("root-credential"));
+//! let root = SensitiveString::from("sk_live_51Hx9f3e4f2a").with_label("root-credential");
+//! assert_eq!(root.reveal(PartialReveal::Last(4)), "[REDACTED]");
+//! ```
+//!
+//! [`masked`](SensitiveString::masked) and
+//! [`masked_prefix`](SensitiveString::masked_prefix) return the same text as
+//! a [`Masked`] value, which can be passed to templates, logs, or `serde`
+//! output without being mistaken for a plain `String`.
+//!
+//! At most half of a secret's characters are ever shown, as with
+//! [`RedactionPolicy::MaskLast`](crate::policy::RedactionPolicy::MaskLast);
+//! the leading part is shortened first. With the `access-policy` feature a
+//! reveal is also checked as a read through `"reveal"`, and a denial masks
+//! the whole secret instead of panicking.
+
+use crate::policy::PLACEHOLDER;
+use crate::SensitiveString;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+/// Shown in place of the hidden part of a secret.
+pub const ELLIPSIS: &str = "…";
+
+type RevealPolicy = dyn Fn(&RevealRequest<'_>) -> bool + Send + Sync;
+
+static POLICIES: RwLock<Vec<Arc<RevealPolicy>>> = RwLock::new(Vec::new());
+
+/// Which characters of a secret [`SensitiveString::reveal`] shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialReveal {
+    /// The first `first` and last `last` characters.
+    FirstLast { first: usize, last: usize },
+    /// The first N characters.
+    First(usize),
+    /// The last N characters.
+    Last(usize),
+}
+
+impl PartialReveal {
+    /// Returns the (leading, trailing) character counts to show for a secret of `chars` characters.
+    fn visible(self, chars: usize) -> (usize, usize) {
+        let (first, last) = match self {
+            PartialReveal::FirstLast { first, last } => (first, last),
+            PartialReveal::First(first) => (first, 0),
+            PartialReveal::Last(last) => (0, last),
+        };
+        let budget = chars / 2;
+        let last = last.min(budget);
+        (first.min(budget - last), last)
+    }
+}
+
+/// A partial reveal, as seen by a policy added with [`add_reveal_policy`].
+#[derive(Debug, Clone, Copy)]
+pub struct RevealRequest<'a> {
+    /// The secret's label, from [`SensitiveString::with_label`].
+    pub label: Option<&'a str>,
+    /// What was asked to be shown.
+    pub reveal: PartialReveal,
+}
+
+/// Adds a policy consulted before every partial reveal; returning `false` masks the whole secret.
+pub fn add_reveal_policy(policy: impl Fn(&RevealRequest<'_>) -> bool + Send + Sync + 'static) {
+    POLICIES
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Arc::new(policy));
+}
+
+/// A partially revealed secret, safe to display.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Masked(String);
+
+impl Masked {
+    /// Returns the masked text, e.g. `…cdef`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Masked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for Masked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Masked({:?})", self.0)
+    }
+}
+
+impl PartialEq<str> for Masked {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Masked {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Masked {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+fn allowed(request: &RevealRequest<'_>) -> bool {
+    let policies = POLICIES.read().unwrap_or_else(|e| e.into_inner());
+    policies.iter().all(|policy| policy(request))
+}
+
+impl SensitiveString {
+    /// Returns the secret with all but the requested ends replaced by `…`,
+    /// or `[REDACTED]` if a policy refuses the reveal.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sensitive_string::reveal::PartialReveal;
+    /// use sensitive_string::SensitiveString;
+    ///
+    /// let card = SensitiveString::from("4111111111111111");
+    /// assert_eq!(card.reveal(PartialReveal::Last(4)), "…1111");
+    /// ```
+    #[track_caller]
+    pub fn reveal(&self, reveal: PartialReveal) -> String {
+        let request = RevealRequest {
+            label: self.label(),
+            reveal,
+        };
+        if !allowed(&request) {
+            return PLACEHOLDER.to_string();
+        }
+        #[cfg(feature = "access-policy")]
+        if crate::access::check(self, "reveal").is_err() {
+            return PLACEHOLDER.to_string();
+        }
+        #[cfg(feature = "audit")]
+        crate::audit::record(self, "reveal");
+
+        let chars = self.value.chars().count();
+        let (first, last) = reveal.visible(chars);
+        let head: String = self.value.chars().take(first).collect();
+        let tail: String = self.value.chars().skip(chars - last).collect();
+        format!("{}{}{}", head, ELLIPSIS, tail)
+    }
+
+    /// Shows only the last `visible_suffix` characters, e.g. `…cdef`.
+    ///
+    /// Like [`reveal`](Self::reveal), never shows more than half of the
+    /// secret, and masks all of it if a policy refuses.
+    ///
+    /// ```
+    /// use sensitive_string::SensitiveString;
+    ///
+    /// let key = SensitiveString::from("ak_0123456789abcdef");
+    /// assert_eq!(format!("ends in {}", key.masked(4)), "ends in …cdef");
+    /// assert_eq!(SensitiveString::from("1234").masked(4), "…34");
+    /// ```
+    #[track_caller]
+    pub fn masked(&self, visible_suffix: usize) -> Masked {
+        Masked(self.reveal(PartialReveal::Last(visible_suffix)))
+    }
+
+    /// Shows only the first `visible_prefix` characters, e.g. `ak_…`.
+    ///
+    /// The same limits as [`masked`](Self::masked) apply.
+    #[track_caller]
+    pub fn masked_prefix(&self, visible_prefix: usize) -> Masked {
+        Masked(self.reveal(PartialReveal::First(visible_prefix)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_never_reveals_more_than_half() {
+        let reveal = |value: &str, how| SensitiveString::from(value).reveal(how);
+        let both = PartialReveal::FirstLast { first: 3, last: 4 };
+        assert_eq!(reveal("sk_live_abcdef4f2a", both), "sk_…4f2a");
+        assert_eq!(reveal("abcdefghij", both), "a…ghij");
+        assert_eq!(reveal("abcdef", both), "…def");
+        assert_eq!(reveal("abcdef", PartialReveal::First(2)), "ab…");
+        assert_eq!(reveal("", both), "…");
+        assert_eq!(reveal("ünïcødé!", PartialReveal::Last(3)), "…dé!");
+    }
+
+    #[test]
+    fn test_policies_can_refuse_by_label() {
+        add_reveal_policy(|request| request.label != Some("reveal-test-denied"));
+        let denied = SensitiveString::from("hunter2hunter2").with_label("reveal-test-denied");
+        assert_eq!(denied.reveal(PartialReveal::Last(2)), PLACEHOLDER);
+        let allowed = SensitiveString::from("hunter2hunter2").with_label("reveal-test-allowed");
+        assert_eq!(allowed.reveal(PartialReveal::Last(2)), "…r2");
+        assert_eq!(denied.masked(2), PLACEHOLDER);
+    }
+
+    #[test]
+    fn test_masked_values() {
+        let card = SensitiveString::from("4111111111111111");
+        let suffix = card.masked(4);
+        assert_eq!(suffix.to_string(), "…1111");
+        assert_eq!(format!("{:?}", suffix), "Masked(\"…1111\")");
+        assert_eq!(card.masked_prefix(6), "411111…");
+        assert_eq!(card.masked(100).as_str().chars().count(), 9);
+    }
+}
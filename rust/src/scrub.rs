@@ -0,0 +1,684 @@
+//! Scrubbing known secrets out of free-form text.
+//!
+//! Wrapping a secret keeps it out of `Display` and `Debug`, but the plaintext
+//! still leaves the wrapper whenever it is used, and from there it can end up
+//! in log lines built by other code. A [`Scrubber`] holds the secrets an
+//! application knows about and replaces every occurrence of them in a piece of
+//! text with the secret's hash.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::scrub::Scrubber;
+//! use sensitive_string::SensitiveString;
+//!
+//! let token = SensitiveString::from("tok-123");
+//! let scrubber = Scrubber::new().secret(&token);
+//!
+//! let line = scrubber.scrub("GET /api?token=tok-123");
+//! assert_eq!(line, format!("GET /api?token={}", token));
+//! ```
+//!
+//! # Writers
+//!
+//! Third-party code prints secrets too, e.g. an HTTP client dumping headers.
+//! [`ScrubbingWriter`] wraps any `io::Write` (stdout, a log file) and
+//! [`ScrubbingFmtWriter`] any `fmt::Write`, scrubbing everything written
+//! through them. A secret split across two writes is still replaced: bytes
+//! that might be the start of a secret are held back until the next write,
+//! `flush`, or drop.
+//!
+//! ```
+//! use sensitive_string::scrub::{Scrubber, ScrubbingWriter};
+//! use sensitive_string::SensitiveString;
+//! use std::io::Write;
+//!
+//! let token = SensitiveString::from("tok-123");
+//! let mut out = ScrubbingWriter::new(Vec::new(), Scrubber::new().secret(&token));
+//! write!(out, "Authorization: Bearer tok-").unwrap();
+//! writeln!(out, "123").unwrap();
+//! out.flush().unwrap();
+//! assert_eq!(out.get_ref(), format!("Authorization: Bearer {}\n", token).as_bytes());
+//! ```
+//!
+//! # Async pipelines
+//!
+//! With the `futures` feature, `ScrubbingSink` and
+//! `ScrubStreamExt::scrubbed` insert a scrubber into a `Sink<String>` or
+//! `Stream<Item = String>` with a single combinator.
+
+use crate::SensitiveString;
+use aho_corasick::{AhoCorasick, MatchKind};
+use std::borrow::Cow;
+use std::sync::OnceLock;
+use std::{fmt, io};
+
+/// A set of known secrets to replace in arbitrary text.
+///
+/// Of several secrets starting at the same position, the longest is replaced,
+/// so a secret that contains another registered secret is still replaced as a whole.
+#[derive(Clone, Debug, Default)]
+pub struct Scrubber {
+    secrets: Vec<SensitiveString>,
+    /// Finds every secret in one pass; rebuilt on first use after a change.
+    matcher: OnceLock<AhoCorasick>,
+}
+
+impl Scrubber {
+    /// Creates a scrubber with no secrets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `secret` and returns the scrubber, for building in one expression.
+    pub fn secret(mut self, secret: &SensitiveString) -> Self {
+        self.add(secret);
+        self
+    }
+
+    /// Adds `secret` to the set of values to replace. Empty secrets are ignored.
+    pub fn add(&mut self, secret: &SensitiveString) {
+        if secret.is_empty() {
+            return;
+        }
+        if let Err(at) = self.position(secret) {
+            self.secrets.insert(at, secret.clone());
+            self.matcher = OnceLock::new();
+        }
+    }
+
+    /// Removes `secret`, returning true if it was present.
+    pub fn remove(&mut self, secret: &SensitiveString) -> bool {
+        match self.position(secret) {
+            Ok(at) => {
+                self.secrets.remove(at);
+                self.matcher = OnceLock::new();
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Finds `secret` in the secrets, which are kept sorted longest first and
+    /// then by plaintext, or the position where it would be inserted.
+    fn position(&self, secret: &SensitiveString) -> Result<usize, usize> {
+        self.secrets.binary_search_by(|probe| {
+            secret
+                .value
+                .len()
+                .cmp(&probe.value.len())
+                .then_with(|| probe.value.cmp(&secret.value))
+        })
+    }
+
+    /// Returns the matcher for the current secrets, preferring the longest
+    /// of several secrets that start at the same position.
+    fn matcher(&self) -> &AhoCorasick {
+        self.matcher.get_or_init(|| {
+            AhoCorasick::builder()
+                .match_kind(MatchKind::LeftmostLongest)
+                .build(self.secrets.iter().map(|secret| secret.value.as_bytes()))
+                .expect("secrets fit in an Aho-Corasick automaton")
+        })
+    }
+
+    /// Returns the secrets, longest first.
+    pub(crate) fn secrets(&self) -> &[SensitiveString] {
+        &self.secrets
+    }
+
+    /// Returns true if no secrets have been added.
+    pub fn is_empty(&self) -> bool {
+        self.secrets.is_empty()
+    }
+
+    /// Returns true if `text` contains any of the secrets.
+    pub fn contains_secret(&self, text: &str) -> bool {
+        self.matcher().is_match(text)
+    }
+
+    /// Replaces every occurrence of a secret in `text` with its hash.
+    ///
+    /// The text is searched once, so a hash inserted for one secret is never
+    /// searched for the others. Borrows `text` unchanged when it contains no secrets.
+    pub fn scrub<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let matcher = self.matcher();
+        if !matcher.is_match(text) {
+            return Cow::Borrowed(text);
+        }
+        let mut scrubbed = String::with_capacity(text.len());
+        matcher.replace_all_with(text, &mut scrubbed, |found, _, out| {
+            self.secrets[found.pattern()].with_hash_str(|digest| out.push_str(digest));
+            true
+        });
+        Cow::Owned(scrubbed)
+    }
+
+    /// Like [`scrub`](Self::scrub), for bytes that may not be UTF-8.
+    fn scrub_bytes(&self, bytes: &[u8]) -> Vec<u8> {
+        let mut scrubbed = Vec::with_capacity(bytes.len());
+        self.matcher()
+            .replace_all_with_bytes(bytes, &mut scrubbed, |found, _, out| {
+                self.secrets[found.pattern()]
+                    .with_hash_str(|digest| out.extend_from_slice(digest.as_bytes()));
+                true
+            });
+        scrubbed
+    }
+
+    /// Returns how much of `bytes` can be written without splitting a secret
+    /// that might continue in the next write.
+    fn safe_prefix_len(&self, bytes: &[u8]) -> usize {
+        let longest = self.secrets.first().map_or(0, |secret| secret.len());
+        let mut cut = bytes.len().saturating_sub(longest.saturating_sub(1));
+        while let Some(start) = self
+            .secrets
+            .iter()
+            .flat_map(|secret| {
+                let needle = secret.value.as_bytes();
+                find_all(bytes, needle).map(move |at| (at, at + needle.len()))
+            })
+            .filter(|&(start, end)| start < cut && cut < end)
+            .map(|(start, _)| start)
+            .min()
+        {
+            cut = start;
+        }
+        while cut < bytes.len() && cut > 0 && bytes[cut] & 0xC0 == 0x80 {
+            cut -= 1;
+        }
+        cut
+    }
+}
+
+/// Returns the start of every occurrence of `needle` in `haystack`, overlapping included.
+fn find_all<'a>(haystack: &'a [u8], needle: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    haystack
+        .windows(needle.len().max(1))
+        .enumerate()
+        .filter(move |(_, window)| !needle.is_empty() && *window == needle)
+        .map(|(at, _)| at)
+}
+
+/// Output held back by the scrubbing writers until it cannot be the start of a secret.
+#[derive(Debug, Default)]
+struct Pending(Vec<u8>);
+
+impl Pending {
+    /// Appends `buf` and returns the scrubbed output that is safe to write now.
+    fn push(&mut self, buf: &[u8], scrubber: &Scrubber) -> Vec<u8> {
+        self.0.extend_from_slice(buf);
+        let ready = scrubber.safe_prefix_len(&self.0);
+        let ready: Vec<u8> = self.0.drain(..ready).collect();
+        scrubber.scrub_bytes(&ready)
+    }
+
+    /// Returns everything held back, scrubbed.
+    fn take(&mut self, scrubber: &Scrubber) -> Vec<u8> {
+        scrubber.scrub_bytes(&std::mem::take(&mut self.0))
+    }
+}
+
+/// An `io::Write` adapter that scrubs known secrets from everything written through it.
+///
+/// Held-back bytes are written on `flush` and, ignoring errors, on drop.
+#[derive(Debug)]
+pub struct ScrubbingWriter<W: io::Write> {
+    inner: W,
+    scrubber: Scrubber,
+    pending: Pending,
+}
+
+impl<W: io::Write> ScrubbingWriter<W> {
+    /// Wraps `inner`, scrubbing its input with `scrubber`.
+    pub fn new(inner: W, scrubber: Scrubber) -> Self {
+        Self {
+            inner,
+            scrubber,
+            pending: Pending::default(),
+        }
+    }
+
+    /// Returns the scrubber, for adding secrets learned after startup.
+    pub fn scrubber_mut(&mut self) -> &mut Scrubber {
+        &mut self.scrubber
+    }
+
+    /// Returns the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+}
+
+impl<W: io::Write> io::Write for ScrubbingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let ready = self.pending.push(buf, &self.scrubber);
+        self.inner.write_all(&ready)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let rest = self.pending.take(&self.scrubber);
+        self.inner.write_all(&rest)?;
+        self.inner.flush()
+    }
+}
+
+impl<W: io::Write> Drop for ScrubbingWriter<W> {
+    fn drop(&mut self) {
+        let rest = self.pending.take(&self.scrubber);
+        let _ = self.inner.write_all(&rest);
+    }
+}
+
+/// A `fmt::Write` adapter that scrubs known secrets from everything written through it.
+///
+/// Held-back text is written by [`flush`](Self::flush) and, ignoring errors, on drop.
+#[derive(Debug)]
+pub struct ScrubbingFmtWriter<W: fmt::Write> {
+    inner: W,
+    scrubber: Scrubber,
+    pending: Pending,
+}
+
+impl<W: fmt::Write> ScrubbingFmtWriter<W> {
+    /// Wraps `inner`, scrubbing its input with `scrubber`.
+    pub fn new(inner: W, scrubber: Scrubber) -> Self {
+        Self {
+            inner,
+            scrubber,
+            pending: Pending::default(),
+        }
+    }
+
+    /// Returns the scrubber, for adding secrets learned after startup.
+    pub fn scrubber_mut(&mut self) -> &mut Scrubber {
+        &mut self.scrubber
+    }
+
+    /// Returns the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Writes any held-back text.
+    pub fn flush(&mut self) -> fmt::Result {
+        let rest = self.pending.take(&self.scrubber);
+        self.inner.write_str(utf8(&rest))
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for ScrubbingFmtWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let ready = self.pending.push(s.as_bytes(), &self.scrubber);
+        self.inner.write_str(utf8(&ready))
+    }
+}
+
+impl<W: fmt::Write> Drop for ScrubbingFmtWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Converts output of the fmt writer back to text; it is cut only at char boundaries.
+fn utf8(bytes: &[u8]) -> &str {
+    std::str::from_utf8(bytes).expect("scrubbed UTF-8 is cut at char boundaries")
+}
+
+/// Replaces every occurrence of the scrubber's secrets in `buf`, in place.
+///
+/// Each secret is replaced with its digest, whose length depends on the
+/// secret's hasher (or the global one). Like [`Scrubber::scrub`], the buffer
+/// is searched once. When every secret found is at least as long as its
+/// digest, the buffer is compacted without allocating; when every one is
+/// shorter, it grows at most once. Returns the number of replacements made.
+pub fn redact_in_place(buf: &mut String, scrubber: &Scrubber) -> usize {
+    let found: Vec<_> = scrubber.matcher().find_iter(buf.as_str()).collect();
+    if found.is_empty() {
+        return 0;
+    }
+    let mut digests = vec![None; scrubber.secrets.len()];
+    for found in &found {
+        digests[found.pattern()]
+            .get_or_insert_with(|| scrubber.secrets[found.pattern()].hash_string());
+    }
+    let matches: Vec<(usize, usize, &str)> = found
+        .iter()
+        .map(|found| {
+            let digest = digests[found.pattern()].as_deref();
+            (
+                found.start(),
+                found.end(),
+                digest.expect("digest of every match"),
+            )
+        })
+        .collect();
+    splice(buf, &matches);
+    matches.len()
+}
+
+impl SensitiveString {
+    /// Replaces every occurrence of this secret in `buf` with its hash, in place.
+    ///
+    /// Returns the number of replacements. See [`redact_in_place`] for
+    /// scrubbing several secrets at once.
+    pub fn redact_in_place(&self, buf: &mut String) -> usize {
+        if self.value.is_empty() {
+            return 0;
+        }
+        let replacement = self.hash_string();
+        let matches: Vec<(usize, usize, &str)> = buf
+            .match_indices(&self.value)
+            .map(|(at, _)| (at, at + self.value.len(), replacement.as_str()))
+            .collect();
+        if !matches.is_empty() {
+            splice(buf, &matches);
+        }
+        matches.len()
+    }
+}
+
+/// Replaces each `(start, end, replacement)` of `matches`, which are in order
+/// and do not overlap, reusing the buffer's allocation where possible.
+fn splice(buf: &mut String, matches: &[(usize, usize, &str)]) {
+    let mut bytes = std::mem::take(buf).into_bytes();
+    let old_len = bytes.len();
+
+    if matches
+        .iter()
+        .all(|&(start, end, replacement)| replacement.len() <= end - start)
+    {
+        let (mut read, mut write) = (0, 0);
+        for &(start, end, replacement) in matches {
+            bytes.copy_within(read..start, write);
+            write += start - read;
+            bytes[write..write + replacement.len()].copy_from_slice(replacement.as_bytes());
+            write += replacement.len();
+            read = end;
+        }
+        bytes.copy_within(read..old_len, write);
+        bytes.truncate(write + old_len - read);
+    } else if matches
+        .iter()
+        .all(|&(start, end, replacement)| replacement.len() >= end - start)
+    {
+        let growth: usize = matches
+            .iter()
+            .map(|&(start, end, replacement)| replacement.len() - (end - start))
+            .sum();
+        bytes.resize(old_len + growth, 0);
+        let (mut read_end, mut write_end) = (old_len, old_len + growth);
+        for &(start, end, replacement) in matches.iter().rev() {
+            let tail = end..read_end;
+            write_end -= tail.len();
+            bytes.copy_within(tail, write_end);
+            write_end -= replacement.len();
+            bytes[write_end..write_end + replacement.len()].copy_from_slice(replacement.as_bytes());
+            read_end = start;
+        }
+    } else {
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut read = 0;
+        for &(start, end, replacement) in matches {
+            out.extend_from_slice(&bytes[read..start]);
+            out.extend_from_slice(replacement.as_bytes());
+            read = end;
+        }
+        out.extend_from_slice(&bytes[read..]);
+        bytes = out;
+    }
+
+    *buf = String::from_utf8(bytes)
+        .expect("replacing whole UTF-8 substrings with ASCII keeps the buffer valid");
+}
+
+#[cfg(feature = "futures")]
+pub use self::adapters::{ScrubStreamExt, Scrubbed, ScrubbingSink};
+
+#[cfg(feature = "futures")]
+mod adapters {
+    use super::Scrubber;
+    use futures_core::Stream;
+    use futures_sink::Sink;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    pin_project_lite::pin_project! {
+        /// A `Sink<String>` that scrubs each item before forwarding it to the inner sink.
+        #[derive(Debug)]
+        pub struct ScrubbingSink<S> {
+            #[pin]
+            inner: S,
+            scrubber: Scrubber,
+        }
+    }
+
+    impl<S> ScrubbingSink<S> {
+        /// Wraps `inner` so that every item sent through it is scrubbed first.
+        pub fn new(inner: S, scrubber: Scrubber) -> Self {
+            Self { inner, scrubber }
+        }
+
+        /// Returns the wrapped sink.
+        pub fn into_inner(self) -> S {
+            self.inner
+        }
+    }
+
+    impl<S: Sink<String>> Sink<String> for ScrubbingSink<S> {
+        type Error = S::Error;
+
+        fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+            self.project().inner.poll_ready(cx)
+        }
+
+        fn start_send(self: Pin<&mut Self>, item: String) -> Result<(), S::Error> {
+            let this = self.project();
+            let item = match this.scrubber.scrub(&item) {
+                std::borrow::Cow::Borrowed(_) => item,
+                std::borrow::Cow::Owned(scrubbed) => scrubbed,
+            };
+            this.inner.start_send(item)
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+            self.project().inner.poll_flush(cx)
+        }
+
+        fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+            self.project().inner.poll_close(cx)
+        }
+    }
+
+    pin_project_lite::pin_project! {
+        /// Stream returned by [`ScrubStreamExt::scrubbed`].
+        #[derive(Debug)]
+        #[must_use = "streams do nothing unless polled"]
+        pub struct Scrubbed<St> {
+            #[pin]
+            inner: St,
+            scrubber: Scrubber,
+        }
+    }
+
+    impl<St: Stream<Item = String>> Stream for Scrubbed<St> {
+        type Item = String;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<String>> {
+            let this = self.project();
+            this.inner.poll_next(cx).map(|item| {
+                item.map(|line| match this.scrubber.scrub(&line) {
+                    std::borrow::Cow::Borrowed(_) => line,
+                    std::borrow::Cow::Owned(scrubbed) => scrubbed,
+                })
+            })
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            self.inner.size_hint()
+        }
+    }
+
+    /// Adds [`scrubbed`](Self::scrubbed) to every `Stream<Item = String>`.
+    pub trait ScrubStreamExt: Stream<Item = String> + Sized {
+        /// Scrubs each item of the stream with `scrubber`.
+        fn scrubbed(self, scrubber: Scrubber) -> Scrubbed<Self> {
+            Scrubbed {
+                inner: self,
+                scrubber,
+            }
+        }
+    }
+
+    impl<St: Stream<Item = String>> ScrubStreamExt for St {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writer_scrubs_secret_split_across_writes() {
+        use std::io::Write;
+
+        let secret = SensitiveString::from("hunter2");
+        let mut out = ScrubbingWriter::new(Vec::new(), Scrubber::new().secret(&secret));
+        for chunk in ["pass", "word=hun", "t", "er2; user=", "hunter"] {
+            out.write_all(chunk.as_bytes()).unwrap();
+        }
+        assert!(!out.get_ref().ends_with(b"hunter"));
+        out.flush().unwrap();
+        assert_eq!(
+            String::from_utf8(out.get_ref().clone()).unwrap(),
+            format!("password={}; user=hunter", secret)
+        );
+    }
+
+    #[test]
+    fn test_fmt_writer_keeps_multibyte_chars_whole() {
+        use std::fmt::Write;
+
+        let secret = SensitiveString::from("ключ");
+        let mut out = String::new();
+        {
+            let mut writer = ScrubbingFmtWriter::new(&mut out, Scrubber::new().secret(&secret));
+            write!(writer, "é ключ ").unwrap();
+            write!(writer, "кл").unwrap();
+            write!(writer, "юч é").unwrap();
+        }
+        assert_eq!(out, format!("é {0} {0} é", secret));
+    }
+
+    #[test]
+    fn test_scrub_replaces_all_occurrences() {
+        let secret = SensitiveString::from("abc");
+        let scrubber = Scrubber::new().secret(&secret);
+        assert_eq!(
+            scrubber.scrub("abc and abc"),
+            format!("{0} and {0}", secret)
+        );
+    }
+
+    #[test]
+    fn test_scrub_borrows_clean_text() {
+        let scrubber = Scrubber::new().secret(&SensitiveString::from("abc"));
+        assert!(matches!(scrubber.scrub("nothing here"), Cow::Borrowed(_)));
+        assert!(!scrubber.contains_secret("nothing here"));
+    }
+
+    #[test]
+    fn test_longer_secrets_win() {
+        let short = SensitiveString::from("pass");
+        let long = SensitiveString::from("password123");
+        let scrubber = Scrubber::new().secret(&short).secret(&long);
+        assert_eq!(scrubber.scrub("password123"), long.to_string());
+    }
+
+    #[test]
+    fn test_hashes_are_not_rescanned() {
+        let long = SensitiveString::from("tok-123");
+        let short = SensitiveString::from("a");
+        let scrubber = Scrubber::new().secret(&long).secret(&short);
+        let expected = format!("{} {}", long, short);
+        assert_eq!(scrubber.scrub("tok-123 a"), expected);
+        assert_eq!(scrubber.scrub_bytes(b"tok-123 a"), expected.as_bytes());
+    }
+
+    #[test]
+    fn test_add_keeps_one_copy_longest_first() {
+        let mut scrubber = Scrubber::new();
+        for value in ["bb", "a", "ccc", "bb", "dd", "a"] {
+            scrubber.add(&SensitiveString::from(value));
+        }
+        let values: Vec<&str> = scrubber
+            .secrets()
+            .iter()
+            .map(|s| s.value.as_str())
+            .collect();
+        assert_eq!(values, ["ccc", "bb", "dd", "a"]);
+
+        assert!(scrubber.remove(&SensitiveString::from("dd")));
+        assert!(!scrubber.remove(&SensitiveString::from("dd")));
+        assert_eq!(scrubber.secrets().len(), 3);
+    }
+
+    #[test]
+    fn test_redact_in_place_shrinks_without_reallocating() {
+        let secret = SensitiveString::new("k".repeat(100));
+        let mut buf = format!("a{}b{}c", secret.value, secret.value);
+        let (ptr, capacity) = (buf.as_ptr(), buf.capacity());
+
+        assert_eq!(
+            redact_in_place(&mut buf, &Scrubber::new().secret(&secret)),
+            2
+        );
+        assert_eq!(buf, format!("a{0}b{0}c", secret));
+        assert_eq!((buf.as_ptr(), buf.capacity()), (ptr, capacity));
+    }
+
+    #[test]
+    fn test_redact_in_place_grows_for_short_secrets() {
+        let short = SensitiveString::from("pw");
+        let other = SensitiveString::from("tok");
+        let mut buf = "pw=pw; tok=tok; ü".to_string();
+        let scrubber = Scrubber::new().secret(&short).secret(&other);
+
+        assert_eq!(redact_in_place(&mut buf, &scrubber), 4);
+        assert_eq!(buf, format!("{0}={0}; {1}={1}; ü", short, other));
+    }
+
+    #[test]
+    fn test_redact_in_place_does_not_rescan_hashes() {
+        let long = SensitiveString::new("k".repeat(100));
+        let short = SensitiveString::from("a");
+        let mut buf = format!("{} a", long.value);
+        let scrubber = Scrubber::new().secret(&long).secret(&short);
+
+        assert_eq!(redact_in_place(&mut buf, &scrubber), 2);
+        assert_eq!(buf, format!("{} {}", long, short));
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn test_stream_and_sink_adapters() {
+        use futures::{executor::block_on, stream, SinkExt, StreamExt};
+
+        let secret = SensitiveString::from("s3cr3t");
+        let scrubber = Scrubber::new().secret(&secret);
+
+        let lines: Vec<String> = block_on(
+            stream::iter(vec!["a s3cr3t".to_string(), "b".to_string()])
+                .scrubbed(scrubber.clone())
+                .collect(),
+        );
+        assert_eq!(lines, vec![format!("a {}", secret), "b".to_string()]);
+
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let mut sink = ScrubbingSink::new(tx, scrubber);
+        block_on(sink.send("key=s3cr3t".to_string())).unwrap();
+        drop(sink);
+        let received: Vec<String> = block_on(rx.collect());
+        assert_eq!(received, vec![format!("key={}", secret)]);
+    }
+}
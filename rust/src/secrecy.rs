@@ -0,0 +1,75 @@
+//! Conversions to and from the `secrecy` crate.
+//!
+//! Workspaces that use `secrecy::SecretString` in some crates and
+//! `SensitiveString` in others would otherwise bridge them by exposing the
+//! plaintext by hand at every boundary. With the `secrecy` feature the two
+//! convert with `From`, and `SensitiveString` implements
+//! [`ExposeSecret<str>`](::secrecy::ExposeSecret) so it can be passed to code
+//! written against that trait.
+//!
+//! # Example
+//!
+//! ```
+//! use secrecy::{ExposeSecret, SecretString};
+//! use sensitive_string::SensitiveString;
+//!
+//! let from_config = SecretString::from("hunter2");
+//! let password = SensitiveString::from(from_config);
+//! # #[cfg(not(feature = "no-plaintext-getters"))]
+//! assert_eq!(password.expose_secret(), "hunter2");
+//!
+//! let back: SecretString = password.into();
+//! assert_eq!(back.expose_secret(), "hunter2");
+//! ```
+//!
+//! Converting into a `SecretString`, and `expose_secret`, count as plaintext
+//! reads for auditing and access policies. `ExposeSecret` is not implemented
+//! with the `no-plaintext-getters` feature.
+
+use crate::SensitiveString;
+use ::secrecy::{ExposeSecret, SecretString};
+
+impl From<SecretString> for SensitiveString {
+    fn from(secret: SecretString) -> Self {
+        Self::from(secret.expose_secret())
+    }
+}
+
+impl From<SensitiveString> for SecretString {
+    #[track_caller]
+    fn from(secret: SensitiveString) -> Self {
+        secret.record_access("into_secret_string");
+        SecretString::from(secret.value)
+    }
+}
+
+#[cfg(not(feature = "no-plaintext-getters"))]
+impl ExposeSecret<str> for SensitiveString {
+    #[track_caller]
+    fn expose_secret(&self) -> &str {
+        self.record_access("expose_secret");
+        &self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips_through_secret_string() {
+        let secret = SensitiveString::from(SecretString::from("hunter2"));
+        assert_eq!(secret, SensitiveString::from("hunter2"));
+        let back = SecretString::from(secret);
+        assert_eq!(back.expose_secret(), "hunter2");
+    }
+
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[test]
+    fn test_expose_secret_through_the_trait() {
+        fn length(secret: &impl ExposeSecret<str>) -> usize {
+            secret.expose_secret().len()
+        }
+        assert_eq!(length(&SensitiveString::from("hunter2")), 7);
+    }
+}
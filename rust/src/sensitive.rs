@@ -0,0 +1,255 @@
+//! A generic wrapper for sensitive values of any type.
+//!
+//! `SensitiveString` covers the common case, but secrets also come as numeric
+//! PINs, structs of tokens, or key pairs. [`Sensitive<T>`] wraps any `T` and
+//! redacts it in `Display`, `Debug`, and `Serialize`:
+//!
+//! - [`Sensitive::hashed`] (for `T: Serialize`, with the `serde` feature)
+//!   shows a hash of a canonical encoding of the value, so equal values can
+//!   still be correlated in logs.
+//! - [`Sensitive::new`] works for any `T` and shows a fixed `[REDACTED]`.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{Sensitive, SensitiveString};
+//!
+//! let pin = Sensitive::new(1234u32);
+//! assert_eq!(pin.to_string(), "[REDACTED]");
+//! assert_eq!(pin.expose(|pin| *pin), 1234);
+//!
+//! # #[cfg(feature = "serde")]
+//! # {
+//! let token = Sensitive::hashed("hunter2".to_string());
+//! assert_eq!(token.to_string(), SensitiveString::from("hunter2").to_string());
+//! # }
+//! ```
+//!
+//! The canonical encoding is the raw bytes for strings (so `Sensitive<String>`
+//! and `SensitiveString` agree) and compact JSON with sorted object keys for
+//! everything else.
+
+use crate::policy::PLACEHOLDER;
+use crate::SensitiveString;
+use std::fmt;
+
+/// A wrapper that keeps any value out of logs and serialized output.
+///
+/// Equality and `Hash` compare the wrapped values.
+#[derive(Clone)]
+pub struct Sensitive<T> {
+    pub(crate) value: T,
+    redacted: Option<String>,
+}
+
+impl<T> Sensitive<T> {
+    /// Wraps `value`, which will be shown as [`PLACEHOLDER`].
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            redacted: None,
+        }
+    }
+
+    /// Explicitly retrieves the wrapped value.
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
+    pub fn get_value(&self) -> &T {
+        crate::record_plaintext_read(None, "get_value");
+        &self.value
+    }
+
+    /// Unwraps the value.
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[track_caller]
+    pub fn into_inner(self) -> T {
+        crate::record_plaintext_read(None, "into_inner");
+        self.value
+    }
+
+    fn redacted(&self) -> &str {
+        self.redacted.as_deref().unwrap_or(PLACEHOLDER)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> Sensitive<T> {
+    /// Wraps `value`, which will be shown as a hash of its canonical encoding.
+    ///
+    /// The hash is computed once, here; the value cannot be mutated afterwards.
+    pub fn hashed(value: T) -> Self {
+        let redacted = canonical_digest(&value);
+        Self {
+            value,
+            redacted: Some(redacted),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn canonical_digest<T: serde::Serialize + ?Sized>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(text)) => crate::redact_bytes(text.as_bytes()),
+        Ok(json) => {
+            let mut encoded = String::new();
+            write_canonical(&json, &mut encoded);
+            let redacted = crate::redact_bytes(encoded.as_bytes());
+            // SAFETY: zeros are valid UTF-8, and the string is dropped next.
+            crate::wipe(unsafe { encoded.as_bytes_mut() });
+            redacted
+        }
+        Err(_) => PLACEHOLDER.to_string(),
+    }
+}
+
+/// Writes `json` compactly with object keys in byte order.
+///
+/// The keys are sorted here because the order of a `serde_json::Map` depends
+/// on whether any crate in the build enables `serde_json/preserve_order`.
+#[cfg(feature = "serde")]
+fn write_canonical(json: &serde_json::Value, out: &mut String) {
+    use serde_json::Value;
+
+    let quote = |text: &str| serde_json::to_string(text).expect("strings always serialize");
+    match json {
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by_key(|&(key, _)| key);
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&quote(key));
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        Value::String(text) => out.push_str(&quote(text)),
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+impl<T> fmt::Display for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.redacted())
+    }
+}
+
+impl<T> fmt::Debug for Sensitive<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Sensitive({})", self.redacted())
+    }
+}
+
+impl<T: PartialEq> PartialEq for Sensitive<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Sensitive<T> {}
+
+impl<T: std::hash::Hash> std::hash::Hash for Sensitive<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+/// Serializes the same redacted form `Display` shows.
+#[cfg(feature = "serde")]
+impl<T> serde::Serialize for Sensitive<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.redacted())
+    }
+}
+
+/// Keeps the secret's redacted form, including any attached hasher's.
+impl From<SensitiveString> for Sensitive<String> {
+    fn from(secret: SensitiveString) -> Self {
+        let redacted = secret.hash_string();
+        Self {
+            value: secret.value,
+            redacted: Some(redacted),
+        }
+    }
+}
+
+impl From<Sensitive<String>> for SensitiveString {
+    fn from(secret: Sensitive<String>) -> Self {
+        SensitiveString::new(secret.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholder_for_any_type() {
+        struct NotSerializable(#[allow(dead_code)] u8);
+        let value = Sensitive::new(NotSerializable(7));
+        assert_eq!(value.to_string(), "[REDACTED]");
+        assert_eq!(format!("{:?}", value), "Sensitive([REDACTED])");
+    }
+
+    #[test]
+    fn test_sensitive_string_interop() {
+        let secret = SensitiveString::from("hunter2");
+        let generic: Sensitive<String> = secret.clone().into();
+        assert_eq!(generic.to_string(), secret.to_string());
+        assert_eq!(SensitiveString::from(generic), secret);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_hashed_struct_is_canonical() {
+        use std::collections::HashMap;
+
+        let a: HashMap<&str, u32> = [("x", 1), ("y", 2)].into_iter().collect();
+        let b: HashMap<&str, u32> = [("y", 2), ("x", 1)].into_iter().collect();
+        let (a, b) = (Sensitive::hashed(a), Sensitive::hashed(b));
+
+        assert_eq!(a.to_string(), b.to_string());
+        assert_ne!(a.to_string(), Sensitive::hashed(1234u32).to_string());
+        assert_eq!(serde_json::to_string(&a).unwrap(), format!("\"{}\"", a));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_hashed_sorts_keys_itself() {
+        #[derive(serde::Serialize)]
+        struct Login {
+            user: &'static str,
+            password: &'static str,
+            scopes: Vec<serde_json::Value>,
+        }
+
+        let nested: serde_json::Value = serde_json::json!({"z": 1, "a": {"y": null, "b": 1.5}});
+        let login = Login {
+            user: "alice",
+            password: "p\"w",
+            scopes: vec![nested, true.into()],
+        };
+        assert_eq!(
+            Sensitive::hashed(login).to_string(),
+            crate::redact_bytes(
+                br#"{"password":"p\"w","scopes":[{"a":{"b":1.5,"y":null},"z":1},true],"user":"alice"}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Serde helpers for fields that cannot be `SensitiveString`.
+//!
+//! A struct from another crate, or one shared with code that needs a plain
+//! `String`, cannot change its field types. [`redact`] serializes `String`,
+//! `Option<String>`, and `Vec<String>` fields the way `SensitiveString`
+//! would, following the redaction policy and any global hasher, and can be
+//! used with `#[serde(serialize_with = ...)]` or `#[serde(with = ...)]`.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::SensitiveString;
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Upstream {
+//!     user: String,
+//!     #[serde(serialize_with = "sensitive_string::serde::redact::serialize")]
+//!     password: String,
+//!     #[serde(with = "sensitive_string::serde::redact")]
+//!     tokens: Vec<String>,
+//! }
+//!
+//! let upstream = Upstream {
+//!     user: "app".into(),
+//!     password: "hunter2".into(),
+//!     tokens: vec!["tok-1".into()],
+//! };
+//! let json = serde_json::to_string(&upstream).unwrap();
+//! assert_eq!(
+//!     json,
+//!     format!(
+//!         r#"{{"user":"app","password":"{}","tokens":["{}"]}}"#,
+//!         SensitiveString::from("hunter2"),
+//!         SensitiveString::from("tok-1")
+//!     )
+//! );
+//! ```
+//!
+//! With `#[serde(with = ...)]`, deserialization reads the field as usual, so
+//! a redacted value read back in is kept as-is rather than rejected.
+//!
+//! # Writing plaintext on purpose
+//!
+//! Writing secrets to an encrypted vault file, or sending credentials to an
+//! authentication API, needs the plaintext. [`SerializePlaintext`] and
+//! [`plaintext`] serialize it for one value or one field, leaving the hashed
+//! default in place everywhere else. Each use is reported to the audit
+//! observer (with the `audit` feature) as a `serialize_plaintext` read.
+//!
+//! ```
+//! use sensitive_string::serde::SerializePlaintext;
+//! use sensitive_string::SensitiveString;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct VaultEntry {
+//!     #[serde(with = "sensitive_string::serde::plaintext")]
+//!     password: SensitiveString,
+//! }
+//!
+//! let entry = VaultEntry { password: SensitiveString::from("hunter2") };
+//! assert_eq!(serde_json::to_string(&entry).unwrap(), r#"{"password":"hunter2"}"#);
+//!
+//! let token = SensitiveString::from("tok-1");
+//! assert_eq!(serde_json::to_string(&SerializePlaintext(&token)).unwrap(), r#""tok-1""#);
+//! ```
+
+use crate::SensitiveString;
+use serde::{Serialize, Serializer};
+
+/// A field type that [`redact`] can serialize in redacted form.
+pub trait SerializeRedacted {
+    /// Serializes the redacted form of this value.
+    fn serialize_redacted<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+}
+
+impl SerializeRedacted for str {
+    fn serialize_redacted<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&crate::redact_plaintext(self))
+    }
+}
+
+impl SerializeRedacted for String {
+    fn serialize_redacted<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_str().serialize_redacted(serializer)
+    }
+}
+
+impl<T: SerializeRedacted> SerializeRedacted for Option<T> {
+    fn serialize_redacted<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Some(value) => serializer.serialize_some(&Redacted(value)),
+            None => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<T: SerializeRedacted> SerializeRedacted for [T] {
+    fn serialize_redacted<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(Redacted))
+    }
+}
+
+impl<T: SerializeRedacted> SerializeRedacted for Vec<T> {
+    fn serialize_redacted<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_slice().serialize_redacted(serializer)
+    }
+}
+
+/// Serializes a borrowed value through its [`SerializeRedacted`] impl.
+struct Redacted<'a, T: ?Sized>(&'a T);
+
+impl<T: SerializeRedacted + ?Sized> Serialize for Redacted<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_redacted(serializer)
+    }
+}
+
+/// Redacts a plain field on serialization: `#[serde(with = "sensitive_string::serde::redact")]`.
+pub mod redact {
+    use super::SerializeRedacted;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Serializes `value` in redacted form.
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: SerializeRedacted + ?Sized,
+        S: Serializer,
+    {
+        value.serialize_redacted(serializer)
+    }
+
+    /// Deserializes the field unchanged.
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer)
+    }
+}
+
+/// Serializes the plaintext of a secret instead of its redacted form.
+#[derive(Debug, Clone, Copy)]
+pub struct SerializePlaintext<'a>(pub &'a SensitiveString);
+
+impl Serialize for SerializePlaintext<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.record_access("serialize_plaintext");
+        serializer.serialize_str(&self.0.value)
+    }
+}
+
+/// Writes a `SensitiveString` field as plaintext: `#[serde(with = "sensitive_string::serde::plaintext")]`.
+pub mod plaintext {
+    use super::SerializePlaintext;
+    use crate::SensitiveString;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serializes the plaintext of `value`.
+    pub fn serialize<S: Serializer>(
+        value: &SensitiveString,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        SerializePlaintext(value).serialize(serializer)
+    }
+
+    /// Deserializes a plaintext secret, as `SensitiveString`'s own `Deserialize` does.
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SensitiveString, D::Error> {
+        SensitiveString::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SensitiveString;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    struct Upstream {
+        #[serde(with = "super::redact")]
+        password: Option<String>,
+        #[serde(with = "super::redact")]
+        missing: Option<String>,
+    }
+
+    #[test]
+    fn test_option_fields_round_trip_through_with() {
+        let upstream = Upstream {
+            password: Some("hunter2".into()),
+            missing: None,
+        };
+        let json = serde_json::to_string(&upstream).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"password":"{}","missing":null}}"#,
+                SensitiveString::from("hunter2")
+            )
+        );
+
+        let read: Upstream = serde_json::from_str(r#"{"password":"p","missing":null}"#).unwrap();
+        assert_eq!(read.password.as_deref(), Some("p"));
+    }
+
+    #[test]
+    fn test_plaintext_is_only_written_when_asked_for() {
+        #[derive(Serialize)]
+        struct Export<'a> {
+            shown: &'a SensitiveString,
+            #[serde(serialize_with = "super::plaintext::serialize")]
+            stored: &'a SensitiveString,
+        }
+
+        let secret = SensitiveString::from("hunter2");
+        let json = serde_json::to_string(&Export {
+            shown: &secret,
+            stored: &secret,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            format!(r#"{{"shown":"{}","stored":"hunter2"}}"#, secret)
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Consistent sharding by secret.
+//!
+//! Routing layers often shard by API key so that one tenant's requests land
+//! on the same backend. [`SensitiveString::shard_for`] picks a shard from the
+//! secret's SHA-256 digest, so callers never handle the plaintext or pick a
+//! hash function themselves.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "hash")]
+//! # {
+//! use sensitive_string::SensitiveString;
+//!
+//! let api_key = SensitiveString::from("sk-live-123");
+//! let shard = api_key.shard_for(16);
+//! assert!(shard < 16);
+//! assert_eq!(shard, SensitiveString::from("sk-live-123").shard_for(16));
+//! # }
+//! ```
+//!
+//! Shards are assigned with jump consistent hashing: going from `n` to `n + 1`
+//! shards moves only about `1 / (n + 1)` of the keys, all of them to the new
+//! shard. The result depends only on the plaintext, not on the redaction
+//! policy or a global hasher, so it is stable across processes and releases.
+
+use crate::SensitiveString;
+use sha2::{Digest, Sha256};
+
+impl SensitiveString {
+    /// Returns the shard in `0..n_shards` for this secret.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n_shards` is zero.
+    pub fn shard_for(&self, n_shards: u32) -> u32 {
+        assert!(n_shards > 0, "n_shards must be at least 1");
+        let digest = Sha256::digest(self.value.as_bytes());
+        let mut key = [0u8; 8];
+        key.copy_from_slice(&digest[..8]);
+        jump_hash(u64::from_be_bytes(key), n_shards)
+    }
+}
+
+/// Jump consistent hash (Lamping and Veach, 2014).
+fn jump_hash(mut key: u64, n_buckets: u32) -> u32 {
+    let mut bucket: i64 = -1;
+    let mut next: i64 = 0;
+    while next < i64::from(n_buckets) {
+        bucket = next;
+        key = key.wrapping_mul(2_862_933_555_777_941_757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    bucket as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shards_are_in_range_and_spread() {
+        let mut counts = [0u32; 8];
+        for i in 0..800 {
+            let shard = SensitiveString::new(format!("key-{}", i)).shard_for(8);
+            counts[shard as usize] += 1;
+        }
+        assert!(counts.iter().all(|&count| (50..150).contains(&count)));
+        assert_eq!(SensitiveString::from("anything").shard_for(1), 0);
+    }
+
+    #[test]
+    fn test_growing_moves_keys_only_to_the_new_shard() {
+        let mut moved = 0;
+        for i in 0..1000 {
+            let key = SensitiveString::new(format!("key-{}", i));
+            let (before, after) = (key.shard_for(10), key.shard_for(11));
+            if before != after {
+                assert_eq!(after, 10);
+                moved += 1;
+            }
+        }
+        assert!((40..150).contains(&moved));
+    }
+}
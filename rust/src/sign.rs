@@ -0,0 +1,80 @@
+//! Signing messages with a secret as the HMAC key.
+//!
+//! Webhook senders sign each payload with a shared API secret, and
+//! receivers check the signature with the same secret. With the `crypto`
+//! feature, [`SensitiveString::sign_hmac_sha256`] and
+//! [`SensitiveString::verify_hmac_sha256`] do both without the secret
+//! leaving the wrapper.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::SensitiveString;
+//!
+//! let webhook_secret = SensitiveString::from("whsec_123");
+//! let payload = br#"{"event":"invoice.paid"}"#;
+//!
+//! let signature = webhook_secret.sign_hmac_sha256(payload);
+//! let header = format!("sha256={}", hex::encode(signature));
+//!
+//! let received = hex::decode(header.strip_prefix("sha256=").unwrap()).unwrap();
+//! assert!(webhook_secret.verify_hmac_sha256(payload, &received));
+//! ```
+//!
+//! Verification compares in constant time, so it is safe to use on
+//! signatures supplied by a client.
+
+use crate::SensitiveString;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+impl SensitiveString {
+    /// Returns the HMAC-SHA256 of `message`, keyed with this secret.
+    pub fn sign_hmac_sha256(&self, message: &[u8]) -> [u8; 32] {
+        self.record_access("sign_hmac_sha256");
+        let mut mac = self.hmac_sha256();
+        mac.update(message);
+        mac.finalize().into_bytes().into()
+    }
+
+    /// Returns true if `signature` is the HMAC-SHA256 of `message` keyed with this secret.
+    ///
+    /// The comparison takes the same time wherever the signatures differ.
+    pub fn verify_hmac_sha256(&self, message: &[u8], signature: &[u8]) -> bool {
+        self.record_access("verify_hmac_sha256");
+        let mut mac = self.hmac_sha256();
+        mac.update(message);
+        mac.verify_slice(signature).is_ok()
+    }
+
+    fn hmac_sha256(&self) -> Hmac<Sha256> {
+        Hmac::<Sha256>::new_from_slice(self.value.as_bytes())
+            .expect("HMAC accepts keys of any length")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_rfc4231_case_2() {
+        let key = SensitiveString::from("Jefe");
+        let signature = key.sign_hmac_sha256(b"what do ya want for nothing?");
+        assert_eq!(
+            hex::encode(signature),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_or_truncated_signatures() {
+        let key = SensitiveString::from("whsec_123");
+        let mut signature = key.sign_hmac_sha256(b"payload");
+        assert!(key.verify_hmac_sha256(b"payload", &signature));
+        assert!(!key.verify_hmac_sha256(b"payload2", &signature));
+        assert!(!key.verify_hmac_sha256(b"payload", &signature[..16]));
+        signature[0] ^= 1;
+        assert!(!key.verify_hmac_sha256(b"payload", &signature));
+    }
+}
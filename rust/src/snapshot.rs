@@ -0,0 +1,111 @@
+//! Explicit handoff of secrets between threads and tasks.
+//!
+//! `SensitiveString` is `Send + Clone`, so nothing stops code from cloning a
+//! secret into every spawned task. [`SendableSecret`] makes the crossing
+//! explicit: it is created with [`SensitiveString::snapshot`], cannot be
+//! cloned, and records where it was taken so the handoff can be traced.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::SensitiveString;
+//!
+//! let token = SensitiveString::from("worker-token");
+//! let snapshot = token.snapshot();
+//!
+//! let handle = std::thread::spawn(move || {
+//!     let token = snapshot.receive();
+//!     token.len()
+//! });
+//! assert_eq!(handle.join().unwrap(), 12);
+//! ```
+
+use crate::SensitiveString;
+use std::fmt;
+use std::panic::Location;
+
+/// A move-only copy of a secret intended to cross a thread or task boundary.
+///
+/// `Debug` shows the hash and the location the snapshot was taken at.
+pub struct SendableSecret {
+    value: SensitiveString,
+    origin: &'static Location<'static>,
+    origin_thread: Option<String>,
+}
+
+impl SendableSecret {
+    /// Unwraps the snapshot on the receiving side.
+    pub fn receive(self) -> SensitiveString {
+        self.value
+    }
+
+    /// Returns the source location where [`SensitiveString::snapshot`] was called.
+    pub fn origin(&self) -> &'static Location<'static> {
+        self.origin
+    }
+
+    /// Returns the name of the thread the snapshot was taken on, if it had one.
+    pub fn origin_thread(&self) -> Option<&str> {
+        self.origin_thread.as_deref()
+    }
+}
+
+impl fmt::Debug for SendableSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendableSecret")
+            .field("value", &self.value)
+            .field("origin", &format_args!("{}", self.origin))
+            .field("origin_thread", &self.origin_thread)
+            .finish()
+    }
+}
+
+impl SensitiveString {
+    /// Takes a snapshot of this secret for moving to another thread or task.
+    ///
+    /// Prefer this over `clone()` when handing a secret to spawned work: the
+    /// snapshot cannot be cloned again and remembers the call site.
+    #[track_caller]
+    pub fn snapshot(&self) -> SendableSecret {
+        SendableSecret {
+            value: self.clone(),
+            origin: Location::caller(),
+            origin_thread: std::thread::current().name().map(str::to_string),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_records_origin() {
+        let secret = SensitiveString::from("snapshot-secret");
+        let line = line!() + 1;
+        let snapshot = secret.snapshot();
+
+        assert_eq!(snapshot.origin().file(), file!());
+        assert_eq!(snapshot.origin().line(), line);
+    }
+
+    #[test]
+    fn test_snapshot_crosses_threads() {
+        let secret = SensitiveString::from("snapshot-secret");
+        let snapshot = secret.snapshot();
+        let received = std::thread::spawn(move || snapshot.receive())
+            .join()
+            .unwrap();
+        assert_eq!(received, secret);
+    }
+
+    #[cfg(feature = "hash")]
+    #[test]
+    fn test_snapshot_debug_is_redacted() {
+        let snapshot = SensitiveString::from("snapshot-secret").snapshot();
+        let debug = format!("{:?}", snapshot);
+        assert!(debug.contains("sha256:"));
+        assert!(debug.contains(file!()));
+        assert!(!debug.contains("snapshot-secret"));
+    }
+}
@@ -0,0 +1,251 @@
+//! SNMPv3 user-based security model key derivation.
+//!
+//! Implements the password-to-key and key localization algorithms from
+//! RFC 3414 (MD5, SHA-1) and RFC 7860 (SHA-2), so the authentication and
+//! privacy passphrases can go straight from a `SensitiveString` into a
+//! localized key without the caller ever holding the plaintext.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::snmp::AuthProtocol;
+//! use sensitive_string::SensitiveString;
+//!
+//! let passphrase = SensitiveString::from("maplesyrup");
+//! let engine_id = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2];
+//! let key = passphrase.snmp_localized_key(AuthProtocol::Sha256, &engine_id).unwrap();
+//! assert_eq!(key.len(), 32);
+//! println!("{:?}", key); // LocalizedKey(HmacSha256, 32 bytes)
+//! ```
+
+use crate::{SensitiveBytes, SensitiveString};
+use sha2::digest::Digest;
+use std::fmt;
+
+/// Number of bytes of repeated password hashed by the password-to-key algorithm.
+const EXPANSION_LEN: usize = 1_048_576;
+
+/// Minimum passphrase length accepted by the USM (RFC 3414, section 11.2).
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// The hash function used by an SNMPv3 authentication protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuthProtocol {
+    /// usmHMACMD5AuthProtocol (RFC 3414).
+    Md5,
+    /// usmHMACSHAAuthProtocol (RFC 3414).
+    Sha1,
+    /// usmHMAC128SHA224AuthProtocol (RFC 7860).
+    Sha224,
+    /// usmHMAC192SHA256AuthProtocol (RFC 7860).
+    Sha256,
+    /// usmHMAC256SHA384AuthProtocol (RFC 7860).
+    Sha384,
+    /// usmHMAC384SHA512AuthProtocol (RFC 7860).
+    Sha512,
+}
+
+/// Errors from SNMPv3 key derivation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnmpKeyError {
+    /// The passphrase is shorter than [`MIN_PASSPHRASE_LEN`] bytes.
+    PassphraseTooShort,
+    /// The engine ID is empty.
+    EmptyEngineId,
+}
+
+impl fmt::Display for SnmpKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnmpKeyError::PassphraseTooShort => write!(
+                f,
+                "SNMPv3 passphrase must be at least {} bytes",
+                MIN_PASSPHRASE_LEN
+            ),
+            SnmpKeyError::EmptyEngineId => write!(f, "SNMPv3 engine ID must not be empty"),
+        }
+    }
+}
+
+impl std::error::Error for SnmpKeyError {}
+
+/// A key localized to a specific SNMP engine.
+///
+/// `Debug` shows the protocol and length only.
+#[derive(Clone, PartialEq, Eq)]
+pub struct LocalizedKey {
+    protocol: AuthProtocol,
+    key: Vec<u8>,
+}
+
+impl LocalizedKey {
+    /// Returns the protocol this key was derived for.
+    pub fn protocol(&self) -> AuthProtocol {
+        self.protocol
+    }
+
+    /// Returns the raw key bytes, for handing to the SNMP engine.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Returns the key length in bytes.
+    pub fn len(&self) -> usize {
+        self.key.len()
+    }
+
+    /// Returns true if the key is empty.
+    pub fn is_empty(&self) -> bool {
+        self.key.is_empty()
+    }
+}
+
+impl fmt::Debug for LocalizedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LocalizedKey(Hmac{:?}, {} bytes)",
+            self.protocol,
+            self.key.len()
+        )
+    }
+}
+
+impl SensitiveString {
+    /// Derives the engine-independent master key (`Ku`) from this passphrase.
+    ///
+    /// The key is as good as the passphrase to anyone who can localize it, so
+    /// it comes back wrapped and redacted like the passphrase itself.
+    #[track_caller]
+    pub fn snmp_master_key(&self, protocol: AuthProtocol) -> Result<SensitiveBytes, SnmpKeyError> {
+        self.record_access("snmp_master_key");
+        self.master_key(protocol).map(SensitiveBytes::from)
+    }
+
+    /// Derives the key localized to `engine_id` (`Kul`) from this passphrase.
+    #[track_caller]
+    pub fn snmp_localized_key(
+        &self,
+        protocol: AuthProtocol,
+        engine_id: &[u8],
+    ) -> Result<LocalizedKey, SnmpKeyError> {
+        self.record_access("snmp_localized_key");
+        if engine_id.is_empty() {
+            return Err(SnmpKeyError::EmptyEngineId);
+        }
+        let mut master = self.master_key(protocol)?;
+        let key = match protocol {
+            AuthProtocol::Md5 => localize::<md5::Md5>(&master, engine_id),
+            AuthProtocol::Sha1 => localize::<sha1::Sha1>(&master, engine_id),
+            AuthProtocol::Sha224 => localize::<sha2::Sha224>(&master, engine_id),
+            AuthProtocol::Sha256 => localize::<sha2::Sha256>(&master, engine_id),
+            AuthProtocol::Sha384 => localize::<sha2::Sha384>(&master, engine_id),
+            AuthProtocol::Sha512 => localize::<sha2::Sha512>(&master, engine_id),
+        };
+        crate::wipe(&mut master);
+        Ok(LocalizedKey { protocol, key })
+    }
+
+    fn master_key(&self, protocol: AuthProtocol) -> Result<Vec<u8>, SnmpKeyError> {
+        if self.value.len() < MIN_PASSPHRASE_LEN {
+            return Err(SnmpKeyError::PassphraseTooShort);
+        }
+        let password = self.value.as_bytes();
+        Ok(match protocol {
+            AuthProtocol::Md5 => password_to_key::<md5::Md5>(password),
+            AuthProtocol::Sha1 => password_to_key::<sha1::Sha1>(password),
+            AuthProtocol::Sha224 => password_to_key::<sha2::Sha224>(password),
+            AuthProtocol::Sha256 => password_to_key::<sha2::Sha256>(password),
+            AuthProtocol::Sha384 => password_to_key::<sha2::Sha384>(password),
+            AuthProtocol::Sha512 => password_to_key::<sha2::Sha512>(password),
+        })
+    }
+}
+
+fn password_to_key<D: Digest>(password: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    let mut block = [0u8; 64];
+    let mut index = 0;
+    for _ in 0..EXPANSION_LEN / block.len() {
+        for byte in block.iter_mut() {
+            *byte = password[index % password.len()];
+            index += 1;
+        }
+        hasher.update(block);
+    }
+    crate::wipe(&mut block);
+    hasher.finalize().to_vec()
+}
+
+fn localize<D: Digest>(master: &[u8], engine_id: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    hasher.update(master);
+    hasher.update(engine_id);
+    hasher.update(master);
+    hasher.finalize().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENGINE_ID: [u8; 12] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2];
+
+    #[test]
+    fn test_rfc3414_md5_vector() {
+        let passphrase = SensitiveString::from("maplesyrup");
+        let master = passphrase.snmp_master_key(AuthProtocol::Md5).unwrap();
+        assert_eq!(
+            hex::encode(&master.value),
+            "9faf3283884e92834ebc9847d8edd963"
+        );
+        assert!(!format!("{:?}", master).contains("9faf"));
+        let key = passphrase
+            .snmp_localized_key(AuthProtocol::Md5, &ENGINE_ID)
+            .unwrap();
+        assert_eq!(
+            hex::encode(key.as_bytes()),
+            "526f5eed9fcce26f8964c2930787d82b"
+        );
+    }
+
+    #[test]
+    fn test_rfc3414_sha1_vector() {
+        let passphrase = SensitiveString::from("maplesyrup");
+        let key = passphrase
+            .snmp_localized_key(AuthProtocol::Sha1, &ENGINE_ID)
+            .unwrap();
+        assert_eq!(
+            hex::encode(key.as_bytes()),
+            "6695febc9288e36282235fc7151f128497b38f3f"
+        );
+    }
+
+    #[test]
+    fn test_short_passphrase_rejected() {
+        let passphrase = SensitiveString::from("short");
+        assert_eq!(
+            passphrase.snmp_localized_key(AuthProtocol::Sha256, &ENGINE_ID),
+            Err(SnmpKeyError::PassphraseTooShort)
+        );
+    }
+
+    #[test]
+    fn test_localized_key_debug_is_redacted() {
+        let passphrase = SensitiveString::from("maplesyrup");
+        let key = passphrase
+            .snmp_localized_key(AuthProtocol::Sha256, &ENGINE_ID)
+            .unwrap();
+        assert_eq!(format!("{:?}", key), "LocalizedKey(HmacSha256, 32 bytes)");
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_key_derivation_is_checked_against_access_policies() {
+        let passphrase = SensitiveString::from("maplesyrup");
+        crate::access::assert_denied(|| passphrase.snmp_master_key(AuthProtocol::Md5));
+        crate::access::assert_denied(|| {
+            passphrase.snmp_localized_key(AuthProtocol::Md5, &ENGINE_ID)
+        });
+    }
+}
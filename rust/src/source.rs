@@ -0,0 +1,847 @@
+//! Declarative secret sources for config files.
+//!
+//! A config field of type [`SecretSource`] accepts a secret from wherever
+//! the deployment keeps it, without the schema having to change:
+//!
+//! ```toml
+//! db_password   = { source = "env", name = "DB_PASSWORD" }
+//! tls_key       = { source = "file", path = "/run/secrets/tls.key" }
+//! api_token     = { source = "provider", name = "vault", key = "kv/api#token" }
+//! local_only    = { source = "plain", value = "dev-password" }
+//! partner_token = { source = "envelope", blob = "ssenv1:partner-2024:..." }
+//! ```
+//!
+//! [`SecretSource::resolve`] (or [`SecretSource::resolve_async`]) turns it into
+//! a [`SensitiveString`]. Named providers (Vault, AWS Secrets Manager, ...) are
+//! implemented outside this crate with [`SecretProvider`] and installed with
+//! [`register_provider`]. Envelopes (with the `envelope` feature) are opened
+//! with the keyring installed by [`set_envelope_keyring`].
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::source::{register_provider, ProviderError, SecretProvider, SecretSource};
+//! use sensitive_string::SensitiveString;
+//! use std::sync::Arc;
+//!
+//! struct Static;
+//!
+//! impl SecretProvider for Static {
+//!     fn fetch(&self, key: &str) -> Result<SensitiveString, ProviderError> {
+//!         match key {
+//!             "api" => Ok(SensitiveString::from("sk-live-123")),
+//!             _ => Err(ProviderError::new(format!("no secret at {}", key))),
+//!         }
+//!     }
+//! }
+//!
+//! register_provider("static", Arc::new(Static));
+//!
+//! let source = SecretSource::Provider {
+//!     name: "static".into(),
+//!     key: "api".into(),
+//! };
+//! assert_eq!(source.resolve().unwrap(), SensitiveString::from("sk-live-123"));
+//! ```
+//!
+//! [`resolve_all`] resolves many sources concurrently with a limit on how
+//! many are in flight, retrying provider failures and reporting each failed
+//! source separately, so startup does not fetch 40 secrets one at a time.
+//!
+//! Retry loops should log a [`RedactedContext`] (attempt counts, labels,
+//! source descriptions) rather than the request that failed. A provider
+//! attaches one with [`ProviderError::with_context`], and `resolve_all` fills
+//! in the source and attempt number before reporting the error.
+//!
+//! Files are read with blocking I/O in both `resolve` and `resolve_async`;
+//! secret files are small, so this is not worth a runtime dependency.
+
+#[cfg(feature = "envelope")]
+use crate::envelope::{EnvelopeError, Keyring};
+use crate::{
+    FromEnvError, FromFileError, PermissionCheck, SecretFile, SensitiveMap, SensitiveString,
+};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::hash::Hash;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::task::Poll;
+
+static PROVIDERS: RwLock<BTreeMap<String, Arc<dyn SecretProvider>>> = RwLock::new(BTreeMap::new());
+
+#[cfg(feature = "envelope")]
+static KEYRING: RwLock<Option<Keyring>> = RwLock::new(None);
+
+/// Where a secret comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(tag = "source", rename_all = "snake_case")
+)]
+pub enum SecretSource {
+    /// The secret itself, inline. Fine for local development.
+    Plain {
+        /// The plaintext.
+        value: SensitiveString,
+    },
+    /// An environment variable.
+    Env {
+        /// The variable name.
+        name: String,
+    },
+    /// A file, such as a mounted Kubernetes or Docker secret.
+    ///
+    /// One trailing newline (`\n` or `\r\n`) is stripped.
+    File {
+        /// The file path.
+        path: PathBuf,
+    },
+    /// A key in a provider installed with [`register_provider`].
+    Provider {
+        /// The name the provider was registered under.
+        name: String,
+        /// The provider-specific key, e.g. a Vault path.
+        key: String,
+    },
+    /// A `ssenv1:` or `ssenv1m:` envelope, opened with the keyring from [`set_envelope_keyring`].
+    #[cfg(feature = "envelope")]
+    Envelope {
+        /// The envelope text.
+        blob: String,
+    },
+}
+
+/// Describes the source without any secret material, e.g. `env:DB_PASSWORD`.
+impl fmt::Display for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretSource::Plain { .. } => write!(f, "plain"),
+            SecretSource::Env { name } => write!(f, "env:{}", name),
+            SecretSource::File { path } => write!(f, "file:{}", path.display()),
+            SecretSource::Provider { name, key } => write!(f, "provider:{}:{}", name, key),
+            #[cfg(feature = "envelope")]
+            SecretSource::Envelope { blob } => match crate::envelope::recipients(blob) {
+                Ok(key_ids) => write!(f, "envelope:{}", key_ids.join(",")),
+                Err(_) => write!(f, "envelope"),
+            },
+        }
+    }
+}
+
+/// Details about a failed fetch that are safe to log.
+///
+/// Built only from things that cannot carry a request body or token: a
+/// label, a [`SecretSource`] description, attempt counts, `&'static str`
+/// notes, and the redacted form of [`SensitiveString`]s.
+///
+/// ```
+/// use sensitive_string::source::{RedactedContext, SecretSource};
+/// use sensitive_string::SensitiveString;
+///
+/// let source = SecretSource::Env { name: "API_TOKEN".into() };
+/// let token = SensitiveString::from("sk-live-123");
+/// let context = RedactedContext::new()
+///     .with_label("billing")
+///     .with_source(&source)
+///     .with_attempt(2, 3)
+///     .with_note("token rejected")
+///     .with_secret("token", &token);
+///
+/// let logged = context.to_string();
+/// assert!(logged.starts_with("label=billing source=env:API_TOKEN attempt=2/3"));
+/// assert!(!logged.contains("sk-live-123"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedactedContext {
+    label: Option<String>,
+    source: Option<String>,
+    attempt: Option<(u32, u32)>,
+    notes: Vec<&'static str>,
+    secrets: Vec<(&'static str, String)>,
+}
+
+impl RedactedContext {
+    /// Creates an empty context.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names the secret being fetched, e.g. `"db-password"`.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Records where the secret was fetched from, described as by `SecretSource`'s `Display`.
+    pub fn with_source(mut self, source: &SecretSource) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Records that this was attempt `attempt` (counting from 1) of at most `max_attempts`.
+    pub fn with_attempt(mut self, attempt: u32, max_attempts: u32) -> Self {
+        self.attempt = Some((attempt, max_attempts));
+        self
+    }
+
+    /// Adds a fixed note, such as `"rate limited"`.
+    pub fn with_note(mut self, note: &'static str) -> Self {
+        self.notes.push(note);
+        self
+    }
+
+    /// Adds the redacted form of `secret` under `name`, so failures can be
+    /// correlated with the credential that was used.
+    pub fn with_secret(mut self, name: &'static str, secret: &SensitiveString) -> Self {
+        self.secrets.push((name, secret.to_string()));
+        self
+    }
+
+    /// Returns the label.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Returns the source description.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Returns the attempt number and the maximum number of attempts.
+    pub fn attempt(&self) -> Option<(u32, u32)> {
+        self.attempt
+    }
+}
+
+/// Formats as space-separated `key=value` pairs followed by the notes.
+impl fmt::Display for RedactedContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(label) = &self.label {
+            parts.push(format!("label={}", label));
+        }
+        if let Some(source) = &self.source {
+            parts.push(format!("source={}", source));
+        }
+        if let Some((attempt, max_attempts)) = self.attempt {
+            parts.push(format!("attempt={}/{}", attempt, max_attempts));
+        }
+        for (name, redacted) in &self.secrets {
+            parts.push(format!("{}={}", name, redacted));
+        }
+        parts.extend(self.notes.iter().map(|note| note.to_string()));
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Error returned by a [`SecretProvider`].
+#[derive(Debug)]
+pub struct ProviderError {
+    error: Box<dyn std::error::Error + Send + Sync>,
+    context: Option<Box<RedactedContext>>,
+}
+
+impl ProviderError {
+    /// Wraps any error, or a message.
+    ///
+    /// The message is shown as-is, so it must not include request bodies or
+    /// tokens; put identifying details in a [`RedactedContext`] instead.
+    pub fn new(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        ProviderError {
+            error: error.into(),
+            context: None,
+        }
+    }
+
+    /// Attaches loggable context, replacing any already attached.
+    pub fn with_context(mut self, context: RedactedContext) -> Self {
+        self.context = Some(Box::new(context));
+        self
+    }
+
+    /// Returns the attached context.
+    pub fn context(&self) -> Option<&RedactedContext> {
+        self.context.as_deref()
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)?;
+        match &self.context {
+            Some(context) => write!(f, " ({})", context),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.error.source()
+    }
+}
+
+/// Error returned when a [`SecretSource`] cannot be resolved.
+#[derive(Debug)]
+pub enum ResolveError {
+    /// The environment variable is not set.
+    EnvNotSet(String),
+    /// The environment variable is not valid Unicode.
+    EnvNotUnicode(String),
+    /// The file could not be read.
+    File(PathBuf, std::io::Error),
+    /// No provider is registered under this name.
+    UnknownProvider(String),
+    /// The provider failed.
+    Provider(String, ProviderError),
+    /// No keyring has been installed with [`set_envelope_keyring`].
+    #[cfg(feature = "envelope")]
+    NoKeyring,
+    /// The envelope could not be opened.
+    #[cfg(feature = "envelope")]
+    Envelope(EnvelopeError),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::EnvNotSet(name) => {
+                write!(f, "environment variable {} is not set", name)
+            }
+            ResolveError::EnvNotUnicode(name) => {
+                write!(f, "environment variable {} is not valid Unicode", name)
+            }
+            ResolveError::File(path, e) => {
+                write!(f, "cannot read secret file {}: {}", path.display(), e)
+            }
+            ResolveError::UnknownProvider(name) => {
+                write!(f, "no secret provider named {:?}", name)
+            }
+            ResolveError::Provider(name, e) => {
+                write!(f, "secret provider {:?} failed: {}", name, e)
+            }
+            #[cfg(feature = "envelope")]
+            ResolveError::NoKeyring => write!(f, "no envelope keyring installed"),
+            #[cfg(feature = "envelope")]
+            ResolveError::Envelope(e) => write!(f, "cannot open secret envelope: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ResolveError::File(_, e) => Some(e),
+            ResolveError::Provider(_, e) => Some(e),
+            #[cfg(feature = "envelope")]
+            ResolveError::Envelope(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The future returned by [`SecretProvider::fetch_async`].
+pub type FetchFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<SensitiveString, ProviderError>> + Send + 'a>>;
+
+/// A named backend that secrets can be fetched from.
+///
+/// Backends such as Vault or AWS Secrets Manager implement this trait in
+/// their own crates. `key` is whatever addresses a secret in that backend
+/// (a path, an ARN, `kv/api#token`); [`EnvProvider`] and [`FileProvider`]
+/// are the in-crate implementations. An implementation should:
+///
+/// - override [`fetch_async`](SecretProvider::fetch_async) if it does network
+///   I/O, so [`resolve_async`](SecretSource::resolve_async) and
+///   [`resolve_all`] do not block the runtime;
+/// - return errors that describe the failure without the secret, the request
+///   body, or credentials, attaching a [`RedactedContext`] for details;
+/// - wrap the secret as soon as it is parsed from the response.
+///
+/// Async support needs no feature or runtime dependency: the trait object
+/// returns a boxed future that any executor can poll.
+pub trait SecretProvider: Send + Sync {
+    /// Fetches the secret stored under `key`.
+    fn fetch(&self, key: &str) -> Result<SensitiveString, ProviderError>;
+
+    /// Fetches the secret stored under `key` without blocking.
+    ///
+    /// Defaults to calling [`fetch`](SecretProvider::fetch); network-backed
+    /// providers should override it.
+    fn fetch_async<'a>(&'a self, key: &'a str) -> FetchFuture<'a> {
+        Box::pin(std::future::ready(self.fetch(key)))
+    }
+}
+
+/// Reads secrets from environment variables, optionally under a common prefix.
+///
+/// ```
+/// use sensitive_string::source::{EnvProvider, SecretProvider};
+///
+/// # std::env::set_var("MYAPP_DB_PASSWORD", "hunter2");
+/// let env = EnvProvider::new().prefix("MYAPP_");
+/// assert_eq!(env.fetch("DB_PASSWORD").unwrap().len(), 7);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnvProvider {
+    prefix: String,
+}
+
+impl EnvProvider {
+    /// Creates a provider that reads the variable named by the key.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `<prefix><key>` instead.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+impl SecretProvider for EnvProvider {
+    fn fetch(&self, key: &str) -> Result<SensitiveString, ProviderError> {
+        SensitiveString::from_env(&format!("{}{}", self.prefix, key)).map_err(ProviderError::new)
+    }
+}
+
+/// Reads secrets from files in one directory, such as `/run/secrets`.
+///
+/// Keys are file names relative to the directory; keys that are absolute or
+/// contain `..` are rejected. A trailing newline is stripped, as with
+/// [`SensitiveString::from_file`].
+#[derive(Debug, Clone)]
+pub struct FileProvider {
+    dir: PathBuf,
+    permissions: PermissionCheck,
+}
+
+impl FileProvider {
+    /// Creates a provider that reads files under `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            permissions: PermissionCheck::Strict,
+        }
+    }
+
+    /// Sets whether to refuse files other users can read; see [`SecretFile`].
+    pub fn permissions(mut self, check: PermissionCheck) -> Self {
+        self.permissions = check;
+        self
+    }
+}
+
+impl SecretProvider for FileProvider {
+    fn fetch(&self, key: &str) -> Result<SensitiveString, ProviderError> {
+        let relative = std::path::Path::new(key);
+        let escapes = relative
+            .components()
+            .any(|part| !matches!(part, std::path::Component::Normal(_)));
+        if key.is_empty() || escapes {
+            return Err(ProviderError::new(format!(
+                "invalid secret file name {:?}: must be relative, without ..",
+                key
+            )));
+        }
+        SecretFile::new(self.dir.join(relative))
+            .permissions(self.permissions)
+            .read_string()
+            .map_err(ProviderError::new)
+    }
+}
+
+/// Installs `provider` under `name`, replacing any provider already there.
+pub fn register_provider(name: impl Into<String>, provider: Arc<dyn SecretProvider>) {
+    PROVIDERS
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(name.into(), provider);
+}
+
+fn provider(name: &str) -> Result<Arc<dyn SecretProvider>, ResolveError> {
+    PROVIDERS
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(name)
+        .cloned()
+        .ok_or_else(|| ResolveError::UnknownProvider(name.to_string()))
+}
+
+/// Installs the keyring used to open [`SecretSource::Envelope`] sources.
+#[cfg(feature = "envelope")]
+pub fn set_envelope_keyring(keyring: Keyring) {
+    *KEYRING
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(keyring);
+}
+
+impl SecretSource {
+    /// Fetches the secret, blocking on providers.
+    pub fn resolve(&self) -> Result<SensitiveString, ResolveError> {
+        match self {
+            SecretSource::Provider { name, key } => provider(name)?
+                .fetch(key)
+                .map_err(|e| ResolveError::Provider(name.clone(), e)),
+            _ => self.resolve_local(),
+        }
+    }
+
+    /// Fetches the secret, awaiting providers with [`SecretProvider::fetch_async`].
+    pub async fn resolve_async(&self) -> Result<SensitiveString, ResolveError> {
+        match self {
+            SecretSource::Provider { name, key } => provider(name)?
+                .fetch_async(key)
+                .await
+                .map_err(|e| ResolveError::Provider(name.clone(), e)),
+            _ => self.resolve_local(),
+        }
+    }
+
+    /// Resolves every source that does not go through a provider.
+    fn resolve_local(&self) -> Result<SensitiveString, ResolveError> {
+        match self {
+            SecretSource::Plain { value } => Ok(value.clone()),
+            SecretSource::Env { name } => SensitiveString::from_env(name).map_err(|e| match e {
+                FromEnvError::NotPresent(name) => ResolveError::EnvNotSet(name),
+                FromEnvError::NotUnicode(name) => ResolveError::EnvNotUnicode(name),
+            }),
+            SecretSource::File { path } => SensitiveString::from_file(path).map_err(|e| match e {
+                FromFileError::Io(path, e) => ResolveError::File(path, e),
+                FromFileError::NotUnicode(path) => ResolveError::File(
+                    path,
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, "not valid UTF-8"),
+                ),
+                FromFileError::TooPermissive(path, mode) => ResolveError::File(
+                    path,
+                    std::io::Error::new(
+                        std::io::ErrorKind::PermissionDenied,
+                        format!("readable by other users (mode {:o})", mode),
+                    ),
+                ),
+            }),
+            #[cfg(feature = "envelope")]
+            SecretSource::Envelope { blob } => {
+                let keyring = KEYRING
+                    .read()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+                let keyring = keyring.as_ref().ok_or(ResolveError::NoKeyring)?;
+                SensitiveString::open_with(blob, keyring).map_err(ResolveError::Envelope)
+            }
+            SecretSource::Provider { .. } => unreachable!("providers are resolved by the caller"),
+        }
+    }
+}
+
+/// Retries for provider failures in [`resolve_all`].
+pub const DEFAULT_RETRIES: u32 = 2;
+
+/// The outcome of resolving many sources at once.
+#[derive(Debug)]
+pub struct BatchResolution<K> {
+    /// The secrets that resolved.
+    pub secrets: SensitiveMap<K>,
+    /// The sources that failed, with the error from their last attempt.
+    pub errors: Vec<(K, ResolveError)>,
+}
+
+impl<K> BatchResolution<K> {
+    /// Returns true if every source resolved.
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// Resolves `sources` concurrently, at most `limit` at a time, keyed by index.
+///
+/// Provider failures are retried up to [`DEFAULT_RETRIES`] times; other
+/// failures (a missing variable or file) are reported immediately.
+pub async fn resolve_all(sources: &[SecretSource], limit: usize) -> BatchResolution<usize> {
+    resolve_all_keyed(sources.iter().enumerate(), limit, DEFAULT_RETRIES).await
+}
+
+/// Resolves named sources concurrently, at most `limit` at a time.
+///
+/// Provider failures are retried up to `retries` times; the error from the
+/// last attempt carries a [`RedactedContext`] with the source and attempt
+/// count. This needs no runtime: the sources are polled together inside the returned future.
+pub async fn resolve_all_keyed<'a, K, I>(
+    sources: I,
+    limit: usize,
+    retries: u32,
+) -> BatchResolution<K>
+where
+    K: Eq + Hash,
+    I: IntoIterator<Item = (K, &'a SecretSource)>,
+{
+    let limit = limit.max(1);
+    let mut pending = sources.into_iter();
+    let mut in_flight: Vec<InFlight<'a, K>> = Vec::new();
+    let mut result = BatchResolution {
+        secrets: SensitiveMap::new(),
+        errors: Vec::new(),
+    };
+
+    std::future::poll_fn(|cx| loop {
+        while in_flight.len() < limit {
+            match pending.next() {
+                Some((key, source)) => in_flight.push(InFlight::start(key, source, 0)),
+                None => break,
+            }
+        }
+        if in_flight.is_empty() {
+            return Poll::Ready(());
+        }
+
+        let mut progressed = false;
+        let mut i = 0;
+        while i < in_flight.len() {
+            let Poll::Ready(outcome) = in_flight[i].attempt.as_mut().poll(cx) else {
+                i += 1;
+                continue;
+            };
+            progressed = true;
+            let done = in_flight.swap_remove(i);
+            match outcome {
+                Ok(secret) => {
+                    result.secrets.insert(done.key, secret);
+                }
+                Err(ResolveError::Provider(..)) if done.attempts < retries => {
+                    in_flight.push(InFlight::start(done.key, done.source, done.attempts + 1));
+                }
+                Err(ResolveError::Provider(name, e)) => {
+                    let context = e
+                        .context()
+                        .cloned()
+                        .unwrap_or_default()
+                        .with_source(done.source)
+                        .with_attempt(done.attempts + 1, retries + 1);
+                    result.errors.push((
+                        done.key,
+                        ResolveError::Provider(name, e.with_context(context)),
+                    ));
+                }
+                Err(e) => result.errors.push((done.key, e)),
+            }
+        }
+        if !progressed {
+            return Poll::Pending;
+        }
+    })
+    .await;
+    result
+}
+
+/// One source being resolved by [`resolve_all_keyed`].
+struct InFlight<'a, K> {
+    key: K,
+    source: &'a SecretSource,
+    attempts: u32,
+    attempt: Pin<Box<dyn Future<Output = Result<SensitiveString, ResolveError>> + 'a>>,
+}
+
+impl<'a, K> InFlight<'a, K> {
+    fn start(key: K, source: &'a SecretSource, attempts: u32) -> Self {
+        Self {
+            key,
+            source,
+            attempts,
+            attempt: Box::pin(source.resolve_async()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Upper;
+
+    impl SecretProvider for Upper {
+        fn fetch(&self, key: &str) -> Result<SensitiveString, ProviderError> {
+            Ok(SensitiveString::new(key.to_uppercase()))
+        }
+    }
+
+    #[test]
+    fn test_env_and_file_sources() {
+        std::env::set_var("SENSITIVE_STRING_SOURCE_TEST", "from-env");
+        let env = SecretSource::Env {
+            name: "SENSITIVE_STRING_SOURCE_TEST".into(),
+        };
+        assert_eq!(env.resolve().unwrap(), SensitiveString::from("from-env"));
+
+        let path = std::env::temp_dir().join("sensitive-string-source-test");
+        std::fs::write(&path, "from-file\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        let file = SecretSource::File { path: path.clone() };
+        assert_eq!(file.resolve().unwrap(), SensitiveString::from("from-file"));
+        std::fs::remove_file(path).unwrap();
+
+        let missing = SecretSource::Env {
+            name: "SENSITIVE_STRING_SOURCE_MISSING".into(),
+        };
+        assert!(matches!(missing.resolve(), Err(ResolveError::EnvNotSet(_))));
+    }
+
+    #[test]
+    fn test_env_and_file_providers() {
+        std::env::set_var("SENSITIVE_STRING_PROVIDER_TOKEN", "from-env");
+        let env = EnvProvider::new().prefix("SENSITIVE_STRING_PROVIDER_");
+        assert_eq!(
+            env.fetch("TOKEN").unwrap(),
+            SensitiveString::from("from-env")
+        );
+        assert!(env.fetch("MISSING").is_err());
+
+        let dir = std::env::temp_dir().join("sensitive-string-provider-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("db-password"), "hunter2\n").unwrap();
+        let files = FileProvider::new(&dir).permissions(PermissionCheck::Allow);
+        assert_eq!(
+            files.fetch("db-password").unwrap(),
+            SensitiveString::from("hunter2")
+        );
+        for escaping in ["", "../db-password", "/etc/passwd"] {
+            assert!(files.fetch(escaping).is_err(), "{}", escaping);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_provider_sync_and_async() {
+        register_provider("upper", Arc::new(Upper));
+        let source = SecretSource::Provider {
+            name: "upper".into(),
+            key: "abc".into(),
+        };
+        assert_eq!(source.resolve().unwrap(), SensitiveString::from("ABC"));
+        let resolved = futures::executor::block_on(source.resolve_async()).unwrap();
+        assert_eq!(resolved, SensitiveString::from("ABC"));
+
+        let unknown = SecretSource::Provider {
+            name: "nope".into(),
+            key: "abc".into(),
+        };
+        assert!(matches!(
+            unknown.resolve(),
+            Err(ResolveError::UnknownProvider(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_tagged() {
+        #[derive(serde::Deserialize)]
+        struct Config {
+            password: SecretSource,
+            token: SecretSource,
+        }
+
+        let config: Config = toml::from_str(
+            r#"
+            password = { source = "plain", value = "hunter2" }
+            token = { source = "env", name = "TOKEN" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.password,
+            SecretSource::Plain {
+                value: SensitiveString::from("hunter2")
+            }
+        );
+        assert_eq!(
+            config.token,
+            SecretSource::Env {
+                name: "TOKEN".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_resolve_all_limits_concurrency_and_retries() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Default)]
+        struct Slow {
+            in_flight: AtomicUsize,
+            max_in_flight: AtomicUsize,
+            flaky_calls: AtomicUsize,
+        }
+
+        impl SecretProvider for Slow {
+            fn fetch(&self, key: &str) -> Result<SensitiveString, ProviderError> {
+                if key == "flaky" && self.flaky_calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    return Err(ProviderError::new("timeout"));
+                }
+                if key == "down" {
+                    return Err(ProviderError::new("unavailable")
+                        .with_context(RedactedContext::new().with_label("down")));
+                }
+                Ok(SensitiveString::from(key))
+            }
+
+            fn fetch_async<'a>(&'a self, key: &'a str) -> FetchFuture<'a> {
+                let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+                let mut yielded = false;
+                Box::pin(std::future::poll_fn(move |cx| {
+                    if !yielded {
+                        yielded = true;
+                        cx.waker().wake_by_ref();
+                        return Poll::Pending;
+                    }
+                    self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Poll::Ready(self.fetch(key))
+                }))
+            }
+        }
+
+        let slow = Arc::new(Slow::default());
+        register_provider("slow", slow.clone());
+        let provider = |key: &str| SecretSource::Provider {
+            name: "slow".into(),
+            key: key.into(),
+        };
+        let sources = vec![
+            provider("a"),
+            provider("flaky"),
+            provider("b"),
+            provider("c"),
+            provider("down"),
+            SecretSource::Env {
+                name: "SENSITIVE_STRING_BATCH_MISSING".into(),
+            },
+        ];
+
+        let batch = futures::executor::block_on(resolve_all(&sources, 2));
+        assert_eq!(slow.max_in_flight.load(Ordering::SeqCst), 2);
+        assert_eq!(batch.secrets.len(), 4);
+        assert_eq!(batch.secrets.get(&1), Some(&SensitiveString::from("flaky")));
+        assert!(!batch.is_complete());
+        let mut errors = batch.errors;
+        errors.sort_by_key(|(key, _)| *key);
+        assert!(matches!(
+            errors[..],
+            [
+                (4, ResolveError::Provider(..)),
+                (5, ResolveError::EnvNotSet(_))
+            ]
+        ));
+        assert_eq!(
+            errors[0].1.to_string(),
+            "secret provider \"slow\" failed: unavailable \
+             (label=down source=provider:slow:down attempt=3/3)"
+        );
+    }
+}
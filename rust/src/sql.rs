@@ -0,0 +1,74 @@
+//! SQL literal quoting.
+//!
+//! Secrets belong in bind parameters. A few statements (`CREATE USER ...
+//! PASSWORD '...'`, `ALTER ROLE ...`) cannot take parameters, so this module
+//! offers a quoting helper that only works inside an explicit exposure scope.
+
+use crate::exposure::{require_exposure, ExposureRequired};
+use crate::SensitiveString;
+
+impl SensitiveString {
+    /// Renders the plaintext as a standard SQL string literal.
+    ///
+    /// Single quotes are doubled and the result is wrapped in single quotes.
+    /// Backslashes are passed through unchanged, which is correct for
+    /// standard-conforming strings (PostgreSQL's default, SQLite, SQL Server)
+    /// but not for MySQL without `NO_BACKSLASH_ESCAPES`.
+    ///
+    /// Prefer parameterized queries. This returns an error unless an
+    /// [`ExposureGuard`](crate::ExposureGuard) is active on the current thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sensitive_string::{ExposureGuard, SensitiveString};
+    ///
+    /// let password = SensitiveString::from("it's-secret");
+    /// let _guard = ExposureGuard::new("CREATE USER DDL");
+    /// let sql = format!("CREATE USER app PASSWORD {}", password.sql_quoted().unwrap());
+    /// assert_eq!(sql, "CREATE USER app PASSWORD 'it''s-secret'");
+    /// ```
+    #[track_caller]
+    pub fn sql_quoted(&self) -> Result<String, ExposureRequired> {
+        require_exposure("sql_quoted")?;
+        self.record_access("sql_quoted");
+
+        let mut quoted = String::with_capacity(self.value.len() + 2);
+        quoted.push('\'');
+        for c in self.value.chars() {
+            if c == '\'' {
+                quoted.push('\'');
+            }
+            quoted.push(c);
+        }
+        quoted.push('\'');
+        Ok(quoted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ExposureGuard, SensitiveString};
+
+    #[test]
+    fn test_sql_quoted_requires_exposure() {
+        let secret = SensitiveString::from("secret");
+        let err = secret.sql_quoted().unwrap_err();
+        assert_eq!(err.operation(), "sql_quoted");
+    }
+
+    #[test]
+    fn test_sql_quoted_escapes_quotes() {
+        let secret = SensitiveString::from("a'b''c");
+        let _guard = ExposureGuard::new("test");
+        assert_eq!(secret.sql_quoted().unwrap(), "'a''b''''c'");
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_sql_quoted_is_checked_against_access_policies() {
+        let secret = SensitiveString::from("secret");
+        let _guard = ExposureGuard::new("test");
+        crate::access::assert_denied(|| secret.sql_quoted());
+    }
+}
@@ -0,0 +1,109 @@
+//! Binding secrets to and reading them from databases with sqlx.
+//!
+//! Storing a token in a database column otherwise means calling
+//! `get_value()` at every bind site and wrapping every decoded row by hand.
+//! With the `sqlx` feature, `SensitiveString` implements sqlx's `Type`,
+//! `Encode`, and `Decode` for every database whose `String` does: it binds as
+//! its plaintext and decodes straight into the wrapper.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use sensitive_string::SensitiveString;
+//!
+//! let token = SensitiveString::from("tok-123");
+//! sqlx::query("INSERT INTO api_tokens (tenant, token) VALUES ($1, $2)")
+//!     .bind(tenant_id)
+//!     .bind(&token)
+//!     .execute(&pool)
+//!     .await?;
+//!
+//! let (token,): (SensitiveString,) =
+//!     sqlx::query_as("SELECT token FROM api_tokens WHERE tenant = $1")
+//!         .bind(tenant_id)
+//!         .fetch_one(&pool)
+//!         .await?;
+//! ```
+//!
+//! Binding counts as a plaintext read for auditing and access policies.
+//! Encrypting the column is still up to the database or application.
+
+use crate::SensitiveString;
+use sqlx_core::database::Database;
+use sqlx_core::decode::Decode;
+use sqlx_core::encode::{Encode, IsNull};
+use sqlx_core::error::BoxDynError;
+use sqlx_core::types::Type;
+
+impl<DB: Database> Type<DB> for SensitiveString
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for SensitiveString
+where
+    String: Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+    ) -> Result<IsNull, BoxDynError> {
+        self.record_access("sqlx_encode");
+        self.value.encode_by_ref(buf)
+    }
+
+    fn produces(&self) -> Option<DB::TypeInfo> {
+        self.value.produces()
+    }
+
+    fn size_hint(&self) -> usize {
+        self.value.size_hint()
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for SensitiveString
+where
+    String: Decode<'r, DB>,
+{
+    fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(Self::new(String::decode(value)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx_core::any::{Any, AnyArguments, AnyValue, AnyValueKind};
+    use sqlx_core::arguments::Arguments;
+    use sqlx_core::value::Value;
+
+    #[test]
+    fn test_binds_plaintext() {
+        let mut arguments = AnyArguments::default();
+        arguments.add(SensitiveString::from("hunter2")).unwrap();
+        assert!(matches!(
+            &arguments.values.0[..],
+            [AnyValueKind::Text(text)] if text == "hunter2"
+        ));
+    }
+
+    #[test]
+    fn test_decodes_into_the_wrapper() {
+        let value = AnyValue {
+            kind: AnyValueKind::Text("hunter2".into()),
+        };
+        let secret = <SensitiveString as Decode<Any>>::decode(value.as_ref()).unwrap();
+        assert_eq!(secret, SensitiveString::from("hunter2"));
+        assert!(<SensitiveString as Type<Any>>::compatible(
+            &<String as Type<Any>>::type_info()
+        ));
+    }
+}
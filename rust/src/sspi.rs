@@ -0,0 +1,220 @@
+//! Wide-string buffers for Windows SSPI authentication identities.
+//!
+//! SSPI (`AcquireCredentialsHandleW` with NTLM, Kerberos, or Negotiate) takes
+//! credentials as a `SEC_WINNT_AUTH_IDENTITY_W` of UTF-16 pointers and
+//! lengths. [`WideSecret`] holds the UTF-16 password and overwrites it when
+//! dropped, and [`AuthIdentity`] lays out the structure SSPI expects while
+//! borrowing from it, so the password never sits in a long-lived `Vec<u16>`.
+//!
+//! The layout types are plain `#[repr(C)]` mirrors and compile on every
+//! platform, which keeps them testable outside Windows.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::sspi::SspiCredentials;
+//! use sensitive_string::SensitiveString;
+//!
+//! let creds = SspiCredentials::new("svc-app", "CORP", &SensitiveString::from("p@ss"));
+//! let identity = creds.auth_identity()?;
+//! assert_eq!(identity.PasswordLength, 4);
+//! // Pass `&identity as *const _ as *mut c_void` as pAuthData to AcquireCredentialsHandleW.
+//! # Ok::<(), sensitive_string::sspi::IdentityTooLong>(())
+//! ```
+
+use crate::SensitiveString;
+use std::fmt;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+/// `SEC_WINNT_AUTH_IDENTITY_UNICODE` from `<sspi.h>`.
+pub const SEC_WINNT_AUTH_IDENTITY_UNICODE: u32 = 0x2;
+
+/// A NUL-terminated UTF-16 copy of a secret that is zeroed on drop.
+pub struct WideSecret {
+    buf: Vec<u16>,
+}
+
+impl WideSecret {
+    /// Returns a pointer to the NUL-terminated UTF-16 buffer.
+    pub fn as_ptr(&self) -> *const u16 {
+        self.buf.as_ptr()
+    }
+
+    /// Returns the length in UTF-16 code units, excluding the terminator.
+    pub fn len(&self) -> usize {
+        self.buf.len() - 1
+    }
+
+    /// Returns true if the secret is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for WideSecret {
+    fn drop(&mut self) {
+        for unit in self.buf.iter_mut() {
+            // SAFETY: `unit` is a valid, aligned reference into our own buffer.
+            unsafe { std::ptr::write_volatile(unit, 0) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl fmt::Debug for WideSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WideSecret({} units)", self.len())
+    }
+}
+
+impl SensitiveString {
+    /// Encodes the plaintext as a NUL-terminated UTF-16 buffer that is zeroed on drop.
+    #[track_caller]
+    pub fn to_wide(&self) -> WideSecret {
+        self.record_access("to_wide");
+        let mut buf = Vec::with_capacity(self.value.len() + 1);
+        buf.extend(self.value.encode_utf16());
+        buf.push(0);
+        WideSecret { buf }
+    }
+}
+
+/// Error returned when a user, domain, or password has more UTF-16 code
+/// units than a `SEC_WINNT_AUTH_IDENTITY_W` length field can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdentityTooLong;
+
+impl fmt::Display for IdentityTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SSPI identity field is longer than u32::MAX code units")
+    }
+}
+
+impl std::error::Error for IdentityTooLong {}
+
+/// Mirror of `SEC_WINNT_AUTH_IDENTITY_W` from `<sspi.h>`.
+///
+/// Field names follow the Windows SDK so the structure can be passed where
+/// `windows-sys` or `winapi` expect theirs.
+#[repr(C)]
+#[allow(non_snake_case)]
+#[derive(Debug)]
+pub struct AuthIdentity {
+    pub User: *const u16,
+    pub UserLength: u32,
+    pub Domain: *const u16,
+    pub DomainLength: u32,
+    pub Password: *const u16,
+    pub PasswordLength: u32,
+    pub Flags: u32,
+}
+
+/// User, domain, and password for an SSPI logon, held as UTF-16.
+///
+/// The password is a [`WideSecret`] and is zeroed when this value is dropped.
+/// `Debug` shows the user and domain only.
+pub struct SspiCredentials {
+    user: Vec<u16>,
+    domain: Vec<u16>,
+    password: WideSecret,
+}
+
+impl SspiCredentials {
+    /// Builds credentials from a user name, domain, and wrapped password.
+    #[track_caller]
+    pub fn new(user: &str, domain: &str, password: &SensitiveString) -> Self {
+        Self {
+            user: user.encode_utf16().chain(Some(0)).collect(),
+            domain: domain.encode_utf16().chain(Some(0)).collect(),
+            password: password.to_wide(),
+        }
+    }
+
+    /// Returns an identity structure borrowing from these credentials.
+    ///
+    /// The pointers inside are valid only while `self` is alive.
+    pub fn auth_identity(&self) -> Result<AuthIdentity, IdentityTooLong> {
+        Ok(AuthIdentity {
+            User: self.user.as_ptr(),
+            UserLength: length(self.user.len() - 1)?,
+            Domain: self.domain.as_ptr(),
+            DomainLength: length(self.domain.len() - 1)?,
+            Password: self.password.as_ptr(),
+            PasswordLength: length(self.password.len())?,
+            Flags: SEC_WINNT_AUTH_IDENTITY_UNICODE,
+        })
+    }
+}
+
+fn length(units: usize) -> Result<u32, IdentityTooLong> {
+    u32::try_from(units).map_err(|_| IdentityTooLong)
+}
+
+impl fmt::Debug for SspiCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SspiCredentials")
+            .field(
+                "user",
+                &String::from_utf16_lossy(&self.user[..self.user.len() - 1]),
+            )
+            .field(
+                "domain",
+                &String::from_utf16_lossy(&self.domain[..self.domain.len() - 1]),
+            )
+            .field("password", &self.password)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_wide_is_nul_terminated_utf16() {
+        let secret = SensitiveString::from("pässword");
+        let wide = secret.to_wide();
+        let units = unsafe { std::slice::from_raw_parts(wide.as_ptr(), wide.len() + 1) };
+
+        assert_eq!(wide.len(), 8);
+        assert_eq!(units[8], 0);
+        assert_eq!(String::from_utf16(&units[..8]).unwrap(), "pässword");
+    }
+
+    #[test]
+    fn test_auth_identity_lengths_exclude_terminator() {
+        let creds = SspiCredentials::new("user", "DOMAIN", &SensitiveString::from("secret"));
+        let identity = creds.auth_identity().unwrap();
+
+        assert_eq!(identity.UserLength, 4);
+        assert_eq!(identity.DomainLength, 6);
+        assert_eq!(identity.PasswordLength, 6);
+        assert_eq!(identity.Flags, SEC_WINNT_AUTH_IDENTITY_UNICODE);
+    }
+
+    #[test]
+    fn test_length_overflow_is_an_error() {
+        assert_eq!(length(u32::MAX as usize), Ok(u32::MAX));
+        if let Some(too_long) = (u32::MAX as usize).checked_add(1) {
+            assert_eq!(length(too_long), Err(IdentityTooLong));
+        }
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_to_wide_is_checked_against_access_policies() {
+        let secret = SensitiveString::from("secret");
+        crate::access::assert_denied(|| secret.to_wide());
+        crate::access::assert_denied(|| SspiCredentials::new("user", "DOMAIN", &secret));
+    }
+
+    #[test]
+    fn test_debug_hides_password() {
+        let creds = SspiCredentials::new("user", "DOMAIN", &SensitiveString::from("secret"));
+        let debug = format!("{:?}", creds);
+
+        assert!(debug.contains("user"));
+        assert!(debug.contains("WideSecret(6 units)"));
+        assert!(!debug.contains("secret"));
+    }
+}
@@ -0,0 +1,269 @@
+//! Support bundles with secrets encrypted instead of redacted.
+//!
+//! A redacted diagnostic dump is safe to attach to a ticket, but sometimes
+//! support genuinely needs the value (was the right API key configured?).
+//! [`dump`] serializes diagnostic state to JSON and replaces each
+//! `SensitiveString` with its fingerprint plus the plaintext encrypted to the
+//! support team's [age](https://age-encryption.org) public key. The bundle is
+//! safe at rest and in transit; only the holder of the identity can [`open`] it.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{support_bundle, SensitiveString};
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Diagnostics {
+//!     endpoint: String,
+//!     api_key: SensitiveString,
+//! }
+//!
+//! let support_identity = age::x25519::Identity::generate();
+//! let state = Diagnostics {
+//!     endpoint: "https://api.example.com".into(),
+//!     api_key: SensitiveString::from("sk-live-123"),
+//! };
+//!
+//! let bundle = support_bundle::dump(&state, &support_identity.to_public()).unwrap();
+//! assert!(!bundle.contains("sk-live-123"));
+//!
+//! // On the support side:
+//! let opened = support_bundle::open(&bundle, &support_identity).unwrap();
+//! assert_eq!(opened["api_key"], "sk-live-123");
+//! ```
+//!
+//! Any `age::Recipient` works, including `age::ssh::Recipient` for RSA or
+//! Ed25519 SSH keys when age's `ssh` feature is enabled.
+
+use crate::SensitiveString;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::fmt;
+
+thread_local! {
+    static COLLECTED: RefCell<Option<Vec<SensitiveString>>> = const { RefCell::new(None) };
+}
+
+/// Key holding the fingerprint in an encrypted value object.
+const FINGERPRINT_KEY: &str = "fingerprint";
+
+/// Key holding the armored age ciphertext in an encrypted value object.
+const ENCRYPTED_KEY: &str = "age";
+
+/// Error returned when building or opening a support bundle.
+#[derive(Debug)]
+pub enum SupportBundleError {
+    /// The state could not be serialized to JSON, or the bundle is not JSON.
+    Json(serde_json::Error),
+    /// Encrypting a value failed.
+    Encrypt(age::EncryptError),
+    /// Decrypting a value failed, e.g. because the identity does not match.
+    Decrypt(age::DecryptError),
+    /// A decrypted value was not valid UTF-8.
+    InvalidPlaintext,
+}
+
+impl fmt::Display for SupportBundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SupportBundleError::Json(e) => write!(f, "support bundle JSON error: {}", e),
+            SupportBundleError::Encrypt(e) => write!(f, "support bundle encryption failed: {}", e),
+            SupportBundleError::Decrypt(e) => write!(f, "support bundle decryption failed: {}", e),
+            SupportBundleError::InvalidPlaintext => {
+                write!(f, "support bundle value is not valid UTF-8")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SupportBundleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SupportBundleError::Json(e) => Some(e),
+            SupportBundleError::Encrypt(e) => Some(e),
+            SupportBundleError::Decrypt(e) => Some(e),
+            SupportBundleError::InvalidPlaintext => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for SupportBundleError {
+    fn from(e: serde_json::Error) -> Self {
+        SupportBundleError::Json(e)
+    }
+}
+
+impl From<age::EncryptError> for SupportBundleError {
+    fn from(e: age::EncryptError) -> Self {
+        SupportBundleError::Encrypt(e)
+    }
+}
+
+impl From<age::DecryptError> for SupportBundleError {
+    fn from(e: age::DecryptError) -> Self {
+        SupportBundleError::Decrypt(e)
+    }
+}
+
+/// Records a secret serialized while a bundle is being built on this thread.
+///
+/// Returns true if a bundle is being built.
+pub(crate) fn note_serialized(secret: &SensitiveString) -> bool {
+    COLLECTED.with(|collected| match collected.borrow_mut().as_mut() {
+        Some(collected) => {
+            if !collected.contains(secret) {
+                collected.push(secret.clone());
+            }
+            true
+        }
+        None => false,
+    })
+}
+
+/// Turns collection on for its lifetime, so a panic during serialization cannot leave it on.
+struct Collecting;
+
+impl Collecting {
+    fn start() -> Self {
+        COLLECTED.with(|collected| *collected.borrow_mut() = Some(Vec::new()));
+        Collecting
+    }
+
+    fn finish(self) -> Vec<SensitiveString> {
+        COLLECTED.with(|collected| collected.borrow_mut().take().unwrap_or_default())
+    }
+}
+
+impl Drop for Collecting {
+    fn drop(&mut self) {
+        COLLECTED.with(|collected| collected.borrow_mut().take());
+    }
+}
+
+/// Serializes `state` to pretty JSON with every `SensitiveString` encrypted to `recipient`.
+///
+/// Each secret becomes `{"fingerprint": "sha256:...", "age": "-----BEGIN AGE ENCRYPTED FILE-----..."}`.
+pub fn dump<T, R>(state: &T, recipient: &R) -> Result<String, SupportBundleError>
+where
+    T: Serialize + ?Sized,
+    R: age::Recipient,
+{
+    let collecting = Collecting::start();
+    let mut document = serde_json::to_value(state)?;
+    let secrets = collecting.finish();
+
+    let mut encrypted = Map::new();
+    for secret in &secrets {
+        let ciphertext = age::encrypt_and_armor(recipient, secret.value.as_bytes())?;
+        encrypted.insert(secret.hash_string(), Value::String(ciphertext));
+    }
+    replace_fingerprints(&mut document, &encrypted);
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+/// Decrypts a bundle produced by [`dump`], restoring each secret's plaintext.
+pub fn open<I: age::Identity>(bundle: &str, identity: &I) -> Result<Value, SupportBundleError> {
+    let mut document: Value = serde_json::from_str(bundle)?;
+    decrypt_values(&mut document, identity)?;
+    Ok(document)
+}
+
+fn replace_fingerprints(value: &mut Value, encrypted: &Map<String, Value>) {
+    match value {
+        Value::String(text) => {
+            if let Some(ciphertext) = encrypted.get(text.as_str()) {
+                let mut object = Map::new();
+                object.insert(FINGERPRINT_KEY.to_string(), Value::String(text.clone()));
+                object.insert(ENCRYPTED_KEY.to_string(), ciphertext.clone());
+                *value = Value::Object(object);
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| replace_fingerprints(item, encrypted)),
+        Value::Object(map) => map
+            .values_mut()
+            .for_each(|item| replace_fingerprints(item, encrypted)),
+        _ => {}
+    }
+}
+
+fn decrypt_values<I: age::Identity>(
+    value: &mut Value,
+    identity: &I,
+) -> Result<(), SupportBundleError> {
+    match value {
+        Value::Object(map) if map.len() == 2 && map.contains_key(FINGERPRINT_KEY) => {
+            if let Some(Value::String(ciphertext)) = map.get(ENCRYPTED_KEY) {
+                let plaintext = age::decrypt(identity, ciphertext.as_bytes())?;
+                let plaintext = String::from_utf8(plaintext)
+                    .map_err(|_| SupportBundleError::InvalidPlaintext)?;
+                *value = Value::String(plaintext);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                decrypt_values(item, identity)?;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                decrypt_values(item, identity)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct State {
+        name: &'static str,
+        tokens: Vec<SensitiveString>,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let identity = age::x25519::Identity::generate();
+        let state = State {
+            name: "worker-1",
+            tokens: vec![SensitiveString::from("t1"), SensitiveString::from("t2")],
+        };
+
+        let bundle = dump(&state, &identity.to_public()).unwrap();
+        assert!(bundle.contains(&SensitiveString::from("t1").to_string()));
+        assert!(!bundle.contains("\"t1\""));
+
+        let opened = open(&bundle, &identity).unwrap();
+        assert_eq!(opened["name"], "worker-1");
+        assert_eq!(opened["tokens"], serde_json::json!(["t1", "t2"]));
+    }
+
+    #[test]
+    fn test_wrong_identity_fails() {
+        let state = State {
+            name: "worker-1",
+            tokens: vec![SensitiveString::from("t1")],
+        };
+        let bundle = dump(&state, &age::x25519::Identity::generate().to_public()).unwrap();
+        let result = open(&bundle, &age::x25519::Identity::generate());
+        assert!(matches!(result, Err(SupportBundleError::Decrypt(_))));
+    }
+
+    #[test]
+    fn test_normal_serialization_is_unchanged() {
+        let state = State {
+            name: "worker-1",
+            tokens: vec![SensitiveString::from("t1")],
+        };
+        let _ = dump(&state, &age::x25519::Identity::generate().to_public()).unwrap();
+        let json = serde_json::to_value(&state).unwrap();
+        assert_eq!(json["tokens"][0], SensitiveString::from("t1").to_string());
+    }
+}
@@ -0,0 +1,220 @@
+//! Test doubles for code that handles secrets.
+//!
+//! Downstream crates should be able to unit-test secret handling without a
+//! Vault in CI. With the `test-util` feature, [`MockProvider`] is a
+//! [`SecretProvider`] with programmable responses, injectable failures, and a
+//! record of every fetch, and [`SensitiveString::test_value`] makes a labeled
+//! placeholder secret. Enable the feature in `[dev-dependencies]` only.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::source::SecretProvider;
+//! use sensitive_string::test_util::MockProvider;
+//! use sensitive_string::SensitiveString;
+//!
+//! let vault = MockProvider::new().with_secret("kv/db", SensitiveString::test_value("db"));
+//! vault.fail_next("kv/db", 1);
+//!
+//! assert!(vault.fetch("kv/db").is_err());
+//! assert_eq!(vault.fetch("kv/db").unwrap(), SensitiveString::test_value("db"));
+//! assert_eq!(vault.calls(), ["kv/db", "kv/db"]);
+//! ```
+//!
+//! # Deterministic digests
+//!
+//! Golden files that contain `sha256:<hex>` change whenever test credentials
+//! are regenerated. In deterministic mode, labeled secrets redact to
+//! `sha256:test:<label>` instead, derived from the label rather than the
+//! value. Enable it for a closure with [`with_deterministic_digests`], or for
+//! the whole test binary with [`enable_deterministic_digests`]. Unlabeled
+//! secrets are redacted as usual.
+//!
+//! ```
+//! use sensitive_string::test_util::with_deterministic_digests;
+//! use sensitive_string::SensitiveString;
+//!
+//! let password = SensitiveString::from("regenerated-every-run").with_label("db");
+//! with_deterministic_digests(|| assert_eq!(password.to_string(), "sha256:test:db"));
+//! ```
+
+use crate::source::{ProviderError, SecretProvider};
+use crate::SensitiveString;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Prefix of the redacted form in deterministic mode.
+pub const DETERMINISTIC_PREFIX: &str = "sha256:test:";
+
+static DETERMINISTIC: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static DETERMINISTIC_SCOPE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A [`SecretProvider`] for tests.
+///
+/// Keys without a programmed secret fail with `no secret at <key>`.
+#[derive(Debug, Default)]
+pub struct MockProvider {
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    secrets: HashMap<String, SensitiveString>,
+    failures: HashMap<String, usize>,
+    unavailable: bool,
+    calls: Vec<String>,
+}
+
+impl MockProvider {
+    /// Creates a provider with no secrets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Programs the secret returned for `key`.
+    pub fn with_secret(self, key: impl Into<String>, secret: impl Into<SensitiveString>) -> Self {
+        self.set_secret(key, secret);
+        self
+    }
+
+    /// Programs or replaces the secret returned for `key`, e.g. to simulate rotation.
+    pub fn set_secret(&self, key: impl Into<String>, secret: impl Into<SensitiveString>) {
+        self.lock().secrets.insert(key.into(), secret.into());
+    }
+
+    /// Makes the next `times` fetches of `key` fail.
+    pub fn fail_next(&self, key: impl Into<String>, times: usize) {
+        *self.lock().failures.entry(key.into()).or_default() += times;
+    }
+
+    /// Makes every fetch fail until called again with `false`, simulating an outage.
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.lock().unavailable = unavailable;
+    }
+
+    /// Returns the keys fetched so far, in order, including failed fetches.
+    pub fn calls(&self) -> Vec<String> {
+        self.lock().calls.clone()
+    }
+
+    /// Returns how many times `key` has been fetched.
+    pub fn call_count(&self, key: &str) -> usize {
+        self.lock().calls.iter().filter(|call| *call == key).count()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SecretProvider for MockProvider {
+    fn fetch(&self, key: &str) -> Result<SensitiveString, ProviderError> {
+        let mut state = self.lock();
+        state.calls.push(key.to_string());
+        if state.unavailable {
+            return Err(ProviderError::new("mock provider unavailable"));
+        }
+        if let Some(remaining) = state.failures.get_mut(key).filter(|n| **n > 0) {
+            *remaining -= 1;
+            return Err(ProviderError::new(format!("injected failure for {}", key)));
+        }
+        state
+            .secrets
+            .get(key)
+            .cloned()
+            .ok_or_else(|| ProviderError::new(format!("no secret at {}", key)))
+    }
+}
+
+/// Turns on deterministic digests for the rest of the process.
+///
+/// Meant for test binaries that compare against golden files; tests in the
+/// same binary that check real digests should use
+/// [`with_deterministic_digests`] instead.
+pub fn enable_deterministic_digests() {
+    DETERMINISTIC.store(true, Ordering::SeqCst);
+}
+
+/// Runs `f` with deterministic digests on for the current thread.
+pub fn with_deterministic_digests<R>(f: impl FnOnce() -> R) -> R {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            DETERMINISTIC_SCOPE.with(|scope| scope.set(self.0));
+        }
+    }
+
+    let _restore = Restore(DETERMINISTIC_SCOPE.with(|scope| scope.replace(true)));
+    f()
+}
+
+/// Returns the deterministic redacted form of `secret`, if the mode is on and it has a label.
+pub(crate) fn deterministic_digest(secret: &SensitiveString) -> Option<String> {
+    let enabled =
+        DETERMINISTIC.load(Ordering::SeqCst) || DETERMINISTIC_SCOPE.with(|scope| scope.get());
+    if !enabled {
+        return None;
+    }
+    secret
+        .label()
+        .map(|label| format!("{}{}", DETERMINISTIC_PREFIX, label))
+}
+
+impl SensitiveString {
+    /// Creates a placeholder secret for tests, `test-<label>`, labeled `label`.
+    ///
+    /// Equal labels give equal secrets, so assertions do not need to know the plaintext.
+    pub fn test_value(label: &str) -> Self {
+        SensitiveString::new(format!("test-{}", label)).with_label(label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outage_and_missing_keys() {
+        let mock = MockProvider::new().with_secret("a", "1");
+        mock.set_unavailable(true);
+        assert!(mock.fetch("a").is_err());
+        mock.set_unavailable(false);
+        assert_eq!(mock.fetch("a").unwrap(), SensitiveString::from("1"));
+        assert_eq!(mock.fetch("b").unwrap_err().to_string(), "no secret at b");
+        assert_eq!(mock.call_count("a"), 2);
+    }
+
+    #[test]
+    fn test_deterministic_digests_are_scoped() {
+        let labeled = SensitiveString::test_value("api");
+        let unlabeled = SensitiveString::from("hunter2");
+        let before = labeled.to_string();
+
+        with_deterministic_digests(|| {
+            assert_eq!(labeled.to_string(), "sha256:test:api");
+            assert_eq!(format!("{:?}", labeled), "SensitiveString(sha256:test:api)");
+            assert_eq!(
+                unlabeled.to_string(),
+                SensitiveString::from("hunter2").to_string()
+            );
+            assert!(SensitiveString::looks_redacted("sha256:test:api"));
+        });
+        assert_eq!(labeled.to_string(), before);
+    }
+
+    #[test]
+    fn test_value_is_labeled_and_redacted() {
+        let secret = SensitiveString::test_value("db");
+        assert_eq!(secret.label(), Some("db"));
+        assert_eq!(secret, SensitiveString::from("test-db"));
+        assert!(!secret.to_string().contains("test-db"));
+    }
+}
@@ -0,0 +1,137 @@
+//! Secrets that remember when they were created and last rotated.
+//!
+//! Rotation dashboards need each credential's age, which otherwise means a
+//! parallel struct of timestamps kept in step with the secrets by hand.
+//! [`Timestamped`] carries both times alongside the value, updates the
+//! rotation time in [`rotate`](Timestamped::rotate), and with the `serde`
+//! feature serializes as the redacted value plus RFC 3339 timestamps.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::{SensitiveString, Timestamped};
+//! use std::time::Duration;
+//!
+//! let mut token = Timestamped::new(SensitiveString::from("tok-1"));
+//! let previous = token.rotate(SensitiveString::from("tok-2"));
+//! assert_eq!(previous, SensitiveString::from("tok-1"));
+//! assert!(token.since_rotation() <= token.age());
+//! assert!(token.age() < Duration::from_secs(60));
+//! ```
+
+use crate::SensitiveString;
+use std::time::{Duration, SystemTime};
+
+/// A value with its creation and last rotation times.
+///
+/// Equality compares the values and both times.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Timestamped<T = SensitiveString> {
+    value: T,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+    created_at: SystemTime,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_time"))]
+    last_rotated: SystemTime,
+}
+
+impl<T> Timestamped<T> {
+    /// Wraps `value`, created and rotated now.
+    pub fn new(value: T) -> Self {
+        Self::at(value, SystemTime::now())
+    }
+
+    /// Wraps `value`, created and rotated at `created_at`, e.g. when
+    /// restoring a secret whose creation time was stored elsewhere.
+    pub fn at(value: T, created_at: SystemTime) -> Self {
+        Self {
+            value,
+            created_at,
+            last_rotated: created_at,
+        }
+    }
+
+    /// Returns the wrapped value.
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the wrapped value, dropping the timestamps.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// Returns when the value was first wrapped.
+    pub fn created_at(&self) -> SystemTime {
+        self.created_at
+    }
+
+    /// Returns when the value was last replaced, or its creation time if it never was.
+    pub fn last_rotated(&self) -> SystemTime {
+        self.last_rotated
+    }
+
+    /// Returns the time since creation, or zero if the clock moved backwards.
+    pub fn age(&self) -> Duration {
+        self.created_at.elapsed().unwrap_or_default()
+    }
+
+    /// Returns the time since the last rotation, or zero if the clock moved backwards.
+    pub fn since_rotation(&self) -> Duration {
+        self.last_rotated.elapsed().unwrap_or_default()
+    }
+
+    /// Replaces the value, records the rotation time, and returns the old value.
+    pub fn rotate(&mut self, value: T) -> T {
+        self.last_rotated = SystemTime::now();
+        std::mem::replace(&mut self.value, value)
+    }
+}
+
+impl<T> From<T> for Timestamped<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_time<S>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_str(&crate::syslog::rfc3339_utc(*time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_keeps_creation_time() {
+        let created = SystemTime::now() - Duration::from_secs(3600);
+        let mut secret = Timestamped::at(SensitiveString::from("old"), created);
+        assert_eq!(secret.last_rotated(), created);
+        assert!(secret.age() >= Duration::from_secs(3600));
+
+        secret.rotate(SensitiveString::from("new"));
+        assert_eq!(secret.created_at(), created);
+        assert!(secret.last_rotated() > created);
+        assert!(secret.since_rotation() < Duration::from_secs(60));
+        assert_eq!(secret.into_inner(), SensitiveString::from("new"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serializes_redacted_with_timestamps() {
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let secret = Timestamped::at(SensitiveString::from("hunter2"), epoch);
+        let json = serde_json::to_value(&secret).unwrap();
+        assert_eq!(json["created_at"], "2023-11-14T22:13:20.000000Z");
+        assert_eq!(json["last_rotated"], "2023-11-14T22:13:20.000000Z");
+        assert_eq!(
+            json["value"],
+            serde_json::to_value(SensitiveString::from("hunter2")).unwrap()
+        );
+        assert!(!json.to_string().contains("hunter2"));
+    }
+}
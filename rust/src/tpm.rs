@@ -0,0 +1,780 @@
+//! Secrets sealed to a TPM's PCR state.
+//!
+//! [`SealedSecret::seal`] asks the TPM to encrypt a secret under its storage
+//! root key with a policy that only allows unsealing while the selected PCRs
+//! hold the values they had when it was sealed. The result is a blob that is
+//! safe to write to disk: it can only be unsealed by the same TPM, and only
+//! on a machine that booted the same firmware, bootloader, and kernel.
+//! [`SealedSecret::unseal`] returns the plaintext as a [`SensitiveString`],
+//! and only inside an exposure scope.
+//!
+//! Commands are sent to the kernel's resource manager at `/dev/tpmrm0`
+//! (Linux), so no TSS library is needed. The storage root key is recreated
+//! from the standard ECC P-256 template on each call instead of being
+//! persisted, and all sessions and objects are flushed afterwards. Secrets are
+//! limited to [`MAX_SECRET_LEN`] bytes, enough for a disk or API key.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use sensitive_string::tpm::{SealedSecret, Tpm};
+//! use sensitive_string::{ExposureGuard, SensitiveString};
+//!
+//! let mut tpm = Tpm::open()?;
+//! let key = SensitiveString::from("disk-unlock-key");
+//! let sealed = SealedSecret::seal(&mut tpm, &key, &[0, 2, 4, 7])?;
+//! std::fs::write("disk.sealed", sealed.to_bytes())?;
+//!
+//! let sealed = SealedSecret::from_bytes(&std::fs::read("disk.sealed")?)?;
+//! let _guard = ExposureGuard::new("unlock data volume");
+//! let key = sealed.unseal(&mut tpm)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::exposure::{require_exposure, ExposureRequired};
+use crate::SensitiveString;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::sync::atomic::{compiler_fence, Ordering};
+
+/// The largest secret that can be sealed, in bytes.
+pub const MAX_SECRET_LEN: usize = 128;
+
+/// The bytes every sealed blob starts with.
+pub const MAGIC: &[u8] = b"sstpm1";
+
+const PCR_COUNT: u8 = 24;
+
+const TPM_ST_NO_SESSIONS: u16 = 0x8001;
+const TPM_ST_SESSIONS: u16 = 0x8002;
+
+const TPM_CC_CREATE_PRIMARY: u32 = 0x131;
+const TPM_CC_CREATE: u32 = 0x153;
+const TPM_CC_LOAD: u32 = 0x157;
+const TPM_CC_UNSEAL: u32 = 0x15E;
+const TPM_CC_FLUSH_CONTEXT: u32 = 0x165;
+const TPM_CC_START_AUTH_SESSION: u32 = 0x176;
+const TPM_CC_POLICY_PCR: u32 = 0x17F;
+const TPM_CC_POLICY_GET_DIGEST: u32 = 0x189;
+
+const TPM_RH_OWNER: u32 = 0x4000_0001;
+const TPM_RH_NULL: u32 = 0x4000_0007;
+const TPM_RS_PW: u32 = 0x4000_0009;
+
+const TPM_ALG_AES: u16 = 0x0006;
+const TPM_ALG_KEYEDHASH: u16 = 0x0008;
+const TPM_ALG_SHA256: u16 = 0x000B;
+const TPM_ALG_NULL: u16 = 0x0010;
+const TPM_ALG_ECC: u16 = 0x0023;
+const TPM_ALG_CFB: u16 = 0x0043;
+const TPM_ECC_NIST_P256: u16 = 0x0003;
+
+const TPM_SE_POLICY: u8 = 0x01;
+const TPM_SE_TRIAL: u8 = 0x03;
+
+/// fixedTPM, fixedParent, sensitiveDataOrigin, userWithAuth, noDA,
+/// restricted, decrypt: the storage root key template.
+const SRK_ATTRIBUTES: u32 = 0x0003_0472;
+/// fixedTPM, fixedParent, noDA. Without userWithAuth, only the policy can
+/// authorize unsealing.
+const SEALED_ATTRIBUTES: u32 = 0x0000_0412;
+
+/// Errors from sealing or unsealing.
+#[derive(Debug)]
+pub enum TpmError {
+    /// [`SealedSecret::unseal`] was called outside an exposure scope.
+    ExposureRequired(ExposureRequired),
+    /// The PCRs no longer hold the values the secret was sealed to.
+    PolicyMismatch,
+    /// The secret is longer than [`MAX_SECRET_LEN`].
+    TooLarge,
+    /// The PCR list is empty or names a PCR above 23.
+    InvalidPcrSelection,
+    /// A sealed blob or a TPM response could not be parsed.
+    Malformed,
+    /// The unsealed data is not valid UTF-8.
+    InvalidUtf8,
+    /// The TPM failed a command with this response code.
+    Tpm(u32),
+    /// The TPM device could not be read or written.
+    Io(io::Error),
+}
+
+impl fmt::Display for TpmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TpmError::ExposureRequired(err) => err.fmt(f),
+            TpmError::PolicyMismatch => write!(f, "PCR values do not match the sealing policy"),
+            TpmError::TooLarge => write!(
+                f,
+                "secret is longer than the {} bytes a TPM can seal",
+                MAX_SECRET_LEN
+            ),
+            TpmError::InvalidPcrSelection => write!(f, "PCR selection must name PCRs 0 to 23"),
+            TpmError::Malformed => write!(f, "malformed sealed blob or TPM response"),
+            TpmError::InvalidUtf8 => write!(f, "unsealed data is not valid UTF-8"),
+            TpmError::Tpm(code) => write!(f, "TPM command failed with response code {:#x}", code),
+            TpmError::Io(err) => write!(f, "TPM device error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for TpmError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TpmError::ExposureRequired(err) => Some(err),
+            TpmError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<ExposureRequired> for TpmError {
+    fn from(err: ExposureRequired) -> Self {
+        TpmError::ExposureRequired(err)
+    }
+}
+
+impl From<io::Error> for TpmError {
+    fn from(err: io::Error) -> Self {
+        TpmError::Io(err)
+    }
+}
+
+/// A connection to a TPM 2.0 that takes raw command buffers.
+#[derive(Debug)]
+pub struct Tpm<D = File> {
+    device: D,
+}
+
+impl Tpm<File> {
+    /// Opens the kernel's TPM resource manager, `/dev/tpmrm0`.
+    pub fn open() -> io::Result<Self> {
+        let device = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tpmrm0")?;
+        Ok(Self::new(device))
+    }
+}
+
+impl<D: Read + Write> Tpm<D> {
+    /// Uses `device`, which must behave like a TPM character device: each
+    /// write is one command and the following read returns its response.
+    pub fn new(device: D) -> Self {
+        Self { device }
+    }
+
+    /// Sends a command and returns the response body after the header.
+    fn execute(&mut self, mut command: Command) -> Result<Vec<u8>, TpmError> {
+        let mut command = command.finish();
+        let written = self.device.write_all(&command);
+        wipe(&mut command);
+        written?;
+
+        let mut response = vec![0u8; 4096];
+        let mut len = self.device.read(&mut response)?;
+        let size = response
+            .get(2..6)
+            .filter(|_| len >= 10)
+            .map(|size| u32::from_be_bytes(size.try_into().unwrap()) as usize)
+            .filter(|&size| (10..=response.len()).contains(&size))
+            .ok_or(TpmError::Malformed)?;
+        if len < size {
+            self.device.read_exact(&mut response[len..size])?;
+            len = size;
+        }
+        response.truncate(len.min(size));
+        let code = u32::from_be_bytes(response[6..10].try_into().unwrap());
+        let body = response.split_off(10);
+        wipe(&mut response);
+        match code {
+            0 => Ok(body),
+            code if code & 0xBF == 0x9D => Err(TpmError::PolicyMismatch),
+            code => Err(TpmError::Tpm(code)),
+        }
+    }
+
+    /// Flushes a transient object or session, ignoring failures: the
+    /// resource manager flushes whatever is left when the device is closed.
+    fn flush(&mut self, handle: u32) {
+        let mut command = Command::new(TPM_ST_NO_SESSIONS, TPM_CC_FLUSH_CONTEXT);
+        command.u32(handle);
+        let _ = self.execute(command);
+    }
+
+    /// Creates the storage root key under the owner hierarchy.
+    fn create_primary(&mut self) -> Result<u32, TpmError> {
+        let mut command = Command::new(TPM_ST_SESSIONS, TPM_CC_CREATE_PRIMARY);
+        command.u32(TPM_RH_OWNER);
+        command.password_auth();
+        command.sized(&[0, 0, 0, 0]); // empty userAuth and data
+        let mut public = Command::body();
+        public.u16(TPM_ALG_ECC);
+        public.u16(TPM_ALG_SHA256);
+        public.u32(SRK_ATTRIBUTES);
+        public.sized(&[]);
+        public.u16(TPM_ALG_AES);
+        public.u16(128);
+        public.u16(TPM_ALG_CFB);
+        public.u16(TPM_ALG_NULL);
+        public.u16(TPM_ECC_NIST_P256);
+        public.u16(TPM_ALG_NULL);
+        public.sized(&[0; 32]);
+        public.sized(&[0; 32]);
+        command.sized(&public.buf);
+        command.sized(&[]);
+        command.u32(0);
+        Parser::new(&self.execute(command)?).u32()
+    }
+
+    /// Starts an unsalted, unbound policy (or trial policy) session.
+    fn start_session(&mut self, session_type: u8) -> Result<u32, TpmError> {
+        let mut command = Command::new(TPM_ST_NO_SESSIONS, TPM_CC_START_AUTH_SESSION);
+        command.u32(TPM_RH_NULL);
+        command.u32(TPM_RH_NULL);
+        // The session is neither salted nor bound, so the caller's nonce only
+        // needs the minimum size, not freshness.
+        command.sized(&[0; 32]);
+        command.sized(&[]);
+        command.u8(session_type);
+        command.u16(TPM_ALG_NULL);
+        command.u16(TPM_ALG_SHA256);
+        Parser::new(&self.execute(command)?).u32()
+    }
+
+    fn policy_pcr(&mut self, session: u32, pcrs: &[u8; 3]) -> Result<(), TpmError> {
+        let mut command = Command::new(TPM_ST_NO_SESSIONS, TPM_CC_POLICY_PCR);
+        command.u32(session);
+        command.sized(&[]); // the TPM reads the current PCR values itself
+        command.pcr_selection(pcrs);
+        self.execute(command).map(drop)
+    }
+
+    /// Returns the policy digest for the PCRs' current values.
+    fn pcr_policy_digest(&mut self, pcrs: &[u8; 3]) -> Result<Vec<u8>, TpmError> {
+        let session = self.start_session(TPM_SE_TRIAL)?;
+        let digest = self.policy_pcr(session, pcrs).and_then(|()| {
+            let mut command = Command::new(TPM_ST_NO_SESSIONS, TPM_CC_POLICY_GET_DIGEST);
+            command.u32(session);
+            Parser::new(&self.execute(command)?).sized()
+        });
+        self.flush(session);
+        digest
+    }
+
+    fn create(
+        &mut self,
+        parent: u32,
+        data: &[u8],
+        policy: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>), TpmError> {
+        let mut command = Command::new(TPM_ST_SESSIONS, TPM_CC_CREATE);
+        command.u32(parent);
+        command.password_auth();
+        let mut sensitive = Command::body();
+        sensitive.sized(&[]);
+        sensitive.sized(data);
+        command.sized(&sensitive.buf);
+        wipe(&mut sensitive.buf);
+        let mut public = Command::body();
+        public.u16(TPM_ALG_KEYEDHASH);
+        public.u16(TPM_ALG_SHA256);
+        public.u32(SEALED_ATTRIBUTES);
+        public.sized(policy);
+        public.u16(TPM_ALG_NULL);
+        public.sized(&[]);
+        command.sized(&public.buf);
+        command.sized(&[]);
+        command.u32(0);
+
+        let response = self.execute(command)?;
+        let mut parser = Parser::new(&response);
+        parser.u32()?; // parameterSize
+        Ok((parser.sized()?, parser.sized()?))
+    }
+
+    fn load(&mut self, parent: u32, private: &[u8], public: &[u8]) -> Result<u32, TpmError> {
+        let mut command = Command::new(TPM_ST_SESSIONS, TPM_CC_LOAD);
+        command.u32(parent);
+        command.password_auth();
+        command.sized(private);
+        command.sized(public);
+        Parser::new(&self.execute(command)?).u32()
+    }
+
+    fn unseal(&mut self, object: u32, session: u32) -> Result<Vec<u8>, TpmError> {
+        let mut command = Command::new(TPM_ST_SESSIONS, TPM_CC_UNSEAL);
+        command.u32(object);
+        command.auth(session);
+        let mut response = self.execute(command)?;
+        let mut parser = Parser::new(&response);
+        let data = parser.u32().and_then(|_| parser.sized());
+        wipe(&mut response);
+        data
+    }
+}
+
+/// A secret encrypted by a TPM and bound to PCR values.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SealedSecret {
+    pcrs: [u8; 3],
+    private: Vec<u8>,
+    public: Vec<u8>,
+}
+
+impl fmt::Debug for SealedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SealedSecret")
+            .field("pcrs", &self.pcrs())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SealedSecret {
+    /// Seals `secret` so that it can only be unsealed by this TPM while the
+    /// PCRs in `pcrs` (indices 0 to 23, SHA-256 bank) hold their current values.
+    #[track_caller]
+    pub fn seal<D: Read + Write>(
+        tpm: &mut Tpm<D>,
+        secret: &SensitiveString,
+        pcrs: &[u8],
+    ) -> Result<Self, TpmError> {
+        secret.record_access("seal");
+        let data = secret.value.as_bytes();
+        if data.len() > MAX_SECRET_LEN {
+            return Err(TpmError::TooLarge);
+        }
+        let selection = pcr_selection(pcrs)?;
+        let policy = tpm.pcr_policy_digest(&selection)?;
+        let primary = tpm.create_primary()?;
+        let created = tpm.create(primary, data, &policy);
+        tpm.flush(primary);
+        let (private, public) = created?;
+        Ok(Self {
+            pcrs: selection,
+            private,
+            public,
+        })
+    }
+
+    /// Unseals the secret. Fails with [`TpmError::ExposureRequired`] unless
+    /// an [`ExposureGuard`](crate::ExposureGuard) is active, and with
+    /// [`TpmError::PolicyMismatch`] if the PCRs have changed since sealing.
+    pub fn unseal<D: Read + Write>(&self, tpm: &mut Tpm<D>) -> Result<SensitiveString, TpmError> {
+        require_exposure("unseal")?;
+
+        let primary = tpm.create_primary()?;
+        let object = tpm.load(primary, &self.private, &self.public);
+        tpm.flush(primary);
+        let object = object?;
+        let data = tpm.start_session(TPM_SE_POLICY).and_then(|session| {
+            let data = tpm
+                .policy_pcr(session, &self.pcrs)
+                .and_then(|()| tpm.unseal(object, session));
+            if data.is_err() {
+                tpm.flush(session);
+            }
+            data
+        });
+        tpm.flush(object);
+
+        String::from_utf8(data?)
+            .map(SensitiveString::new)
+            .map_err(|err| {
+                wipe(&mut err.into_bytes());
+                TpmError::InvalidUtf8
+            })
+    }
+
+    /// Returns the PCR indices the secret is sealed to.
+    pub fn pcrs(&self) -> Vec<u8> {
+        (0..PCR_COUNT)
+            .filter(|&pcr| self.pcrs[usize::from(pcr / 8)] & (1 << (pcr % 8)) != 0)
+            .collect()
+    }
+
+    /// Serializes the sealed blob for storage: [`MAGIC`], the 3-byte PCR
+    /// bitmap, then the TPM's private and public areas, each with a
+    /// big-endian `u16` length.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Command::body();
+        bytes.buf.extend_from_slice(MAGIC);
+        bytes.buf.extend_from_slice(&self.pcrs);
+        bytes.sized(&self.private);
+        bytes.sized(&self.public);
+        bytes.buf
+    }
+
+    /// Parses a blob written by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TpmError> {
+        let rest = bytes.strip_prefix(MAGIC).ok_or(TpmError::Malformed)?;
+        let mut parser = Parser::new(rest);
+        let pcrs = [parser.u8()?, parser.u8()?, parser.u8()?];
+        if pcrs == [0; 3] {
+            return Err(TpmError::Malformed);
+        }
+        let private = parser.sized()?;
+        let public = parser.sized()?;
+        if !parser.rest.is_empty() {
+            return Err(TpmError::Malformed);
+        }
+        Ok(Self {
+            pcrs,
+            private,
+            public,
+        })
+    }
+}
+
+fn pcr_selection(pcrs: &[u8]) -> Result<[u8; 3], TpmError> {
+    if pcrs.is_empty() || pcrs.iter().any(|&pcr| pcr >= PCR_COUNT) {
+        return Err(TpmError::InvalidPcrSelection);
+    }
+    let mut bitmap = [0u8; 3];
+    for &pcr in pcrs {
+        bitmap[usize::from(pcr / 8)] |= 1 << (pcr % 8);
+    }
+    Ok(bitmap)
+}
+
+/// A big-endian command buffer.
+struct Command {
+    buf: Vec<u8>,
+}
+
+impl Command {
+    fn new(tag: u16, code: u32) -> Self {
+        let mut command = Self::body();
+        command.u16(tag);
+        command.u32(0); // size, set by finish
+        command.u32(code);
+        command
+    }
+
+    fn body() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// A `TPM2B`: a `u16` length and the bytes.
+    fn sized(&mut self, bytes: &[u8]) {
+        self.u16(bytes.len() as u16);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// An authorization area with one session, no nonce, and no HMAC.
+    fn auth(&mut self, session: u32) {
+        self.u32(9);
+        self.u32(session);
+        self.sized(&[]);
+        self.u8(0);
+        self.sized(&[]);
+    }
+
+    /// Authorizes with the empty password of the owner hierarchy and SRK.
+    fn password_auth(&mut self) {
+        self.auth(TPM_RS_PW);
+    }
+
+    fn pcr_selection(&mut self, bitmap: &[u8; 3]) {
+        self.u32(1);
+        self.u16(TPM_ALG_SHA256);
+        self.u8(3);
+        self.buf.extend_from_slice(bitmap);
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        let size = (self.buf.len() as u32).to_be_bytes();
+        self.buf[2..6].copy_from_slice(&size);
+        std::mem::take(&mut self.buf)
+    }
+}
+
+struct Parser<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Parser<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { rest: bytes }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], TpmError> {
+        if self.rest.len() < len {
+            return Err(TpmError::Malformed);
+        }
+        let (taken, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, TpmError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, TpmError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn sized(&mut self) -> Result<Vec<u8>, TpmError> {
+        let len = u16::from_be_bytes(self.take(2)?.try_into().unwrap());
+        Ok(self.take(usize::from(len))?.to_vec())
+    }
+}
+
+fn wipe(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        // SAFETY: `byte` is a valid, aligned reference into the buffer.
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExposureGuard;
+    use std::collections::VecDeque;
+
+    /// Replays scripted responses and records the commands it was sent.
+    #[derive(Default)]
+    struct FakeTpm {
+        responses: VecDeque<Vec<u8>>,
+        commands: Vec<Vec<u8>>,
+        pending: Vec<u8>,
+    }
+
+    impl FakeTpm {
+        fn reply(mut self, code: u32, body: &[u8]) -> Self {
+            let mut response = Command::new(TPM_ST_NO_SESSIONS, code);
+            response.buf.extend_from_slice(body);
+            self.responses.push_back(response.finish());
+            self
+        }
+
+        fn codes(&self) -> Vec<u32> {
+            self.commands
+                .iter()
+                .map(|command| u32::from_be_bytes(command[6..10].try_into().unwrap()))
+                .collect()
+        }
+    }
+
+    impl Write for FakeTpm {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.commands.push(buf.to_vec());
+            self.pending = self.responses.pop_front().expect("unexpected command");
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Read for FakeTpm {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.pending.len().min(buf.len());
+            buf[..len].copy_from_slice(&self.pending[..len]);
+            self.pending.drain(..len);
+            Ok(len)
+        }
+    }
+
+    fn sized(bytes: &[u8]) -> Vec<u8> {
+        let mut body = Command::body();
+        body.sized(bytes);
+        body.buf
+    }
+
+    fn with_parameter_size(params: &[u8]) -> Vec<u8> {
+        let mut body = (params.len() as u32).to_be_bytes().to_vec();
+        body.extend_from_slice(params);
+        body
+    }
+
+    const SESSION: [u8; 4] = [0x03, 0, 0, 0];
+    const PRIMARY: [u8; 4] = [0x80, 0, 0, 0];
+    const OBJECT: [u8; 4] = [0x80, 0, 0, 1];
+
+    fn sealed() -> SealedSecret {
+        SealedSecret {
+            pcrs: [0b1000_0101, 0, 0],
+            private: b"private".to_vec(),
+            public: b"public".to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_seal_sends_pcr_policy_and_data() {
+        let digest = [0xAB; 32];
+        let mut created = sized(b"private");
+        created.extend(sized(b"public"));
+        let device = FakeTpm::default()
+            .reply(0, &[&SESSION[..], &sized(&[1; 32])].concat())
+            .reply(0, &[])
+            .reply(0, &sized(&digest))
+            .reply(0, &[])
+            .reply(0, &PRIMARY)
+            .reply(0, &with_parameter_size(&created))
+            .reply(0, &[]);
+        let mut tpm = Tpm::new(device);
+
+        let secret = SensitiveString::from("hunter2");
+        let sealed = SealedSecret::seal(&mut tpm, &secret, &[0, 2, 7]).unwrap();
+        assert_eq!(sealed, self::sealed());
+        assert_eq!(
+            tpm.device.codes(),
+            [
+                TPM_CC_START_AUTH_SESSION,
+                TPM_CC_POLICY_PCR,
+                TPM_CC_POLICY_GET_DIGEST,
+                TPM_CC_FLUSH_CONTEXT,
+                TPM_CC_CREATE_PRIMARY,
+                TPM_CC_CREATE,
+                TPM_CC_FLUSH_CONTEXT,
+            ]
+        );
+        let commands = &tpm.device.commands;
+        assert_eq!(commands[0][10..18], [0x40, 0, 0, 7, 0x40, 0, 0, 7]);
+        assert!(commands[1].ends_with(&[0, 0, 0, 1, 0, 0x0B, 3, 0b1000_0101, 0, 0]));
+        let create = &commands[5];
+        assert!(create
+            .windows(9)
+            .any(|w| w == [0, 7, b'h', b'u', b'n', b't', b'e', b'r', b'2']));
+        assert!(create
+            .windows(34)
+            .any(|w| w == [&[0, 32][..], &digest].concat()));
+        assert!(commands[6].ends_with(&PRIMARY));
+    }
+
+    #[test]
+    fn test_unseal_requires_exposure_before_touching_the_tpm() {
+        let mut tpm = Tpm::new(FakeTpm::default());
+        let err = sealed().unseal(&mut tpm).unwrap_err();
+        assert!(matches!(err, TpmError::ExposureRequired(_)));
+        assert!(tpm.device.commands.is_empty());
+    }
+
+    #[test]
+    fn test_unseal_returns_secret_and_flushes() {
+        let device = FakeTpm::default()
+            .reply(0, &PRIMARY)
+            .reply(
+                0,
+                &[&OBJECT[..], &with_parameter_size(&sized(b"name"))].concat(),
+            )
+            .reply(0, &[])
+            .reply(0, &[&SESSION[..], &sized(&[1; 32])].concat())
+            .reply(0, &[])
+            .reply(0, &with_parameter_size(&sized(b"hunter2")))
+            .reply(0, &[]);
+        let mut tpm = Tpm::new(device);
+
+        let _guard = ExposureGuard::new("test");
+        let secret = sealed().unseal(&mut tpm).unwrap();
+        assert_eq!(secret.value, "hunter2");
+        assert_eq!(
+            tpm.device.codes(),
+            [
+                TPM_CC_CREATE_PRIMARY,
+                TPM_CC_LOAD,
+                TPM_CC_FLUSH_CONTEXT,
+                TPM_CC_START_AUTH_SESSION,
+                TPM_CC_POLICY_PCR,
+                TPM_CC_UNSEAL,
+                TPM_CC_FLUSH_CONTEXT,
+            ]
+        );
+        let unseal = &tpm.device.commands[5];
+        assert_eq!(unseal[10..14], OBJECT);
+        assert_eq!(unseal[18..22], SESSION);
+        assert!(tpm.device.commands[6].ends_with(&OBJECT));
+    }
+
+    #[test]
+    fn test_unseal_reports_changed_pcrs() {
+        let device = FakeTpm::default()
+            .reply(0, &PRIMARY)
+            .reply(
+                0,
+                &[&OBJECT[..], &with_parameter_size(&sized(b"name"))].concat(),
+            )
+            .reply(0, &[])
+            .reply(0, &[&SESSION[..], &sized(&[1; 32])].concat())
+            .reply(0, &[])
+            .reply(0x99D, &[])
+            .reply(0, &[])
+            .reply(0, &[]);
+        let mut tpm = Tpm::new(device);
+
+        let _guard = ExposureGuard::new("test");
+        let err = sealed().unseal(&mut tpm).unwrap_err();
+        assert!(matches!(err, TpmError::PolicyMismatch));
+        assert_eq!(
+            tpm.device.codes()[6..],
+            [TPM_CC_FLUSH_CONTEXT, TPM_CC_FLUSH_CONTEXT]
+        );
+        assert!(tpm.device.responses.is_empty());
+    }
+
+    #[test]
+    fn test_seal_rejects_bad_input_without_touching_the_tpm() {
+        let mut tpm = Tpm::new(FakeTpm::default());
+        let secret = SensitiveString::from("hunter2");
+        for pcrs in [&[][..], &[24]] {
+            assert!(matches!(
+                SealedSecret::seal(&mut tpm, &secret, pcrs),
+                Err(TpmError::InvalidPcrSelection)
+            ));
+        }
+        let long = SensitiveString::from("x".repeat(MAX_SECRET_LEN + 1));
+        assert!(matches!(
+            SealedSecret::seal(&mut tpm, &long, &[7]),
+            Err(TpmError::TooLarge)
+        ));
+        assert!(tpm.device.commands.is_empty());
+    }
+
+    #[cfg(feature = "access-policy")]
+    #[test]
+    fn test_seal_is_checked_against_access_policies() {
+        let mut tpm = Tpm::new(FakeTpm::default());
+        let secret = SensitiveString::from("hunter2");
+        crate::access::assert_denied(|| SealedSecret::seal(&mut tpm, &secret, &[7]));
+        assert!(tpm.device.commands.is_empty());
+    }
+
+    #[test]
+    fn test_blob_round_trips() {
+        let sealed = sealed();
+        let bytes = sealed.to_bytes();
+        assert!(bytes.starts_with(MAGIC));
+        assert_eq!(SealedSecret::from_bytes(&bytes).unwrap(), sealed);
+        assert_eq!(sealed.pcrs(), [0, 2, 7]);
+        assert!(!format!("{:?}", sealed).contains("private"));
+        for bad in [
+            &b"sstpm1"[..],
+            &bytes[..bytes.len() - 1],
+            &[&bytes[..], b"x"].concat(),
+        ] {
+            assert!(matches!(
+                SealedSecret::from_bytes(bad),
+                Err(TpmError::Malformed)
+            ));
+        }
+    }
+}
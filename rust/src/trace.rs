@@ -0,0 +1,121 @@
+//! Recording secrets as `tracing` fields.
+//!
+//! `SensitiveString` cannot implement `tracing::Value` (the trait is sealed),
+//! so a secret has to be wrapped before it can be a span or event field.
+//! [`record_sensitive`] records the redacted form on a span, and
+//! `%secret` / `?secret` in event macros format through the redacted
+//! `Display` / `Debug`.
+//!
+//! For subscribers that record structured values (JSON layers built on
+//! `valuable`, with `--cfg tracing_unstable` and tracing's `valuable`
+//! feature), `SensitiveString` implements [`valuable::Valuable`] as a
+//! one-field tuple struct holding the redacted form, so the plaintext never
+//! reaches the subscriber.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::trace::record_sensitive;
+//! use sensitive_string::SensitiveString;
+//!
+//! let token = SensitiveString::from("sk-live-123");
+//! let span = tracing::info_span!("request", token = tracing::field::Empty);
+//! record_sensitive(&span, "token", &token);
+//!
+//! tracing::info!(token = %token, "authenticated");
+//! ```
+
+use crate::SensitiveString;
+use valuable::{Fields, StructDef, Structable, Valuable, Value, Visit};
+
+/// Records the redacted form of `secret` as the field `field` of `span`.
+///
+/// The field must have been declared when the span was created, e.g. with
+/// `tracing::field::Empty`.
+pub fn record_sensitive(span: &tracing::Span, field: &str, secret: &SensitiveString) {
+    span.record(field, tracing::field::display(secret));
+}
+
+/// Visits as `SensitiveString("<redacted>")`.
+impl Valuable for SensitiveString {
+    fn as_value(&self) -> Value<'_> {
+        Value::Structable(self)
+    }
+
+    fn visit(&self, visit: &mut dyn Visit) {
+        self.with_redacted(|redacted| visit.visit_unnamed_fields(&[Value::String(redacted)]));
+    }
+}
+
+impl Structable for SensitiveString {
+    fn definition(&self) -> StructDef<'_> {
+        StructDef::new_static("SensitiveString", Fields::Unnamed(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_record_sensitive_emits_redacted_form() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let token = SensitiveString::from("sk-live-123");
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", token = tracing::field::Empty);
+            record_sensitive(&span, "token", &token);
+            span.in_scope(|| tracing::info!("authenticated"));
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains(&format!("token={}", token)));
+        assert!(!output.contains("sk-live-123"));
+    }
+
+    #[test]
+    fn test_valuable_visits_redacted_form() {
+        struct Collect(Vec<String>);
+
+        impl Visit for Collect {
+            fn visit_value(&mut self, value: Value<'_>) {
+                if let Value::Structable(structable) = value {
+                    structable.visit(self);
+                }
+            }
+
+            fn visit_unnamed_fields(&mut self, values: &[Value<'_>]) {
+                for value in values {
+                    if let Value::String(text) = value {
+                        self.0.push(text.to_string());
+                    }
+                }
+            }
+        }
+
+        let secret = SensitiveString::from("hunter2");
+        let mut collect = Collect(Vec::new());
+        valuable::visit(&secret, &mut collect);
+        assert_eq!(collect.0, vec![secret.to_string()]);
+    }
+}
@@ -0,0 +1,164 @@
+//! Re-validating a secret each time it is used.
+//!
+//! A token loaded at startup can be revoked or expire long before the
+//! process stops using it, and the failure then surfaces as an opaque 401
+//! from some downstream service. [`with_validator`](SensitiveString::with_validator)
+//! attaches a check, such as a lookup in a local revocation denylist or
+//! [`not_after`] for an expiry time, that runs on every
+//! [`with_value`](SensitiveString::with_value) call, so a stale credential
+//! fails at the point of use with an error naming the secret.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::validate::not_after;
+//! use sensitive_string::SensitiveString;
+//! use std::collections::HashSet;
+//! use std::sync::{Arc, RwLock};
+//! use std::time::{Duration, SystemTime};
+//!
+//! let revoked = Arc::new(RwLock::new(HashSet::new()));
+//! let denylist = revoked.clone();
+//! let token = SensitiveString::from("tok-123")
+//!     .with_label("api-token")
+//!     .with_validator(not_after(SystemTime::now() + Duration::from_secs(3600)))
+//!     .with_validator(move |token| match denylist.read().unwrap().contains(token) {
+//!         true => Err("revoked".to_string()),
+//!         false => Ok(()),
+//!     });
+//!
+//! assert_eq!(token.with_value(|token| token.len()), Ok(7));
+//!
+//! revoked.write().unwrap().insert("tok-123".to_string());
+//! let error = token.with_value(|token| token.len()).unwrap_err();
+//! assert_eq!(error.to_string(), "api-token failed validation: revoked");
+//! ```
+//!
+//! Validators run on `with_value` and [`validate`](SensitiveString::validate)
+//! only; `get_value()`, `expose()`, and the other infallible accessors do
+//! not call them. Clones share the validators.
+
+use crate::SensitiveString;
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// A check run against the plaintext before each validated access.
+pub(crate) type Validator = dyn Fn(&str) -> Result<(), String> + Send + Sync;
+
+/// Error returned when a secret's validator rejects it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationFailed {
+    /// The secret's label.
+    pub label: Option<String>,
+    /// The reason given by the validator.
+    pub reason: String,
+}
+
+impl fmt::Display for ValidationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} failed validation: {}",
+            self.label.as_deref().unwrap_or("unlabeled secret"),
+            self.reason
+        )
+    }
+}
+
+impl std::error::Error for ValidationFailed {}
+
+/// Returns a validator that rejects the secret once `deadline` has passed.
+pub fn not_after(deadline: SystemTime) -> impl Fn(&str) -> Result<(), String> + Send + Sync {
+    move |_| {
+        if SystemTime::now() < deadline {
+            Ok(())
+        } else {
+            Err("expired".to_string())
+        }
+    }
+}
+
+impl SensitiveString {
+    /// Adds a check that [`with_value`](Self::with_value) runs before every
+    /// access.
+    ///
+    /// Validators run in the order they were added, and the first error is
+    /// returned. The error message is shown in logs, so it must not include
+    /// the plaintext.
+    pub fn with_validator(
+        mut self,
+        validator: impl Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.validator = Some(match self.validator.take() {
+            None => Arc::new(validator),
+            Some(previous) => Arc::new(move |plaintext: &str| {
+                previous(plaintext)?;
+                validator(plaintext)
+            }),
+        });
+        self
+    }
+
+    /// Runs the attached validators without reading the plaintext.
+    pub fn validate(&self) -> Result<(), ValidationFailed> {
+        let Some(validator) = &self.validator else {
+            return Ok(());
+        };
+        validator(&self.value).map_err(|reason| ValidationFailed {
+            label: self.label().map(str::to_string),
+            reason,
+        })
+    }
+
+    /// Runs `f` with the plaintext if the attached validators accept it.
+    #[track_caller]
+    pub fn with_value<R>(&self, f: impl FnOnce(&str) -> R) -> Result<R, ValidationFailed> {
+        self.validate()?;
+        self.record_access("with_value");
+        Ok(f(&self.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_validators_run_on_every_access() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = calls.clone();
+        let secret = SensitiveString::from("hunter2").with_validator(move |plaintext| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(plaintext, "hunter2");
+            Ok(())
+        });
+        let clone = secret.clone();
+
+        assert_eq!(secret.with_value(str::len), Ok(7));
+        assert_eq!(clone.with_value(str::len), Ok(7));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(SensitiveString::from("x").with_value(str::len), Ok(1));
+    }
+
+    #[test]
+    fn test_first_failing_validator_wins() {
+        let secret = SensitiveString::from("hunter2")
+            .with_validator(not_after(SystemTime::now() - Duration::from_secs(1)))
+            .with_validator(|_| Err("revoked".to_string()));
+        let error = secret.with_value(|_| ()).unwrap_err();
+        assert_eq!(
+            error,
+            ValidationFailed {
+                label: None,
+                reason: "expired".to_string(),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "unlabeled secret failed validation: expired"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! Constant-time comparison against user input.
+//!
+//! `secret.get_value() == user_input` stops at the first differing byte, so
+//! response timing tells an attacker how much of a guess was right. With the
+//! `constant-time` feature, [`SensitiveString::verify`] and the `PartialEq<str>`
+//! impls compare with [`subtle`], which takes the same time wherever the
+//! inputs differ.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::SensitiveString;
+//!
+//! let token = SensitiveString::from("s3cr3t-token");
+//! assert!(token.verify("s3cr3t-token"));
+//! assert!(token != "s3cr3t-tokeX");
+//! ```
+//!
+//! The length of the secret is not hidden: inputs of a different length are
+//! rejected immediately.
+
+use crate::SensitiveString;
+use subtle::ConstantTimeEq;
+
+impl SensitiveString {
+    /// Returns true if `candidate` equals the secret, in constant time.
+    pub fn verify(&self, candidate: &str) -> bool {
+        self.value.as_bytes().ct_eq(candidate.as_bytes()).into()
+    }
+}
+
+/// Compares in constant time, like [`SensitiveString::verify`].
+impl PartialEq<str> for SensitiveString {
+    fn eq(&self, other: &str) -> bool {
+        self.verify(other)
+    }
+}
+
+/// Compares in constant time, like [`SensitiveString::verify`].
+impl PartialEq<&str> for SensitiveString {
+    fn eq(&self, other: &&str) -> bool {
+        self.verify(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let secret = SensitiveString::from("hunter2");
+        assert!(secret.verify("hunter2"));
+        assert!(!secret.verify("hunter3"));
+        assert!(!secret.verify("hunter"));
+        assert!(!secret.verify(""));
+    }
+
+    #[test]
+    fn test_partial_eq_str() {
+        let secret = SensitiveString::from("hunter2");
+        assert!(secret == "hunter2");
+        assert!(secret == *"hunter2");
+        assert!(secret != "Hunter2");
+    }
+}
@@ -0,0 +1,90 @@
+//! JavaScript bindings for WebAssembly builds.
+//!
+//! Web and Node code can use this implementation instead of the TypeScript
+//! one when it already loads a Rust WebAssembly module. The `wasm` feature
+//! exports a `SensitiveString` class with the same methods as the TypeScript
+//! implementation in this repository:
+//!
+//! ```js
+//! const secret = new SensitiveString("foo");
+//! secret.toString();      // "sha256:2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae"
+//! JSON.stringify(secret); // "\"sha256:2c26...\"", through toJSON()
+//! secret.getValue();      // "foo"
+//! ```
+//!
+//! Digests are the same as the TypeScript and Rust implementations give for
+//! the same plaintext, so logs from all of them can be correlated and
+//! cross-language tests can share expected values. The redaction policy,
+//! global hasher, and auditing configured on the Rust side apply to the
+//! exported class too.
+//!
+//! On the Rust side the class is [`JsSensitiveString`], which converts to and
+//! from [`SensitiveString`].
+
+use crate::SensitiveString;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// A `SensitiveString` exported to JavaScript as the class `SensitiveString`.
+#[wasm_bindgen(js_name = SensitiveString)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JsSensitiveString {
+    inner: SensitiveString,
+}
+
+#[wasm_bindgen(js_class = SensitiveString)]
+impl JsSensitiveString {
+    /// Wraps `value`; `new SensitiveString(value)` in JavaScript.
+    #[wasm_bindgen(constructor)]
+    pub fn new(value: String) -> Self {
+        SensitiveString::new(value).into()
+    }
+
+    /// Returns the plaintext; `getValue()` in JavaScript.
+    #[cfg(not(feature = "no-plaintext-getters"))]
+    #[wasm_bindgen(js_name = getValue)]
+    pub fn get_value(&self) -> String {
+        self.inner.record_access("getValue");
+        self.inner.value.clone()
+    }
+
+    /// Returns the redacted form; `toString()` in JavaScript.
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        self.inner.to_string()
+    }
+
+    /// Returns the redacted form, which `JSON.stringify` writes in place of the object.
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> String {
+        self.inner.to_string()
+    }
+}
+
+impl From<SensitiveString> for JsSensitiveString {
+    fn from(inner: SensitiveString) -> Self {
+        Self { inner }
+    }
+}
+
+impl From<JsSensitiveString> for SensitiveString {
+    fn from(secret: JsSensitiveString) -> Self {
+        secret.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest_matches_typescript() {
+        // Expected value from typescript/src/SensitiveString.test.ts.
+        let secret = JsSensitiveString::new("foo".to_string());
+        let expected = "sha256:2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae";
+        assert_eq!(secret.to_js_string(), expected);
+        assert_eq!(secret.to_json(), expected);
+        #[cfg(not(feature = "no-plaintext-getters"))]
+        assert_eq!(secret.get_value(), "foo");
+        assert_eq!(SensitiveString::from(secret), SensitiveString::from("foo"));
+    }
+}
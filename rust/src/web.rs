@@ -0,0 +1,296 @@
+//! Extracting credentials from requests in axum handlers.
+//!
+//! Handlers that read an `Authorization` header or a login form otherwise
+//! hold the credential as a plain `String`. With the `web` feature,
+//! [`SensitiveBearer`] and [`SensitiveBasicAuth`] extract it from the
+//! `Authorization` header already wrapped, and [`SensitiveForm`] and
+//! [`SensitiveQuery`] deserialize a form body or query string into a struct
+//! whose secret fields are `SensitiveString`s.
+//!
+//! # Example
+//!
+//! ```
+//! use sensitive_string::web::{SensitiveBasicAuth, SensitiveBearer, SensitiveForm};
+//! use sensitive_string::SensitiveString;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Login {
+//!     username: String,
+//!     password: SensitiveString,
+//! }
+//!
+//! async fn api(SensitiveBearer(token): SensitiveBearer) { /* ... */ }
+//! async fn admin(auth: SensitiveBasicAuth) { /* auth.username, auth.password */ }
+//! async fn login(SensitiveForm(login): SensitiveForm<Login>) { /* ... */ }
+//! ```
+//!
+//! Rejections never echo any part of the request: a malformed header or a
+//! form that fails to deserialize is reported as such, without the serde
+//! error, which can quote the offending value. The decoded copy of a basic
+//! auth header is zeroed; the request's own header and body buffers are not,
+//! since the framework owns them. Extraction is not a plaintext read.
+
+use crate::SensitiveString;
+use ::base64::engine::general_purpose::STANDARD;
+use ::base64::Engine;
+use ::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use ::http::request::Parts;
+use ::http::{HeaderMap, StatusCode};
+use axum_core::extract::{FromRequest, FromRequestParts, Request};
+use axum_core::response::{IntoResponse, Response};
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// A bearer token from the `Authorization` header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SensitiveBearer(pub SensitiveString);
+
+/// A user name and password from a basic `Authorization` header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SensitiveBasicAuth {
+    /// The user name, which is not treated as a secret.
+    pub username: String,
+    /// The password.
+    pub password: SensitiveString,
+}
+
+/// Why a credential could not be extracted from the `Authorization` header.
+///
+/// Responds with `401 Unauthorized`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthRejection {
+    /// The request has no `Authorization` header.
+    Missing,
+    /// The header uses a different scheme, e.g. `Basic` where `Bearer` was expected.
+    WrongScheme,
+    /// The header could not be decoded.
+    Malformed,
+}
+
+impl fmt::Display for AuthRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuthRejection::Missing => "missing Authorization header",
+            AuthRejection::WrongScheme => "unexpected Authorization scheme",
+            AuthRejection::Malformed => "malformed Authorization header",
+        })
+    }
+}
+
+impl std::error::Error for AuthRejection {}
+
+impl IntoResponse for AuthRejection {
+    fn into_response(self) -> Response {
+        (StatusCode::UNAUTHORIZED, self.to_string()).into_response()
+    }
+}
+
+/// Returns the credentials after `scheme` in the `Authorization` header.
+fn credentials<'a>(headers: &'a HeaderMap, scheme: &str) -> Result<&'a str, AuthRejection> {
+    let header = headers.get(AUTHORIZATION).ok_or(AuthRejection::Missing)?;
+    let header = header.to_str().map_err(|_| AuthRejection::Malformed)?;
+    let (found, rest) = header.split_once(' ').ok_or(AuthRejection::Malformed)?;
+    if !found.eq_ignore_ascii_case(scheme) {
+        return Err(AuthRejection::WrongScheme);
+    }
+    match rest.trim() {
+        "" => Err(AuthRejection::Malformed),
+        credentials => Ok(credentials),
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for SensitiveBearer {
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        credentials(&parts.headers, "Bearer").map(|token| Self(SensitiveString::from(token)))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for SensitiveBasicAuth {
+    type Rejection = AuthRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let encoded = credentials(&parts.headers, "Basic")?;
+        let decoded = STANDARD
+            .decode(encoded)
+            .map_err(|_| AuthRejection::Malformed)?;
+        let mut decoded = String::from_utf8(decoded).map_err(|err| {
+            crate::wipe(&mut err.into_bytes());
+            AuthRejection::Malformed
+        })?;
+        let auth = decoded
+            .split_once(':')
+            .map(|(username, password)| Self {
+                username: username.to_string(),
+                password: SensitiveString::from(password),
+            })
+            .ok_or(AuthRejection::Malformed);
+        // SAFETY: zeros are valid UTF-8.
+        crate::wipe(unsafe { decoded.as_bytes_mut() });
+        auth
+    }
+}
+
+/// Why a form body or query string could not be extracted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormRejection {
+    /// The body is not `application/x-www-form-urlencoded`. Responds with
+    /// `415 Unsupported Media Type`.
+    UnsupportedMediaType,
+    /// The body could not be read. Responds with `400 Bad Request`.
+    Body,
+    /// The data did not deserialize into the target type. Responds with
+    /// `400 Bad Request`.
+    Invalid,
+}
+
+impl fmt::Display for FormRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FormRejection::UnsupportedMediaType => {
+                "expected an application/x-www-form-urlencoded body"
+            }
+            FormRejection::Body => "failed to read the request body",
+            FormRejection::Invalid => "invalid form data",
+        })
+    }
+}
+
+impl std::error::Error for FormRejection {}
+
+impl IntoResponse for FormRejection {
+    fn into_response(self) -> Response {
+        let status = match self {
+            FormRejection::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            FormRejection::Body | FormRejection::Invalid => StatusCode::BAD_REQUEST,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+/// A URL-encoded form body deserialized into `T`, like axum's `Form`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SensitiveForm<T>(pub T);
+
+impl<S, T> FromRequest<S> for SensitiveForm<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = FormRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_form = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+        if !is_form {
+            return Err(FormRejection::UnsupportedMediaType);
+        }
+        let body = Bytes::from_request(req, state)
+            .await
+            .map_err(|_| FormRejection::Body)?;
+        serde_urlencoded::from_bytes(&body)
+            .map(Self)
+            .map_err(|_| FormRejection::Invalid)
+    }
+}
+
+/// A query string deserialized into `T`, like axum's `Query`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SensitiveQuery<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for SensitiveQuery<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    type Rejection = FormRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        serde_urlencoded::from_str(parts.uri.query().unwrap_or_default())
+            .map(Self)
+            .map_err(|_| FormRejection::Invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum_core::body::Body;
+
+    fn parts(authorization: &str) -> Parts {
+        ::http::Request::builder()
+            .header(AUTHORIZATION, authorization)
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0
+    }
+
+    #[tokio::test]
+    async fn test_authorization_extractors() {
+        let SensitiveBearer(token) =
+            SensitiveBearer::from_request_parts(&mut parts("bearer tok-123"), &())
+                .await
+                .unwrap();
+        assert_eq!(token, SensitiveString::from("tok-123"));
+
+        let header = SensitiveString::from("hunter2").as_basic_auth("alice");
+        let auth =
+            SensitiveBasicAuth::from_request_parts(&mut parts(header.to_str().unwrap()), &())
+                .await
+                .unwrap();
+        assert_eq!(auth.username, "alice");
+        assert_eq!(auth.password, SensitiveString::from("hunter2"));
+
+        let mut basic = parts(header.to_str().unwrap());
+        let wrong = SensitiveBearer::from_request_parts(&mut basic, &()).await;
+        assert_eq!(wrong, Err(AuthRejection::WrongScheme));
+        let mut bad = parts("Basic hunter2!");
+        let bad = SensitiveBasicAuth::from_request_parts(&mut bad, &()).await;
+        assert_eq!(bad, Err(AuthRejection::Malformed));
+    }
+
+    #[tokio::test]
+    async fn test_form_rejection_does_not_echo_values() {
+        #[derive(serde::Deserialize)]
+        struct Login {
+            password: SensitiveString,
+            #[allow(dead_code)]
+            attempts: u8,
+        }
+
+        let request = |body: &'static str| {
+            ::http::Request::builder()
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let SensitiveForm(login) =
+            SensitiveForm::<Login>::from_request(request("password=hunter2&attempts=1"), &())
+                .await
+                .unwrap();
+        assert_eq!(login.password, SensitiveString::from("hunter2"));
+
+        let rejection =
+            SensitiveForm::<Login>::from_request(request("password=x&attempts=hunter2"), &())
+                .await
+                .err()
+                .unwrap();
+        assert_eq!(rejection, FormRejection::Invalid);
+        assert!(!rejection.to_string().contains("hunter2"));
+
+        let query = "/login?password=hunter2&attempts=2"
+            .parse::<::http::Uri>()
+            .unwrap();
+        let mut parts = ::http::Request::get(query).body(()).unwrap().into_parts().0;
+        let SensitiveQuery(login) = SensitiveQuery::<Login>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        assert_eq!(login.password, SensitiveString::from("hunter2"));
+    }
+}
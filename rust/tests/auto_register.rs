@@ -0,0 +1,21 @@
+//! Auto-registration is a process-wide switch, so it is tested in its own
+//! binary where no other test can create secrets while it is on.
+
+use sensitive_string::registry::SecretRegistry;
+use sensitive_string::SensitiveString;
+
+#[test]
+fn test_auto_register() {
+    let user = SensitiveString::from("derived-user-7f3a");
+    SecretRegistry::set_auto_register(true);
+    let secret = SensitiveString::from("auto-registered-7f3a");
+    let derived = SensitiveString::concat([&user, &secret]);
+    SecretRegistry::set_auto_register(false);
+    let skipped = SensitiveString::from("not-registered-7f3a");
+
+    let redacted = SecretRegistry::global().redact_str("auto-registered-7f3a not-registered-7f3a");
+    assert_eq!(redacted, format!("{} not-registered-7f3a", secret));
+    let redacted = SecretRegistry::global().redact_str("derived-user-7f3aauto-registered-7f3a");
+    assert_eq!(redacted, format!("derived-user-7f3a{}", secret));
+    drop((skipped, derived));
+}
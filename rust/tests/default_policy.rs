@@ -0,0 +1,27 @@
+//! The default redaction policy is process-wide and can only be set once, so
+//! it is tested in its own binary.
+
+use sensitive_string::policy::{set_default_policy, RedactionPolicy};
+use sensitive_string::SensitiveString;
+
+#[test]
+fn test_default_policy() {
+    set_default_policy(RedactionPolicy::MaskLast(4)).unwrap();
+    let card = SensitiveString::from("4111111111111111");
+    assert_eq!(card.to_string(), "****1111");
+    assert!(SensitiveString::looks_redacted("****1111"));
+
+    #[cfg(feature = "derive")]
+    {
+        #[derive(sensitive_string::Redact)]
+        struct Payment {
+            #[sensitive]
+            card: String,
+        }
+
+        let payment = Payment {
+            card: "4111111111111111".into(),
+        };
+        assert_eq!(format!("{:?}", payment), "Payment { card: ****1111 }");
+    }
+}
@@ -0,0 +1,22 @@
+//! The panic scrubber replaces the process-wide panic hook, so it is tested
+//! in its own binary where it cannot change how other tests' panics print.
+
+use sensitive_string::registry::install_panic_scrubber;
+use sensitive_string::SensitiveString;
+
+#[test]
+fn test_panic_scrubber_keeps_payload() {
+    install_panic_scrubber();
+    install_panic_scrubber();
+    let _secret = SensitiveString::from("panic-secret-9e0d").registered();
+
+    let leaked = "panic-secret-9e0d".to_string();
+    let payload = std::panic::catch_unwind(|| panic!("leaked {}", leaked)).unwrap_err();
+    assert_eq!(
+        payload.downcast_ref::<String>().map(String::as_str),
+        Some("leaked panic-secret-9e0d")
+    );
+
+    let payload = std::panic::catch_unwind(|| std::panic::panic_any(42)).unwrap_err();
+    assert_eq!(payload.downcast_ref::<i32>(), Some(&42));
+}